@@ -6,9 +6,13 @@
 use tracing::info;
 
 use crate::config::themes_dir;
-use crate::daemon::KillSignal;
+use crate::daemon::{KillScope, KillSignal};
+use crate::data::ProcessState;
 
-use super::types::{Action, AppView, MAX_REFRESH_MS, MIN_REFRESH_MS, REFRESH_STEP_MS};
+use super::types::{
+    Action, AppView, ProcessMenuItem, MAX_REFRESH_MS, MIN_REFRESH_MS, PROCESS_ACTIONS,
+    REFRESH_STEP_MS,
+};
 use super::App;
 
 impl App {
@@ -20,18 +24,26 @@ impl App {
             Quit => return false,
             None => {}
             // View toggles
-            ToggleHelp | ToggleAbout | ToggleSettings | ToggleHistory | ToggleBatteryDetails => {
-                self.handle_view_action(action)
-            }
+            ToggleHelp | ToggleAbout | ToggleSettings | ToggleHistory | ToggleBatteryDetails
+            | TogglePlan => self.handle_view_action(action),
             // Navigation
             SelectNext | SelectPrevious | ExitSelectionMode | PageUp | PageDown | Home | End => {
                 self.handle_navigation_action(action)
             }
             // Process management
-            ToggleExpand | KillProcess | ConfirmKill | CancelKill | ToggleKillSignal
-            | ToggleMerge | CycleSortColumn | ToggleSortDirection => {
-                self.handle_process_action(action)
-            }
+            ToggleExpand
+            | KillProcess
+            | ConfirmKill
+            | CancelKill
+            | ToggleKillSignal
+            | CycleKillScope
+            | ToggleMerge
+            | CycleSortColumn
+            | ToggleSortDirection
+            | UndoBatterySaver
+            | OpenProcessActions
+            | ConfirmProcessActions
+            | CloseProcessActions => self.handle_process_action(action),
             // Theme
             CycleAppearance
             | OpenThemePicker
@@ -58,11 +70,15 @@ impl App {
             }
             // Refresh rate
             IncreaseRefreshRate | DecreaseRefreshRate => self.handle_refresh_action(action),
+            // Plan
+            PlanChar(c) => self.plan_input_char(c),
+            PlanBackspace => self.plan_input_backspace(),
+            PlanSubmit => self.submit_plan(),
         }
         true
     }
 
-    /// Handles view toggle actions (Help, About, Settings, History, BatteryDetails).
+    /// Handles view toggle actions (Help, About, Settings, History, BatteryDetails, Plan).
     fn handle_view_action(&mut self, action: Action) {
         match action {
             Action::ToggleHelp => {
@@ -102,6 +118,12 @@ impl App {
                     _ => AppView::BatteryDetails,
                 };
             }
+            Action::TogglePlan => {
+                self.view = match self.view {
+                    AppView::Plan => AppView::Main,
+                    _ => AppView::Plan,
+                };
+            }
             _ => {}
         }
     }
@@ -121,6 +143,8 @@ impl App {
                     }
                 } else if self.view == AppView::Settings {
                     self.move_settings_selection_up();
+                } else if self.view == AppView::ProcessActions {
+                    self.process_action_index = self.process_action_index.saturating_sub(1);
                 } else {
                     self.enter_selection_mode();
                     if self.selected_process_index > 0 {
@@ -142,6 +166,10 @@ impl App {
                     }
                 } else if self.view == AppView::Settings {
                     self.move_settings_selection_down();
+                } else if self.view == AppView::ProcessActions {
+                    if self.process_action_index < PROCESS_ACTIONS.len() - 1 {
+                        self.process_action_index += 1;
+                    }
                 } else {
                     self.enter_selection_mode();
                     let visible_count = self.visible_process_count();
@@ -205,6 +233,10 @@ impl App {
             }
             _ => {}
         }
+
+        if self.view == AppView::Main {
+            self.load_selected_process_history();
+        }
     }
 
     /// Handles process-related actions (expand, kill, sort, merge).
@@ -227,6 +259,8 @@ impl App {
                     if process.is_killable {
                         self.process_to_kill = Some(process.clone());
                         self.kill_signal = KillSignal::default();
+                        self.kill_scope = KillScope::default();
+                        self.load_kill_targets();
                         self.view = AppView::KillConfirm;
                     }
                 }
@@ -236,21 +270,40 @@ impl App {
                     let signal_label = match self.kill_signal {
                         KillSignal::Graceful => "gracefully",
                         KillSignal::Force => "forcefully",
+                        KillSignal::Stop => "stop",
+                        KillSignal::Continue => "continue",
                     };
-                    info!(pid = process.pid, name = %process.name, signal = signal_label, "Killing process");
-                    self.kill_process_impl(process.pid, self.kill_signal);
+                    info!(
+                        pid = process.pid,
+                        name = %process.name,
+                        signal = signal_label,
+                        scope = self.kill_scope.as_str(),
+                        targets = self.kill_targets.len(),
+                        "Killing process"
+                    );
+                    self.kill_process_impl(process.pid, self.kill_signal, self.kill_scope);
                 }
                 self.process_to_kill = None;
+                self.kill_targets.clear();
                 self.view = AppView::Main;
             }
             Action::CancelKill => {
                 self.process_to_kill = None;
+                self.kill_targets.clear();
                 self.view = AppView::Main;
             }
+            Action::CycleKillScope => {
+                self.kill_scope = match self.kill_scope {
+                    KillScope::Process => KillScope::Tree,
+                    KillScope::Tree => KillScope::Group,
+                    KillScope::Group => KillScope::Process,
+                };
+                self.load_kill_targets();
+            }
             Action::ToggleKillSignal => {
                 self.kill_signal = match self.kill_signal {
                     KillSignal::Graceful => KillSignal::Force,
-                    KillSignal::Force => KillSignal::Graceful,
+                    _ => KillSignal::Graceful,
                 };
             }
             Action::ToggleMerge => {
@@ -262,6 +315,35 @@ impl App {
             Action::ToggleSortDirection => {
                 self.sort_ascending = !self.sort_ascending;
             }
+            Action::UndoBatterySaver => self.undo_battery_saver_impl(),
+            Action::OpenProcessActions => {
+                if let Some(process) = self.get_selected_process() {
+                    if process.is_killable {
+                        // Start on Resume for a stopped process, Suspend otherwise
+                        self.process_action_index =
+                            usize::from(process.status == ProcessState::Stopped);
+                        self.process_for_action = Some(process);
+                        self.view = AppView::ProcessActions;
+                    }
+                }
+            }
+            Action::ConfirmProcessActions => {
+                if let Some(process) = self.process_for_action.take() {
+                    match PROCESS_ACTIONS[self.process_action_index].0 {
+                        ProcessMenuItem::Action(action) => {
+                            info!(pid = process.pid, name = %process.name, %action, "Applying process action");
+                            self.process_action_impl(process.pid, action);
+                        }
+                        ProcessMenuItem::Throttle => self.throttle_impl(process.pid),
+                        ProcessMenuItem::RemoveThrottle => self.remove_throttle_impl(process.pid),
+                    }
+                }
+                self.view = AppView::Main;
+            }
+            Action::CloseProcessActions => {
+                self.process_for_action = None;
+                self.view = AppView::Main;
+            }
             _ => {}
         }
     }
@@ -350,17 +432,13 @@ impl App {
             Action::ImporterToggleSearch => {
                 self.importer_search_focused = !self.importer_search_focused;
             }
-            Action::ImporterFilterChar(c) => {
-                if self.importer_search_focused {
-                    self.importer_filter.push(c);
-                    self.importer_index = 0;
-                }
+            Action::ImporterFilterChar(c) if self.importer_search_focused => {
+                self.importer_filter.push(c);
+                self.importer_index = 0;
             }
-            Action::ImporterFilterBackspace => {
-                if self.importer_search_focused {
-                    self.importer_filter.pop();
-                    self.importer_index = 0;
-                }
+            Action::ImporterFilterBackspace if self.importer_search_focused => {
+                self.importer_filter.pop();
+                self.importer_index = 0;
             }
             Action::ImporterClearFilter => {
                 self.importer_filter.clear();
@@ -9,13 +9,18 @@ use color_eyre::eyre::Result;
 use tracing::{debug, info};
 
 use super::App;
-use crate::daemon::{ClientError, DaemonClient, DataSnapshot};
+use crate::daemon::{
+    Alert, BatterySaverSnapshot, ClientError, DaemonClient, DataSnapshot, ThrottleSnapshot,
+};
+
+/// How long an alert stays in the status bar
+const ALERT_DISPLAY_DURATION: Duration = Duration::from_secs(60);
 
 impl App {
     /// Attempts to connect to the daemon and subscribe for real-time updates.
-    /// If the daemon is not running, attempts to auto-start it.
+    /// If the local daemon is not running, attempts to auto-start it.
     pub(crate) fn try_connect_daemon(&mut self) {
-        if self.try_subscribe_to_daemon() {
+        if self.try_subscribe_to_daemon() || self.daemon_target.is_remote() {
             return;
         }
 
@@ -37,7 +42,7 @@ impl App {
     /// Attempts to subscribe to the daemon for real-time updates.
     /// Returns true if subscription was successful.
     fn try_subscribe_to_daemon(&mut self) -> bool {
-        let client = match DaemonClient::connect_with_version_check() {
+        let client = match DaemonClient::connect_with_version_check(&self.daemon_target) {
             Ok(c) => c,
             Err(ClientError::VersionMismatch(e)) => {
                 tracing::warn!("{}", e);
@@ -51,13 +56,19 @@ impl App {
             info!("Subscribed to daemon for real-time data");
 
             let (tx, rx) = std::sync::mpsc::channel();
+            let (alert_tx, alert_rx) = std::sync::mpsc::channel();
             self.snapshot_rx = Some(rx);
+            self.alert_rx = Some(alert_rx);
 
             std::thread::spawn(move || {
                 debug!("Background daemon reader thread started");
                 let mut client = client;
                 loop {
-                    match client.read_update() {
+                    let update = client.read_update();
+                    for alert in client.take_alerts() {
+                        let _ = alert_tx.send(alert);
+                    }
+                    match update {
                         Ok(Some(snapshot)) => {
                             if tx.send(snapshot).is_err() {
                                 debug!("Channel closed, reader thread exiting");
@@ -130,6 +141,29 @@ impl App {
         }
     }
 
+    /// The most recent alert from the daemon, while it is still worth showing.
+    pub fn active_alert(&self) -> Option<&Alert> {
+        self.last_alert
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < ALERT_DISPLAY_DURATION)
+            .map(|(alert, _)| alert)
+    }
+
+    /// The daemon's battery saver state, while the saver is on.
+    pub fn battery_saver(&self) -> Option<&BatterySaverSnapshot> {
+        self.last_snapshot
+            .as_ref()
+            .map(|snapshot| &snapshot.battery_saver)
+            .filter(|saver| saver.active)
+    }
+
+    /// CPU caps the daemon keeps on processes.
+    pub fn throttles(&self) -> &[ThrottleSnapshot] {
+        self.last_snapshot
+            .as_ref()
+            .map_or(&[], |snapshot| snapshot.throttles.as_slice())
+    }
+
     /// Checks if the app is currently attempting to reconnect to the daemon.
     pub fn is_reconnecting(&self) -> bool {
         self.using_daemon_data && self.snapshot_rx.is_none() && self.reconnect_attempts > 0
@@ -183,6 +217,13 @@ impl App {
             }
         }
 
+        if let Some(ref rx) = self.alert_rx {
+            while let Ok(alert) = rx.try_recv() {
+                info!(rule = %alert.rule, "Alert received from daemon");
+                self.last_alert = Some((alert, std::time::Instant::now()));
+            }
+        }
+
         if !received_data {
             if let Some(last_update) = self.last_daemon_update {
                 let elapsed = last_update.elapsed();
@@ -192,6 +233,7 @@ impl App {
                         "No daemon data for 5s, attempting reconnect"
                     );
                     self.snapshot_rx = None;
+                    self.alert_rx = None;
                     self.daemon_connected = false;
                     self.attempt_reconnect();
                 }
@@ -212,7 +254,11 @@ impl App {
         const MAX_RECONNECT_ATTEMPTS: u32 = 3;
         const RECONNECT_BACKOFF_MS: u64 = 1000;
 
-        if self.reconnect_attempts >= MAX_RECONNECT_ATTEMPTS {
+        // Local data would describe this machine, not the remote one, so
+        // keep retrying a remote daemon at the maximum backoff instead.
+        if self.daemon_target.is_remote() {
+            self.reconnect_attempts = self.reconnect_attempts.min(MAX_RECONNECT_ATTEMPTS - 1);
+        } else if self.reconnect_attempts >= MAX_RECONNECT_ATTEMPTS {
             debug!("Max reconnect attempts reached, falling back to local data");
             self.using_daemon_data = false;
             self.daemon_connected = false;
@@ -271,14 +317,26 @@ impl App {
 
     /// Synchronizes the daemon's broadcast interval with the app's refresh rate.
     pub fn sync_daemon_broadcast_interval(&self) {
-        if let Ok(mut client) = DaemonClient::connect() {
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
             let _ = client.set_broadcast_interval(self.refresh_ms);
         }
     }
 
+    /// Pushes a changed config value to the daemon so it takes effect live.
+    pub fn push_config_to_daemon(&self, key: &str) {
+        let Some(value) = self.config.user_config.get_value(key) else {
+            return;
+        };
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+            if let Err(e) = client.set_config(key, &value) {
+                debug!(key, error = %e, "Failed to push config to daemon");
+            }
+        }
+    }
+
     /// Refreshes the daemon status by connecting and querying its current state.
     pub(crate) fn refresh_daemon_status(&mut self) {
-        if let Ok(mut client) = DaemonClient::connect() {
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
             self.daemon_connected = true;
             if let Ok(status) = client.get_status() {
                 self.daemon_status = Some(status);
@@ -3,21 +3,26 @@
 //! This module contains methods for loading and managing history data,
 //! including daily/hourly stats, cycle summaries, and charge sessions.
 
-use crate::daemon::DaemonClient;
+use crate::daemon::{DaemonClient, DownsampleMethod};
+use crate::data::DayZone;
 
 use super::types::HistoryPeriod;
 use super::App;
 
+/// Braille markers give two plotted points per terminal column.
+const CHART_POINTS_PER_COLUMN: usize = 2;
+
 impl App {
     /// Loads history data from the daemon for the current period.
     ///
     /// This method fetches daily stats, top processes, cycle summary,
-    /// daily cycles, charge sessions, and hourly stats (for Today period).
+    /// daily cycles, charge sessions, hourly stats (for Today period), and
+    /// raw samples downsampled by the daemon to the terminal width.
     /// If the daemon is not connected, it clears all history data.
     pub(crate) fn load_history_data(&mut self) {
         self.history_loading = true;
 
-        if let Ok(mut client) = DaemonClient::connect() {
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
             self.daemon_connected = true;
 
             let (from_date, to_date) = self.get_period_dates();
@@ -46,22 +51,37 @@ impl App {
                 self.recent_charge_sessions = sessions.into_iter().map(Into::into).collect();
             }
 
+            let (samples_from, _) = self.day_zone().date_range(&from_date, &to_date);
+
             if self.history_period == HistoryPeriod::Today {
-                let start_of_day = now
-                    .date_naive()
-                    .and_hms_opt(0, 0, 0)
-                    .unwrap()
-                    .and_utc()
-                    .timestamp();
                 let end_ts = now.timestamp();
-                if let Ok(hourly) = client.get_hourly_stats(start_of_day, end_ts) {
+                if let Ok(hourly) = client.get_hourly_stats(samples_from, end_ts) {
                     self.history_hourly_stats = hourly.into_iter().map(Into::into).collect();
                 }
             }
+
+            let width = crossterm::terminal::size()
+                .map(|(cols, _)| cols as usize)
+                .unwrap_or(80);
+            if let Ok(events) = client.get_events(samples_from, now.timestamp()) {
+                self.history_events = events.into_iter().map(Into::into).collect();
+            }
+
+            self.history_samples = client
+                .get_samples(
+                    samples_from,
+                    now.timestamp(),
+                    width * CHART_POINTS_PER_COLUMN,
+                    DownsampleMethod::Lttb,
+                )
+                .map(|samples| samples.into_iter().map(Into::into).collect())
+                .unwrap_or_default();
         } else {
             self.daemon_connected = false;
             self.history_daily_stats.clear();
             self.history_hourly_stats.clear();
+            self.history_samples.clear();
+            self.history_events.clear();
             self.history_top_processes.clear();
             self.cycle_summary = None;
             self.recent_charge_sessions.clear();
@@ -79,20 +99,22 @@ impl App {
     /// - Week: 7 days ago
     /// - Month: 30 days ago
     /// - All: 1970-01-01 (beginning of time)
+    ///
+    /// Dates are calendar days in the configured `history.timezone`.
     pub(crate) fn get_period_dates(&self) -> (String, String) {
-        use chrono::{Duration, Utc};
-
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let zone = self.day_zone();
+        let today = zone.today();
         let from = match self.history_period {
             HistoryPeriod::Today => today.clone(),
-            HistoryPeriod::Week => (Utc::now() - Duration::days(7))
-                .format("%Y-%m-%d")
-                .to_string(),
-            HistoryPeriod::Month => (Utc::now() - Duration::days(30))
-                .format("%Y-%m-%d")
-                .to_string(),
+            HistoryPeriod::Week => zone.days_ago(7),
+            HistoryPeriod::Month => zone.days_ago(30),
             HistoryPeriod::All => "1970-01-01".to_string(),
         };
         (from, today)
     }
+
+    /// Zone that history periods and chart labels use.
+    pub(crate) fn day_zone(&self) -> DayZone {
+        self.config.user_config.history.timezone
+    }
 }
@@ -5,6 +5,7 @@
 mod actions;
 mod daemon;
 mod history;
+mod plan;
 mod process;
 mod theme;
 mod tui;
@@ -18,19 +19,24 @@ use color_eyre::eyre::Result;
 use tracing::{debug, info};
 
 use crate::config::{GraphMetric, RuntimeConfig, UserConfig};
-use crate::daemon::CycleSummary;
-use crate::daemon::{DaemonClient, DaemonStatus, DataSnapshot, KillSignal};
+use crate::daemon::{Alert, CycleSummary, HourlyProcessStat};
+use crate::daemon::{
+    DaemonClient, DaemonStatus, DaemonTarget, DataSnapshot, KillScope, KillSignal, KillTarget,
+};
+use crate::data::plan::Plan;
 use crate::data::{
-    BatteryData, ChargeSession, DailyCycle, DailyStat, DailyTopProcess, ForecastData, HistoryData,
-    HistoryMetric, HourlyStat, PowerData, ProcessData, ProcessInfo, SystemInfo, SystemStatsData,
+    BatteryData, ChargeSession, DailyCycle, DailyStat, DailyTopProcess, Event, ForecastData,
+    HistoryData, HistoryMetric, HourlyStat, PowerData, ProcessData, ProcessInfo, Sample,
+    SystemInfo, SystemStatsData,
 };
 use jolt_theme::cache::ThemeGroup;
 use jolt_theme::NamedTheme;
 
-pub use types::{Action, AppView, HistoryPeriod, SortColumn};
+pub use types::{Action, AppView, HistoryPeriod, ProcessMenuItem, SortColumn, PROCESS_ACTIONS};
 
 /// How many ticks between forecast refreshes.
 const FORECAST_REFRESH_TICKS: u32 = 10;
+const EVENTS_REFRESH_TICKS: u32 = 10;
 
 /// Interval for checking system theme changes.
 const THEME_CHECK_INTERVAL: Duration = Duration::from_secs(2);
@@ -60,8 +66,19 @@ pub struct App {
     pub refresh_ms: u64,
     pub(crate) frozen_processes: Option<Vec<ProcessInfo>>,
     pub(crate) process_to_kill: Option<ProcessInfo>,
+    pub selected_process_history: Option<(String, Vec<HourlyProcessStat>)>,
     pub kill_signal: KillSignal,
+    pub kill_scope: KillScope,
+    /// Processes the kill dialog's scope reaches, the selected one first
+    pub(crate) kill_targets: Vec<KillTarget>,
+    pub(crate) process_for_action: Option<ProcessInfo>,
+    pub process_action_index: usize,
     tick_count: u32,
+    charge_profile_loaded: bool,
+    pub plan_input: String,
+    pub plan: Option<Plan>,
+    pub plan_target: Option<i64>,
+    pub plan_message: Option<String>,
     pub theme_picker_themes: Vec<NamedTheme>,
     pub theme_picker_index: usize,
     pub(crate) preview_theme_id: Option<String>,
@@ -77,6 +94,8 @@ pub struct App {
     pub history_period: HistoryPeriod,
     pub history_daily_stats: Vec<DailyStat>,
     pub history_hourly_stats: Vec<HourlyStat>,
+    pub history_samples: Vec<Sample>,
+    pub history_events: Vec<Event>,
     pub history_top_processes: Vec<DailyTopProcess>,
     pub history_loading: bool,
     pub cycle_summary: Option<CycleSummary>,
@@ -84,7 +103,9 @@ pub struct App {
     pub daily_cycles: Vec<DailyCycle>,
     pub daemon_status: Option<DaemonStatus>,
     pub daemon_connected: bool,
+    pub recent_events: Vec<Event>,
     pub settings_selected_item: usize,
+    pub(crate) daemon_target: DaemonTarget,
     pub(crate) daemon_subscription: Option<DaemonClient>,
     pub(crate) last_snapshot: Option<DataSnapshot>,
     pub using_daemon_data: bool,
@@ -93,14 +114,17 @@ pub struct App {
     pub(crate) last_reconnect_attempt: Option<std::time::Instant>,
     last_theme_check: std::time::Instant,
     pub(crate) snapshot_rx: Option<std::sync::mpsc::Receiver<DataSnapshot>>,
+    pub(crate) alert_rx: Option<std::sync::mpsc::Receiver<Alert>>,
+    pub(crate) last_alert: Option<(Alert, std::time::Instant)>,
 }
 
 impl App {
     /// Creates a new App instance with the given user configuration.
     ///
     /// This initializes all data sources, sets up the initial state,
-    /// and attempts to connect to the daemon for real-time updates.
-    pub fn new(user_config: UserConfig) -> Result<Self> {
+    /// and attempts to connect to the daemon at `daemon_target` for
+    /// real-time updates.
+    pub fn new(user_config: UserConfig, daemon_target: DaemonTarget) -> Result<Self> {
         info!(refresh_ms = user_config.refresh_ms, "Initializing app");
 
         let refresh_ms = user_config.refresh_ms;
@@ -118,13 +142,20 @@ impl App {
             .collect();
         let config = RuntimeConfig::new(user_config);
 
+        // A remote daemon supplies the battery, and this machine may not have one
+        let battery = if daemon_target.is_remote() {
+            BatteryData::from_daemon()
+        } else {
+            BatteryData::new()?
+        };
+
         debug!("Data sources initialized");
 
         let mut app = Self {
             config,
             view: AppView::Main,
             system_info: SystemInfo::new(),
-            battery: BatteryData::new()?,
+            battery,
             power: PowerData::new()?,
             processes: ProcessData::with_exclusions(excluded)?,
             system_stats: SystemStatsData::new()?,
@@ -140,8 +171,18 @@ impl App {
             refresh_ms,
             frozen_processes: None,
             process_to_kill: None,
+            selected_process_history: None,
             kill_signal: KillSignal::default(),
+            kill_scope: KillScope::default(),
+            kill_targets: Vec::new(),
+            process_for_action: None,
+            process_action_index: 0,
             tick_count: 0,
+            charge_profile_loaded: false,
+            plan_input: String::new(),
+            plan: None,
+            plan_target: None,
+            plan_message: None,
             theme_picker_themes: Vec::new(),
             theme_picker_index: 0,
             preview_theme_id: None,
@@ -157,6 +198,8 @@ impl App {
             history_period: HistoryPeriod::default(),
             history_daily_stats: Vec::new(),
             history_hourly_stats: Vec::new(),
+            history_samples: Vec::new(),
+            history_events: Vec::new(),
             history_top_processes: Vec::new(),
             history_loading: false,
             cycle_summary: None,
@@ -164,7 +207,9 @@ impl App {
             daily_cycles: Vec::new(),
             daemon_status: None,
             daemon_connected: false,
+            recent_events: Vec::new(),
             settings_selected_item: crate::settings::first_selectable_index(),
+            daemon_target,
             daemon_subscription: None,
             last_snapshot: None,
             using_daemon_data: false,
@@ -173,6 +218,8 @@ impl App {
             last_reconnect_attempt: None,
             last_theme_check: std::time::Instant::now(),
             snapshot_rx: None,
+            alert_rx: None,
+            last_alert: None,
         };
 
         app.try_connect_daemon();
@@ -232,6 +279,10 @@ impl App {
             if !self.using_daemon_data && self.tick_count.is_multiple_of(FORECAST_REFRESH_TICKS) {
                 self.refresh_forecast();
             }
+
+            if self.using_daemon_data && self.tick_count.is_multiple_of(EVENTS_REFRESH_TICKS) {
+                self.refresh_recent_events();
+            }
         }
 
         debug!(
@@ -282,9 +333,16 @@ impl App {
     /// This uses daemon samples if available, falling back to session
     /// history data if not.
     fn refresh_forecast(&mut self) {
+        use crate::config::ForecastEstimator;
         use crate::data::battery::ChargeState;
+        use crate::data::estimator::ESTIMATOR_HISTORY_SECS;
         use crate::data::history::DataPoint;
 
+        if self.battery.state() == ChargeState::Charging {
+            self.refresh_charge_forecast();
+            return;
+        }
+        self.forecast.clear_charge();
         if self.battery.state() != ChargeState::Discharging {
             return;
         }
@@ -293,8 +351,17 @@ impl App {
         let battery_capacity_wh = self.battery.max_capacity_wh();
 
         let forecast_window = self.config.user_config.forecast_window_secs;
-        if let Ok(mut client) = DaemonClient::connect() {
-            if let Ok(samples) = client.get_recent_samples(forecast_window) {
+        let estimator = self.config.user_config.forecast_estimator;
+        self.forecast
+            .set_estimator(estimator, forecast_window as i64);
+        // Estimators other than the window average can use a longer history
+        let history_secs = if estimator == ForecastEstimator::Window {
+            forecast_window
+        } else {
+            forecast_window.max(ESTIMATOR_HISTORY_SECS as u64)
+        };
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+            if let Ok(samples) = client.get_recent_samples(history_secs) {
                 let converted: Vec<crate::data::Sample> =
                     samples.into_iter().map(Into::into).collect();
                 if self.forecast.calculate_from_daemon_samples(
@@ -313,6 +380,41 @@ impl App {
             .calculate_from_session_data(&points, battery_percent, battery_capacity_wh);
     }
 
+    /// Forecasts the charge from curves learned from the history database,
+    /// loaded on first use.
+    fn refresh_charge_forecast(&mut self) {
+        use crate::data::charge_forecast::ChargeProfile;
+        use crate::data::HistoryStore;
+
+        if !self.charge_profile_loaded {
+            self.charge_profile_loaded = true;
+            let now = chrono::Utc::now().timestamp();
+            let profile = HistoryStore::open_read_only()
+                .ok()
+                .and_then(|store| ChargeProfile::load(&store, now).ok());
+            self.forecast.set_charge_profile(profile);
+        }
+        self.forecast.calculate_charge(
+            self.battery.charge_percent(),
+            self.battery.charger_watts(),
+            self.config.user_config.charge_limit_percent as f32,
+            self.battery.time_remaining(),
+        );
+    }
+
+    /// Fetches events covering the live graph window so they can be marked.
+    fn refresh_recent_events(&mut self) {
+        let Some(oldest) = self.history.points.front().map(|p| p.timestamp) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+            if let Ok(events) = client.get_events(oldest, now) {
+                self.recent_events = events.into_iter().map(Into::into).collect();
+            }
+        }
+    }
+
     /// Moves the settings selection up, skipping section headers.
     pub fn move_settings_selection_up(&mut self) {
         if self.settings_selected_item == 0 {
@@ -345,14 +447,16 @@ impl App {
     /// Performs cleanup before the application exits.
     ///
     /// This optionally shuts down the daemon if background recording is disabled,
-    /// and unsubscribes from daemon updates.
+    /// and unsubscribes from daemon updates. A remote daemon is never shut down.
     pub fn cleanup(&mut self) {
         // Drop the snapshot receiver to signal background thread to exit
         self.snapshot_rx = None;
 
         // Connect with a new client for cleanup operations
-        if let Ok(mut client) = DaemonClient::connect() {
-            if !self.config.user_config.history.background_recording {
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+            if !self.config.user_config.history.background_recording
+                && !self.daemon_target.is_remote()
+            {
                 let _ = client.shutdown();
             } else {
                 let _ = client.unsubscribe();
@@ -362,3 +466,21 @@ impl App {
         self.daemon_subscription = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_app_starts_without_reading_the_local_battery() {
+        // Nothing listens on port 1, so the app starts disconnected
+        let target = DaemonTarget::Remote {
+            addr: "127.0.0.1:1".to_string(),
+            token: "token".to_string(),
+        };
+        let app = App::new(UserConfig::default(), target).unwrap();
+        assert!(!app.daemon_connected);
+        assert_eq!(app.battery.charge_percent(), 0.0);
+        assert_eq!(app.battery.vendor(), None);
+    }
+}
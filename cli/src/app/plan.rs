@@ -0,0 +1,114 @@
+//! Runway planner dialog methods for App.
+//!
+//! Answers whether the battery lasts until the time typed into the dialog,
+//! using the current forecast and the process list shown in the TUI.
+
+use std::time::Duration;
+
+use crate::daemon::DaemonClient;
+use crate::data::battery::ChargeState;
+use crate::data::plan::{self, Load, Plan, PROCESS_HISTORY_DAYS};
+use crate::data::usage_profile::{UsageProfile, PROFILE_WEEKS};
+use crate::data::{DailyTopProcess, ForecastData, HistoryStore, HourlyStat};
+
+use super::App;
+
+/// Longest input: `HH:MM`
+const PLAN_INPUT_LEN: usize = 5;
+
+impl App {
+    pub(crate) fn plan_input_char(&mut self, c: char) {
+        if (c.is_ascii_digit() || c == ':') && self.plan_input.len() < PLAN_INPUT_LEN {
+            self.plan_input.push(c);
+        }
+    }
+
+    pub(crate) fn plan_input_backspace(&mut self) {
+        self.plan_input.pop();
+    }
+
+    /// Plans for the time typed into the dialog.
+    pub(crate) fn submit_plan(&mut self) {
+        self.plan = None;
+        let Some(time) = plan::parse_until(&self.plan_input) else {
+            self.plan_message = Some("Enter a time as HH:MM, e.g. 17:30".to_string());
+            return;
+        };
+
+        let zone = self.day_zone();
+        let now = chrono::Utc::now().timestamp();
+        let target = zone.next_at(now, time);
+        let needed = Duration::from_secs((target - now) as u64);
+        let (history, hourly) = self.load_plan_history(now);
+
+        // While plugged in, plan for unplugging now with the usual usage
+        let plugged_in = self.battery.state() != ChargeState::Discharging;
+        let forecast = if plugged_in {
+            let mut forecast = ForecastData::new();
+            forecast.set_profile(Some(UsageProfile::from_hourly(&hourly, zone)));
+            forecast.calculate_from_profile(
+                self.battery.charge_percent(),
+                self.battery.max_capacity_wh(),
+                now,
+            );
+            forecast
+        } else {
+            self.forecast.clone()
+        };
+
+        let load = Load {
+            processes: &self.processes.processes,
+            history: &history,
+            cpu_watts: self.power.cpu_power_watts(),
+            gpu_watts: self.power.gpu_power_watts(),
+            power_mode: self.power.power_mode(),
+        };
+        match Plan::new(&forecast, needed, &load) {
+            Some(plan) => {
+                self.plan = Some(plan);
+                self.plan_target = Some(target);
+                self.plan_message = plugged_in
+                    .then(|| "Plugged in: assumes you unplug now and use it as usual".to_string());
+            }
+            None if plugged_in => {
+                self.plan_message =
+                    Some("Not enough usage history yet to plan while plugged in".to_string());
+            }
+            None => {
+                self.plan_message = Some("No forecast yet; try again in a minute".to_string());
+            }
+        }
+    }
+
+    /// Recorded per-process power and hourly stats, from the daemon if it
+    /// is running, else straight from the history database.
+    fn load_plan_history(&self, now: i64) -> (Vec<DailyTopProcess>, Vec<HourlyStat>) {
+        let zone = self.day_zone();
+        let (from, to) = (zone.days_ago(PROCESS_HISTORY_DAYS), zone.today());
+        let profile_from = now - PROFILE_WEEKS * 7 * 86400;
+
+        if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+            let history = client
+                .get_top_processes_range(&from, &to, 50)
+                .map(|top| top.into_iter().map(Into::into).collect())
+                .unwrap_or_default();
+            let hourly = client
+                .get_hourly_stats(profile_from, now)
+                .map(|stats| stats.into_iter().map(Into::into).collect())
+                .unwrap_or_default();
+            return (history, hourly);
+        }
+
+        let Ok(store) = HistoryStore::open_read_only() else {
+            return (Vec::new(), Vec::new());
+        };
+        (
+            store
+                .get_top_processes_range(&from, &to, 50)
+                .unwrap_or_default(),
+            store
+                .get_hourly_stats(profile_from, now)
+                .unwrap_or_default(),
+        )
+    }
+}
@@ -5,7 +5,12 @@
 
 use std::collections::HashMap;
 
-use crate::daemon::{DaemonClient, KillSignal};
+use tracing::{info, warn};
+
+use crate::daemon::{
+    DaemonClient, KillScope, KillSignal, KillTarget, ProcessAction, ThrottleTarget,
+};
+use crate::data::processes::{apply_action, kill_targets};
 use crate::data::ProcessInfo;
 
 use super::types::SortColumn;
@@ -150,6 +155,7 @@ impl App {
                 existing.disk_write_bytes += process.disk_write_bytes;
                 existing.total_cpu_time_secs += process.total_cpu_time_secs;
                 existing.run_time_secs = existing.run_time_secs.max(process.run_time_secs);
+                existing.throttled |= process.throttled;
                 if let Some(ref mut children) = existing.children {
                     children.push(process);
                     existing.name = format!("{} ({})", base_name, children.len());
@@ -171,6 +177,7 @@ impl App {
                     status: process.status,
                     run_time_secs: process.run_time_secs,
                     total_cpu_time_secs: process.total_cpu_time_secs,
+                    throttled: process.throttled,
                 };
                 merged.insert(base_name, group);
             }
@@ -192,6 +199,16 @@ impl App {
         self.process_to_kill.as_ref()
     }
 
+    /// Returns the processes the pending kill reaches, the selected one first.
+    pub fn kill_targets(&self) -> &[KillTarget] {
+        &self.kill_targets
+    }
+
+    /// Returns the process the actions menu is open for, if any.
+    pub fn process_for_action(&self) -> Option<&ProcessInfo> {
+        self.process_for_action.as_ref()
+    }
+
     /// Enters selection mode, freezing the current process list.
     ///
     /// When in selection mode, the process list is frozen to prevent
@@ -211,6 +228,29 @@ impl App {
         self.frozen_processes = None;
         self.selected_process_index = 0;
         self.process_scroll_offset = 0;
+        self.selected_process_history = None;
+    }
+
+    /// Fetches the last day of hourly usage for the selected process.
+    ///
+    /// Results are cached by process name so moving within the list only
+    /// queries the daemon when the selection lands on a different process.
+    pub(crate) fn load_selected_process_history(&mut self) {
+        if !self.selection_mode || !self.daemon_connected {
+            return;
+        }
+        let Some(process) = self.get_selected_process() else {
+            return;
+        };
+        if matches!(&self.selected_process_history, Some((name, _)) if *name == process.name) {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let stats = DaemonClient::connect_to(&self.daemon_target)
+            .and_then(|mut client| client.get_process_history(&process.name, now - 86400, now))
+            .unwrap_or_default();
+        self.selected_process_history = Some((process.name, stats));
     }
 
     /// Adjusts the scroll offset to keep the selected process visible.
@@ -224,18 +264,145 @@ impl App {
         }
     }
 
-    /// Kills a process by PID with the specified signal.
+    /// Works out which processes the kill dialog's scope reaches, asking
+    /// the daemon when connected so the list matches what it will signal.
+    pub(crate) fn load_kill_targets(&mut self) {
+        let Some(process) = &self.process_to_kill else {
+            return;
+        };
+        let (pid, scope) = (process.pid, self.kill_scope);
+        let mut targets = None;
+        if scope != KillScope::Process {
+            if self.using_daemon_data {
+                targets = DaemonClient::connect_to(&self.daemon_target)
+                    .and_then(|mut client| client.get_kill_targets(pid, scope))
+                    .ok();
+            }
+            if targets.is_none() && !self.daemon_target.is_remote() {
+                targets = Some(kill_targets(pid, scope));
+            }
+        }
+        self.kill_targets = targets.filter(|t| !t.is_empty()).unwrap_or_else(|| {
+            vec![KillTarget {
+                pid,
+                name: process.name.clone(),
+            }]
+        });
+    }
+
+    /// Kills a process, or every process in `scope`, with the specified
+    /// signal.
     ///
     /// If connected to the daemon, the kill request is sent through the daemon.
-    /// Otherwise, the process is killed directly.
-    pub(crate) fn kill_process_impl(&self, pid: u32, signal: KillSignal) {
+    /// Otherwise, the processes are killed directly. PIDs shown for a remote
+    /// daemon belong to that machine, so they are never killed locally.
+    pub(crate) fn kill_process_impl(&self, pid: u32, signal: KillSignal, scope: KillScope) {
         if self.using_daemon_data {
-            if let Ok(mut client) = DaemonClient::connect() {
-                let _ = client.kill_process(pid, signal);
+            if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+                match client.kill_process(pid, signal, scope) {
+                    Ok(results) => {
+                        for result in results.iter().filter(|r| !r.success) {
+                            warn!(pid = result.pid, error = ?result.error, "Failed to kill process");
+                        }
+                    }
+                    Err(e) => warn!(pid, error = %e, "Failed to kill process"),
+                }
                 return;
             }
         }
-        let _ = self.processes.kill_process(pid, signal);
+        if self.daemon_target.is_remote() {
+            return;
+        }
+        let targets = match scope {
+            KillScope::Process => vec![pid],
+            _ => kill_targets(pid, scope).iter().map(|t| t.pid).collect(),
+        };
+        for target in targets {
+            if let Err(e) = self.processes.kill_process(target, signal) {
+                warn!(pid = target, error = %e, "Failed to kill process");
+            }
+        }
+    }
+
+    /// Suspends, resumes or reprioritizes a process, through the daemon when
+    /// connected and directly otherwise. Like kills, actions on a remote
+    /// daemon's PIDs are never applied locally.
+    pub(crate) fn process_action_impl(&self, pid: u32, action: ProcessAction) {
+        let mut result = None;
+        if self.using_daemon_data {
+            if let Ok(mut client) = DaemonClient::connect_to(&self.daemon_target) {
+                result = Some(match client.process_action(pid, action) {
+                    Ok(reply) => reply.error.map_or(Ok(()), Err),
+                    Err(e) => Err(e.to_string()),
+                });
+            }
+        }
+        let result = match result {
+            Some(result) => result,
+            None if self.daemon_target.is_remote() => return,
+            None => apply_action(pid, action).map_err(|e| e.to_string()),
+        };
+        if let Err(e) = result {
+            warn!(pid, %action, error = %e, "Process action failed");
+        }
+    }
+
+    /// Asks the daemon to cap the process at the configured CPU percentage.
+    /// Only the daemon can throttle, since it keeps and later lifts the cap.
+    pub(crate) fn throttle_impl(&self, pid: u32) {
+        if !self.using_daemon_data {
+            warn!(pid, "CPU throttling needs the daemon");
+            return;
+        }
+        let config = &self.config.user_config.throttle;
+        match DaemonClient::connect_to(&self.daemon_target).and_then(|mut client| {
+            client.throttle(
+                ThrottleTarget::Pid(pid),
+                config.cpu_percent,
+                config.battery_only,
+            )
+        }) {
+            Ok(throttle) => info!(
+                pid,
+                cpu_percent = throttle.cpu_percent,
+                processes = throttle.pids.len(),
+                "Process throttled"
+            ),
+            Err(e) => warn!(pid, error = %e, "Failed to throttle process"),
+        }
+    }
+
+    /// Removes the CPU cap covering the process, which may have been set on
+    /// its name from the command line.
+    pub(crate) fn remove_throttle_impl(&self, pid: u32) {
+        if !self.using_daemon_data {
+            return;
+        }
+        let target = self
+            .throttles()
+            .iter()
+            .find(|t| t.pids.contains(&pid))
+            .map_or(ThrottleTarget::Pid(pid), |t| t.target.clone());
+        match DaemonClient::connect_to(&self.daemon_target)
+            .and_then(|mut client| client.remove_throttle(target.clone()))
+        {
+            Ok(()) => info!(%target, "CPU throttle removed"),
+            Err(e) => warn!(%target, error = %e, "Failed to remove CPU throttle"),
+        }
+    }
+
+    /// Asks the daemon to undo its battery saver, resuming the processes it
+    /// stopped.
+    pub(crate) fn undo_battery_saver_impl(&self) {
+        if self.battery_saver().is_none() {
+            return;
+        }
+        match DaemonClient::connect_to(&self.daemon_target)
+            .and_then(|mut client| client.undo_battery_saver())
+        {
+            Ok(()) => info!("Battery saver undone"),
+            Err(e) => warn!(error = %e, "Failed to undo battery saver"),
+        }
     }
 }
 
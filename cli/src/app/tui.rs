@@ -16,6 +16,7 @@ use ratatui::prelude::*;
 use tracing::{debug, trace};
 
 use crate::config::UserConfig;
+use crate::daemon::DaemonTarget;
 use crate::input;
 use crate::ui;
 
@@ -47,9 +48,9 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Re
 ///
 /// Sets up the terminal, runs the main event loop, and restores the terminal
 /// when finished. This is the main entry point called from the CLI handler.
-pub fn run_tui(user_config: UserConfig) -> Result<()> {
+pub fn run_tui(user_config: UserConfig, daemon_target: DaemonTarget) -> Result<()> {
     let mut terminal = setup_terminal()?;
-    let result = run_tui_loop(&mut terminal, user_config);
+    let result = run_tui_loop(&mut terminal, user_config, daemon_target);
     restore_terminal(&mut terminal)?;
     result
 }
@@ -65,8 +66,9 @@ pub fn run_tui(user_config: UserConfig) -> Result<()> {
 fn run_tui_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     user_config: UserConfig,
+    daemon_target: DaemonTarget,
 ) -> Result<()> {
-    let mut app = App::new(user_config)?;
+    let mut app = App::new(user_config, daemon_target)?;
     let mut needs_redraw = true;
     let mut last_tick = std::time::Instant::now();
     let mut tick_count: u64 = 0;
@@ -1,5 +1,7 @@
 //! Core types and constants for the TUI application.
 
+use crate::daemon::{IoClass, ProcessAction};
+
 /// Minimum refresh rate in milliseconds.
 pub const MIN_REFRESH_MS: u64 = 500;
 
@@ -23,6 +25,7 @@ pub enum Action {
     ConfirmKill,
     CancelKill,
     ToggleKillSignal,
+    CycleKillScope,
     CycleAppearance,
     OpenThemePicker,
     CloseThemePicker,
@@ -56,6 +59,14 @@ pub enum Action {
     SettingsIncrement,
     SettingsDecrement,
     ToggleBatteryDetails,
+    TogglePlan,
+    PlanChar(char),
+    PlanBackspace,
+    PlanSubmit,
+    UndoBatterySaver,
+    OpenProcessActions,
+    ConfirmProcessActions,
+    CloseProcessActions,
     None,
 }
 
@@ -147,8 +158,53 @@ pub enum AppView {
     History,
     Settings,
     BatteryDetails,
+    Plan,
+    ProcessActions,
 }
 
+/// An entry of the process actions menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessMenuItem {
+    Action(ProcessAction),
+    /// Cap CPU time at the configured `throttle.cpu_percent`
+    Throttle,
+    RemoveThrottle,
+}
+
+/// Entries of the process actions menu, in display order.
+pub const PROCESS_ACTIONS: &[(ProcessMenuItem, &str)] = &[
+    (
+        ProcessMenuItem::Action(ProcessAction::Stop),
+        "Suspend (SIGSTOP)",
+    ),
+    (
+        ProcessMenuItem::Action(ProcessAction::Continue),
+        "Resume (SIGCONT)",
+    ),
+    (
+        ProcessMenuItem::Action(ProcessAction::Renice { nice: 10 }),
+        "Lower CPU priority (nice 10)",
+    ),
+    (
+        ProcessMenuItem::Action(ProcessAction::Renice { nice: 0 }),
+        "Normal CPU priority (nice 0)",
+    ),
+    (
+        ProcessMenuItem::Action(ProcessAction::Ionice {
+            class: IoClass::Idle,
+        }),
+        "Idle disk priority",
+    ),
+    (
+        ProcessMenuItem::Action(ProcessAction::Ionice {
+            class: IoClass::BestEffort,
+        }),
+        "Normal disk priority",
+    ),
+    (ProcessMenuItem::Throttle, "Cap CPU"),
+    (ProcessMenuItem::RemoveThrottle, "Remove CPU cap"),
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
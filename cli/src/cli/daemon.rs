@@ -12,6 +12,19 @@ pub enum DaemonCommands {
     Status,
     Disable,
 
+    /// Re-read the config file into the running daemon
+    Reload,
+
+    /// Show the running daemon's config, or set a single value
+    Config {
+        /// Dotted key, e.g. history.sample_interval_secs
+        key: Option<String>,
+
+        /// New value as a TOML literal
+        #[arg(requires = "key")]
+        value: Option<String>,
+    },
+
     #[cfg(target_os = "macos")]
     Install {
         #[arg(short, long)]
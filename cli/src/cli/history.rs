@@ -1,10 +1,18 @@
+use std::path::PathBuf;
+
 use clap::Subcommand;
 
+use crate::data::upower::DEFAULT_UPOWER_DIR;
+
 #[derive(Debug, Subcommand)]
 pub enum HistoryCommands {
     Summary {
         #[arg(short, long, default_value = "week")]
         period: String,
+
+        /// Summarize a merged machine instead of this one
+        #[arg(long)]
+        source: Option<String>,
     },
 
     Top {
@@ -15,12 +23,33 @@ pub enum HistoryCommands {
         limit: usize,
     },
 
+    /// List plug/unplug, session, daemon and annotation events
+    Events {
+        #[arg(short, long, default_value = "today")]
+        period: String,
+    },
+
+    /// Show hourly usage of a single process
+    Process {
+        /// Process name as shown in `jolt history top`
+        name: String,
+
+        #[arg(long, default_value_t = 24)]
+        hours: u32,
+    },
+
     Export {
+        /// Output file (stdout if omitted or -)
         #[arg(short, long)]
         output: Option<String>,
 
-        #[arg(short, long, default_value = "json")]
-        format: String,
+        /// json (summary document), or ndjson, csv or influx with --table
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Stream one table: samples, hourly, daily, processes, sessions or cycles
+        #[arg(short, long)]
+        table: Option<String>,
 
         #[arg(long)]
         from: Option<String>,
@@ -31,14 +60,92 @@ pub enum HistoryCommands {
         #[arg(short, long)]
         period: Option<String>,
 
-        #[arg(long)]
+        #[arg(long, conflicts_with = "table")]
         include_samples: bool,
+
+        /// Downsample exported samples to at most this many points (0 keeps all)
+        #[arg(long, default_value_t = 0, requires = "include_samples")]
+        max_points: usize,
+
+        /// Downsampling method: lttb or min_max
+        #[arg(long, default_value = "lttb", requires = "max_points")]
+        downsample: String,
+    },
+
+    /// Backfill history from another tool's records
+    Import {
+        /// Import UPower's history-*.dat files (default dir: /var/lib/upower)
+        #[arg(
+            long,
+            value_name = "DIR",
+            num_args = 0..=1,
+            default_missing_value = DEFAULT_UPOWER_DIR,
+            required = true
+        )]
+        upower: Option<PathBuf>,
+
+        /// UPower device id to import, e.g. hidpp_battery_0 (default: the system battery)
+        #[arg(long, requires = "upower")]
+        device: Option<String>,
+    },
+
+    /// Copy the database to FILE (safe while the daemon is running)
+    Backup { file: PathBuf },
+
+    /// Replace the database with a backup (stop the daemon first)
+    Restore {
+        file: PathBuf,
+
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Import another machine's database, tagged with a source name
+    Merge {
+        file: PathBuf,
+
+        /// Name to tag the imported data with, e.g. the other machine's hostname
+        #[arg(long)]
+        source: String,
+    },
+
+    /// List this machine and any merged machines with their data range
+    Sources,
+
+    /// Run a read-only SQL query against the history database
+    Query {
+        /// A single SELECT statement; the v_* views decode enum columns
+        sql: String,
+
+        /// Output format: table, csv or json
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Check the database for corruption, schema problems and missing rows
+    Doctor {
+        /// Also recompute hourly and daily stats from raw samples
+        #[arg(long)]
+        rebuild_aggregates: bool,
+
+        /// Apply fixes without asking
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
 
     Prune {
+        /// Delete data older than this many days (default 30 unless --max-size is given)
         #[arg(long)]
         older_than: Option<u32>,
 
+        /// Delete the oldest data until the database fits in this many MB
+        #[arg(long)]
+        max_size: Option<u32>,
+
+        /// Report what would be deleted without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
         #[arg(short = 'y', long)]
         yes: bool,
     },
@@ -11,7 +11,21 @@ use clap::{Parser, Subcommand};
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     #[command(alias = "tui", about = "Launch the terminal UI (default)")]
-    Ui,
+    Ui {
+        #[arg(
+            long,
+            value_name = "HOST:PORT",
+            help = "Attach to a remote daemon's TCP listener instead of the local daemon"
+        )]
+        connect: Option<String>,
+
+        #[arg(
+            long,
+            requires = "connect",
+            help = "Token for the remote daemon (defaults to $JOLT_TOKEN)"
+        )]
+        token: Option<String>,
+    },
 
     #[command(alias = "raw", about = "Output metrics as JSON for scripting")]
     Pipe {
@@ -68,6 +82,85 @@ pub enum Commands {
         command: Option<HistoryCommands>,
     },
 
+    #[command(about = "Add a note to the history timeline")]
+    Annotate {
+        #[arg(help = "Text of the annotation")]
+        text: String,
+
+        #[arg(
+            long,
+            value_name = "TIME",
+            help = "When it happened, as Unix seconds or RFC 3339 (default: now)"
+        )]
+        at: Option<String>,
+    },
+
+    #[command(about = "Show the battery forecast and score its estimators")]
+    Forecast {
+        #[arg(
+            long,
+            help = "Replay recorded samples and score each estimator against actual discharge"
+        )]
+        backtest: bool,
+
+        #[arg(
+            long,
+            default_value_t = 14,
+            help = "Days of samples to replay with --backtest"
+        )]
+        days: u32,
+
+        #[arg(
+            long,
+            value_name = "WH",
+            help = "Battery capacity in Wh (default: current battery, then last recorded health)"
+        )]
+        capacity: Option<f32>,
+
+        #[arg(
+            long,
+            value_name = "SECS",
+            help = "Averaging window in seconds (default: forecast_window_secs, widened to hold 10 samples)"
+        )]
+        window: Option<u64>,
+    },
+
+    #[command(about = "Check whether the battery lasts until a given time, and what would help")]
+    Plan {
+        #[arg(long, value_name = "HH:MM", help = "Time to last until, e.g. 17:30")]
+        until: String,
+
+        #[arg(
+            long,
+            value_name = "WH",
+            help = "Battery capacity in Wh (default: current battery, then last recorded health)"
+        )]
+        capacity: Option<f32>,
+    },
+
+    #[command(about = "Cap the CPU time of an energy-hungry process (Linux, cgroup v2)")]
+    Throttle {
+        #[arg(
+            value_name = "PID|NAME",
+            help = "Process ID or name to throttle (omit to list throttles)"
+        )]
+        target: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "PERCENT",
+            value_parser = parse_percent,
+            help = "CPU cap as a percentage of one CPU, e.g. 25% (default: throttle.cpu_percent)"
+        )]
+        cpu: Option<u32>,
+
+        #[arg(long, help = "Only apply the cap while on battery")]
+        battery_only: bool,
+
+        #[arg(long, conflicts_with_all = ["cpu", "battery_only"], help = "Remove the cap")]
+        remove: bool,
+    },
+
     #[command(about = "View daemon logs")]
     Logs {
         #[arg(short, long, default_value_t = 50, help = "Number of lines to show")]
@@ -95,3 +188,16 @@ pub struct Cli {
     )]
     pub log_level: Option<String>,
 }
+
+/// Parses a CPU percentage such as `25` or `25%`.
+fn parse_percent(value: &str) -> Result<u32, String> {
+    let percent: u32 = value
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid percentage '{}'", value))?;
+    if percent == 0 {
+        return Err("the CPU cap must be at least 1%".to_string());
+    }
+    Ok(percent)
+}
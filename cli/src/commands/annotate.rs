@@ -0,0 +1,33 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::daemon::{is_daemon_running, DaemonClient};
+use crate::data::{Event, HistoryStore};
+
+pub fn run(text: String, at: Option<String>) -> Result<()> {
+    let timestamp = at.as_deref().map(parse_time).transpose()?;
+
+    if is_daemon_running() {
+        let mut client = DaemonClient::connect().map_err(|e| eyre!("{}", e))?;
+        client
+            .add_annotation(&text, timestamp)
+            .map_err(|e| eyre!("{}", e))?;
+    } else {
+        // Without a daemon the database has no writer, so add the row directly.
+        let event = Event::annotation(&text, timestamp).map_err(|e| eyre!(e))?;
+        let store = HistoryStore::open().map_err(|e| eyre!("{}", e))?;
+        store.insert_event(&event).map_err(|e| eyre!("{}", e))?;
+    }
+
+    println!("Annotation added.");
+    Ok(())
+}
+
+/// Accepts a Unix timestamp or an RFC 3339 date-time.
+pub fn parse_time(value: &str) -> Result<i64> {
+    if let Ok(ts) = value.parse::<i64>() {
+        return Ok(ts);
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.timestamp())
+        .map_err(|_| eyre!("Invalid time '{}': use Unix seconds or RFC 3339", value))
+}
@@ -107,6 +107,36 @@ pub fn run(
                 println!("Running:      no");
             }
         }
+        DaemonCommands::Reload => {
+            let mut client = connect_or_exit();
+            client
+                .reload_config()
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            println!("Config reloaded.");
+        }
+        DaemonCommands::Config { key, value } => {
+            let mut client = connect_or_exit();
+            match (key, value) {
+                (Some(key), Some(value)) => {
+                    client
+                        .set_config(&key, &value)
+                        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                    println!("{} = {}", key, value);
+                }
+                (key, _) => {
+                    let entries = client
+                        .get_config()
+                        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                    for entry in entries {
+                        if key.as_deref().is_none_or(|k| {
+                            entry.key == k || entry.key.starts_with(&format!("{}.", k))
+                        }) {
+                            println!("{} = {}", entry.key, entry.value);
+                        }
+                    }
+                }
+            }
+        }
         DaemonCommands::Disable => {
             crate::daemon::service::disable_service()?;
         }
@@ -122,3 +152,13 @@ pub fn run(
 
     Ok(())
 }
+
+fn connect_or_exit() -> DaemonClient {
+    match DaemonClient::connect() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to connect to daemon: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::config::{ForecastEstimator, UserConfig};
+use crate::data::backtest::{self, HORIZON_SECS};
+use crate::data::estimator::{ESTIMATOR_HISTORY_SECS, MIN_READINGS};
+use crate::data::usage_profile::UsageProfile;
+use crate::data::{BatteryData, ForecastData, HistoryStore, Sample};
+
+/// Newest sample must be this recent for a current forecast
+pub const MAX_SAMPLE_AGE_SECS: i64 = 600;
+
+pub fn run(backtest: bool, days: u32, capacity: Option<f32>, window: Option<u64>) -> Result<()> {
+    let user_config = UserConfig::load();
+    let store = match HistoryStore::open_read_only() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Failed to open history database: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let Some(capacity_wh) = battery_capacity(&store, capacity)? else {
+        eprintln!("Battery capacity unknown; pass it with --capacity <WH>.");
+        std::process::exit(1);
+    };
+
+    if backtest {
+        run_backtest(&store, &user_config, days, capacity_wh, window)
+    } else {
+        run_current(&store, &user_config, capacity_wh, window)
+    }
+}
+
+/// Recorded samples are sparser than the TUI's, so by default the window
+/// is widened to hold enough of them for the window average to report.
+pub fn window_secs(samples: &[Sample], user_config: &UserConfig, window: Option<u64>) -> i64 {
+    if let Some(secs) = window {
+        return secs.max(1) as i64;
+    }
+    let mut gaps: Vec<i64> = samples
+        .windows(2)
+        .map(|pair| pair[1].timestamp - pair[0].timestamp)
+        .filter(|gap| *gap > 0)
+        .collect();
+    gaps.sort_unstable();
+    let interval = gaps
+        .get(gaps.len() / 2)
+        .copied()
+        .unwrap_or(user_config.history.sample_interval_secs as i64);
+    (user_config.forecast_window_secs as i64).max(MIN_READINGS as i64 * interval)
+}
+
+/// `--capacity`, else the battery's current capacity, else the last one
+/// recorded, so a backtest can run on a machine other than the laptop
+pub fn battery_capacity(store: &HistoryStore, capacity: Option<f32>) -> Result<Option<f32>> {
+    if capacity.is_some() {
+        return Ok(capacity.filter(|wh| *wh > 0.0));
+    }
+    if let Some(wh) = BatteryData::new()
+        .ok()
+        .map(|b| b.max_capacity_wh())
+        .filter(|wh| *wh > 0.0)
+    {
+        return Ok(Some(wh));
+    }
+    Ok(store
+        .get_latest_battery_health()
+        .map_err(|e| eyre!("{}", e))?
+        .map(|health| health.max_capacity_wh)
+        .filter(|wh| *wh > 0.0))
+}
+
+fn run_current(
+    store: &HistoryStore,
+    user_config: &UserConfig,
+    capacity_wh: f32,
+    window: Option<u64>,
+) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let samples = store
+        .get_samples(now - ESTIMATOR_HISTORY_SECS, now)
+        .map_err(|e| eyre!("{}", e))?;
+    let window_secs = window_secs(&samples, user_config, window);
+    let Some(latest) = samples.iter().max_by_key(|s| s.timestamp) else {
+        println!("No samples recorded in the last 30 minutes.");
+        println!("\nMake sure the daemon is running to collect data:");
+        println!("  jolt daemon start");
+        return Ok(());
+    };
+    if now - latest.timestamp > MAX_SAMPLE_AGE_SECS {
+        println!("No recent samples; the daemon may have stopped recording.");
+        return Ok(());
+    }
+
+    let profile =
+        UsageProfile::load(store, user_config.history.timezone, now).map_err(|e| eyre!("{}", e))?;
+
+    println!(
+        "Battery Forecast ({:.0}%, {:.1} Wh capacity)",
+        latest.battery_percent, capacity_wh
+    );
+    println!("{}", "=".repeat(60));
+    println!(
+        "  {:<16} {:>8} {:>10}  {:<7}",
+        "Estimator", "Power", "Runtime", "P10–P90"
+    );
+    println!("{}", "-".repeat(60));
+    for estimator in ForecastEstimator::ALL {
+        let mut forecast = ForecastData::new();
+        forecast.set_estimator(estimator, window_secs);
+        forecast.set_profile(Some(profile.clone()));
+        forecast.calculate_from_daemon_samples(
+            &samples,
+            latest.battery_percent,
+            capacity_wh,
+            MAX_SAMPLE_AGE_SECS,
+        );
+
+        let marker = if estimator == user_config.forecast_estimator {
+            "*"
+        } else {
+            " "
+        };
+        match (forecast.avg_power_watts(), forecast.formatted()) {
+            (Some(watts), Some(runtime)) => println!(
+                "{} {:<16} {:>7.1}W {:>10}  {}",
+                marker,
+                estimator.label(),
+                watts,
+                runtime,
+                forecast.formatted_range().unwrap_or_default()
+            ),
+            _ => println!(
+                "{} {:<16} {:>8} {:>10}",
+                marker,
+                estimator.label(),
+                "-",
+                "-"
+            ),
+        }
+    }
+    println!("\n* configured estimator (forecast_estimator)");
+    if !profile.is_usable() {
+        println!("Usage profile not used yet: fewer than a day of discharging hours recorded.");
+    }
+    Ok(())
+}
+
+fn run_backtest(
+    store: &HistoryStore,
+    user_config: &UserConfig,
+    days: u32,
+    capacity_wh: f32,
+    window: Option<u64>,
+) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let samples = store
+        .get_samples(now - days as i64 * 86400, now)
+        .map_err(|e| eyre!("{}", e))?;
+    let window_secs = window_secs(&samples, user_config, window);
+    let result = backtest::run(&samples, capacity_wh, window_secs);
+
+    println!(
+        "Forecast Backtest (last {} day{}, {} samples, {:.1} Wh capacity)",
+        days,
+        if days == 1 { "" } else { "s" },
+        samples.len(),
+        capacity_wh
+    );
+    println!("{}", "=".repeat(60));
+
+    if result.runs == 0 {
+        println!(
+            "No discharge lasting over {} minutes in this period.",
+            HORIZON_SECS / 60
+        );
+        println!("Try a longer period with --days.");
+        return Ok(());
+    }
+
+    println!(
+        "Predicted drop over the next {} minutes vs actual, across {} discharge{}",
+        HORIZON_SECS / 60,
+        result.runs,
+        if result.runs == 1 { "" } else { "s" }
+    );
+    println!("Window: {}s\n", window_secs);
+    println!(
+        "  {:<16} {:>11} {:>9} {:>9} {:>10}",
+        "Estimator", "Predictions", "MAE", "Bias", "P10–P90"
+    );
+    println!("{}", "-".repeat(60));
+    for score in &result.scores {
+        let marker = if score.estimator == user_config.forecast_estimator {
+            "*"
+        } else {
+            " "
+        };
+        if score.predictions == 0 {
+            println!(
+                "{} {:<16} {:>11} {:>9} {:>9} {:>10}",
+                marker,
+                score.estimator.label(),
+                0,
+                "-",
+                "-",
+                "-"
+            );
+            continue;
+        }
+        println!(
+            "{} {:<16} {:>11} {:>8.2}% {:>+8.2}% {:>9.0}%",
+            marker,
+            score.estimator.label(),
+            score.predictions,
+            score.mean_abs_error,
+            score.bias,
+            score.coverage * 100.0
+        );
+    }
+    println!("\nMAE and bias are in battery percentage points; positive bias overestimates drain.");
+    println!("P10–P90 is how often the actual drop fell inside the predicted range.");
+    println!("* configured estimator (forecast_estimator)");
+
+    if let Some(best) = result.best() {
+        println!("\nBest on this machine: {}", best.estimator.label());
+        if best.estimator != user_config.forecast_estimator {
+            println!("Switch with:");
+            println!(
+                "  jolt daemon config forecast_estimator {}",
+                best.estimator.name()
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,17 +1,38 @@
-use std::io::Write;
+use std::io::{BufWriter, Write};
 
 use color_eyre::eyre::Result;
 
 use crate::cli::HistoryCommands;
-use crate::data::{self, HistoryStore};
+use crate::config::{HistoryConfig, UserConfig};
+use crate::daemon::DownsampleMethod;
+use crate::data::aggregator::{Aggregator, PruneResult};
+use crate::data::export::{export_table, ExportFormat, ExportRange, ExportTable};
+use crate::data::history_store::{CURRENT_SCHEMA_VERSION, LOCAL_SOURCE};
+use crate::data::query::{render, QueryFormat};
+use crate::data::{self, DayZone, HistoryStore};
 use crate::ui::utils::truncate_str;
 
 pub fn run(command: Option<HistoryCommands>) -> Result<()> {
     let cmd = command.unwrap_or(HistoryCommands::Summary {
         period: "week".to_string(),
+        source: None,
     });
 
-    let store = match HistoryStore::open() {
+    let history_config = UserConfig::load().history;
+    let zone = history_config.timezone;
+
+    // The doctor has to work on databases that fail to open, and queries
+    // must not open the database for writing.
+    let cmd = match cmd {
+        HistoryCommands::Doctor {
+            rebuild_aggregates,
+            yes,
+        } => return run_doctor(&history_config, rebuild_aggregates, yes),
+        HistoryCommands::Query { sql, format } => return run_query(&sql, &format),
+        cmd => cmd,
+    };
+
+    let mut store = match HistoryStore::open() {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to open history database: {}", e);
@@ -21,13 +42,23 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
     };
 
     match cmd {
-        HistoryCommands::Summary { period } => {
-            let (from, to) = get_date_range(&period);
+        HistoryCommands::Summary { period, source } => {
+            let (from, to) = get_date_range(&period, zone);
+            let source = source.as_deref().unwrap_or(LOCAL_SOURCE);
 
-            println!("History Summary ({})", period);
+            if source == LOCAL_SOURCE {
+                println!("History Summary ({})", period);
+            } else {
+                println!("History Summary ({}, {})", period, source);
+            }
             println!("{}", "=".repeat(50));
 
-            match store.get_daily_stats(&from, &to) {
+            match store.get_source_daily_stats(source, &from, &to, 365) {
+                Ok(stats) if stats.is_empty() && source != LOCAL_SOURCE => {
+                    println!("No data for this period.");
+                    println!("\nSee merged machines with:");
+                    println!("  jolt history sources");
+                }
                 Ok(stats) if stats.is_empty() => {
                     println!("No data for this period.");
                     println!("\nMake sure the daemon is running to collect data:");
@@ -54,7 +85,7 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
             }
         }
         HistoryCommands::Top { period, limit } => {
-            let (from, to) = get_date_range(&period);
+            let (from, to) = get_date_range(&period, zone);
 
             println!("Top Power Consumers ({})", period);
             println!("{}", "=".repeat(60));
@@ -84,21 +115,132 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
                 }
             }
         }
+        HistoryCommands::Events { period } => {
+            let (from, to) = get_date_range(&period, zone);
+            let (from_ts, to_ts) = zone.date_range(&from, &to);
+
+            println!("Events ({})", period);
+            println!("{}", "=".repeat(60));
+
+            match store.get_events(from_ts, to_ts) {
+                Ok(events) if events.is_empty() => {
+                    println!("No events for this period.");
+                }
+                Ok(events) => {
+                    for event in &events {
+                        let time = zone.format(event.timestamp, "%Y-%m-%d %H:%M");
+                        println!("{}  {:<14} {}", time, event.kind.label(), event.message);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error reading events: {}", e);
+                }
+            }
+        }
+        HistoryCommands::Process { name, hours } => {
+            let now = chrono::Utc::now().timestamp();
+            let from = now - hours as i64 * 3600;
+
+            println!("{} (last {} hours)", name, hours);
+            println!("{}", "=".repeat(60));
+
+            match store.get_process_history(&name, from, now) {
+                Ok(stats) if stats.is_empty() => {
+                    println!("No data for this process.");
+                }
+                Ok(stats) => {
+                    println!(
+                        "{:<17} {:>8} {:>8} {:>10} {:>10}",
+                        "Hour", "Impact", "CPU %", "Avg W", "Energy Wh"
+                    );
+                    println!("{}", "-".repeat(60));
+                    for s in &stats {
+                        let hour = zone.format(s.hour_start, "%Y-%m-%d %H:%M");
+                        println!(
+                            "{:<17} {:>8.1} {:>8.1} {:>10.2} {:>10.2}",
+                            hour, s.avg_impact, s.avg_cpu, s.avg_power, s.total_energy_wh
+                        );
+                    }
+                    let total: f32 = stats.iter().map(|s| s.total_energy_wh).sum();
+                    println!("{}", "-".repeat(60));
+                    println!("Total energy: {:.2} Wh", total);
+                }
+                Err(e) => {
+                    eprintln!("Error reading process history: {}", e);
+                }
+            }
+        }
         HistoryCommands::Export {
             output,
             format,
+            table,
             from,
             to,
             period,
             include_samples,
+            max_points,
+            downsample,
         } => {
             let (from_date, to_date) = if let (Some(f), Some(t)) = (from, to) {
                 (f, t)
             } else if let Some(p) = period {
-                get_date_range(&p)
+                get_date_range(&p, zone)
             } else {
-                get_date_range("week")
+                get_date_range("week", zone)
             };
+            let output = output.filter(|path| path != "-");
+
+            if let Some(table) = table {
+                let Some(table) = ExportTable::parse(&table) else {
+                    eprintln!(
+                        "Unknown table '{}'. Choose samples, hourly, daily, processes, sessions or cycles.",
+                        table
+                    );
+                    std::process::exit(1);
+                };
+                let format = match format.as_deref() {
+                    None => ExportFormat::Ndjson,
+                    Some(name) => ExportFormat::parse(name).unwrap_or_else(|| {
+                        eprintln!(
+                            "Unknown table format '{}'. Choose ndjson, csv or influx.",
+                            name
+                        );
+                        std::process::exit(1);
+                    }),
+                };
+                let range = ExportRange::new(&from_date, &to_date, zone);
+
+                if let Some(path) = output {
+                    let mut out = BufWriter::new(std::fs::File::create(&path)?);
+                    let rows = export_table(&store, table, format, &range, zone, &mut out)?;
+                    out.flush()?;
+                    println!("Exported {} rows from {} to: {}", rows, table.name(), path);
+                } else {
+                    let mut out = BufWriter::new(std::io::stdout().lock());
+                    let result = export_table(&store, table, format, &range, zone, &mut out)
+                        .and_then(|_| Ok(out.flush()?));
+                    match result {
+                        // Stopped early by `head` or similar
+                        Err(data::HistoryStoreError::Io(e))
+                            if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+                        result => {
+                            result?;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+
+            match format.as_deref().map(str::to_lowercase).as_deref() {
+                None | Some("json") => {}
+                Some(name) => {
+                    eprintln!(
+                        "The {} format exports one table at a time; add --table <TABLE>.",
+                        name
+                    );
+                    std::process::exit(1);
+                }
+            }
 
             let daily_stats = store
                 .get_daily_stats(&from_date, &to_date)
@@ -108,32 +250,16 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
                 .unwrap_or_default();
 
             let samples = if include_samples {
-                store
-                    .get_samples(
-                        chrono::NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
-                            .map(|d| {
-                                let time = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
-                                d.and_time(time).and_utc().timestamp()
-                            })
-                            .unwrap_or(0),
-                        chrono::NaiveDate::parse_from_str(&to_date, "%Y-%m-%d")
-                            .map(|d| {
-                                let time = chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap();
-                                d.and_time(time).and_utc().timestamp()
-                            })
-                            .unwrap_or(i64::MAX),
-                    )
-                    .unwrap_or_default()
+                let (from_ts, to_ts) = zone.date_range(&from_date, &to_date);
+                store.get_samples(from_ts, to_ts).unwrap_or_default()
             } else {
                 Vec::new()
             };
+            let samples =
+                data::downsample::downsample(samples, max_points, parse_downsample(&downsample));
 
-            let content = match format.to_lowercase().as_str() {
-                "csv" => {
-                    export_to_csv(&from_date, &to_date, &daily_stats, &top_processes, &samples)
-                }
-                _ => export_to_json(&from_date, &to_date, &daily_stats, &top_processes, &samples),
-            };
+            let content =
+                export_to_json(&from_date, &to_date, &daily_stats, &top_processes, &samples);
 
             if let Some(path) = output {
                 std::fs::write(&path, &content)?;
@@ -142,9 +268,143 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
                 println!("{}", content);
             }
         }
-        HistoryCommands::Prune { older_than, yes } => {
-            let days = older_than.unwrap_or(30);
-            let before_date = data::history_store::days_ago_date_string(days);
+        HistoryCommands::Import { upower, device } => {
+            let Some(dir) = upower else {
+                return Ok(());
+            };
+            let battery = data::BatteryData::new().ok();
+            let capacity_wh = battery.as_ref().map_or(0.0, |b| b.max_capacity_wh());
+            let identity = data::upower::BatteryIdentity {
+                model: battery.as_ref().and_then(|b| b.model()).map(str::to_string),
+                serial: battery
+                    .as_ref()
+                    .and_then(|b| b.serial_number())
+                    .map(str::to_string),
+            };
+
+            println!("Importing UPower history from {}...", dir.display());
+            let result = data::upower::import_dir(
+                &store,
+                &history_config,
+                &dir,
+                device.as_deref(),
+                &identity,
+                capacity_wh,
+            )?;
+
+            println!("  Device:           {}", result.device);
+            println!("  Files read:       {}", result.files);
+            if result.skipped_lines > 0 {
+                println!("  Unreadable lines: {}", result.skipped_lines);
+            }
+            if let Some((first, last)) = result.range {
+                println!(
+                    "  Range:            {} to {}",
+                    zone.format(first, "%Y-%m-%d %H:%M"),
+                    zone.format(last, "%Y-%m-%d %H:%M")
+                );
+            }
+            println!(
+                "  Samples:          {} imported, {} already present",
+                result.samples_imported, result.duplicate_samples
+            );
+            println!("  Sessions:         {} imported", result.sessions_imported);
+            println!(
+                "  Aggregated:       {} hours, {} days",
+                result.hours_aggregated, result.days_aggregated
+            );
+        }
+        HistoryCommands::Backup { file } => {
+            if file.exists() {
+                eprintln!("{} already exists; choose a new file name.", file.display());
+                std::process::exit(1);
+            }
+
+            store.backup_to(&file)?;
+            let size = std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+            println!(
+                "Backed up {} to {} ({})",
+                store.path().display(),
+                file.display(),
+                data::history_store::format_bytes(size)
+            );
+        }
+        HistoryCommands::Restore { file, yes } => {
+            if crate::daemon::is_daemon_running() {
+                eprintln!("The daemon is running and writing to the database.");
+                eprintln!("Stop it first with: jolt daemon stop");
+                std::process::exit(1);
+            }
+
+            println!(
+                "This replaces all history in {} with {}.",
+                store.path().display(),
+                file.display()
+            );
+            if !yes && !confirm("Proceed?")? {
+                println!("Cancelled.");
+                return Ok(());
+            }
+
+            store.restore_from(&file)?;
+            let stats = store.get_stats()?;
+            println!(
+                "Restored {} samples, {} daily stats ({})",
+                stats.sample_count,
+                stats.daily_count,
+                stats.size_formatted()
+            );
+        }
+        HistoryCommands::Merge { file, source } => {
+            println!("Merging {} as '{}'...", file.display(), source);
+            let result = store.merge_from(&file, &source)?;
+
+            println!("  Samples:          {}", result.samples);
+            println!("  Sessions:         {}", result.sessions);
+            println!("  Hourly stats:     {}", result.hourly);
+            println!("  Daily rows:       {}", result.daily);
+            println!("\nCompare with: jolt history summary --source {}", source);
+        }
+        HistoryCommands::Sources => {
+            let sources = store.get_sources()?;
+            if sources.is_empty() {
+                println!("No data recorded yet.");
+                return Ok(());
+            }
+
+            println!(
+                "{:<20} {:>10} {:>6}  {:<23}",
+                "Source", "Samples", "Days", "Range"
+            );
+            println!("{}", "-".repeat(63));
+            for s in &sources {
+                let range = match (&s.first_date, &s.last_date) {
+                    (Some(first), Some(last)) => format!("{} to {}", first, last),
+                    _ => "-".to_string(),
+                };
+                println!(
+                    "{:<20} {:>10} {:>6}  {:<23}",
+                    truncate_str(&s.source, 20),
+                    s.sample_count,
+                    s.day_count,
+                    range
+                );
+            }
+        }
+        HistoryCommands::Doctor { .. } | HistoryCommands::Query { .. } => {
+            unreachable!("handled before opening the store")
+        }
+        HistoryCommands::Prune {
+            older_than,
+            max_size,
+            dry_run,
+            yes,
+        } => {
+            let older_than = match (older_than, max_size) {
+                (None, None) => Some(30),
+                (days, _) => days,
+            };
+            let before_date = older_than.map(|days| zone.days_ago(days));
 
             let stats = store.get_stats().unwrap_or(data::DatabaseStats {
                 sample_count: 0,
@@ -158,45 +418,76 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
             println!("Current database stats:");
             println!("  Samples: {}", stats.sample_count);
             println!("  Size: {}", stats.size_formatted());
-            println!(
-                "\nWill delete data older than {} days (before {})",
-                days, before_date
-            );
 
-            if !yes {
-                print!("Proceed? [y/N] ");
-                std::io::stdout().flush()?;
+            let verb = if dry_run {
+                "Would delete"
+            } else {
+                "Will delete"
+            };
+            if let (Some(days), Some(date)) = (older_than, &before_date) {
+                println!("\n{} data older than {} days (before {})", verb, days, date);
+            }
+            if let Some(mb) = max_size {
+                println!(
+                    "\n{} the oldest data until the database fits in {} MB",
+                    verb, mb
+                );
+            }
+
+            if !yes && !dry_run && !confirm("Proceed?")? {
+                println!("Cancelled.");
+                return Ok(());
+            }
+
+            let prune = |store: &HistoryStore| -> data::history_store::Result<(PruneResult, u64)> {
+                let mut result = PruneResult::default();
 
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
+                if let Some(date) = &before_date {
+                    let (before_ts, _) = zone.date_range(date, date);
 
-                if !input.trim().eq_ignore_ascii_case("y") {
-                    println!("Cancelled.");
-                    return Ok(());
+                    result.samples_deleted = store.delete_samples_before(before_ts)?;
+                    result.hourly_deleted = store.delete_hourly_stats_before(before_ts)?;
+                    result.daily_deleted = store.delete_daily_stats_before(date)?;
+                    result.processes_deleted = store.delete_daily_processes_before(date)?
+                        + store.delete_hourly_processes_before(before_ts)?
+                        + store.delete_process_samples_before(before_ts)?;
                 }
-            }
 
-            let before_ts = chrono::NaiveDate::parse_from_str(&before_date, "%Y-%m-%d")
-                .map(|d| {
-                    let time = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
-                    d.and_time(time).and_utc().timestamp()
-                })
-                .unwrap_or(0);
-
-            let deleted_samples = store.delete_samples_before(before_ts).unwrap_or(0);
-            let deleted_hourly = store.delete_hourly_stats_before(before_ts).unwrap_or(0);
-            let deleted_daily = store.delete_daily_stats_before(&before_date).unwrap_or(0);
-            let deleted_processes = store
-                .delete_daily_processes_before(&before_date)
-                .unwrap_or(0);
-
-            println!("\nDeleted:");
-            println!("  {} samples", deleted_samples);
-            println!("  {} hourly stats", deleted_hourly);
-            println!("  {} daily stats", deleted_daily);
-            println!("  {} process entries", deleted_processes);
-
-            if let Err(e) = store.vacuum() {
+                if let Some(mb) = max_size {
+                    let aggregator = Aggregator::new(store, &history_config);
+                    result.size = Some(aggregator.prune_to_size(mb as u64 * 1024 * 1024)?);
+                }
+
+                Ok((result, store.used_bytes()?))
+            };
+
+            let (result, size_after) = if dry_run {
+                store.dry_run(prune)?
+            } else {
+                prune(&store)?
+            };
+
+            let (size_samples, size_hourly, size_processes) = result
+                .size
+                .as_ref()
+                .map(|s| (s.samples_deleted, s.hourly_deleted, s.processes_deleted))
+                .unwrap_or_default();
+
+            println!("\n{}:", if dry_run { "Would delete" } else { "Deleted" });
+            println!("  {} samples", result.samples_deleted + size_samples);
+            println!("  {} hourly stats", result.hourly_deleted + size_hourly);
+            println!("  {} daily stats", result.daily_deleted);
+            println!(
+                "  {} process entries",
+                result.processes_deleted + size_processes
+            );
+
+            if dry_run {
+                println!(
+                    "\nEstimated size after vacuum: {}",
+                    data::history_store::format_bytes(size_after)
+                );
+            } else if let Err(e) = store.vacuum() {
                 eprintln!("Warning: vacuum failed: {}", e);
             } else {
                 println!("\nDatabase vacuumed to reclaim space.");
@@ -207,15 +498,218 @@ pub fn run(command: Option<HistoryCommands>) -> Result<()> {
     Ok(())
 }
 
-pub fn get_date_range(period: &str) -> (String, String) {
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+fn run_query(sql: &str, format: &str) -> Result<()> {
+    let Some(format) = QueryFormat::parse(format) else {
+        eprintln!("Unknown format '{}'. Choose table, csv or json.", format);
+        std::process::exit(1);
+    };
+
+    let store = match HistoryStore::open_read_only() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Failed to open history database: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = match store.query(sql) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    match render(&result, format, &mut out).and_then(|_| out.flush()) {
+        // Stopped early by `head` or similar
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn run_doctor(config: &HistoryConfig, rebuild_aggregates: bool, yes: bool) -> Result<()> {
+    let path = HistoryStore::default_path();
+
+    println!("History Doctor");
+    println!("{}", "=".repeat(50));
+    println!("Database:         {}", path.display());
+
+    if !path.exists() {
+        println!("\nNo history database yet. Start the daemon to begin recording:");
+        println!("  jolt daemon start");
+        return Ok(());
+    }
+
+    let mut check = HistoryStore::check_file(&path)?;
+    if !check.integrity_errors.is_empty() {
+        print_integrity(&check.integrity_errors);
+        if yes || confirm("\nRebuild indexes to repair the database?")? {
+            match HistoryStore::reindex_file(&path) {
+                Ok(()) => {
+                    check = HistoryStore::check_file(&path)?;
+                    if !check.integrity_errors.is_empty() {
+                        print_integrity(&check.integrity_errors);
+                    }
+                }
+                Err(e) => println!("Reindex failed:   {}", e),
+            }
+        }
+        if !check.integrity_errors.is_empty() {
+            println!("\nThe database is damaged beyond what jolt can repair.");
+            print_recovery(&path);
+            std::process::exit(1);
+        }
+    }
+    println!("Integrity:        ok");
+
+    let version = check.schema_version;
+    if version > CURRENT_SCHEMA_VERSION {
+        println!(
+            "Schema:           v{}, newer than this jolt supports (v{})",
+            version, CURRENT_SCHEMA_VERSION
+        );
+        println!("\nUpdate jolt to use this database.");
+        std::process::exit(1);
+    }
+
+    let store = match HistoryStore::open() {
+        Ok(store) => store,
+        Err(e) => {
+            println!("Schema:           v{}, failed to open: {}", version, e);
+            println!();
+            print_recovery(&path);
+            std::process::exit(1);
+        }
+    };
+    if version < CURRENT_SCHEMA_VERSION {
+        println!(
+            "Schema:           migrated v{} to v{}",
+            version, CURRENT_SCHEMA_VERSION
+        );
+    } else if check.missing_tables.is_empty() && check.missing_columns.is_empty() {
+        println!("Schema:           v{} (current)", version);
+    } else {
+        println!(
+            "Schema:           v{}, with missing tables or columns",
+            version
+        );
+        for table in &check.missing_tables {
+            println!("  missing table {}", table);
+        }
+        for column in &check.missing_columns {
+            println!("  missing column {}", column);
+        }
+    }
+
+    let orphans = data::doctor::find_orphaned_sessions(&store)?;
+    if orphans.is_empty() {
+        println!("Charge sessions:  ok");
+    } else {
+        println!(
+            "Charge sessions:  {} left incomplete by an earlier daemon run",
+            orphans.len()
+        );
+    }
+
+    let gaps = data::doctor::find_gaps(&store, config)?;
+    if gaps.hours.is_empty() {
+        println!("Hourly stats:     ok");
+    } else {
+        println!(
+            "Hourly stats:     {} hours with samples but no stats",
+            gaps.hours.len()
+        );
+    }
+    if gaps.days.is_empty() {
+        println!("Daily stats:      ok");
+    } else {
+        println!(
+            "Daily stats:      {} days with samples but no stats ({})",
+            gaps.days.len(),
+            gaps.days.join(", ")
+        );
+    }
+
+    let fixable = check.missing_tables.len() + orphans.len() + gaps.hours.len() + gaps.days.len();
+    let mut fixed = false;
+    if fixable > 0 && (yes || confirm("\nFix these problems?")?) {
+        println!();
+        let tables = store.create_missing_tables()?;
+        if !tables.is_empty() {
+            println!("Created tables:   {}", tables.join(", "));
+        }
+        if !orphans.is_empty() {
+            let repaired = data::doctor::repair_sessions(&store, &orphans)?;
+            println!("Closed sessions:  {}", repaired);
+        }
+        if !gaps.is_empty() {
+            let (hours, days) = data::doctor::fill_gaps(&store, config)?;
+            println!("Aggregated:       {} hours, {} days", hours, days);
+        }
+        fixed = true;
+    }
+
+    if rebuild_aggregates
+        && (yes || confirm("\nRecompute hourly and daily stats from raw samples?")?)
+    {
+        let (hours, days) = Aggregator::new(&store, config).rebuild_aggregates()?;
+        println!("Rebuilt:          {} hours, {} days", hours, days);
+    }
+
+    if !check.missing_columns.is_empty() {
+        println!("\nMissing columns can't be added safely.");
+        print_recovery(&path);
+        std::process::exit(1);
+    }
+    if fixable == 0 {
+        println!("\nNo problems found.");
+    } else if !fixed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn print_recovery(path: &std::path::Path) {
+    println!("Restore a backup with `jolt history restore <backup>`, or stop the");
+    println!("daemon and delete {} to start over.", path.display());
+}
+
+fn print_integrity(errors: &[String]) {
+    println!("Integrity:        failed");
+    for error in errors.iter().take(5) {
+        println!("  {}", error);
+    }
+    if errors.len() > 5 {
+        println!("  ...");
+    }
+}
+
+pub fn parse_downsample(method: &str) -> DownsampleMethod {
+    match method.to_lowercase().replace('-', "_").as_str() {
+        "min_max" | "minmax" => DownsampleMethod::MinMax,
+        _ => DownsampleMethod::Lttb,
+    }
+}
+
+pub fn get_date_range(period: &str, zone: DayZone) -> (String, String) {
+    let today = zone.today();
 
     match period.to_lowercase().as_str() {
         "today" => (today.clone(), today),
-        "week" => (data::history_store::days_ago_date_string(7), today),
-        "month" => (data::history_store::days_ago_date_string(30), today),
+        "week" => (zone.days_ago(7), today),
+        "month" => (zone.days_ago(30), today),
         "all" => ("2000-01-01".to_string(), today),
-        _ => (data::history_store::days_ago_date_string(7), today),
+        _ => (zone.days_ago(7), today),
     }
 }
 
@@ -237,80 +731,3 @@ fn export_to_json(
     });
     serde_json::to_string_pretty(&export_data).unwrap_or_default()
 }
-
-fn export_to_csv(
-    from: &str,
-    to: &str,
-    daily_stats: &[data::DailyStat],
-    top_processes: &[data::DailyTopProcess],
-    samples: &[data::Sample],
-) -> String {
-    let mut output = String::new();
-
-    output.push_str(&format!("# Jolt History Export: {} to {}\n\n", from, to));
-
-    output.push_str("# Daily Statistics\n");
-    output
-        .push_str("date,avg_power_w,max_power_w,total_energy_wh,screen_on_hours,charging_hours\n");
-    for stat in daily_stats {
-        output.push_str(&format!(
-            "{},{:.2},{:.2},{:.2},{:.2},{:.2}\n",
-            stat.date,
-            stat.avg_power,
-            stat.max_power,
-            stat.total_energy_wh,
-            stat.screen_on_hours,
-            stat.charging_hours
-        ));
-    }
-
-    output.push_str("\n# Top Processes\n");
-    output.push_str(
-        "process_name,avg_power_w,total_energy_wh,avg_cpu_percent,avg_memory_mb,sample_count\n",
-    );
-    for proc in top_processes {
-        output.push_str(&format!(
-            "{},{:.2},{:.2},{:.2},{:.2},{}\n",
-            escape_csv(&proc.process_name),
-            proc.avg_power,
-            proc.total_energy_wh,
-            proc.avg_cpu,
-            proc.avg_memory_mb,
-            proc.sample_count
-        ));
-    }
-
-    if !samples.is_empty() {
-        output.push_str("\n# Raw Samples\n");
-        output
-            .push_str("timestamp,battery_percent,power_watts,cpu_power,gpu_power,charging_state\n");
-        for sample in samples {
-            let charging = match sample.charging_state {
-                data::ChargingState::Discharging => "discharging",
-                data::ChargingState::Charging => "charging",
-                data::ChargingState::Full => "full",
-                data::ChargingState::Unknown => "unknown",
-            };
-            output.push_str(&format!(
-                "{},{:.1},{:.2},{:.2},{:.2},{}\n",
-                sample.timestamp,
-                sample.battery_percent,
-                sample.power_watts,
-                sample.cpu_power,
-                sample.gpu_power,
-                charging
-            ));
-        }
-    }
-
-    output
-}
-
-pub fn escape_csv(s: &str) -> String {
-    if s.contains(',') || s.contains('"') || s.contains('\n') {
-        let escaped = s.replace('"', "\"\"").replace('\n', " ");
-        format!("\"{}\"", escaped)
-    } else {
-        s.to_string()
-    }
-}
@@ -1,7 +1,11 @@
+pub mod annotate;
 pub mod config;
 pub mod daemon;
 pub mod debug;
+pub mod forecast;
 pub mod history;
 pub mod logs;
 pub mod pipe;
+pub mod plan;
 pub mod theme;
+pub mod throttle;
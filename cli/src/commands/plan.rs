@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::commands::forecast::{battery_capacity, window_secs, MAX_SAMPLE_AGE_SECS};
+use crate::config::UserConfig;
+use crate::data::estimator::ESTIMATOR_HISTORY_SECS;
+use crate::data::forecast::format_duration;
+use crate::data::plan::{self, CutKind, Load, Plan, PROCESS_HISTORY_DAYS};
+use crate::data::power::PowerMode;
+use crate::data::usage_profile::UsageProfile;
+use crate::data::{ChargingState, ForecastData, HistoryStore, PowerData, ProcessData};
+
+/// Time given to CPU usage and power readings to settle
+const MEASURE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn run(until: &str, capacity: Option<f32>) -> Result<()> {
+    let Some(time) = plan::parse_until(until) else {
+        eprintln!("Invalid time '{}': use HH:MM, e.g. 17:30", until);
+        std::process::exit(1);
+    };
+    let user_config = UserConfig::load();
+    let store = match HistoryStore::open_read_only() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Failed to open history database: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let Some(capacity_wh) = battery_capacity(&store, capacity)? else {
+        eprintln!("Battery capacity unknown; pass it with --capacity <WH>.");
+        std::process::exit(1);
+    };
+
+    let zone = user_config.history.timezone;
+    let now = chrono::Utc::now().timestamp();
+    let target = zone.next_at(now, time);
+    let needed = Duration::from_secs((target - now) as u64);
+
+    let samples = store
+        .get_samples(now - ESTIMATOR_HISTORY_SECS, now)
+        .map_err(|e| eyre!("{}", e))?;
+    let Some(latest) = samples.iter().max_by_key(|s| s.timestamp) else {
+        println!("No samples recorded in the last 30 minutes.");
+        println!("\nMake sure the daemon is running to collect data:");
+        println!("  jolt daemon start");
+        return Ok(());
+    };
+    if now - latest.timestamp > MAX_SAMPLE_AGE_SECS {
+        println!("No recent samples; the daemon may have stopped recording.");
+        return Ok(());
+    }
+
+    let mut forecast = ForecastData::new();
+    forecast.set_estimator(
+        user_config.forecast_estimator,
+        window_secs(&samples, &user_config, None),
+    );
+    forecast.set_profile(Some(
+        UsageProfile::load(&store, zone, now).map_err(|e| eyre!("{}", e))?,
+    ));
+    let plugged_in = latest.charging_state != ChargingState::Discharging;
+    let forecasted = if plugged_in {
+        forecast.calculate_from_profile(latest.battery_percent, capacity_wh, now)
+    } else {
+        forecast.calculate_from_daemon_samples(
+            &samples,
+            latest.battery_percent,
+            capacity_wh,
+            MAX_SAMPLE_AGE_SECS,
+        )
+    };
+    if !forecasted {
+        if plugged_in {
+            println!("Plugged in, and not enough usage history to plan for unplugging.");
+            println!("The usage profile needs a day of discharging hours recorded.");
+        } else {
+            println!("Not enough recent discharge samples to forecast yet.");
+        }
+        return Ok(());
+    }
+
+    let excluded: Vec<String> = user_config
+        .effective_excluded_processes()
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+    let mut processes = ProcessData::with_exclusions(excluded)?;
+    let mut power = PowerData::new().ok();
+    std::thread::sleep(MEASURE_INTERVAL);
+    processes.refresh()?;
+    if let Some(power) = power.as_mut() {
+        power.refresh()?;
+    }
+    let history = store
+        .get_top_processes_range(&zone.days_ago(PROCESS_HISTORY_DAYS), &zone.today(), 50)
+        .map_err(|e| eyre!("{}", e))?;
+    let load = Load {
+        processes: &processes.processes,
+        history: &history,
+        cpu_watts: power.as_ref().map_or(0.0, |p| p.cpu_power_watts()),
+        gpu_watts: power.as_ref().map_or(0.0, |p| p.gpu_power_watts()),
+        power_mode: power
+            .as_ref()
+            .map_or(PowerMode::Unknown, |p| p.power_mode()),
+    };
+    let Some(plan) = Plan::new(&forecast, needed, &load) else {
+        println!("Not enough recent discharge samples to forecast yet.");
+        return Ok(());
+    };
+
+    println!(
+        "Until {} ({} from now, {:.0}% now)",
+        zone.format(target, "%a %H:%M"),
+        format_duration(plan.needed),
+        latest.battery_percent
+    );
+    println!("{}", "=".repeat(60));
+    println!("  {}", plan.summary());
+    println!(
+        "  Forecast runtime {} (pessimistic {})",
+        format_duration(plan.runtime),
+        format_duration(plan.pessimistic)
+    );
+    if plugged_in {
+        println!("  Plugged in: assumes you unplug now and use it as usual.");
+    }
+
+    if plan.cuts.is_empty() {
+        return Ok(());
+    }
+    println!("\nWhat would help:");
+    println!(
+        "  {:<34} {:>7} {:>8}  {:<8}",
+        "Cut", "Saves", "Adds", "Makes it"
+    );
+    println!("{}", "-".repeat(60));
+    for cut in &plan.cuts {
+        println!(
+            "  {:<34} {:>6.1}W {:>8}  {}",
+            cut.label,
+            cut.watts,
+            format!("+{}", format_duration(cut.added)),
+            cut.verdict.label()
+        );
+    }
+    println!();
+    if plan.cuts.iter().any(|c| c.kind != CutKind::LowPower) {
+        println!("Process power is its share of CPU power, averaged with the last week's.");
+    }
+    if plan.cuts.iter().any(|c| c.kind != CutKind::Quit) {
+        println!("Low power mode is assumed to save about 30% of CPU and GPU power.");
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::config::UserConfig;
+use crate::daemon::{is_daemon_running, DaemonClient, ThrottleSnapshot, ThrottleTarget};
+
+pub fn run(
+    target: Option<String>,
+    cpu: Option<u32>,
+    battery_only: bool,
+    remove: bool,
+) -> Result<()> {
+    if !is_daemon_running() {
+        eprintln!("Throttling needs the daemon, which keeps and lifts the caps:");
+        eprintln!("  jolt daemon start");
+        std::process::exit(1);
+    }
+    let mut client = DaemonClient::connect().map_err(|e| eyre!("{}", e))?;
+
+    let Some(target) = target else {
+        let snapshot = client.get_current_data().map_err(|e| eyre!("{}", e))?;
+        print_throttles(&snapshot.throttles);
+        return Ok(());
+    };
+    let target = ThrottleTarget::parse(&target);
+
+    if remove {
+        client
+            .remove_throttle(target.clone())
+            .map_err(|e| eyre!("{}", e))?;
+        println!("Removed the CPU cap on {}.", target);
+        return Ok(());
+    }
+
+    let config = UserConfig::load().throttle;
+    let cpu_percent = cpu.unwrap_or(config.cpu_percent);
+    let battery_only = battery_only || config.battery_only;
+    let throttle = client
+        .throttle(target, cpu_percent, battery_only)
+        .map_err(|e| eyre!("{}", e))?;
+    println!(
+        "Capped {} at {}% CPU ({} {}){}.",
+        throttle.target,
+        throttle.cpu_percent,
+        throttle.pids.len(),
+        if throttle.pids.len() == 1 {
+            "process"
+        } else {
+            "processes"
+        },
+        if throttle.active {
+            ""
+        } else {
+            "; applies on battery"
+        }
+    );
+    Ok(())
+}
+
+fn print_throttles(throttles: &[ThrottleSnapshot]) {
+    if throttles.is_empty() {
+        println!("No processes are throttled.");
+        return;
+    }
+    println!(
+        "{:<24} {:>6} {:>6} {:<10} {:>15}",
+        "Target", "Cap", "Procs", "State", "Impact"
+    );
+    for throttle in throttles {
+        // Only battery-only caps are ever lifted
+        let state = if throttle.active { "capped" } else { "on AC" };
+        println!(
+            "{:<24} {:>5}% {:>6} {:<10} {:>15}",
+            throttle.target.to_string(),
+            throttle.cpu_percent,
+            throttle.pids.len(),
+            state,
+            impact(throttle)
+        );
+    }
+}
+
+/// Energy impact before the cap and on average while capped.
+fn impact(throttle: &ThrottleSnapshot) -> String {
+    match throttle.energy_after {
+        Some(after) => format!("{:.1} → {:.1}", throttle.energy_before, after),
+        None => format!("{:.1} → …", throttle.energy_before),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::data::DayZone;
 use crate::theme::ThemeColors;
 use jolt_theme::NamedTheme;
 use serde::{Deserialize, Serialize};
@@ -92,6 +93,9 @@ pub struct HistoryConfig {
     pub retention_daily_days: u32,
     pub retention_sessions_days: u32,
     pub max_database_mb: u32,
+    /// Zone daily rollups and period filters use: "local", "utc" or an
+    /// offset like "-08:00"
+    pub timezone: DayZone,
 }
 
 impl Default for HistoryConfig {
@@ -104,6 +108,264 @@ impl Default for HistoryConfig {
             retention_daily_days: 0,
             retention_sessions_days: 90,
             max_database_mb: 500,
+            timezone: DayZone::Local,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub top_processes: usize,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9101,
+            top_processes: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Connect over TLS; brokers usually listen on 8883 for this
+    pub tls: bool,
+    /// PEM CA certificate to trust instead of the system roots
+    pub ca_file: Option<PathBuf>,
+    pub topic_prefix: String,
+    pub discovery_prefix: String,
+    pub interval_secs: u64,
+    /// Seconds to wait before reconnecting after the broker goes away
+    pub reconnect_secs: u64,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            tls: false,
+            ca_file: None,
+            topic_prefix: "jolt".to_string(),
+            discovery_prefix: "homeassistant".to_string(),
+            interval_secs: 30,
+            reconnect_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    pub enabled: bool,
+    pub listen: String,
+    pub token: String,
+    pub allow_mutations: bool,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: "127.0.0.1:8617".to_string(),
+            token: String::new(),
+            allow_mutations: false,
+        }
+    }
+}
+
+/// Daemon event a notification sink can be subscribed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    PluggedIn,
+    Unplugged,
+    /// A charge or discharge session finished
+    SessionEnd,
+    LowBattery,
+    ChargeLimit,
+    /// An `[[alerts]]` rule fired
+    Alert,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 6] = [
+        NotificationEvent::PluggedIn,
+        NotificationEvent::Unplugged,
+        NotificationEvent::SessionEnd,
+        NotificationEvent::LowBattery,
+        NotificationEvent::ChargeLimit,
+        NotificationEvent::Alert,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotificationEvent::PluggedIn => "plugged_in",
+            NotificationEvent::Unplugged => "unplugged",
+            NotificationEvent::SessionEnd => "session_end",
+            NotificationEvent::LowBattery => "low_battery",
+            NotificationEvent::ChargeLimit => "charge_limit",
+            NotificationEvent::Alert => "alert",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopSinkConfig {
+    pub enabled: bool,
+    pub events: Vec<NotificationEvent>,
+}
+
+impl Default for DesktopSinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            events: NotificationEvent::ALL.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandSinkConfig {
+    pub enabled: bool,
+    /// Run with `sh -c`, with the event as JSON on stdin
+    pub command: String,
+    pub events: Vec<NotificationEvent>,
+}
+
+impl Default for CommandSinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: String::new(),
+            events: NotificationEvent::ALL.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSinkConfig {
+    pub enabled: bool,
+    /// Receives the event as a JSON POST
+    pub url: String,
+    pub events: Vec<NotificationEvent>,
+}
+
+impl Default for WebhookSinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            events: NotificationEvent::ALL.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Charge level that raises the low battery event
+    pub low_battery_percent: u8,
+    pub desktop: DesktopSinkConfig,
+    pub command: CommandSinkConfig,
+    pub webhook: WebhookSinkConfig,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            low_battery_percent: 20,
+            desktop: DesktopSinkConfig::default(),
+            command: CommandSinkConfig::default(),
+            webhook: WebhookSinkConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatterySaverConfig {
+    pub enabled: bool,
+    /// Charge level on battery that turns the saver on
+    pub threshold_percent: u8,
+    /// Switch the power profile to low power
+    pub low_power_mode: bool,
+    /// Process names to pause with SIGSTOP until plugged in again
+    pub stop_processes: Vec<String>,
+    /// Slowest update rate for subscribed clients, 0 to leave it alone
+    pub broadcast_interval_ms: u64,
+}
+
+impl Default for BatterySaverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_percent: 15,
+            low_power_mode: true,
+            stop_processes: Vec::new(),
+            broadcast_interval_ms: 5000,
+        }
+    }
+}
+
+/// Defaults for `jolt throttle` and the TUI's CPU cap action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// CPU cap as a percentage of one CPU
+    pub cpu_percent: u32,
+    /// Only apply caps on battery power
+    pub battery_only: bool,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            cpu_percent: 25,
+            battery_only: false,
+        }
+    }
+}
+
+/// A threshold alert rule from an `[[alerts]]` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Shown in the alert; defaults to the condition itself
+    pub name: String,
+    /// Condition, e.g. `power.total_power_watts > 35 for 2m`
+    pub when: String,
+    /// How far back past the threshold the value must go before the rule
+    /// can fire again
+    pub hysteresis: f32,
+    /// Minimum seconds between two alerts from this rule
+    pub cooldown_secs: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            when: String::new(),
+            hysteresis: 1.0,
+            cooldown_secs: 600,
         }
     }
 }
@@ -122,6 +384,9 @@ pub struct UserConfig {
     pub merge_mode: bool,
     pub transparent_background: bool,
     pub forecast_window_secs: u64,
+    pub forecast_estimator: ForecastEstimator,
+    /// Charge level the battery stops at, for the time-to-limit forecast
+    pub charge_limit_percent: u8,
     #[serde(default)]
     pub excluded_processes: Vec<String>,
     #[serde(default)]
@@ -130,6 +395,20 @@ pub struct UserConfig {
     pub units: UnitsConfig,
     #[serde(default)]
     pub log_level: LogLevel,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub battery_saver: BatterySaverConfig,
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
+    #[serde(default)]
+    pub alerts: Vec<AlertConfig>,
 }
 
 impl Default for UserConfig {
@@ -145,10 +424,19 @@ impl Default for UserConfig {
             merge_mode: true,
             transparent_background: false,
             forecast_window_secs: 300,
+            forecast_estimator: ForecastEstimator::Window,
+            charge_limit_percent: 100,
             excluded_processes: Vec::new(),
             history: HistoryConfig::default(),
             units: UnitsConfig::default(),
             log_level: LogLevel::Info,
+            metrics: MetricsConfig::default(),
+            mqtt: MqttConfig::default(),
+            remote: RemoteConfig::default(),
+            notifications: NotificationsConfig::default(),
+            battery_saver: BatterySaverConfig::default(),
+            throttle: ThrottleConfig::default(),
+            alerts: Vec::new(),
         }
     }
 }
@@ -163,6 +451,68 @@ pub enum GraphMetric {
     Merged,
 }
 
+/// How the forecast estimates the current discharge rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ForecastEstimator {
+    /// Mean power over the forecast window
+    #[default]
+    Window,
+    /// Exponentially weighted moving average of power
+    Ewma,
+    /// Kalman filter on remaining energy and drain rate
+    Kalman,
+    /// Linear regression on the battery percentage, for machines without
+    /// power readings
+    Slope,
+}
+
+impl ForecastEstimator {
+    pub const ALL: [ForecastEstimator; 4] = [
+        ForecastEstimator::Window,
+        ForecastEstimator::Ewma,
+        ForecastEstimator::Kalman,
+        ForecastEstimator::Slope,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ForecastEstimator::Window => "Window average",
+            ForecastEstimator::Ewma => "EWMA",
+            ForecastEstimator::Kalman => "Kalman",
+            ForecastEstimator::Slope => "Charge slope",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForecastEstimator::Window => "window",
+            ForecastEstimator::Ewma => "ewma",
+            ForecastEstimator::Kalman => "kalman",
+            ForecastEstimator::Slope => "slope",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ForecastEstimator::Window => ForecastEstimator::Ewma,
+            ForecastEstimator::Ewma => ForecastEstimator::Kalman,
+            ForecastEstimator::Kalman => ForecastEstimator::Slope,
+            ForecastEstimator::Slope => ForecastEstimator::Window,
+        }
+    }
+
+    pub fn prev(&self) -> Self {
+        match self {
+            ForecastEstimator::Window => ForecastEstimator::Slope,
+            ForecastEstimator::Ewma => ForecastEstimator::Window,
+            ForecastEstimator::Kalman => ForecastEstimator::Ewma,
+            ForecastEstimator::Slope => ForecastEstimator::Kalman,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum EnergyUnit {
@@ -356,6 +706,134 @@ impl UserConfig {
         excluded.extend(self.excluded_processes.iter().map(|s| s.as_str()));
         excluded
     }
+
+    /// Flattens the config into dotted keys with TOML-encoded values.
+    ///
+    /// Secrets are redacted so entries can be shown to any client.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        if let Ok(toml::Value::Table(table)) = toml::Value::try_from(self) {
+            flatten_table("", &table, &mut entries);
+        }
+        for (key, value) in &mut entries {
+            if SECRET_KEYS.contains(&key.as_str()) {
+                *value = REDACTED.to_string();
+            }
+        }
+        entries
+    }
+
+    /// Returns the TOML-encoded value at a dotted key, if set.
+    pub fn get_value(&self, key: &str) -> Option<String> {
+        let root = toml::Value::try_from(self).ok()?;
+        lookup(&root, key).map(|v| v.to_string())
+    }
+
+    /// Sets a dotted key from a TOML literal, e.g. `history.retention_raw_days` = `14`.
+    ///
+    /// Values that don't parse as TOML are treated as bare strings, so
+    /// `appearance` = `dark` works without quoting. This backs the daemon's
+    /// `SetConfig` request, so keys that could run commands or open up
+    /// remote access are refused.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        if is_protected_key(key) {
+            return Err(ConfigError::ProtectedKey(key.to_string()));
+        }
+        let parsed = parse_toml_literal(value);
+        let mut root =
+            toml::Value::try_from(&*self).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+
+        let (parent_path, leaf) = match key.rsplit_once('.') {
+            Some((parent, leaf)) => (Some(parent), leaf),
+            None => (None, key),
+        };
+        let mut parent = &mut root;
+        for segment in parent_path.into_iter().flat_map(|p| p.split('.')) {
+            parent = parent
+                .get_mut(segment)
+                .filter(|v| v.is_table())
+                .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        }
+        let toml::Value::Table(table) = parent else {
+            return Err(ConfigError::UnknownKey(key.to_string()));
+        };
+        if table.get(leaf).is_some_and(|v| v.is_table()) {
+            return Err(ConfigError::UnknownKey(key.to_string()));
+        }
+        table.insert(leaf.to_string(), parsed.clone());
+
+        let updated: UserConfig =
+            root.try_into()
+                .map_err(|e: toml::de::Error| ConfigError::InvalidValue {
+                    key: key.to_string(),
+                    message: e.message().to_string(),
+                })?;
+
+        // Unknown keys are silently dropped by serde, so check the value survived.
+        if updated.get_value(key) != Some(parsed.to_string()) {
+            return Err(ConfigError::UnknownKey(key.to_string()));
+        }
+
+        *self = updated;
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Unknown config key: {0}")]
+    UnknownKey(String),
+
+    #[error("{0} can only be changed by editing the config file")]
+    ProtectedKey(String),
+
+    #[error("Invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+
+    #[error("Failed to serialize config: {0}")]
+    Serialize(String),
+}
+
+/// Sections that can't be changed over the daemon socket: the command sink
+/// runs shell commands as the daemon's user, and `remote` controls who may
+/// reach the daemon in the first place.
+const PROTECTED_KEYS: &[&str] = &["notifications.command", "remote"];
+
+fn is_protected_key(key: &str) -> bool {
+    PROTECTED_KEYS.iter().any(|protected| {
+        key.strip_prefix(protected)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+const SECRET_KEYS: &[&str] = &["mqtt.password", "remote.token", "notifications.webhook.url"];
+const REDACTED: &str = "\"<redacted>\"";
+
+fn flatten_table(prefix: &str, table: &toml::Table, out: &mut Vec<(String, String)>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            toml::Value::Table(inner) => flatten_table(&key, inner, out),
+            other => out.push((key, other.to_string())),
+        }
+    }
+}
+
+fn lookup<'a>(root: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(root, |value, segment| value.get(segment))
+}
+
+fn parse_toml_literal(value: &str) -> toml::Value {
+    format!("v = {}", value)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
 }
 
 pub struct RuntimeConfig {
@@ -446,3 +924,129 @@ pub fn detect_system_dark_mode() -> bool {
         })
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_value_updates_nested_key() {
+        let mut config = UserConfig::default();
+        config
+            .set_value("history.sample_interval_secs", "120")
+            .unwrap();
+        assert_eq!(config.history.sample_interval_secs, 120);
+        assert_eq!(
+            config.get_value("history.sample_interval_secs").as_deref(),
+            Some("120")
+        );
+    }
+
+    #[test]
+    fn set_value_accepts_bare_strings_and_arrays() {
+        let mut config = UserConfig::default();
+        config.set_value("appearance", "dark").unwrap();
+        assert_eq!(config.appearance, AppearanceMode::Dark);
+
+        config
+            .set_value("excluded_processes", r#"["Dropbox", "zoom.us"]"#)
+            .unwrap();
+        assert_eq!(config.excluded_processes, vec!["Dropbox", "zoom.us"]);
+    }
+
+    #[test]
+    fn set_value_sets_absent_optional_field() {
+        let mut config = UserConfig::default();
+        config.set_value("mqtt.username", "jolt").unwrap();
+        assert_eq!(config.mqtt.username.as_deref(), Some("jolt"));
+    }
+
+    #[test]
+    fn set_value_rejects_unknown_key() {
+        let mut config = UserConfig::default();
+        assert!(matches!(
+            config.set_value("history.sample_intervl_secs", "60"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            config.set_value("nope.nested", "1"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            config.set_value("history", "1"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn set_value_rejects_protected_keys() {
+        let mut config = UserConfig::default();
+        for key in [
+            "notifications.command.command",
+            "notifications.command.enabled",
+            "remote.token",
+            "remote.allow_mutations",
+        ] {
+            assert!(matches!(
+                config.set_value(key, "true"),
+                Err(ConfigError::ProtectedKey(_))
+            ));
+        }
+        assert!(config.notifications.command.command.is_empty());
+        assert!(!config.remote.allow_mutations);
+        assert!(matches!(
+            config.set_value("remote_port", "1"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        config
+            .set_value("notifications.webhook.enabled", "true")
+            .unwrap();
+    }
+
+    #[test]
+    fn set_value_rejects_wrong_type() {
+        let mut config = UserConfig::default();
+        assert!(matches!(
+            config.set_value("history.sample_interval_secs", "often"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert_eq!(config.history.sample_interval_secs, 60);
+    }
+
+    #[test]
+    fn alerts_round_trip_with_defaults() {
+        let config: UserConfig = toml::from_str(
+            r#"
+            refresh_ms = 1000
+
+            [[alerts]]
+            name = "Low battery"
+            when = "battery.charge_percent < 15 while discharging"
+
+            [[alerts]]
+            when = "health < 80"
+            cooldown_secs = 86400
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.alerts.len(), 2);
+        assert_eq!(config.alerts[0].hysteresis, 1.0);
+        assert_eq!(config.alerts[1].cooldown_secs, 86400);
+
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let reloaded: UserConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.alerts, config.alerts);
+    }
+
+    #[test]
+    fn entries_redact_secrets() {
+        let mut config = UserConfig::default();
+        config.remote.token = "s3cret".to_string();
+        let entries = config.entries();
+        let token = entries.iter().find(|(k, _)| k == "remote.token").unwrap();
+        assert_eq!(token.1, REDACTED);
+        assert!(entries
+            .iter()
+            .any(|(k, v)| k == "history.retention_raw_days" && v == "30"));
+    }
+}
@@ -0,0 +1,609 @@
+//! Threshold alert rules evaluated against each daemon snapshot.
+//!
+//! A rule is a short condition from an `[[alerts]]` config entry, such as
+//! `battery.charge_percent < 15 while discharging` or
+//! `process "node" energy > 20 for 5m`. It fires once the condition has held
+//! for its `for` duration, then stays quiet until the value moves back past
+//! the threshold by the rule's hysteresis. Cooldowns stop a value hovering
+//! around the threshold from firing over and over.
+
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::warn;
+
+use crate::config::AlertConfig;
+use crate::daemon::protocol::{Alert, BatteryState, DataSnapshot, ProcessSnapshot};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RuleError {
+    #[error("Empty rule")]
+    Empty,
+
+    #[error("Unknown metric '{0}'")]
+    UnknownMetric(String),
+
+    #[error("Expected a quoted process name after 'process'")]
+    MissingProcessName,
+
+    #[error("Unknown process field '{0}', expected energy, cpu or memory")]
+    UnknownProcessField(String),
+
+    #[error("Expected <, <=, > or >= after the metric")]
+    MissingComparison,
+
+    #[error("Invalid threshold '{0}'")]
+    InvalidThreshold(String),
+
+    #[error("Invalid duration '{0}', expected e.g. 30s, 2m or 1h")]
+    InvalidDuration(String),
+
+    #[error("Unknown battery state '{0}', expected charging or discharging")]
+    UnknownState(String),
+
+    #[error("Unexpected '{0}'")]
+    Unexpected(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessField {
+    Energy,
+    Cpu,
+    MemoryMb,
+}
+
+impl ProcessField {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "energy" => Some(ProcessField::Energy),
+            "cpu" => Some(ProcessField::Cpu),
+            "memory" | "memory_mb" => Some(ProcessField::MemoryMb),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ProcessField::Energy => "energy",
+            ProcessField::Cpu => "cpu",
+            ProcessField::MemoryMb => "memory",
+        }
+    }
+}
+
+/// A value read from each snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metric {
+    ChargePercent,
+    TemperatureC,
+    HealthPercent,
+    TotalPowerWatts,
+    CpuPowerWatts,
+    GpuPowerWatts,
+    /// Summed over every process with this name
+    Process {
+        name: String,
+        field: ProcessField,
+    },
+}
+
+impl Metric {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "battery.charge_percent" | "charge" => Some(Metric::ChargePercent),
+            "battery.temperature_c" | "temperature" => Some(Metric::TemperatureC),
+            "battery.health_percent" | "health" => Some(Metric::HealthPercent),
+            "power.total_power_watts" | "power" => Some(Metric::TotalPowerWatts),
+            "power.cpu_power_watts" => Some(Metric::CpuPowerWatts),
+            "power.gpu_power_watts" => Some(Metric::GpuPowerWatts),
+            _ => None,
+        }
+    }
+
+    /// Current value, or `None` if the snapshot doesn't report it
+    fn value(&self, snapshot: &DataSnapshot) -> Option<f32> {
+        match self {
+            Metric::ChargePercent => Some(snapshot.battery.charge_percent),
+            Metric::TemperatureC => snapshot.battery.temperature_c,
+            Metric::HealthPercent => {
+                Some(snapshot.battery.health_percent).filter(|health| *health > 0.0)
+            }
+            Metric::TotalPowerWatts => Some(snapshot.power.total_power_watts),
+            Metric::CpuPowerWatts => Some(snapshot.power.cpu_power_watts),
+            Metric::GpuPowerWatts => Some(snapshot.power.gpu_power_watts),
+            Metric::Process { name, field } => {
+                Some(process_total(&snapshot.processes, name, *field))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::ChargePercent => write!(f, "battery.charge_percent"),
+            Metric::TemperatureC => write!(f, "battery.temperature_c"),
+            Metric::HealthPercent => write!(f, "battery.health_percent"),
+            Metric::TotalPowerWatts => write!(f, "power.total_power_watts"),
+            Metric::CpuPowerWatts => write!(f, "power.cpu_power_watts"),
+            Metric::GpuPowerWatts => write!(f, "power.gpu_power_watts"),
+            Metric::Process { name, field } => write!(f, "process \"{}\" {}", name, field.name()),
+        }
+    }
+}
+
+/// Sums `field` over processes named `name`. A matching parent already
+/// includes a share of its children's energy, so its children are skipped.
+fn process_total(processes: &[ProcessSnapshot], name: &str, field: ProcessField) -> f32 {
+    processes
+        .iter()
+        .map(|p| {
+            if p.name == name {
+                match field {
+                    ProcessField::Energy => p.energy_impact,
+                    ProcessField::Cpu => p.cpu_usage,
+                    ProcessField::MemoryMb => p.memory_mb as f32,
+                }
+            } else {
+                p.children
+                    .as_deref()
+                    .map_or(0.0, |children| process_total(children, name, field))
+            }
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Below,
+    AtMost,
+    Above,
+    AtLeast,
+}
+
+impl Comparison {
+    fn parse(op: &str) -> Option<Self> {
+        match op {
+            "<" => Some(Comparison::Below),
+            "<=" => Some(Comparison::AtMost),
+            ">" => Some(Comparison::Above),
+            ">=" => Some(Comparison::AtLeast),
+            _ => None,
+        }
+    }
+
+    fn holds(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+        }
+    }
+
+    /// Whether `value` has moved back past `threshold` by `hysteresis`
+    fn cleared(self, value: f32, threshold: f32, hysteresis: f32) -> bool {
+        let rearm_at = match self {
+            Comparison::Below | Comparison::AtMost => threshold + hysteresis,
+            Comparison::Above | Comparison::AtLeast => threshold - hysteresis,
+        };
+        !self.holds(value, rearm_at)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+        })
+    }
+}
+
+/// Battery state a rule is limited to with `while`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    Charging,
+    Discharging,
+}
+
+impl Gate {
+    fn parse(state: &str) -> Option<Self> {
+        match state {
+            "charging" => Some(Gate::Charging),
+            "discharging" => Some(Gate::Discharging),
+            _ => None,
+        }
+    }
+
+    fn is_open(self, snapshot: &DataSnapshot) -> bool {
+        match self {
+            Gate::Charging => snapshot.battery.state == BatteryState::Charging,
+            Gate::Discharging => snapshot.battery.state == BatteryState::Discharging,
+        }
+    }
+}
+
+/// A parsed condition: `<metric> <op> <threshold> [for <duration>] [while <state>]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: f32,
+    /// Seconds the condition must hold before the rule fires
+    pub hold_secs: i64,
+    pub gate: Option<Gate>,
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(spec).into_iter();
+
+        let first = tokens.next().ok_or(RuleError::Empty)?;
+        let metric = if first == "process" {
+            let name = tokens.next().ok_or(RuleError::MissingProcessName)?;
+            let field = tokens.next().unwrap_or_default();
+            Metric::Process {
+                name,
+                field: ProcessField::parse(&field).ok_or(RuleError::UnknownProcessField(field))?,
+            }
+        } else {
+            Metric::parse(&first).ok_or(RuleError::UnknownMetric(first))?
+        };
+
+        let comparison = tokens
+            .next()
+            .and_then(|op| Comparison::parse(&op))
+            .ok_or(RuleError::MissingComparison)?;
+        let threshold = tokens.next().unwrap_or_default();
+        let threshold = threshold
+            .trim_end_matches(['%', 'W'])
+            .parse()
+            .map_err(|_| RuleError::InvalidThreshold(threshold))?;
+
+        let mut hold_secs = 0;
+        let mut gate = None;
+        while let Some(word) = tokens.next() {
+            match word.as_str() {
+                "for" => {
+                    let duration = tokens.next().unwrap_or_default();
+                    hold_secs = humantime::parse_duration(&duration)
+                        .map_err(|_| RuleError::InvalidDuration(duration))?
+                        .as_secs() as i64;
+                }
+                "while" => {
+                    let state = tokens.next().unwrap_or_default();
+                    gate = Some(Gate::parse(&state).ok_or(RuleError::UnknownState(state))?);
+                }
+                _ => return Err(RuleError::Unexpected(word)),
+            }
+        }
+
+        Ok(Self {
+            metric,
+            comparison,
+            threshold,
+            hold_secs,
+            gate,
+        })
+    }
+}
+
+/// Splits a rule into words, quoted strings and comparison operators, so
+/// `health<80` and `health < 80` read the same.
+fn tokenize(spec: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            tokens.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else if c == '<' || c == '>' {
+            chars.next();
+            let mut op = c.to_string();
+            if chars.next_if_eq(&'=').is_some() {
+                op.push('=');
+            }
+            tokens.push(op);
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"\"<>".contains(*c)) {
+                word.push(c);
+            }
+            tokens.push(word);
+        }
+    }
+    tokens
+}
+
+struct RuleState {
+    name: String,
+    text: String,
+    rule: Rule,
+    hysteresis: f32,
+    cooldown_secs: i64,
+    /// When the condition started holding, while waiting out `hold_secs`
+    breached_since: Option<i64>,
+    /// Fired (or would have, but for the cooldown) and not yet cleared
+    active: bool,
+    last_fired: Option<i64>,
+}
+
+impl RuleState {
+    fn evaluate(&mut self, snapshot: &DataSnapshot) -> Option<Alert> {
+        let now = snapshot.timestamp;
+        if self.rule.gate.is_some_and(|gate| !gate.is_open(snapshot)) {
+            self.active = false;
+            self.breached_since = None;
+            return None;
+        }
+        let value = self.rule.metric.value(snapshot)?;
+        let threshold = self.rule.threshold;
+
+        if self.active {
+            if self
+                .rule
+                .comparison
+                .cleared(value, threshold, self.hysteresis)
+            {
+                self.active = false;
+            }
+            return None;
+        }
+        if !self.rule.comparison.holds(value, threshold) {
+            self.breached_since = None;
+            return None;
+        }
+        let since = *self.breached_since.get_or_insert(now);
+        if now - since < self.rule.hold_secs {
+            return None;
+        }
+
+        self.active = true;
+        self.breached_since = None;
+        if self
+            .last_fired
+            .is_some_and(|at| now - at < self.cooldown_secs)
+        {
+            return None;
+        }
+        self.last_fired = Some(now);
+
+        let reading = format!(
+            "{} is {:.1} ({} {})",
+            self.rule.metric, value, self.rule.comparison, threshold
+        );
+        Some(Alert {
+            timestamp: now,
+            message: if self.name.is_empty() {
+                reading
+            } else {
+                format!("{}: {}", self.name, reading)
+            },
+            name: if self.name.is_empty() {
+                self.text.clone()
+            } else {
+                self.name.clone()
+            },
+            rule: self.text.clone(),
+            value,
+            threshold,
+        })
+    }
+}
+
+/// Evaluates the configured alert rules against each snapshot.
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+}
+
+impl AlertEngine {
+    /// Parses the rules in `configs`, skipping any that don't parse.
+    pub fn new(configs: &[AlertConfig]) -> Self {
+        let rules = configs
+            .iter()
+            .filter_map(|config| match config.when.parse::<Rule>() {
+                Ok(rule) => Some(RuleState {
+                    name: config.name.trim().to_string(),
+                    text: config.when.trim().to_string(),
+                    rule,
+                    hysteresis: config.hysteresis.max(0.0),
+                    cooldown_secs: config.cooldown_secs as i64,
+                    breached_since: None,
+                    active: false,
+                    last_fired: None,
+                }),
+                Err(e) => {
+                    warn!(rule = %config.when, error = %e, "Ignoring invalid alert rule");
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Alerts that fire on `snapshot`.
+    pub fn evaluate(&mut self, snapshot: &DataSnapshot) -> Vec<Alert> {
+        self.rules
+            .iter_mut()
+            .filter_map(|rule| rule.evaluate(snapshot))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::protocol::ProcessState;
+
+    fn config(when: &str) -> AlertConfig {
+        AlertConfig {
+            when: when.to_string(),
+            ..AlertConfig::default()
+        }
+    }
+
+    fn snapshot(timestamp: i64, percent: f32, watts: f32) -> DataSnapshot {
+        let mut snapshot = DataSnapshot {
+            timestamp,
+            ..DataSnapshot::default()
+        };
+        snapshot.battery.charge_percent = percent;
+        snapshot.battery.state = BatteryState::Discharging;
+        snapshot.power.total_power_watts = watts;
+        snapshot
+    }
+
+    fn process(name: &str, energy_impact: f32) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid: 1000,
+            name: name.to_string(),
+            command: name.to_string(),
+            command_args: String::new(),
+            cpu_usage: energy_impact,
+            memory_mb: 100.0,
+            energy_impact,
+            parent_pid: None,
+            children: None,
+            is_killable: true,
+            disk_read_bytes: 0,
+            disk_write_bytes: 0,
+            status: ProcessState::Running,
+            run_time_secs: 0,
+            total_cpu_time_secs: 0,
+            throttled: false,
+        }
+    }
+
+    #[test]
+    fn parses_rule_forms() {
+        let rule: Rule = "battery.charge_percent < 15 while discharging"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.metric, Metric::ChargePercent);
+        assert_eq!(rule.comparison, Comparison::Below);
+        assert_eq!(rule.threshold, 15.0);
+        assert_eq!(rule.gate, Some(Gate::Discharging));
+
+        let rule: Rule = "power.total_power_watts > 35 for 2m".parse().unwrap();
+        assert_eq!(rule.hold_secs, 120);
+
+        let rule: Rule = "process \"node\" energy > 20 for 5m".parse().unwrap();
+        assert_eq!(
+            rule.metric,
+            Metric::Process {
+                name: "node".to_string(),
+                field: ProcessField::Energy
+            }
+        );
+        assert_eq!(rule.hold_secs, 300);
+
+        let rule: Rule = "health<80".parse().unwrap();
+        assert_eq!(rule.metric, Metric::HealthPercent);
+        assert_eq!(rule.threshold, 80.0);
+        assert!("battery.temperature_c >= 45".parse::<Rule>().is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        let err = |spec: &str| spec.parse::<Rule>().unwrap_err();
+        assert_eq!(err(""), RuleError::Empty);
+        assert_eq!(err("volts < 3"), RuleError::UnknownMetric("volts".into()));
+        assert_eq!(err("health 80"), RuleError::MissingComparison);
+        assert_eq!(
+            err("health < high"),
+            RuleError::InvalidThreshold("high".into())
+        );
+        assert_eq!(
+            err("power > 30 for 2x"),
+            RuleError::InvalidDuration("2x".into())
+        );
+        assert_eq!(
+            err("power > 30 while idle"),
+            RuleError::UnknownState("idle".into())
+        );
+        assert_eq!(err("power > 30 soon"), RuleError::Unexpected("soon".into()));
+    }
+
+    #[test]
+    fn fires_after_hold_and_rearms_past_hysteresis() {
+        let mut engine = AlertEngine::new(&[AlertConfig {
+            name: "Heavy load".to_string(),
+            hysteresis: 5.0,
+            cooldown_secs: 0,
+            ..config("power > 35 for 2m")
+        }]);
+
+        assert!(engine.evaluate(&snapshot(0, 50.0, 40.0)).is_empty());
+        assert!(engine.evaluate(&snapshot(60, 50.0, 40.0)).is_empty());
+        let alerts = engine.evaluate(&snapshot(120, 50.0, 40.0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "Heavy load");
+        assert_eq!(
+            alerts[0].message,
+            "Heavy load: power.total_power_watts is 40.0 (> 35)"
+        );
+
+        // Still above the threshold less the hysteresis: stays quiet
+        assert!(engine.evaluate(&snapshot(180, 50.0, 32.0)).is_empty());
+        assert!(engine.evaluate(&snapshot(240, 50.0, 40.0)).is_empty());
+        assert!(engine.evaluate(&snapshot(300, 50.0, 29.0)).is_empty());
+
+        // Re-armed, so a fresh breach fires after holding again
+        assert!(engine.evaluate(&snapshot(360, 50.0, 40.0)).is_empty());
+        assert_eq!(engine.evaluate(&snapshot(480, 50.0, 40.0)).len(), 1);
+    }
+
+    #[test]
+    fn cooldown_and_gate_suppress_alerts() {
+        let mut engine = AlertEngine::new(&[AlertConfig {
+            hysteresis: 0.0,
+            cooldown_secs: 600,
+            ..config("charge < 15 while discharging")
+        }]);
+
+        let alerts = engine.evaluate(&snapshot(0, 14.0, 5.0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "charge < 15 while discharging");
+
+        // Plugging in clears the rule, but the cooldown holds the next alert
+        let mut charging = snapshot(60, 14.0, 5.0);
+        charging.battery.state = BatteryState::Charging;
+        assert!(engine.evaluate(&charging).is_empty());
+        assert!(engine.evaluate(&snapshot(120, 14.0, 5.0)).is_empty());
+
+        let mut charging = snapshot(660, 20.0, 5.0);
+        charging.battery.state = BatteryState::Charging;
+        assert!(engine.evaluate(&charging).is_empty());
+        assert_eq!(engine.evaluate(&snapshot(720, 14.0, 5.0)).len(), 1);
+    }
+
+    #[test]
+    fn process_metric_sums_matching_processes() {
+        let mut parent = process("code", 10.0);
+        parent.children = Some(vec![process("node", 8.0), process("node", 4.0)]);
+        let mut snap = snapshot(0, 50.0, 10.0);
+        snap.processes = vec![parent, process("node", 15.0)];
+
+        let mut engine = AlertEngine::new(&[config("process \"node\" energy > 20")]);
+        let alerts = engine.evaluate(&snap);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].value, 27.0);
+    }
+
+    #[test]
+    fn skips_invalid_rules() {
+        let engine = AlertEngine::new(&[config("health < 80"), config("health is bad")]);
+        assert_eq!(engine.rule_count(), 1);
+    }
+}
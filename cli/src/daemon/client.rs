@@ -1,11 +1,14 @@
 use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
 use std::os::unix::net::UnixStream;
 use std::time::Duration;
 
 use crate::daemon::protocol::{
-    ChargeSession, CycleSummary, DaemonRequest, DaemonResponse, DaemonStatus, DailyCycle,
-    DailyStat, DailyTopProcess, DataSnapshot, HourlyStat, KillProcessResult, KillSignal, Sample,
-    MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
+    Alert, ChargeSession, ConfigEntry, CycleSummary, DaemonRequest, DaemonResponse, DaemonStatus,
+    DailyCycle, DailyStat, DailyTopProcess, DataSnapshot, DownsampleMethod, Event,
+    HourlyProcessStat, HourlyStat, KillProcessResult, KillScope, KillSignal, KillTarget,
+    ProcessAction, Sample, ThrottleSnapshot, ThrottleTarget, MIN_SUPPORTED_VERSION,
+    PROTOCOL_VERSION,
 };
 use crate::daemon::socket_path;
 
@@ -105,32 +108,148 @@ pub fn check_version_compatibility(status: &DaemonStatus) -> Result<()> {
     Ok(())
 }
 
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Which daemon a client talks to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DaemonTarget {
+    /// The local daemon on its Unix socket.
+    #[default]
+    Local,
+    /// A remote daemon's TCP listener, authenticated with a token.
+    Remote { addr: String, token: String },
+}
+
+impl DaemonTarget {
+    pub fn is_remote(&self) -> bool {
+        matches!(self, DaemonTarget::Remote { .. })
+    }
+}
+
+enum ClientStream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl ClientStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            ClientStream::Unix(s) => s.set_read_timeout(timeout),
+            ClientStream::Tcp(s) => s.set_read_timeout(timeout),
+        }
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            ClientStream::Unix(s) => s.set_write_timeout(timeout),
+            ClientStream::Tcp(s) => s.set_write_timeout(timeout),
+        }
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        match self {
+            ClientStream::Unix(s) => s.set_nonblocking(nonblocking),
+            ClientStream::Tcp(s) => s.set_nonblocking(nonblocking),
+        }
+    }
+}
+
+impl Read for ClientStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ClientStream::Unix(s) => s.read(buf),
+            ClientStream::Tcp(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for ClientStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ClientStream::Unix(s) => s.write(buf),
+            ClientStream::Tcp(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ClientStream::Unix(s) => s.flush(),
+            ClientStream::Tcp(s) => s.flush(),
+        }
+    }
+}
+
 pub struct DaemonClient {
-    stream: UnixStream,
+    stream: ClientStream,
     read_buffer: Vec<u8>,
+    /// Alerts pushed to a subscription, kept until `take_alerts`
+    alerts: Vec<Alert>,
 }
 
 impl DaemonClient {
     pub fn connect() -> Result<Self> {
         let path = socket_path();
         let stream = UnixStream::connect(&path)?;
-        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
-        stream.set_write_timeout(Some(Duration::from_secs(5)))?;
-        Ok(Self {
-            stream,
-            read_buffer: Vec::with_capacity(64 * 1024),
-        })
+        Self::with_stream(ClientStream::Unix(stream))
+    }
+
+    /// Connects to a remote daemon's TCP listener and authenticates with `token`.
+    pub fn connect_remote(addr: &str, token: &str) -> Result<Self> {
+        let mut last_err = None;
+        let mut stream = None;
+        for socket_addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&socket_addr, IO_TIMEOUT) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        let stream = match (stream, last_err) {
+            (Some(s), _) => s,
+            (None, Some(e)) => return Err(e.into()),
+            (None, None) => {
+                return Err(ClientError::Protocol(format!(
+                    "Could not resolve address: {}",
+                    addr
+                )))
+            }
+        };
+        stream.set_nodelay(true)?;
+
+        let mut client = Self::with_stream(ClientStream::Tcp(stream))?;
+        client.authenticate(token)?;
+        Ok(client)
+    }
+
+    /// Connects to the daemon described by `target`.
+    pub fn connect_to(target: &DaemonTarget) -> Result<Self> {
+        match target {
+            DaemonTarget::Local => Self::connect(),
+            DaemonTarget::Remote { addr, token } => Self::connect_remote(addr, token),
+        }
     }
 
     /// Connects to the daemon and validates protocol version compatibility.
     /// This is the preferred connection method for the TUI.
-    pub fn connect_with_version_check() -> Result<Self> {
-        let mut client = Self::connect()?;
+    pub fn connect_with_version_check(target: &DaemonTarget) -> Result<Self> {
+        let mut client = Self::connect_to(target)?;
         let status = client.get_status()?;
         check_version_compatibility(&status)?;
         Ok(client)
     }
 
+    fn with_stream(stream: ClientStream) -> Result<Self> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(Self {
+            stream,
+            read_buffer: Vec::with_capacity(64 * 1024),
+            alerts: Vec::new(),
+        })
+    }
+
     fn read_line_blocking(&mut self) -> Result<String> {
         let mut temp_buf = [0u8; 8192];
         loop {
@@ -206,6 +325,14 @@ impl DaemonClient {
         }
     }
 
+    pub fn get_current_data(&mut self) -> Result<DataSnapshot> {
+        match self.send_request(DaemonRequest::GetCurrentData)? {
+            DaemonResponse::CurrentData(snapshot) => Ok(snapshot),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
     pub fn get_hourly_stats(&mut self, from: i64, to: i64) -> Result<Vec<HourlyStat>> {
         match self.send_request(DaemonRequest::GetHourlyStats { from, to })? {
             DaemonResponse::HourlyStats(stats) => Ok(stats),
@@ -242,6 +369,16 @@ impl DaemonClient {
         }
     }
 
+    pub fn authenticate(&mut self, token: &str) -> Result<()> {
+        match self.send_request(DaemonRequest::Authenticate {
+            token: token.to_string(),
+        })? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
     pub fn shutdown(&mut self) -> Result<()> {
         match self.send_request(DaemonRequest::Shutdown)? {
             DaemonResponse::Ok => Ok(()),
@@ -250,6 +387,42 @@ impl DaemonClient {
         }
     }
 
+    pub fn get_process_history(
+        &mut self,
+        name: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<HourlyProcessStat>> {
+        match self.send_request(DaemonRequest::GetProcessHistory {
+            name: name.to_string(),
+            from,
+            to,
+        })? {
+            DaemonResponse::ProcessHistory(stats) => Ok(stats),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn add_annotation(&mut self, text: &str, timestamp: Option<i64>) -> Result<()> {
+        match self.send_request(DaemonRequest::AddAnnotation {
+            text: text.to_string(),
+            timestamp,
+        })? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn get_events(&mut self, from: i64, to: i64) -> Result<Vec<Event>> {
+        match self.send_request(DaemonRequest::GetEvents { from, to })? {
+            DaemonResponse::Events(events) => Ok(events),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
     pub fn get_recent_samples(&mut self, window_secs: u64) -> Result<Vec<Sample>> {
         match self.send_request(DaemonRequest::GetRecentSamples { window_secs })? {
             DaemonResponse::RecentSamples(samples) => Ok(samples),
@@ -258,8 +431,52 @@ impl DaemonClient {
         }
     }
 
-    pub fn kill_process(&mut self, pid: u32, signal: KillSignal) -> Result<KillProcessResult> {
-        match self.send_request(DaemonRequest::KillProcess { pid, signal })? {
+    pub fn get_samples(
+        &mut self,
+        from: i64,
+        to: i64,
+        max_points: usize,
+        method: DownsampleMethod,
+    ) -> Result<Vec<Sample>> {
+        match self.send_request(DaemonRequest::GetSamples {
+            from,
+            to,
+            max_points,
+            method,
+        })? {
+            DaemonResponse::Samples(samples) => Ok(samples),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Signals `pid`, or every process in `scope`, returning a result per
+    /// process signalled.
+    pub fn kill_process(
+        &mut self,
+        pid: u32,
+        signal: KillSignal,
+        scope: KillScope,
+    ) -> Result<Vec<KillProcessResult>> {
+        match self.send_request(DaemonRequest::KillProcess { pid, signal, scope })? {
+            DaemonResponse::KillResults(results) => Ok(results),
+            // Daemons before protocol version 3 only kill the one process
+            DaemonResponse::KillResult(result) => Ok(vec![result]),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn get_kill_targets(&mut self, pid: u32, scope: KillScope) -> Result<Vec<KillTarget>> {
+        match self.send_request(DaemonRequest::GetKillTargets { pid, scope })? {
+            DaemonResponse::KillTargets(targets) => Ok(targets),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn process_action(&mut self, pid: u32, action: ProcessAction) -> Result<KillProcessResult> {
+        match self.send_request(DaemonRequest::ProcessAction { pid, action })? {
             DaemonResponse::KillResult(result) => Ok(result),
             DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
             _ => Err(ClientError::Protocol("Unexpected response".into())),
@@ -277,6 +494,66 @@ impl DaemonClient {
         }
     }
 
+    pub fn reload_config(&mut self) -> Result<()> {
+        match self.send_request(DaemonRequest::ReloadConfig)? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn undo_battery_saver(&mut self) -> Result<()> {
+        match self.send_request(DaemonRequest::UndoBatterySaver)? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn throttle(
+        &mut self,
+        target: ThrottleTarget,
+        cpu_percent: u32,
+        battery_only: bool,
+    ) -> Result<ThrottleSnapshot> {
+        match self.send_request(DaemonRequest::Throttle {
+            target,
+            cpu_percent,
+            battery_only,
+        })? {
+            DaemonResponse::Throttle(throttle) => Ok(throttle),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn remove_throttle(&mut self, target: ThrottleTarget) -> Result<()> {
+        match self.send_request(DaemonRequest::RemoveThrottle { target })? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn get_config(&mut self) -> Result<Vec<ConfigEntry>> {
+        match self.send_request(DaemonRequest::GetConfig)? {
+            DaemonResponse::Config(entries) => Ok(entries),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    pub fn set_config(&mut self, key: &str, value: &str) -> Result<()> {
+        match self.send_request(DaemonRequest::SetConfig {
+            key: key.to_string(),
+            value: value.to_string(),
+        })? {
+            DaemonResponse::Ok => Ok(()),
+            DaemonResponse::Error(e) => Err(ClientError::Daemon(e)),
+            _ => Err(ClientError::Protocol("Unexpected response".into())),
+        }
+    }
+
     pub fn set_broadcast_interval(&mut self, interval_ms: u64) -> Result<()> {
         match self.send_request(DaemonRequest::SetBroadcastInterval { interval_ms })? {
             DaemonResponse::Ok => Ok(()),
@@ -355,6 +632,7 @@ impl DaemonClient {
                         DaemonResponse::DataUpdate(snapshot) => {
                             latest_snapshot = Some(snapshot);
                         }
+                        DaemonResponse::Alert(alert) => self.alerts.push(alert),
                         DaemonResponse::Error(e) => return Err(ClientError::Daemon(e)),
                         _ => {}
                     }
@@ -373,6 +651,11 @@ impl DaemonClient {
         Ok(latest_snapshot)
     }
 
+    /// Alerts received by `read_update` since the last call.
+    pub fn take_alerts(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.alerts)
+    }
+
     pub fn set_nonblocking(&mut self, nonblocking: bool) -> Result<()> {
         self.stream.set_nonblocking(nonblocking)?;
         if nonblocking {
@@ -0,0 +1,560 @@
+//! Prometheus / OpenMetrics exporter for the daemon.
+//!
+//! Serves the most recent `DataSnapshot` as text exposition format on
+//! `GET /metrics`. The listener only binds to localhost and is disabled
+//! unless `[metrics] enabled = true` is set in the config.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::config::MetricsConfig;
+use crate::daemon::protocol::{BatteryState, DaemonStatus, DataSnapshot};
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Binds the metrics listener if enabled in config.
+///
+/// Bind failures are logged and treated as "disabled" so a port conflict
+/// never prevents the daemon from starting.
+pub async fn bind(config: &MetricsConfig) -> Option<TcpListener> {
+    if !config.enabled {
+        return None;
+    }
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], config.port));
+    match TcpListener::bind(addr).await {
+        Ok(listener) => {
+            info!(%addr, "Serving Prometheus metrics");
+            Some(listener)
+        }
+        Err(e) => {
+            warn!(%addr, error = %e, "Failed to bind metrics listener, exporter disabled");
+            None
+        }
+    }
+}
+
+/// Accepts a connection from an optional listener, pending forever when the
+/// exporter is disabled so it can sit in the daemon's `select!` loop.
+pub async fn accept(listener: Option<&TcpListener>) -> std::io::Result<(TcpStream, SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Daemon-level values exported alongside the snapshot.
+pub struct DaemonMetrics {
+    pub status: DaemonStatus,
+    pub client_count: usize,
+}
+
+/// Reads a single HTTP request and answers it with the rendered metrics.
+pub async fn serve_connection(
+    mut stream: TcpStream,
+    snapshot: Option<DataSnapshot>,
+    daemon: DaemonMetrics,
+    top_processes: usize,
+) {
+    let head = match tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut stream)).await {
+        Ok(Ok(head)) => head,
+        Ok(Err(e)) => {
+            debug!(error = %e, "Failed to read metrics request");
+            return;
+        }
+        Err(_) => {
+            debug!("Metrics request timed out");
+            return;
+        }
+    };
+
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let openmetrics = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("accept")
+                && value.contains("application/openmetrics-text")
+        })
+    });
+
+    let response = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => {
+            let format = if openmetrics {
+                Format::OpenMetrics
+            } else {
+                Format::Prometheus
+            };
+            let body = render(snapshot.as_ref(), &daemon, top_processes, format);
+            let content_type = match format {
+                Format::Prometheus => PROMETHEUS_CONTENT_TYPE,
+                Format::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+            };
+            http_response("200 OK", content_type, &body, method == "HEAD")
+        }
+        ("GET" | "HEAD", _) => http_response(
+            "404 Not Found",
+            "text/plain",
+            "Not found\n",
+            method == "HEAD",
+        ),
+        _ => http_response(
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n",
+            false,
+        ),
+    };
+
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        debug!(error = %e, "Failed to write metrics response");
+        return;
+    }
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.windows(4).any(|w| w == b"\r\n\r\n") || buf.len() >= MAX_REQUEST_BYTES {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).to_string())
+}
+
+fn http_response(status: &str, content_type: &str, body: &str, head_only: bool) -> String {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    if !head_only {
+        response.push_str(body);
+    }
+    response
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Prometheus,
+    OpenMetrics,
+}
+
+/// Renders the snapshot and daemon state in text exposition format.
+pub fn render(
+    snapshot: Option<&DataSnapshot>,
+    daemon: &DaemonMetrics,
+    top_processes: usize,
+    format: Format,
+) -> String {
+    let mut out = MetricsWriter::default();
+
+    if let Some(snapshot) = snapshot {
+        let battery = &snapshot.battery;
+        out.gauge(
+            "jolt_battery_charge_percent",
+            "Battery state of charge in percent",
+            battery.charge_percent as f64,
+        );
+        out.gauge(
+            "jolt_battery_health_percent",
+            "Battery health relative to design capacity in percent",
+            battery.health_percent as f64,
+        );
+        if let Some(cycles) = battery.cycle_count {
+            out.gauge(
+                "jolt_battery_cycle_count",
+                "Battery charge cycle count reported by the OS",
+                cycles as f64,
+            );
+        }
+        if let Some(temp) = battery.temperature_c {
+            out.gauge(
+                "jolt_battery_temperature_celsius",
+                "Battery temperature in degrees Celsius",
+                temp as f64,
+            );
+        }
+        out.gauge(
+            "jolt_battery_voltage_volts",
+            "Battery voltage in volts",
+            battery.voltage_mv as f64 / 1000.0,
+        );
+        out.gauge(
+            "jolt_battery_current_amperes",
+            "Battery current in amperes (negative while discharging)",
+            battery.amperage_ma as f64 / 1000.0,
+        );
+        out.gauge(
+            "jolt_battery_max_capacity_watt_hours",
+            "Current full charge capacity in watt-hours",
+            battery.max_capacity_wh as f64,
+        );
+        out.gauge(
+            "jolt_battery_design_capacity_watt_hours",
+            "Design capacity in watt-hours",
+            battery.design_capacity_wh as f64,
+        );
+        out.gauge(
+            "jolt_battery_external_connected",
+            "Whether external power is connected (1) or not (0)",
+            if battery.external_connected { 1.0 } else { 0.0 },
+        );
+
+        out.header(
+            "jolt_battery_state",
+            "Current battery state (1 for the active state)",
+        );
+        for state in [
+            BatteryState::Charging,
+            BatteryState::Discharging,
+            BatteryState::Full,
+            BatteryState::NotCharging,
+            BatteryState::Unknown,
+        ] {
+            let value = if state == battery.state { 1.0 } else { 0.0 };
+            out.sample(
+                "jolt_battery_state",
+                &[("state", battery_state_label(state))],
+                value,
+            );
+        }
+
+        let power = &snapshot.power;
+        out.gauge(
+            "jolt_power_cpu_watts",
+            "CPU power draw in watts",
+            power.cpu_power_watts as f64,
+        );
+        out.gauge(
+            "jolt_power_gpu_watts",
+            "GPU power draw in watts",
+            power.gpu_power_watts as f64,
+        );
+        out.gauge(
+            "jolt_power_system_watts",
+            "Total system power draw in watts",
+            power.total_power_watts as f64,
+        );
+
+        if let Some(secs) = snapshot.forecast.duration_secs {
+            out.gauge(
+                "jolt_forecast_seconds",
+                "Forecast battery runtime remaining in seconds",
+                secs as f64,
+            );
+        }
+        if let Some(secs) = snapshot.forecast.p10_secs {
+            out.gauge(
+                "jolt_forecast_p10_seconds",
+                "Pessimistic (P10) battery runtime remaining in seconds",
+                secs as f64,
+            );
+        }
+        if let Some(secs) = snapshot.forecast.p90_secs {
+            out.gauge(
+                "jolt_forecast_p90_seconds",
+                "Optimistic (P90) battery runtime remaining in seconds",
+                secs as f64,
+            );
+        }
+        if let Some(watts) = snapshot.forecast.avg_power_watts {
+            out.gauge(
+                "jolt_forecast_avg_power_watts",
+                "Average power draw used for the forecast in watts",
+                watts as f64,
+            );
+        }
+        if let Some(charge) = &snapshot.forecast.charge {
+            if let Some(secs) = charge.to_limit_secs {
+                out.gauge(
+                    "jolt_charge_forecast_limit_seconds",
+                    "Forecast time until the charge limit is reached in seconds",
+                    secs as f64,
+                );
+            }
+            if let Some(secs) = charge.to_full_secs {
+                out.gauge(
+                    "jolt_charge_forecast_full_seconds",
+                    "Forecast time until fully charged in seconds",
+                    secs as f64,
+                );
+            }
+        }
+
+        let top = top_processes_by_name(snapshot, top_processes);
+        if !top.is_empty() {
+            out.header(
+                "jolt_process_energy_impact",
+                "Estimated energy impact of the top processes, summed by name",
+            );
+            for (name, impact) in top {
+                out.sample(
+                    "jolt_process_energy_impact",
+                    &[("name", &name)],
+                    impact as f64,
+                );
+            }
+        }
+
+        out.gauge(
+            "jolt_snapshot_timestamp_seconds",
+            "Unix time the exported snapshot was taken",
+            snapshot.timestamp as f64,
+        );
+    }
+
+    let status = &daemon.status;
+    out.header("jolt_daemon_info", "Daemon build information");
+    out.sample(
+        "jolt_daemon_info",
+        &[
+            ("version", &status.version),
+            ("protocol_version", &status.protocol_version.to_string()),
+        ],
+        1.0,
+    );
+    out.gauge(
+        "jolt_daemon_uptime_seconds",
+        "Seconds since the daemon started",
+        status.uptime_secs as f64,
+    );
+    out.gauge(
+        "jolt_daemon_clients",
+        "Connected socket clients",
+        daemon.client_count as f64,
+    );
+    out.gauge(
+        "jolt_daemon_subscribers",
+        "Clients subscribed to live updates",
+        status.subscriber_count as f64,
+    );
+    out.gauge(
+        "jolt_daemon_history_enabled",
+        "Whether background history recording is enabled",
+        if status.history_enabled { 1.0 } else { 0.0 },
+    );
+    out.gauge(
+        "jolt_daemon_history_samples",
+        "Raw samples stored in the history database",
+        status.sample_count as f64,
+    );
+    out.gauge(
+        "jolt_daemon_database_size_bytes",
+        "History database size in bytes",
+        status.database_size_bytes as f64,
+    );
+
+    let mut body = out.finish();
+    if format == Format::OpenMetrics {
+        body.push_str("# EOF\n");
+    }
+    body
+}
+
+fn battery_state_label(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Charging => "charging",
+        BatteryState::Discharging => "discharging",
+        BatteryState::Full => "full",
+        BatteryState::NotCharging => "not_charging",
+        BatteryState::Unknown => "unknown",
+    }
+}
+
+/// Sums energy impact by process name and keeps the `limit` highest.
+///
+/// Aggregating by name keeps label cardinality bounded and avoids duplicate
+/// series when several processes share a name.
+fn top_processes_by_name(snapshot: &DataSnapshot, limit: usize) -> Vec<(String, f32)> {
+    let mut by_name: HashMap<&str, f32> = HashMap::new();
+    for process in &snapshot.processes {
+        *by_name.entry(process.name.as_str()).or_default() += process.energy_impact;
+    }
+
+    let mut sorted: Vec<(String, f32)> = by_name
+        .into_iter()
+        .map(|(name, impact)| (name.to_string(), impact))
+        .collect();
+    sorted.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    sorted.truncate(limit);
+    sorted
+}
+
+#[derive(Default)]
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn header(&mut self, name: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} gauge");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, val)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{key}=\"{}\"", escape_label_value(val));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, help);
+        self.sample(name, &[], value);
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::protocol::ProcessSnapshot;
+
+    fn make_process(pid: u32, name: &str, energy_impact: f32) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            name: name.to_string(),
+            command: name.to_string(),
+            command_args: String::new(),
+            cpu_usage: 0.0,
+            memory_mb: 0.0,
+            energy_impact,
+            parent_pid: None,
+            children: None,
+            is_killable: true,
+            disk_read_bytes: 0,
+            disk_write_bytes: 0,
+            status: Default::default(),
+            run_time_secs: 0,
+            total_cpu_time_secs: 0,
+            throttled: false,
+        }
+    }
+
+    fn make_daemon() -> DaemonMetrics {
+        DaemonMetrics {
+            status: DaemonStatus {
+                running: true,
+                version: "1.2.0".to_string(),
+                ..Default::default()
+            },
+            client_count: 2,
+        }
+    }
+
+    #[test]
+    fn test_render_without_snapshot_only_has_daemon_metrics() {
+        let body = render(None, &make_daemon(), 10, Format::Prometheus);
+        assert!(body.contains("jolt_daemon_clients 2\n"));
+        assert!(body.contains("jolt_daemon_info{version=\"1.2.0\""));
+        assert!(!body.contains("jolt_battery_charge_percent"));
+        assert!(!body.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_render_snapshot_metrics() {
+        let mut snapshot = DataSnapshot::default();
+        snapshot.battery.charge_percent = 80.0;
+        snapshot.battery.voltage_mv = 12500;
+        snapshot.battery.state = BatteryState::Discharging;
+        snapshot.battery.temperature_c = None;
+        snapshot.power.total_power_watts = 12.5;
+        snapshot.forecast.duration_secs = Some(3600);
+
+        let body = render(Some(&snapshot), &make_daemon(), 10, Format::Prometheus);
+        assert!(body.contains("jolt_battery_charge_percent 80\n"));
+        assert!(body.contains("jolt_battery_voltage_volts 12.5\n"));
+        assert!(body.contains("jolt_power_system_watts 12.5\n"));
+        assert!(body.contains("jolt_forecast_seconds 3600\n"));
+        assert!(body.contains("jolt_battery_state{state=\"discharging\"} 1\n"));
+        assert!(body.contains("jolt_battery_state{state=\"charging\"} 0\n"));
+        assert!(!body.contains("jolt_battery_temperature_celsius"));
+    }
+
+    #[test]
+    fn test_render_openmetrics_terminates_with_eof() {
+        let body = render(None, &make_daemon(), 10, Format::OpenMetrics);
+        assert!(body.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_top_processes_summed_by_name_and_limited() {
+        let snapshot = DataSnapshot {
+            processes: vec![
+                make_process(1, "node", 5.0),
+                make_process(2, "chrome", 8.0),
+                make_process(3, "node", 4.0),
+                make_process(4, "zsh", 0.5),
+            ],
+            ..Default::default()
+        };
+
+        let top = top_processes_by_name(&snapshot, 2);
+        assert_eq!(
+            top,
+            vec![("node".to_string(), 9.0), ("chrome".to_string(), 8.0)]
+        );
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,16 +1,27 @@
+mod alerts;
 mod client;
+mod metrics;
+mod mqtt;
+mod notify;
+mod peer;
 mod protocol;
+mod remote;
+mod saver;
 mod server;
 pub mod service;
+mod throttle;
 
-pub use client::{ClientError, DaemonClient};
+pub use client::{ClientError, DaemonClient, DaemonTarget};
 #[allow(unused_imports)]
 pub use jolt_protocol::{
-    BatterySnapshot, BatteryState, ChargeSession, ChargingState, CycleSummary, DaemonRequest,
+    Alert, BatterySaverSnapshot, BatterySnapshot, BatteryState, ChargeForecastSnapshot,
+    ChargeForecastSource, ChargeSession, ChargingState, ConfigEntry, CycleSummary, DaemonRequest,
     DaemonResponse, DaemonStatus, DailyCycle, DailyStat, DailyTopProcess, DataSnapshot,
-    ForecastSnapshot, ForecastSource, HourlyStat, KillProcessResult, KillSignal, PowerMode,
-    PowerSnapshot, ProcessSnapshot, ProcessState, Sample, SessionType, SystemSnapshot,
-    SystemStatsSnapshot, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
+    DownsampleMethod, Event, EventKind, ForecastEstimator, ForecastModel, ForecastSnapshot,
+    ForecastSource, HourlyProcessStat, HourlyStat, IoClass, KillProcessResult, KillScope,
+    KillSignal, KillTarget, PowerMode, PowerSnapshot, ProcessAction, ProcessSnapshot, ProcessState,
+    Sample, SessionType, StoppedProcess, SystemSnapshot, SystemStatsSnapshot, ThrottleSnapshot,
+    ThrottleTarget, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
 };
 pub use server::run_daemon;
 #[allow(unused_imports)]
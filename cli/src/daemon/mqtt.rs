@@ -0,0 +1,594 @@
+//! MQTT publisher with Home Assistant discovery.
+//!
+//! Publishes the daemon's snapshots to a broker as a JSON state message and
+//! announces each sensor through retained discovery configs, so the machine
+//! shows up as a device in Home Assistant without any manual YAML.
+
+use std::time::Duration;
+
+use rumqttc::{
+    AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS, TlsConfiguration, Transport,
+};
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use crate::config::MqttConfig;
+use crate::daemon::protocol::{BatteryState, DataSnapshot};
+
+const CLIENT_CAPACITY: usize = 32;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+const ONLINE: &str = "online";
+const OFFLINE: &str = "offline";
+
+/// A sensor announced through Home Assistant discovery.
+struct SensorSpec {
+    key: &'static str,
+    name: &'static str,
+    device_class: Option<&'static str>,
+    unit: Option<&'static str>,
+    state_class: Option<&'static str>,
+    icon: Option<&'static str>,
+}
+
+const SENSORS: &[SensorSpec] = &[
+    SensorSpec {
+        key: "charge_percent",
+        name: "Battery",
+        device_class: Some("battery"),
+        unit: Some("%"),
+        state_class: Some("measurement"),
+        icon: None,
+    },
+    SensorSpec {
+        key: "state",
+        name: "Battery State",
+        device_class: None,
+        unit: None,
+        state_class: None,
+        icon: Some("mdi:battery-charging"),
+    },
+    SensorSpec {
+        key: "power_watts",
+        name: "Power",
+        device_class: Some("power"),
+        unit: Some("W"),
+        state_class: Some("measurement"),
+        icon: None,
+    },
+    SensorSpec {
+        key: "health_percent",
+        name: "Battery Health",
+        device_class: None,
+        unit: Some("%"),
+        state_class: Some("measurement"),
+        icon: Some("mdi:battery-heart-variant"),
+    },
+    SensorSpec {
+        key: "time_remaining_mins",
+        name: "Time Remaining",
+        device_class: Some("duration"),
+        unit: Some("min"),
+        state_class: Some("measurement"),
+        icon: None,
+    },
+];
+
+/// Topic layout and device identity shared by discovery and state messages.
+#[derive(Debug, Clone)]
+pub struct MqttTopics {
+    device_id: String,
+    device_name: String,
+    topic_prefix: String,
+    discovery_prefix: String,
+}
+
+impl MqttTopics {
+    pub fn new(config: &MqttConfig, device_name: &str) -> Self {
+        Self {
+            device_id: sanitize_id(device_name),
+            device_name: device_name.to_string(),
+            topic_prefix: config.topic_prefix.trim_end_matches('/').to_string(),
+            discovery_prefix: config.discovery_prefix.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn state(&self) -> String {
+        format!("{}/{}/state", self.topic_prefix, self.device_id)
+    }
+
+    pub fn availability(&self) -> String {
+        format!("{}/{}/availability", self.topic_prefix, self.device_id)
+    }
+
+    fn discovery(&self, key: &str) -> String {
+        format!(
+            "{}/sensor/{}/{}/config",
+            self.discovery_prefix, self.device_id, key
+        )
+    }
+
+    /// Builds the retained discovery messages, one per sensor.
+    pub fn discovery_messages(&self, model: &str) -> Vec<(String, Value)> {
+        let device = json!({
+            "identifiers": [format!("jolt_{}", self.device_id)],
+            "name": self.device_name,
+            "manufacturer": "jolt",
+            "model": model,
+            "sw_version": env!("CARGO_PKG_VERSION"),
+        });
+
+        SENSORS
+            .iter()
+            .map(|sensor| {
+                let mut config = json!({
+                    "name": sensor.name,
+                    "unique_id": format!("jolt_{}_{}", self.device_id, sensor.key),
+                    "object_id": format!("jolt_{}_{}", self.device_id, sensor.key),
+                    "state_topic": self.state(),
+                    "value_template": format!("{{{{ value_json.{} }}}}", sensor.key),
+                    "availability_topic": self.availability(),
+                    "device": device,
+                });
+                let obj = config.as_object_mut().expect("config is an object");
+                if let Some(class) = sensor.device_class {
+                    obj.insert("device_class".into(), class.into());
+                }
+                if let Some(unit) = sensor.unit {
+                    obj.insert("unit_of_measurement".into(), unit.into());
+                }
+                if let Some(state_class) = sensor.state_class {
+                    obj.insert("state_class".into(), state_class.into());
+                }
+                if let Some(icon) = sensor.icon {
+                    obj.insert("icon".into(), icon.into());
+                }
+                (self.discovery(sensor.key), config)
+            })
+            .collect()
+    }
+}
+
+/// Builds the JSON state payload referenced by the discovery value templates.
+pub fn state_payload(snapshot: &DataSnapshot) -> Value {
+    let battery = &snapshot.battery;
+    let time_remaining_mins = snapshot
+        .forecast
+        .duration_secs
+        .map(|secs| secs / 60)
+        .or(battery.time_remaining_mins);
+
+    json!({
+        "timestamp": snapshot.timestamp,
+        "charge_percent": round1(battery.charge_percent),
+        "state": battery_state_name(battery.state),
+        "power_watts": round1(snapshot.power.total_power_watts),
+        "health_percent": round1(battery.health_percent),
+        "time_remaining_mins": time_remaining_mins,
+        "external_connected": battery.external_connected,
+        "temperature_c": battery.temperature_c.map(round1),
+        "cycle_count": battery.cycle_count,
+    })
+}
+
+fn round1(value: f32) -> f64 {
+    (value as f64 * 10.0).round() / 10.0
+}
+
+fn battery_state_name(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Charging => "charging",
+        BatteryState::Discharging => "discharging",
+        BatteryState::Full => "full",
+        BatteryState::NotCharging => "not_charging",
+        BatteryState::Unknown => "unknown",
+    }
+}
+
+/// Lowercases and replaces anything outside `[a-z0-9_-]` so the id is safe
+/// in both MQTT topics and Home Assistant entity ids.
+fn sanitize_id(name: &str) -> String {
+    let id: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.is_empty() {
+        "jolt".to_string()
+    } else {
+        id
+    }
+}
+
+/// Daemon-side MQTT publisher.
+///
+/// The broker connection lives in a background task; the daemon loop only
+/// hands over snapshots via [`MqttPublisher::publish`].
+pub struct MqttPublisher {
+    client: AsyncClient,
+    topics: MqttTopics,
+    interval: Duration,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MqttPublisher {
+    /// Creates the publisher and spawns its connection task on the current
+    /// `LocalSet`. Returns `None` when MQTT is disabled.
+    pub fn spawn(config: &MqttConfig, model: &str) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        let device_name = sysinfo::System::host_name().unwrap_or_else(|| "jolt".to_string());
+        let topics = MqttTopics::new(config, &device_name);
+
+        let client_id = format!("jolt-{}", topics.device_id);
+        let mut options = MqttOptions::new(client_id, config.host.clone(), config.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        options.set_last_will(LastWill::new(
+            topics.availability(),
+            OFFLINE,
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Some(username) = &config.username {
+            options.set_credentials(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            );
+        }
+        if config.tls {
+            match tls_configuration(config) {
+                Ok(tls) => {
+                    options.set_transport(Transport::Tls(tls));
+                }
+                Err(e) => {
+                    warn!(error = %e, "Failed to read MQTT CA certificate, MQTT disabled");
+                    return None;
+                }
+            }
+        }
+
+        let (client, eventloop) = AsyncClient::new(options, CLIENT_CAPACITY);
+        info!(
+            host = %config.host,
+            port = config.port,
+            tls = config.tls,
+            state_topic = %topics.state(),
+            "MQTT publisher enabled"
+        );
+
+        let task = tokio::task::spawn_local(connection_task(
+            eventloop,
+            client.clone(),
+            topics.clone(),
+            model.to_string(),
+            Duration::from_secs(config.reconnect_secs.max(1)),
+        ));
+
+        Some(Self {
+            client,
+            topics,
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+            task,
+        })
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn publish(&self, snapshot: &DataSnapshot) {
+        let payload = state_payload(snapshot).to_string();
+        if let Err(e) =
+            self.client
+                .try_publish(self.topics.state(), QoS::AtMostOnce, false, payload)
+        {
+            debug!(error = %e, "Failed to queue MQTT state message");
+        }
+    }
+
+    /// Marks the device offline and disconnects, giving the connection task
+    /// a moment to flush. A clean disconnect suppresses the last will, so the
+    /// offline message has to be sent explicitly.
+    pub async fn shutdown(self) {
+        let _ =
+            self.client
+                .try_publish(self.topics.availability(), QoS::AtLeastOnce, true, OFFLINE);
+        let _ = self.client.try_disconnect();
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, self.task)
+            .await
+            .is_err()
+        {
+            debug!("Timed out flushing MQTT messages on shutdown");
+        }
+    }
+}
+
+/// Trusts `ca_file` when it is set and the system roots otherwise.
+fn tls_configuration(config: &MqttConfig) -> std::io::Result<TlsConfiguration> {
+    match &config.ca_file {
+        Some(path) => Ok(TlsConfiguration::Simple {
+            ca: std::fs::read(path)?,
+            alpn: None,
+            client_auth: None,
+        }),
+        None => Ok(TlsConfiguration::default()),
+    }
+}
+
+/// Drives the MQTT event loop, re-announcing discovery on every (re)connect
+/// so Home Assistant picks the device back up after a broker restart.
+async fn connection_task(
+    mut eventloop: EventLoop,
+    client: AsyncClient,
+    topics: MqttTopics,
+    model: String,
+    reconnect_delay: Duration,
+) {
+    loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Connected to MQTT broker");
+                for (topic, config) in topics.discovery_messages(&model) {
+                    if let Err(e) =
+                        client.try_publish(topic, QoS::AtLeastOnce, true, config.to_string())
+                    {
+                        warn!(error = %e, "Failed to queue MQTT discovery message");
+                    }
+                }
+                let _ = client.try_publish(topics.availability(), QoS::AtLeastOnce, true, ONLINE);
+            }
+            Ok(Event::Outgoing(rumqttc::Outgoing::Disconnect)) => {
+                debug!("MQTT disconnect sent");
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!(error = %e, "MQTT connection error, retrying");
+                tokio::time::sleep(reconnect_delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::BytesMut;
+    use rumqttc::{ConnAck, ConnectReturnCode, PubAck, Publish};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const MAX_PACKET: usize = 64 * 1024;
+
+    /// One client connection to the in-process broker below. Acks whatever
+    /// needs acking and hands everything else to the test.
+    struct BrokerConnection {
+        stream: TcpStream,
+        buffer: BytesMut,
+        connect: rumqttc::Connect,
+    }
+
+    impl BrokerConnection {
+        async fn accept(listener: &TcpListener) -> Self {
+            let (stream, _) = tokio::time::timeout(Duration::from_secs(10), listener.accept())
+                .await
+                .expect("client never connected")
+                .unwrap();
+            let mut conn = Self {
+                stream,
+                buffer: BytesMut::new(),
+                connect: rumqttc::Connect::new(""),
+            };
+            let Packet::Connect(connect) = conn.read().await else {
+                panic!("expected CONNECT first");
+            };
+            conn.connect = connect;
+            conn.write(Packet::ConnAck(ConnAck::new(
+                ConnectReturnCode::Success,
+                false,
+            )))
+            .await;
+            conn
+        }
+
+        async fn read(&mut self) -> Packet {
+            self.try_read().await.expect("client closed the connection")
+        }
+
+        /// Like `read`, but `None` once the client has hung up
+        async fn try_read(&mut self) -> Option<Packet> {
+            loop {
+                match Packet::read(&mut self.buffer, MAX_PACKET) {
+                    Ok(Packet::Publish(publish)) => {
+                        if publish.qos == QoS::AtLeastOnce {
+                            self.write(Packet::PubAck(PubAck::new(publish.pkid))).await;
+                        }
+                        return Some(Packet::Publish(publish));
+                    }
+                    Ok(Packet::PingReq) => self.write(Packet::PingResp).await,
+                    Ok(packet) => return Some(packet),
+                    Err(rumqttc::Error::InsufficientBytes(_)) => {
+                        let read = tokio::time::timeout(
+                            Duration::from_secs(10),
+                            self.stream.read_buf(&mut self.buffer),
+                        )
+                        .await
+                        .expect("client went quiet");
+                        // A reset counts as hanging up: the client may close
+                        // before reading our last ack
+                        if matches!(read, Ok(0) | Err(_)) {
+                            return None;
+                        }
+                    }
+                    Err(e) => panic!("malformed packet: {e}"),
+                }
+            }
+        }
+
+        async fn write(&mut self, packet: Packet) {
+            let mut out = BytesMut::new();
+            packet.write(&mut out, MAX_PACKET).unwrap();
+            // The client may already have hung up after its DISCONNECT
+            let _ = self.stream.write_all(&out).await;
+        }
+
+        async fn publishes(&mut self, count: usize) -> Vec<Publish> {
+            let mut publishes = Vec::new();
+            while publishes.len() < count {
+                if let Packet::Publish(publish) = self.read().await {
+                    publishes.push(publish);
+                }
+            }
+            publishes
+        }
+    }
+
+    fn assert_announced(publishes: &[Publish], topics: &MqttTopics) {
+        assert!(publishes.iter().all(|p| p.retain));
+        for sensor in SENSORS {
+            let topic = topics.discovery(sensor.key);
+            let config = publishes
+                .iter()
+                .find(|p| p.topic == topic)
+                .unwrap_or_else(|| panic!("no discovery config for {}", sensor.key));
+            let config: Value = serde_json::from_slice(&config.payload).unwrap();
+            assert_eq!(config["device"]["model"], "Test Model");
+        }
+        let online = publishes.last().unwrap();
+        assert_eq!(online.topic, topics.availability());
+        assert_eq!(&online.payload[..], ONLINE.as_bytes());
+    }
+
+    fn make_topics() -> MqttTopics {
+        MqttTopics::new(&MqttConfig::default(), "Jordon's MacBook Pro")
+    }
+
+    #[test]
+    fn test_sanitize_id() {
+        assert_eq!(sanitize_id("Jordon's MacBook Pro"), "jordon_s_macbook_pro");
+        assert_eq!(sanitize_id("build-box_01"), "build-box_01");
+        assert_eq!(sanitize_id(""), "jolt");
+    }
+
+    #[test]
+    fn test_topics_use_prefix_and_device_id() {
+        let topics = make_topics();
+        assert_eq!(topics.state(), "jolt/jordon_s_macbook_pro/state");
+        assert_eq!(
+            topics.availability(),
+            "jolt/jordon_s_macbook_pro/availability"
+        );
+    }
+
+    #[test]
+    fn test_discovery_messages_cover_all_sensors() {
+        let topics = make_topics();
+        let messages = topics.discovery_messages("Apple M1 Pro");
+        assert_eq!(messages.len(), SENSORS.len());
+
+        let (topic, config) = &messages[0];
+        assert_eq!(
+            topic,
+            "homeassistant/sensor/jordon_s_macbook_pro/charge_percent/config"
+        );
+        assert_eq!(config["device_class"], "battery");
+        assert_eq!(config["unit_of_measurement"], "%");
+        assert_eq!(config["value_template"], "{{ value_json.charge_percent }}");
+        assert_eq!(config["state_topic"], topics.state());
+        assert_eq!(config["device"]["model"], "Apple M1 Pro");
+        assert_eq!(config["device"]["name"], "Jordon's MacBook Pro");
+    }
+
+    #[test]
+    fn test_state_payload_prefers_forecast_for_time_remaining() {
+        let mut snapshot = DataSnapshot::default();
+        snapshot.battery.charge_percent = 84.96;
+        snapshot.battery.state = BatteryState::Discharging;
+        snapshot.battery.time_remaining_mins = Some(200);
+        snapshot.forecast.duration_secs = Some(7260);
+
+        let payload = state_payload(&snapshot);
+        assert_eq!(payload["charge_percent"], 85.0);
+        assert_eq!(payload["state"], "discharging");
+        assert_eq!(payload["time_remaining_mins"], 121);
+    }
+
+    #[test]
+    fn test_state_payload_falls_back_to_os_time_remaining() {
+        let mut snapshot = DataSnapshot::default();
+        snapshot.battery.time_remaining_mins = Some(200);
+
+        let payload = state_payload(&snapshot);
+        assert_eq!(payload["time_remaining_mins"], 200);
+    }
+
+    #[test]
+    fn test_tls_with_missing_ca_file_disables_mqtt() {
+        let config = MqttConfig {
+            enabled: true,
+            tls: true,
+            ca_file: Some("/nonexistent/jolt-ca.pem".into()),
+            ..MqttConfig::default()
+        };
+        assert!(MqttPublisher::spawn(&config, "Test Model").is_none());
+    }
+
+    /// Runs the publisher against an in-process broker: the last will,
+    /// retained discovery, re-announcing after the broker drops the
+    /// connection, and the explicit offline message on shutdown.
+    #[test]
+    fn test_publisher_against_broker() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let local = tokio::task::LocalSet::new();
+        local.block_on(&runtime, async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let config = MqttConfig {
+                enabled: true,
+                host: "127.0.0.1".to_string(),
+                port: listener.local_addr().unwrap().port(),
+                reconnect_secs: 1,
+                ..MqttConfig::default()
+            };
+            let publisher = MqttPublisher::spawn(&config, "Test Model").unwrap();
+            let topics = publisher.topics.clone();
+
+            let mut conn = BrokerConnection::accept(&listener).await;
+            let will = conn.connect.last_will.clone().expect("no last will");
+            assert_eq!(will.topic, topics.availability());
+            assert_eq!(&will.message[..], OFFLINE.as_bytes());
+            assert!(will.retain);
+            assert_announced(&conn.publishes(SENSORS.len() + 1).await, &topics);
+
+            // Broker restart: the client reconnects and announces again
+            drop(conn);
+            let mut conn = BrokerConnection::accept(&listener).await;
+            assert_announced(&conn.publishes(SENSORS.len() + 1).await, &topics);
+
+            publisher.publish(&DataSnapshot::default());
+            let state = conn.publishes(1).await.remove(0);
+            assert_eq!(state.topic, topics.state());
+            assert!(!state.retain);
+
+            let (_, offline) = tokio::join!(publisher.shutdown(), conn.publishes(1));
+            assert_eq!(offline[0].topic, topics.availability());
+            assert_eq!(&offline[0].payload[..], OFFLINE.as_bytes());
+            assert!(offline[0].retain);
+            // The client may hang up before its DISCONNECT is read
+            while let Some(packet) = conn.try_read().await {
+                assert_eq!(packet, Packet::Disconnect);
+            }
+        });
+    }
+}
@@ -0,0 +1,666 @@
+//! Notifications for daemon events.
+//!
+//! Plugging in, unplugging, finished charge sessions, low battery, reaching
+//! the charge limit and fired alert rules are sent to the sinks enabled under
+//! `[notifications]`: desktop notifications (freedesktop D-Bus on Linux,
+//! Notification Center on macOS), a shell command that gets the event as JSON
+//! on stdin, and a webhook that receives the same JSON as a POST body.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::config::{NotificationEvent, NotificationsConfig};
+use crate::daemon::protocol::{Alert, BatteryState, DataSnapshot};
+use crate::data::forecast::format_duration;
+use crate::data::history_store::{ChargeSession, SessionType};
+
+/// Longest a command hook or webhook request may run
+const SINK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Points above the low battery level the charge must rise before the
+/// event can fire again on the same discharge
+const LOW_BATTERY_REARM: f32 = 2.0;
+/// Points below the charge limit the charge must fall before reaching it
+/// again counts as a new event
+const CHARGE_LIMIT_REARM: f32 = 5.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(target_os = "linux")]
+    #[error("D-Bus error: {0}")]
+    DBus(#[from] zbus::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(String),
+
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Command failed: {0}")]
+    CommandFailed(std::process::ExitStatus),
+}
+
+/// A daemon event as delivered to every sink. Command hooks and webhooks
+/// receive it serialized as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub timestamp: i64,
+    pub title: String,
+    pub message: String,
+    pub battery_percent: f32,
+}
+
+impl Notification {
+    fn new(
+        event: NotificationEvent,
+        snapshot: &DataSnapshot,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            event,
+            timestamp: snapshot.timestamp,
+            title: title.into(),
+            message: message.into(),
+            battery_percent: snapshot.battery.charge_percent,
+        }
+    }
+
+    pub fn alert(alert: &Alert, snapshot: &DataSnapshot) -> Self {
+        let title = if alert.name.is_empty() {
+            "Alert".to_string()
+        } else {
+            alert.name.clone()
+        };
+        Self::new(
+            NotificationEvent::Alert,
+            snapshot,
+            title,
+            alert.message.clone(),
+        )
+    }
+
+    fn session_end(session: &ChargeSession, snapshot: &DataSnapshot) -> Self {
+        let title = match session.session_type {
+            SessionType::Charge => "Charge session ended",
+            SessionType::Discharge => "Discharge session ended",
+        };
+        let end_time = session.end_time.unwrap_or(snapshot.timestamp);
+        let elapsed = Duration::from_secs((end_time - session.start_time).max(0) as u64);
+        let mut message = format!(
+            "{:.0}% → {:.0}% over {}",
+            session.start_percent,
+            session
+                .end_percent
+                .unwrap_or(snapshot.battery.charge_percent),
+            format_duration(elapsed)
+        );
+        if let Some(energy_wh) = session.energy_wh {
+            message.push_str(&format!(" ({:.1} Wh)", energy_wh));
+        }
+        Self::new(NotificationEvent::SessionEnd, snapshot, title, message)
+    }
+}
+
+/// Derives plug, low battery and charge limit events from successive daemon
+/// snapshots. Session ends come from the recorder, which already tracks them.
+pub struct EventWatcher {
+    last_external: Option<bool>,
+    low_battery_armed: bool,
+    /// `None` until the first snapshot, so a battery that is already
+    /// sitting at its limit when the daemon starts doesn't notify
+    charge_limit_armed: Option<bool>,
+}
+
+impl EventWatcher {
+    pub fn new() -> Self {
+        Self {
+            last_external: None,
+            low_battery_armed: true,
+            charge_limit_armed: None,
+        }
+    }
+
+    pub fn check(
+        &mut self,
+        snapshot: &DataSnapshot,
+        ended_sessions: &[ChargeSession],
+        low_battery_percent: f32,
+        charge_limit_percent: f32,
+    ) -> Vec<Notification> {
+        let battery = &snapshot.battery;
+        let percent = battery.charge_percent;
+        let mut notifications = Vec::new();
+
+        if let Some(was_external) = self.last_external.replace(battery.external_connected) {
+            if was_external != battery.external_connected {
+                notifications.push(if battery.external_connected {
+                    Notification::new(
+                        NotificationEvent::PluggedIn,
+                        snapshot,
+                        "Plugged in",
+                        format!("Power adapter connected at {:.0}%", percent),
+                    )
+                } else {
+                    Notification::new(
+                        NotificationEvent::Unplugged,
+                        snapshot,
+                        "Unplugged",
+                        format!("Running on battery at {:.0}%", percent),
+                    )
+                });
+            }
+        }
+
+        for session in ended_sessions {
+            notifications.push(Notification::session_end(session, snapshot));
+        }
+
+        let discharging = !battery.external_connected && battery.state == BatteryState::Discharging;
+        if !discharging || percent > low_battery_percent + LOW_BATTERY_REARM {
+            self.low_battery_armed = true;
+        } else if self.low_battery_armed && percent <= low_battery_percent {
+            self.low_battery_armed = false;
+            let mut message = format!("Battery at {:.0}%", percent);
+            if let Some(remaining) = snapshot.forecast.formatted() {
+                message.push_str(&format!(", about {} left", remaining));
+            }
+            notifications.push(Notification::new(
+                NotificationEvent::LowBattery,
+                snapshot,
+                "Low battery",
+                message,
+            ));
+        }
+
+        // Some machines report 99.x% at the limit, so allow half a point
+        let at_limit = battery.external_connected && percent + 0.5 >= charge_limit_percent;
+        let armed = self.charge_limit_armed.get_or_insert(!at_limit);
+        if !battery.external_connected || percent < charge_limit_percent - CHARGE_LIMIT_REARM {
+            *armed = true;
+        } else if *armed && at_limit {
+            *armed = false;
+            let title = if charge_limit_percent >= 100.0 {
+                "Fully charged"
+            } else {
+                "Charge limit reached"
+            };
+            notifications.push(Notification::new(
+                NotificationEvent::ChargeLimit,
+                snapshot,
+                title,
+                format!("Battery at {:.0}%", percent),
+            ));
+        }
+
+        notifications
+    }
+}
+
+/// Delivers notifications to the sinks enabled in `[notifications]`.
+pub struct Notifier {
+    config: NotificationsConfig,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationsConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    pub fn low_battery_percent(&self) -> f32 {
+        self.config.low_battery_percent as f32
+    }
+
+    /// Sends `notification` to every enabled sink subscribed to its event.
+    ///
+    /// Delivery runs in the background so a slow hook or webhook can't stall
+    /// the daemon; this must be called from within the daemon's `LocalSet`.
+    pub fn notify(&self, notification: &Notification) {
+        let event = notification.event;
+        debug!(event = event.name(), title = %notification.title, "Sending notification");
+
+        let desktop = &self.config.desktop;
+        if desktop.enabled && desktop.events.contains(&event) {
+            let notification = notification.clone();
+            tokio::task::spawn_local(async move {
+                if let Err(e) = send_desktop(&notification).await {
+                    warn!(error = %e, "Failed to show desktop notification");
+                }
+            });
+        }
+
+        let command = &self.config.command;
+        if command.enabled && !command.command.trim().is_empty() && command.events.contains(&event)
+        {
+            let notification = notification.clone();
+            let command = command.command.clone();
+            tokio::task::spawn_local(async move {
+                if let Err(e) = run_command(&command, &notification).await {
+                    warn!(error = %e, %command, "Notification command failed");
+                }
+            });
+        }
+
+        let webhook = &self.config.webhook;
+        if webhook.enabled && !webhook.url.is_empty() && webhook.events.contains(&event) {
+            let notification = notification.clone();
+            let url = webhook.url.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = post_webhook(&url, &notification) {
+                    warn!(error = %e, "Notification webhook failed");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn send_desktop(notification: &Notification) -> Result<(), NotifyError> {
+    let connection = zbus::Connection::session().await?;
+    show_desktop(&connection, notification).await?;
+    Ok(())
+}
+
+/// Calls `org.freedesktop.Notifications.Notify`, returning the server's
+/// notification id.
+#[cfg(target_os = "linux")]
+async fn show_desktop(
+    connection: &zbus::Connection,
+    notification: &Notification,
+) -> Result<u32, NotifyError> {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::Value;
+
+    // freedesktop urgency levels: 0 low, 1 normal, 2 critical
+    let urgency: u8 = match notification.event {
+        NotificationEvent::LowBattery => 2,
+        _ => 1,
+    };
+    let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "jolt",
+                0u32,
+                "battery",
+                notification.title.as_str(),
+                notification.message.as_str(),
+                Vec::<&str>::new(),
+                hints,
+                -1i32,
+            ),
+        )
+        .await?;
+    Ok(reply.body().deserialize::<u32>()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn send_desktop(notification: &Notification) -> Result<(), NotifyError> {
+    let script = format!(
+        "display notification {} with title \"jolt\" subtitle {}",
+        applescript_string(&notification.message),
+        applescript_string(&notification.title)
+    );
+    let status = tokio::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    if !status.success() {
+        return Err(NotifyError::CommandFailed(status));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Runs `command` through `sh -c` with the notification as JSON on stdin
+/// and its event name in `JOLT_EVENT`.
+async fn run_command(command: &str, notification: &Notification) -> Result<(), NotifyError> {
+    let payload = serde_json::to_vec(notification).unwrap_or_default();
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("JOLT_EVENT", notification.event.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks that don't care about the payload may exit without reading it
+        let _ = stdin.write_all(&payload).await;
+    }
+
+    let status = tokio::time::timeout(SINK_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| NotifyError::Timeout(SINK_TIMEOUT))??;
+    if !status.success() {
+        return Err(NotifyError::CommandFailed(status));
+    }
+    Ok(())
+}
+
+fn post_webhook(url: &str, notification: &Notification) -> Result<(), NotifyError> {
+    let payload = serde_json::to_string(notification).unwrap_or_default();
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(SINK_TIMEOUT))
+        .build()
+        .into();
+    agent
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", concat!("jolt/", env!("CARGO_PKG_VERSION")))
+        .send(payload)
+        .map_err(|e| NotifyError::Http(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn snapshot(timestamp: i64, percent: f32, external: bool) -> DataSnapshot {
+        let mut snapshot = DataSnapshot {
+            timestamp,
+            ..Default::default()
+        };
+        snapshot.battery.charge_percent = percent;
+        snapshot.battery.external_connected = external;
+        snapshot.battery.state = if external {
+            BatteryState::Charging
+        } else {
+            BatteryState::Discharging
+        };
+        snapshot
+    }
+
+    fn events(notifications: &[Notification]) -> Vec<NotificationEvent> {
+        notifications.iter().map(|n| n.event).collect()
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn notification() -> Notification {
+        Notification::new(
+            NotificationEvent::LowBattery,
+            &snapshot(1_700_000_000, 15.0, false),
+            "Low battery",
+            "Battery at 15%",
+        )
+    }
+
+    fn ended(session_type: SessionType, start_time: i64, end_time: i64) -> ChargeSession {
+        ChargeSession {
+            id: Some(1),
+            start_time,
+            end_time: Some(end_time),
+            start_percent: 40.0,
+            end_percent: Some(60.0),
+            energy_wh: None,
+            charger_watts: None,
+            avg_power_watts: None,
+            session_type,
+            is_complete: true,
+        }
+    }
+
+    #[test]
+    fn plug_changes_and_session_end() {
+        let mut watcher = EventWatcher::new();
+        assert!(watcher
+            .check(&snapshot(0, 40.0, true), &[], 20.0, 100.0)
+            .is_empty());
+
+        let unplugged = watcher.check(
+            &snapshot(1200, 60.0, false),
+            &[ended(SessionType::Charge, 0, 1200)],
+            20.0,
+            100.0,
+        );
+        assert_eq!(
+            events(&unplugged),
+            [NotificationEvent::Unplugged, NotificationEvent::SessionEnd]
+        );
+        assert_eq!(unplugged[1].title, "Charge session ended");
+        assert!(unplugged[1].message.starts_with("40% → 60% over 20m"));
+
+        let plugged = watcher.check(
+            &snapshot(1800, 55.0, true),
+            &[ended(SessionType::Discharge, 1200, 1800)],
+            20.0,
+            100.0,
+        );
+        assert_eq!(
+            events(&plugged),
+            [NotificationEvent::PluggedIn, NotificationEvent::SessionEnd]
+        );
+        assert_eq!(plugged[1].title, "Discharge session ended");
+        assert!(plugged[1].message.starts_with("40% → 60% over 10m"));
+
+        // Sessions only come from the recorder, so a plug change alone
+        // doesn't end one here
+        assert_eq!(
+            events(&watcher.check(&snapshot(2400, 58.0, false), &[], 20.0, 100.0)),
+            [NotificationEvent::Unplugged]
+        );
+    }
+
+    #[test]
+    fn low_battery_fires_once_until_recovered() {
+        let mut watcher = EventWatcher::new();
+        watcher.check(&snapshot(0, 25.0, false), &[], 20.0, 100.0);
+        assert_eq!(
+            events(&watcher.check(&snapshot(10, 20.0, false), &[], 20.0, 100.0)),
+            [NotificationEvent::LowBattery]
+        );
+        assert!(watcher
+            .check(&snapshot(20, 19.0, false), &[], 20.0, 100.0)
+            .is_empty());
+        // Within the re-arm margin, so a reading wobbling around the
+        // threshold doesn't fire again
+        assert!(watcher
+            .check(&snapshot(30, 21.0, false), &[], 20.0, 100.0)
+            .is_empty());
+        assert!(watcher
+            .check(&snapshot(40, 20.0, false), &[], 20.0, 100.0)
+            .is_empty());
+
+        watcher.check(&snapshot(50, 20.0, true), &[], 20.0, 100.0);
+        // Unplugging below the threshold counts as a new discharge
+        assert!(
+            events(&watcher.check(&snapshot(60, 19.0, false), &[], 20.0, 100.0))
+                .contains(&NotificationEvent::LowBattery)
+        );
+    }
+
+    #[test]
+    fn charge_limit_skips_startup_and_rearms_on_unplug() {
+        let mut watcher = EventWatcher::new();
+        assert!(watcher
+            .check(&snapshot(0, 80.0, true), &[], 20.0, 80.0)
+            .is_empty());
+
+        watcher.check(&snapshot(10, 78.0, false), &[], 20.0, 80.0);
+        watcher.check(&snapshot(20, 78.0, true), &[], 20.0, 80.0);
+        let reached = watcher.check(&snapshot(30, 79.6, true), &[], 20.0, 80.0);
+        assert_eq!(events(&reached), [NotificationEvent::ChargeLimit]);
+        assert_eq!(reached[0].title, "Charge limit reached");
+        assert!(watcher
+            .check(&snapshot(40, 80.0, true), &[], 20.0, 80.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn command_receives_json_on_stdin() {
+        let path = std::env::temp_dir().join(format!("jolt-notify-{}.json", std::process::id()));
+        let command = format!(
+            "cat > '{}'; test \"$JOLT_EVENT\" = low_battery",
+            path.display()
+        );
+
+        runtime()
+            .block_on(run_command(&command, &notification()))
+            .unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written["event"], "low_battery");
+        assert_eq!(written["message"], "Battery at 15%");
+        assert_eq!(written["battery_percent"], 15.0);
+
+        let failed = runtime().block_on(run_command("exit 3", &notification()));
+        assert!(matches!(failed, Err(NotifyError::CommandFailed(_))));
+    }
+
+    #[test]
+    fn webhook_posts_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = Vec::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push(line);
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (head, body)
+        });
+
+        post_webhook(&url, &notification()).unwrap();
+
+        let (head, body) = server.join().unwrap();
+        assert!(head[0].starts_with("POST /hook "));
+        assert!(head
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case("content-type: application/json\r\n")));
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["event"], "low_battery");
+        assert_eq!(body["title"], "Low battery");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn desktop_notification_over_dbus() {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        use zbus::zvariant::OwnedValue;
+
+        type Received = Arc<Mutex<Vec<(String, String, String)>>>;
+
+        struct NotificationServer {
+            received: Received,
+        }
+
+        #[zbus::interface(name = "org.freedesktop.Notifications")]
+        impl NotificationServer {
+            #[allow(clippy::too_many_arguments)]
+            fn notify(
+                &self,
+                app_name: String,
+                _replaces_id: u32,
+                _app_icon: String,
+                summary: String,
+                body: String,
+                _actions: Vec<String>,
+                _hints: HashMap<String, OwnedValue>,
+                _expire_timeout: i32,
+            ) -> u32 {
+                self.received
+                    .lock()
+                    .unwrap()
+                    .push((app_name, summary, body));
+                42
+            }
+        }
+
+        // Needs a private bus; skip where dbus-daemon isn't installed
+        let Ok(mut bus) = std::process::Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return;
+        };
+        let mut address = String::new();
+        BufReader::new(bus.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let received = Received::default();
+        let result = runtime().block_on(async {
+            let _server = zbus::connection::Builder::address(address.trim())?
+                .name("org.freedesktop.Notifications")?
+                .serve_at(
+                    "/org/freedesktop/Notifications",
+                    NotificationServer {
+                        received: received.clone(),
+                    },
+                )?
+                .build()
+                .await?;
+            let client = zbus::connection::Builder::address(address.trim())?
+                .build()
+                .await?;
+            show_desktop(&client, &notification()).await
+        });
+        let _ = bus.kill();
+        let _ = bus.wait();
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(
+            received.lock().unwrap().as_slice(),
+            [(
+                "jolt".to_string(),
+                "Low battery".to_string(),
+                "Battery at 15%".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Peer credential checks for local socket clients.
+//!
+//! The daemon acts with its own privileges, so requests that signal
+//! processes or stop the daemon are checked against the uid of the process on
+//! the other end of the Unix socket (`SO_PEERCRED` / `getpeereid`).
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::daemon::protocol::DaemonRequest;
+
+const ROOT_UID: u32 = 0;
+
+/// Returns the effective uid the daemon is running as.
+pub fn daemon_uid() -> u32 {
+    // SAFETY: `libc::geteuid` is a read-only syscall with no arguments and
+    // cannot fail or touch memory owned by Rust.
+    unsafe { libc::geteuid() }
+}
+
+/// Looks up the uid that owns `pid`, if the process exists.
+pub fn process_owner(pid: u32) -> Option<u32> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_user(UpdateKind::Always),
+    );
+    system
+        .process(pid)
+        .and_then(|p| p.user_id())
+        .map(|uid| **uid)
+}
+
+/// Checks whether a local peer may issue `request`.
+///
+/// Kills are allowed for root or for processes owned by the peer's uid. Tree
+/// and group kills are checked against the named process; they never reach
+/// processes of another user (see `kill_targets`).
+/// Shutdown, config changes and battery saver undo are limited to the
+/// daemon's own user. A peer whose credentials could not be read may only
+/// issue read-only requests.
+pub fn authorize(
+    request: &DaemonRequest,
+    peer_uid: Option<u32>,
+    daemon_uid: u32,
+    owner_of: impl Fn(u32) -> Option<u32>,
+) -> Result<(), String> {
+    if !request.is_mutating() {
+        return Ok(());
+    }
+    let Some(peer_uid) = peer_uid else {
+        return Err("Peer credentials unavailable".to_string());
+    };
+
+    match request {
+        DaemonRequest::Shutdown
+        | DaemonRequest::SetConfig { .. }
+        | DaemonRequest::UndoBatterySaver
+        | DaemonRequest::Throttle { .. }
+        | DaemonRequest::RemoveThrottle { .. }
+            if peer_uid != daemon_uid =>
+        {
+            Err(format!("Only permitted for uid {}", daemon_uid))
+        }
+        DaemonRequest::KillProcess { pid, .. } | DaemonRequest::ProcessAction { pid, .. }
+            if peer_uid != ROOT_UID =>
+        {
+            match owner_of(*pid) {
+                Some(owner) if owner == peer_uid => Ok(()),
+                Some(_) => Err(format!(
+                    "Permission denied: process {} is not owned by uid {}",
+                    pid, peer_uid
+                )),
+                None => Err(format!("Process {} not found", pid)),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::protocol::{KillScope, KillSignal, ProcessAction, ThrottleTarget};
+
+    fn kill(pid: u32) -> DaemonRequest {
+        DaemonRequest::KillProcess {
+            pid,
+            signal: KillSignal::Graceful,
+            scope: KillScope::Process,
+        }
+    }
+
+    fn owned_by_1000(pid: u32) -> Option<u32> {
+        (pid == 42).then_some(1000)
+    }
+
+    #[test]
+    fn read_only_requests_need_no_credentials() {
+        assert!(authorize(&DaemonRequest::GetStatus, None, 1000, owned_by_1000).is_ok());
+    }
+
+    #[test]
+    fn kill_allowed_for_owner() {
+        assert!(authorize(&kill(42), Some(1000), 1000, owned_by_1000).is_ok());
+    }
+
+    #[test]
+    fn kill_denied_for_other_user() {
+        let err = authorize(&kill(42), Some(1001), 0, owned_by_1000).unwrap_err();
+        assert!(err.contains("not owned"));
+    }
+
+    #[test]
+    fn kill_allowed_for_root() {
+        assert!(authorize(&kill(42), Some(0), 1000, owned_by_1000).is_ok());
+    }
+
+    #[test]
+    fn kill_of_missing_process_denied() {
+        assert!(authorize(&kill(7), Some(1000), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn process_action_follows_kill_rules() {
+        let request = |pid| DaemonRequest::ProcessAction {
+            pid,
+            action: ProcessAction::Renice { nice: 10 },
+        };
+        assert!(authorize(&request(42), Some(1000), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&request(42), Some(1001), 1000, owned_by_1000).is_err());
+        assert!(authorize(&request(42), Some(0), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&request(7), Some(1000), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn shutdown_limited_to_daemon_user() {
+        assert!(authorize(&DaemonRequest::Shutdown, Some(1000), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&DaemonRequest::Shutdown, Some(1001), 1000, owned_by_1000).is_err());
+        assert!(authorize(&DaemonRequest::Shutdown, Some(0), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn set_config_limited_to_daemon_user() {
+        let request = DaemonRequest::SetConfig {
+            key: "history.sample_interval_secs".to_string(),
+            value: "30".to_string(),
+        };
+        assert!(authorize(&request, Some(1000), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&request, Some(1001), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn battery_saver_undo_limited_to_daemon_user() {
+        let request = DaemonRequest::UndoBatterySaver;
+        assert!(authorize(&request, Some(1000), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&request, Some(0), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn throttles_limited_to_daemon_user() {
+        let request = DaemonRequest::Throttle {
+            target: ThrottleTarget::Pid(42),
+            cpu_percent: 25,
+            battery_only: false,
+        };
+        assert!(authorize(&request, Some(1000), 1000, owned_by_1000).is_ok());
+        assert!(authorize(&request, Some(1001), 1000, owned_by_1000).is_err());
+        let request = DaemonRequest::RemoveThrottle {
+            target: ThrottleTarget::Name("firefox".to_string()),
+        };
+        assert!(authorize(&request, Some(0), 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn mutations_denied_without_credentials() {
+        assert!(authorize(&DaemonRequest::Shutdown, None, 1000, owned_by_1000).is_err());
+        assert!(authorize(&kill(42), None, 1000, owned_by_1000).is_err());
+    }
+
+    #[test]
+    fn process_owner_finds_current_process() {
+        assert_eq!(process_owner(std::process::id()), Some(daemon_uid()));
+    }
+}
@@ -1,11 +1,17 @@
 pub use jolt_protocol::{
-    BatterySnapshot, BatteryState, ChargeSession, ChargingState, CycleSummary, DaemonRequest,
+    Alert, BatterySaverSnapshot, BatterySnapshot, BatteryState, ChargeForecastSnapshot,
+    ChargeForecastSource, ChargeSession, ChargingState, ConfigEntry, CycleSummary, DaemonRequest,
     DaemonResponse, DaemonStatus, DailyCycle, DailyStat, DailyTopProcess, DataSnapshot,
-    ForecastSnapshot, ForecastSource, HourlyStat, KillProcessResult, KillSignal, PowerMode,
-    PowerSnapshot, ProcessSnapshot, ProcessState, Sample, SessionType, SystemSnapshot,
-    SystemStatsSnapshot, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
+    DownsampleMethod, Event, EventKind, ForecastEstimator, ForecastModel, ForecastSnapshot,
+    ForecastSource, HourlyProcessStat, HourlyStat, KillProcessResult, KillScope, KillSignal,
+    KillTarget, PowerMode, PowerSnapshot, ProcessAction, ProcessSnapshot, ProcessState, Sample,
+    SessionType, StoppedProcess, SystemSnapshot, SystemStatsSnapshot, ThrottleSnapshot,
+    ThrottleTarget, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
 };
 
+use std::time::Duration;
+
+use crate::config;
 use crate::data;
 
 impl From<&data::HourlyStat> for HourlyStat {
@@ -22,6 +28,48 @@ impl From<&data::HourlyStat> for HourlyStat {
     }
 }
 
+impl From<&data::HourlyProcessStat> for HourlyProcessStat {
+    fn from(s: &data::HourlyProcessStat) -> Self {
+        Self {
+            hour_start: s.hour_start,
+            process_name: s.process_name.clone(),
+            avg_impact: s.avg_impact,
+            avg_cpu: s.avg_cpu,
+            avg_memory_mb: s.avg_memory_mb,
+            avg_power: s.avg_power,
+            total_energy_wh: s.total_energy_wh,
+            sample_count: s.sample_count,
+        }
+    }
+}
+
+impl From<data::EventKind> for EventKind {
+    fn from(kind: data::EventKind) -> Self {
+        match kind {
+            data::EventKind::DaemonStart => EventKind::DaemonStart,
+            data::EventKind::DaemonStop => EventKind::DaemonStop,
+            data::EventKind::PowerConnected => EventKind::PowerConnected,
+            data::EventKind::PowerDisconnected => EventKind::PowerDisconnected,
+            data::EventKind::SessionStart => EventKind::SessionStart,
+            data::EventKind::SessionEnd => EventKind::SessionEnd,
+            data::EventKind::PowerModeChange => EventKind::PowerModeChange,
+            data::EventKind::Annotation => EventKind::Annotation,
+            data::EventKind::Alert => EventKind::Alert,
+            data::EventKind::BatterySaver => EventKind::BatterySaver,
+        }
+    }
+}
+
+impl From<&data::Event> for Event {
+    fn from(e: &data::Event) -> Self {
+        Self {
+            timestamp: e.timestamp,
+            kind: e.kind.into(),
+            message: e.message.clone(),
+        }
+    }
+}
+
 impl From<&data::DailyStat> for DailyStat {
     fn from(s: &data::DailyStat) -> Self {
         Self {
@@ -153,6 +201,85 @@ impl From<&data::ForecastData> for ForecastSnapshot {
             avg_power_watts: f.avg_power_watts(),
             sample_count: f.sample_count(),
             source: f.source().into(),
+            p10_secs: f.p10_secs(),
+            p90_secs: f.p90_secs(),
+            model: f.model().into(),
+            estimator: f.estimator().into(),
+            charge: f.charge().map(Into::into),
+        }
+    }
+}
+
+impl From<&data::charge_forecast::ChargeForecast> for ChargeForecastSnapshot {
+    fn from(c: &data::charge_forecast::ChargeForecast) -> Self {
+        Self {
+            to_80_secs: c.to_80.map(|d| d.as_secs()),
+            to_limit_secs: c.to_limit.map(|d| d.as_secs()),
+            to_full_secs: c.to_full.map(|d| d.as_secs()),
+            limit_percent: c.limit_percent,
+            source: match c.source {
+                data::charge_forecast::ChargeForecastSource::Learned => {
+                    ChargeForecastSource::Learned
+                }
+                data::charge_forecast::ChargeForecastSource::Os => ChargeForecastSource::Os,
+            },
+        }
+    }
+}
+
+impl From<&ChargeForecastSnapshot> for data::charge_forecast::ChargeForecast {
+    fn from(c: &ChargeForecastSnapshot) -> Self {
+        Self {
+            to_80: c.to_80_secs.map(Duration::from_secs),
+            to_limit: c.to_limit_secs.map(Duration::from_secs),
+            to_full: c.to_full_secs.map(Duration::from_secs),
+            limit_percent: c.limit_percent,
+            source: match c.source {
+                ChargeForecastSource::Learned => {
+                    data::charge_forecast::ChargeForecastSource::Learned
+                }
+                ChargeForecastSource::Os => data::charge_forecast::ChargeForecastSource::Os,
+            },
+        }
+    }
+}
+
+impl From<data::forecast::ForecastModel> for ForecastModel {
+    fn from(m: data::forecast::ForecastModel) -> Self {
+        match m {
+            data::forecast::ForecastModel::Window => ForecastModel::Window,
+            data::forecast::ForecastModel::Profile => ForecastModel::Profile,
+        }
+    }
+}
+
+impl From<config::ForecastEstimator> for ForecastEstimator {
+    fn from(e: config::ForecastEstimator) -> Self {
+        match e {
+            config::ForecastEstimator::Window => ForecastEstimator::Window,
+            config::ForecastEstimator::Ewma => ForecastEstimator::Ewma,
+            config::ForecastEstimator::Kalman => ForecastEstimator::Kalman,
+            config::ForecastEstimator::Slope => ForecastEstimator::Slope,
+        }
+    }
+}
+
+impl From<ForecastEstimator> for config::ForecastEstimator {
+    fn from(e: ForecastEstimator) -> Self {
+        match e {
+            ForecastEstimator::Window => config::ForecastEstimator::Window,
+            ForecastEstimator::Ewma => config::ForecastEstimator::Ewma,
+            ForecastEstimator::Kalman => config::ForecastEstimator::Kalman,
+            ForecastEstimator::Slope => config::ForecastEstimator::Slope,
+        }
+    }
+}
+
+impl From<ForecastModel> for data::forecast::ForecastModel {
+    fn from(m: ForecastModel) -> Self {
+        match m {
+            ForecastModel::Window => data::forecast::ForecastModel::Window,
+            ForecastModel::Profile => data::forecast::ForecastModel::Profile,
         }
     }
 }
@@ -192,6 +319,34 @@ impl From<HourlyStat> for data::HourlyStat {
     }
 }
 
+impl From<EventKind> for data::EventKind {
+    fn from(kind: EventKind) -> Self {
+        match kind {
+            EventKind::DaemonStart => data::EventKind::DaemonStart,
+            EventKind::DaemonStop => data::EventKind::DaemonStop,
+            EventKind::PowerConnected => data::EventKind::PowerConnected,
+            EventKind::PowerDisconnected => data::EventKind::PowerDisconnected,
+            EventKind::SessionStart => data::EventKind::SessionStart,
+            EventKind::SessionEnd => data::EventKind::SessionEnd,
+            EventKind::PowerModeChange => data::EventKind::PowerModeChange,
+            EventKind::Annotation => data::EventKind::Annotation,
+            EventKind::Alert => data::EventKind::Alert,
+            EventKind::BatterySaver => data::EventKind::BatterySaver,
+        }
+    }
+}
+
+impl From<Event> for data::Event {
+    fn from(e: Event) -> Self {
+        Self {
+            id: None,
+            timestamp: e.timestamp,
+            kind: e.kind.into(),
+            message: e.message,
+        }
+    }
+}
+
 impl From<DailyStat> for data::DailyStat {
     fn from(s: DailyStat) -> Self {
         Self {
@@ -0,0 +1,230 @@
+//! Authenticated TCP / WebSocket listener for remote clients.
+//!
+//! Speaks the same newline-delimited `DaemonRequest`/`DaemonResponse` JSON as
+//! the Unix socket. A connection whose first bytes look like an HTTP `GET` is
+//! upgraded to a WebSocket, with one JSON message per text frame. Every remote
+//! client must send `Authenticate` with the configured token before any other
+//! request is served.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use subtle::ConstantTimeEq;
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use crate::config::RemoteConfig;
+use crate::daemon::protocol::DaemonResponse;
+use crate::daemon::server::{
+    client_reader_task, client_writer_task, forward_request, ClientId, ClientMessage,
+};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a remote client gets to send a valid `Authenticate` before it is
+/// disconnected.
+pub const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Remote connections beyond this are closed as soon as they are accepted.
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// Binds the remote listener if enabled in config.
+///
+/// The listener refuses to start without a token. Bind failures are logged
+/// and treated as "disabled" so they never prevent the daemon from starting.
+pub async fn bind(config: &RemoteConfig) -> Option<TcpListener> {
+    if !config.enabled {
+        return None;
+    }
+
+    if config.token.is_empty() {
+        warn!("Remote listener enabled without a token, refusing to start it");
+        return None;
+    }
+
+    match TcpListener::bind(&config.listen).await {
+        Ok(listener) => {
+            info!(
+                addr = %config.listen,
+                allow_mutations = config.allow_mutations,
+                "Listening for remote clients"
+            );
+            if !listener
+                .local_addr()
+                .is_ok_and(|addr| addr.ip().is_loopback())
+            {
+                warn!(
+                    addr = %config.listen,
+                    "Remote listener is reachable from the network; the token and data are sent in plain text, use an SSH or TLS tunnel"
+                );
+            }
+            Some(listener)
+        }
+        Err(e) => {
+            warn!(addr = %config.listen, error = %e, "Failed to bind remote listener, remote access disabled");
+            None
+        }
+    }
+}
+
+/// Accepts a connection from an optional listener, pending forever when
+/// remote access is disabled so it can sit in the daemon's `select!` loop.
+pub async fn accept(listener: Option<&TcpListener>) -> std::io::Result<(TcpStream, SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Compares a presented token against the configured one in constant time.
+pub fn verify_token(expected: &str, provided: &str) -> bool {
+    !expected.is_empty() && bool::from(expected.as_bytes().ct_eq(provided.as_bytes()))
+}
+
+/// Drives a single remote connection until either side closes it.
+///
+/// The connection ends as soon as the daemon drops the client's response
+/// channel, which is how failed authentication disconnects a peer.
+pub async fn serve_connection(
+    stream: TcpStream,
+    client_id: ClientId,
+    msg_tx: mpsc::Sender<(ClientId, ClientMessage)>,
+    response_rx: mpsc::Receiver<DaemonResponse>,
+) {
+    let is_websocket = match tokio::time::timeout(HANDSHAKE_TIMEOUT, peek_is_http(&stream)).await {
+        Ok(Ok(is_http)) => is_http,
+        Ok(Err(e)) => {
+            debug!(client_id, error = %e, "Failed to read from remote client");
+            let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+            return;
+        }
+        Err(_) => {
+            debug!(client_id, "Remote client sent nothing, closing");
+            let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+            return;
+        }
+    };
+
+    if is_websocket {
+        serve_websocket(stream, client_id, msg_tx, response_rx).await;
+    } else {
+        let (reader, writer) = stream.into_split();
+        tokio::select! {
+            _ = client_reader_task(BufReader::new(reader), msg_tx.clone(), client_id) => {}
+            _ = client_writer_task(writer, response_rx) => {
+                let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+            }
+        }
+    }
+}
+
+async fn peek_is_http(stream: &TcpStream) -> std::io::Result<bool> {
+    let mut buf = [0u8; 4];
+    loop {
+        let n = stream.peek(&mut buf).await?;
+        if n == 0 {
+            return Ok(false);
+        }
+        if n == buf.len() || !b"GET ".starts_with(&buf[..n]) {
+            return Ok(&buf[..n] == b"GET ");
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+async fn serve_websocket(
+    stream: TcpStream,
+    client_id: ClientId,
+    msg_tx: mpsc::Sender<(ClientId, ClientMessage)>,
+    mut response_rx: mpsc::Receiver<DaemonResponse>,
+) {
+    let ws = match tokio::time::timeout(HANDSHAKE_TIMEOUT, tokio_tungstenite::accept_async(stream))
+        .await
+    {
+        Ok(Ok(ws)) => ws,
+        Ok(Err(e)) => {
+            debug!(client_id, error = %e, "WebSocket handshake failed");
+            let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+            return;
+        }
+        Err(_) => {
+            debug!(client_id, "WebSocket handshake timed out");
+            let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+            return;
+        }
+    };
+    debug!(client_id, "WebSocket client upgraded");
+
+    let (mut sink, mut source) = ws.split();
+
+    let reader = async {
+        while let Some(message) = source.next().await {
+            let keep_going = match message {
+                Ok(Message::Text(text)) => forward_request(&msg_tx, client_id, &text).await,
+                Ok(Message::Binary(data)) => match std::str::from_utf8(&data) {
+                    Ok(text) => forward_request(&msg_tx, client_id, text).await,
+                    Err(_) => {
+                        warn!(client_id, "Non UTF-8 WebSocket frame from client");
+                        true
+                    }
+                },
+                Ok(Message::Close(_)) => false,
+                Ok(_) => true,
+                Err(e) => {
+                    debug!(client_id, error = %e, "WebSocket read error");
+                    false
+                }
+            };
+            if !keep_going {
+                break;
+            }
+        }
+    };
+
+    let writer = async {
+        while let Some(response) = response_rx.recv().await {
+            let json = match response.to_json() {
+                Ok(j) => j,
+                Err(e) => {
+                    warn!(error = %e, "Failed to serialize response");
+                    continue;
+                }
+            };
+            if let Err(e) = sink.send(Message::text(json)).await {
+                debug!(client_id, error = %e, "WebSocket write failed, closing connection");
+                return;
+            }
+        }
+        let _ = sink.close().await;
+    };
+
+    tokio::select! {
+        _ = reader => {}
+        _ = writer => {}
+    }
+    let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_token_accepts_exact_match() {
+        assert!(verify_token("s3cret", "s3cret"));
+    }
+
+    #[test]
+    fn verify_token_rejects_mismatch() {
+        assert!(!verify_token("s3cret", "s3cre"));
+        assert!(!verify_token("s3cret", "S3cret"));
+        assert!(!verify_token("s3cret", ""));
+    }
+
+    #[test]
+    fn verify_token_rejects_empty_expected() {
+        assert!(!verify_token("", ""));
+    }
+}
@@ -0,0 +1,526 @@
+//! Automatic battery saver.
+//!
+//! When the charge falls to `threshold_percent` on battery, the daemon
+//! switches to the low power profile, pauses the configured background
+//! processes with SIGSTOP and slows updates to subscribed clients. All of it
+//! is put back when the power adapter is connected or the daemon stops. A
+//! client can also undo it, which keeps the saver off until the next plug-in.
+//!
+//! Stopped processes are also written to a file in the runtime directory, so
+//! a daemon that died without reverting leaves a record the next one uses to
+//! resume them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::{runtime_dir, BatterySaverConfig};
+use crate::daemon::protocol::{
+    BatterySaverSnapshot, BatterySnapshot, BatteryState, KillSignal, StoppedProcess,
+};
+use crate::data::processes::{find_by_name, send_signal, start_time};
+
+/// Processes stopped by the saver, kept until it reverts
+const STOPPED_FILE: &str = "battery-saver-stopped.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Activate,
+    Revert,
+}
+
+/// A stopped process as recorded on disk. The start time tells it apart from
+/// a later process that reused the pid.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoppedRecord {
+    pid: u32,
+    name: String,
+    start_time: u64,
+}
+
+/// What the saver changed, so it can be put back.
+struct Applied {
+    since: i64,
+    /// Power profile to restore, if the saver switched it
+    previous_profile: Option<String>,
+    stopped: Vec<StoppedProcess>,
+}
+
+pub struct BatterySaver {
+    config: BatterySaverConfig,
+    applied: Option<Applied>,
+    /// Set by an undo; keeps the saver off until the next plug-in
+    suppressed: bool,
+    /// Where stopped processes are recorded
+    stopped_path: PathBuf,
+}
+
+impl BatterySaver {
+    /// Creates the saver, resuming processes a previous daemon left stopped.
+    pub fn new(config: &BatterySaverConfig) -> Self {
+        let stopped_path = runtime_dir().join(STOPPED_FILE);
+        let resumed = resume_leftovers(&stopped_path);
+        if !resumed.is_empty() {
+            info!(
+                processes = %describe(&resumed),
+                "Resumed processes left stopped by a previous daemon"
+            );
+        }
+        Self::with_stopped_path(config, stopped_path)
+    }
+
+    fn with_stopped_path(config: &BatterySaverConfig, stopped_path: PathBuf) -> Self {
+        Self {
+            config: config.clone(),
+            applied: None,
+            suppressed: false,
+            stopped_path,
+        }
+    }
+
+    /// Applies or reverts the saver for the latest battery reading. Returns
+    /// a summary for the event log when it did either.
+    pub fn update(&mut self, battery: &BatterySnapshot, timestamp: i64) -> Option<String> {
+        match self.transition(battery)? {
+            Transition::Activate => Some(self.activate(battery.charge_percent, timestamp)),
+            Transition::Revert => {
+                let applied = self.applied.take()?;
+                Some(self.revert(applied, "plugged in"))
+            }
+        }
+    }
+
+    fn transition(&mut self, battery: &BatterySnapshot) -> Option<Transition> {
+        if battery.external_connected {
+            self.suppressed = false;
+            return self.applied.is_some().then_some(Transition::Revert);
+        }
+        let low = battery.state == BatteryState::Discharging
+            && battery.charge_percent <= self.config.threshold_percent as f32;
+        (self.config.enabled && low && self.applied.is_none() && !self.suppressed)
+            .then_some(Transition::Activate)
+    }
+
+    /// Reverts the saver and keeps it off until the next plug-in.
+    pub fn undo(&mut self) -> Option<String> {
+        let applied = self.applied.take()?;
+        self.suppressed = true;
+        Some(self.revert(applied, "undone"))
+    }
+
+    /// Reverts the saver before the daemon exits.
+    pub fn shutdown(&mut self) -> Option<String> {
+        let applied = self.applied.take()?;
+        Some(self.revert(applied, "daemon stopping"))
+    }
+
+    /// Takes new settings, reverting the saver if it was turned off.
+    pub fn apply_config(&mut self, config: &BatterySaverConfig) -> Option<String> {
+        self.config = config.clone();
+        if config.enabled {
+            return None;
+        }
+        let applied = self.applied.take()?;
+        Some(self.revert(applied, "disabled"))
+    }
+
+    /// Slowest broadcast interval allowed while the saver is on.
+    pub fn broadcast_interval_ms(&self) -> Option<u64> {
+        self.applied
+            .as_ref()
+            .filter(|_| self.config.broadcast_interval_ms > 0)
+            .map(|_| self.config.broadcast_interval_ms)
+    }
+
+    pub fn snapshot(&self) -> BatterySaverSnapshot {
+        match &self.applied {
+            Some(applied) => BatterySaverSnapshot {
+                active: true,
+                since: Some(applied.since),
+                low_power: applied.previous_profile.is_some(),
+                stopped: applied.stopped.clone(),
+                broadcast_interval_ms: self.broadcast_interval_ms(),
+            },
+            None => BatterySaverSnapshot::default(),
+        }
+    }
+
+    fn activate(&mut self, percent: f32, timestamp: i64) -> String {
+        let mut actions = Vec::new();
+
+        let previous_profile = if self.config.low_power_mode {
+            match enable_low_power() {
+                Ok(previous) => previous,
+                Err(e) => {
+                    warn!(error = %e, "Battery saver could not switch to low power mode");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if previous_profile.is_some() {
+            actions.push("low power mode".to_string());
+        }
+
+        let mut stopped = Vec::new();
+        if !self.config.stop_processes.is_empty() {
+            for (pid, name) in find_by_name(&self.config.stop_processes) {
+                match send_signal(pid, KillSignal::Stop) {
+                    Ok(()) => stopped.push(StoppedProcess { pid, name }),
+                    Err(e) => warn!(pid, %name, error = %e, "Battery saver could not stop process"),
+                }
+            }
+        }
+        if !stopped.is_empty() {
+            actions.push(format!("stopped {}", describe(&stopped)));
+            if let Err(e) = write_stopped(&self.stopped_path, &stopped) {
+                warn!(error = %e, "Battery saver could not record stopped processes");
+            }
+        }
+
+        self.applied = Some(Applied {
+            since: timestamp,
+            previous_profile,
+            stopped,
+        });
+        if let Some(interval_ms) = self.broadcast_interval_ms() {
+            actions.push(format!("updates every {:.0}s", interval_ms as f64 / 1000.0));
+        }
+
+        format!(
+            "Battery saver on at {:.0}%: {}",
+            percent,
+            summarize(&actions, "nothing to change")
+        )
+    }
+
+    fn revert(&mut self, applied: Applied, reason: &str) -> String {
+        let mut actions = Vec::new();
+
+        if let Some(profile) = applied.previous_profile {
+            match restore_power_profile(&profile) {
+                Ok(()) => actions.push("power profile restored".to_string()),
+                Err(e) => warn!(
+                    error = %e,
+                    %profile,
+                    "Battery saver could not restore power profile"
+                ),
+            }
+        }
+
+        let mut resumed = Vec::new();
+        if !applied.stopped.is_empty() {
+            resumed = resume(applied.stopped);
+            let _ = fs::remove_file(&self.stopped_path);
+        }
+        if !resumed.is_empty() {
+            actions.push(format!("resumed {}", describe(&resumed)));
+        }
+
+        format!(
+            "Battery saver off ({}): {}",
+            reason,
+            summarize(&actions, "nothing to restore")
+        )
+    }
+}
+
+/// Sends SIGCONT to each process, returning the ones that got it.
+fn resume(processes: Vec<StoppedProcess>) -> Vec<StoppedProcess> {
+    let mut resumed = Vec::new();
+    for process in processes {
+        match send_signal(process.pid, KillSignal::Continue) {
+            Ok(()) => resumed.push(process),
+            // Most likely the process exited while it was stopped
+            Err(e) => warn!(
+                pid = process.pid,
+                name = %process.name,
+                error = %e,
+                "Battery saver could not resume process"
+            ),
+        }
+    }
+    resumed
+}
+
+fn write_stopped(path: &Path, stopped: &[StoppedProcess]) -> std::io::Result<()> {
+    let records: Vec<StoppedRecord> = stopped
+        .iter()
+        .filter_map(|process| {
+            Some(StoppedRecord {
+                pid: process.pid,
+                name: process.name.clone(),
+                start_time: start_time(process.pid)?,
+            })
+        })
+        .collect();
+    fs::write(path, serde_json::to_vec(&records)?)
+}
+
+/// Resumes the processes recorded at `path` that are still the ones that
+/// were stopped, then removes the record.
+fn resume_leftovers(path: &Path) -> Vec<StoppedProcess> {
+    let Ok(contents) = fs::read(path) else {
+        return Vec::new();
+    };
+    let records: Vec<StoppedRecord> = match serde_json::from_slice(&contents) {
+        Ok(records) => records,
+        Err(e) => {
+            warn!(error = %e, path = %path.display(), "Ignoring unreadable battery saver record");
+            Vec::new()
+        }
+    };
+    let _ = fs::remove_file(path);
+    let leftovers = records
+        .into_iter()
+        .filter(|record| start_time(record.pid) == Some(record.start_time))
+        .map(|record| StoppedProcess {
+            pid: record.pid,
+            name: record.name,
+        })
+        .collect();
+    resume(leftovers)
+}
+
+fn describe(processes: &[StoppedProcess]) -> String {
+    processes
+        .iter()
+        .map(|p| format!("{} ({})", p.name, p.pid))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn summarize(actions: &[String], empty: &str) -> String {
+    if actions.is_empty() {
+        empty.to_string()
+    } else {
+        actions.join(", ")
+    }
+}
+
+/// Switches to the low power profile through power-profiles-daemon,
+/// returning the profile to restore, or `None` if it was already active.
+#[cfg(target_os = "linux")]
+fn enable_low_power() -> Result<Option<String>, String> {
+    let current = run("powerprofilesctl", &["get"])?;
+    if current == "power-saver" {
+        return Ok(None);
+    }
+    run("powerprofilesctl", &["set", "power-saver"])?;
+    Ok(Some(current))
+}
+
+#[cfg(target_os = "linux")]
+fn restore_power_profile(profile: &str) -> Result<(), String> {
+    run("powerprofilesctl", &["set", profile]).map(|_| ())
+}
+
+/// Turns on Low Power Mode for battery power, returning the previous
+/// `lowpowermode` value, or `None` if it was already on.
+#[cfg(not(target_os = "linux"))]
+fn enable_low_power() -> Result<Option<String>, String> {
+    let settings = run("pmset", &["-g"])?;
+    let current = settings
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("lowpowermode"), Some(value)) => Some(value.to_string()),
+                _ => None,
+            }
+        })
+        .unwrap_or_else(|| "0".to_string());
+    if current == "1" {
+        return Ok(None);
+    }
+    run("pmset", &["-b", "lowpowermode", "1"])?;
+    Ok(Some(current))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn restore_power_profile(profile: &str) -> Result<(), String> {
+    run("pmset", &["-b", "lowpowermode", profile]).map(|_| ())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BatterySaverConfig {
+        // No power profile or process changes, so tests don't touch the system
+        BatterySaverConfig {
+            enabled: true,
+            low_power_mode: false,
+            ..Default::default()
+        }
+    }
+
+    /// A saver that records stopped processes outside the real runtime dir
+    fn saver(config: &BatterySaverConfig) -> BatterySaver {
+        let path = std::env::temp_dir().join(format!("jolt-saver-{}.json", std::process::id()));
+        BatterySaver::with_stopped_path(config, path)
+    }
+
+    fn battery(percent: f32, external: bool) -> BatterySnapshot {
+        BatterySnapshot {
+            charge_percent: percent,
+            external_connected: external,
+            state: if external {
+                BatteryState::Charging
+            } else {
+                BatteryState::Discharging
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn activates_below_threshold_and_reverts_on_plug_in() {
+        let mut saver = saver(&config());
+        assert_eq!(saver.update(&battery(16.0, false), 0), None);
+
+        let message = saver.update(&battery(15.0, false), 10).unwrap();
+        assert_eq!(message, "Battery saver on at 15%: updates every 5s");
+        assert_eq!(saver.broadcast_interval_ms(), Some(5000));
+        assert_eq!(saver.snapshot().since, Some(10));
+        assert_eq!(saver.update(&battery(14.0, false), 20), None);
+
+        let message = saver.update(&battery(14.0, true), 30).unwrap();
+        assert_eq!(
+            message,
+            "Battery saver off (plugged in): nothing to restore"
+        );
+        assert!(!saver.snapshot().active);
+        assert_eq!(saver.broadcast_interval_ms(), None);
+    }
+
+    #[test]
+    fn undo_holds_off_until_plug_in() {
+        let mut saver = saver(&config());
+        assert_eq!(saver.undo(), None);
+        saver.update(&battery(10.0, false), 0).unwrap();
+        assert!(saver
+            .undo()
+            .unwrap()
+            .starts_with("Battery saver off (undone)"));
+        assert_eq!(saver.update(&battery(9.0, false), 10), None);
+
+        saver.update(&battery(9.0, true), 20);
+        assert!(saver.update(&battery(9.0, false), 30).is_some());
+    }
+
+    #[test]
+    fn needs_enabled_and_discharging() {
+        let mut disabled = saver(&BatterySaverConfig {
+            enabled: false,
+            ..config()
+        });
+        assert_eq!(disabled.update(&battery(5.0, false), 0), None);
+
+        // Machines without a battery report 0% in an unknown state
+        let mut saver = saver(&config());
+        let no_battery = BatterySnapshot::default();
+        assert_eq!(saver.update(&no_battery, 0), None);
+    }
+
+    #[test]
+    fn disabling_reverts() {
+        let mut saver = saver(&config());
+        saver.update(&battery(10.0, false), 0).unwrap();
+        assert_eq!(saver.apply_config(&config()), None);
+        let disabled = BatterySaverConfig {
+            enabled: false,
+            ..config()
+        };
+        assert!(saver.apply_config(&disabled).is_some());
+        assert!(!saver.snapshot().active);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stop_and_continue_signals() {
+        fn state(pid: u32) -> char {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+            // The state follows the parenthesised command name
+            stat.rsplit(')')
+                .next()
+                .unwrap()
+                .trim()
+                .chars()
+                .next()
+                .unwrap()
+        }
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        send_signal(pid, KillSignal::Stop).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(state(pid), 'T');
+        send_signal(pid, KillSignal::Continue).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_ne!(state(pid), 'T');
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn resumes_leftovers_from_a_previous_daemon() {
+        fn stopped(pid: u32) -> bool {
+            let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap();
+            status.contains("State:\tT")
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("jolt-saver-leftovers-{}.json", std::process::id()));
+        let mut left = Command::new("sleep").arg("30").spawn().unwrap();
+        let mut reused = Command::new("sleep").arg("30").spawn().unwrap();
+        for child in [&left, &reused] {
+            send_signal(child.id(), KillSignal::Stop).unwrap();
+        }
+        let process = |child: &std::process::Child| StoppedProcess {
+            pid: child.id(),
+            name: "sleep".to_string(),
+        };
+        write_stopped(&path, &[process(&left), process(&reused)]).unwrap();
+
+        // A different start time means the pid now belongs to another process
+        let mut records: Vec<StoppedRecord> =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        records[1].start_time -= 1;
+        std::fs::write(&path, serde_json::to_vec(&records).unwrap()).unwrap();
+
+        let resumed = resume_leftovers(&path);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(resumed, [process(&left)]);
+        assert!(!stopped(left.id()));
+        assert!(stopped(reused.id()));
+        assert!(!path.exists());
+        assert!(resume_leftovers(&path).is_empty());
+
+        for child in [&mut left, &mut reused] {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -1,25 +1,39 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
 use std::sync::mpsc as std_mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
 use tokio::net::UnixListener;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, trace, warn};
 
-use crate::config::{runtime_dir, HistoryConfig, UserConfig};
+use crate::config::{runtime_dir, ForecastEstimator, HistoryConfig, UserConfig};
+use crate::daemon::alerts::AlertEngine;
+use crate::daemon::notify::{EventWatcher, Notification, Notifier};
 use crate::daemon::protocol::{
-    BatterySnapshot, BatteryState, ChargeSession, DaemonRequest, DaemonResponse, DaemonStatus,
-    DailyCycle, DailyStat, DailyTopProcess, DataSnapshot, ForecastSnapshot, HourlyStat,
-    KillProcessResult, PowerMode, PowerSnapshot, ProcessSnapshot, ProcessState, Sample,
-    SystemSnapshot, SystemStatsSnapshot, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
+    Alert, BatterySaverSnapshot, BatterySnapshot, BatteryState, ChargeSession, ConfigEntry,
+    DaemonRequest, DaemonResponse, DaemonStatus, DailyCycle, DailyStat, DailyTopProcess,
+    DataSnapshot, ForecastSnapshot, HourlyStat, KillProcessResult, KillScope, KillSignal,
+    KillTarget, PowerMode, PowerSnapshot, ProcessSnapshot, ProcessState, Sample, SystemSnapshot,
+    SystemStatsSnapshot, ThrottleTarget, MAX_SUBSCRIBERS, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION,
 };
-use crate::daemon::socket_path;
+use crate::daemon::saver::BatterySaver;
+use crate::daemon::throttle::Throttler;
+use crate::daemon::{metrics, mqtt, peer, remote, socket_path};
 use crate::data::aggregator::Aggregator;
+use crate::data::charge_forecast::ChargeProfile;
+use crate::data::downsample;
+use crate::data::estimator::ESTIMATOR_HISTORY_SECS;
+use crate::data::processes::{apply_action, kill_targets};
+use crate::data::usage_profile::UsageProfile;
 use crate::data::{
-    BatteryData, ForecastData, PowerData, ProcessData, Recorder, SystemInfo, SystemStatsData,
+    BatteryData, Event, EventKind, ForecastData, PowerData, ProcessData, Recorder, SystemInfo,
+    SystemStatsData,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -39,37 +53,64 @@ pub enum DaemonError {
 
 pub type Result<T> = std::result::Result<T, DaemonError>;
 
-type ClientId = u64;
+pub(super) type ClientId = u64;
 
-enum ClientMessage {
-    Request { request: DaemonRequest },
+pub(super) enum ClientMessage {
+    Request {
+        request: DaemonRequest,
+    },
     Disconnect,
+    /// The remote client's time to authenticate is up
+    AuthTimeout,
 }
 
 struct ClientHandle {
     response_tx: mpsc::Sender<DaemonResponse>,
     is_subscriber: bool,
+    is_remote: bool,
+    authenticated: bool,
+    peer_uid: Option<u32>,
 }
 
+/// Upper bound on a single newline-delimited request.
+const MAX_REQUEST_LINE_BYTES: u64 = 64 * 1024;
+
 const PROCESS_REFRESH_INTERVAL: Duration = Duration::from_secs(3);
 const FORECAST_WINDOW_SECS: i64 = 300;
+/// How often the forecast's usage profile is rebuilt from hourly stats
+const PROFILE_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
 
 enum RefreshRequest {
     Full,
     MetricsOnly,
+    UpdateConfig {
+        history: HistoryConfig,
+        excluded: Vec<String>,
+        estimator: ForecastEstimator,
+        charge_limit: u8,
+    },
+    /// Pids in the daemon's CPU throttle groups
+    SetThrottled(HashSet<u32>),
     Shutdown,
 }
 
+/// A finished refresh along with any charge sessions the worker's recorder
+/// closed while taking it.
+struct RefreshResult {
+    snapshot: DataSnapshot,
+    ended_sessions: Vec<crate::data::ChargeSession>,
+}
+
 struct RefreshWorker {
     request_tx: std_mpsc::Sender<RefreshRequest>,
-    response_rx: std_mpsc::Receiver<DataSnapshot>,
+    response_rx: std_mpsc::Receiver<RefreshResult>,
     _handle: Option<thread::JoinHandle<()>>,
 }
 
 impl RefreshWorker {
     fn new(user_config: &UserConfig) -> Result<Self> {
         let (request_tx, request_rx) = std_mpsc::channel::<RefreshRequest>();
-        let (response_tx, response_rx) = std_mpsc::channel::<DataSnapshot>();
+        let (response_tx, response_rx) = std_mpsc::channel::<RefreshResult>();
 
         let excluded: Vec<String> = user_config
             .effective_excluded_processes()
@@ -79,9 +120,18 @@ impl RefreshWorker {
 
         let config = user_config.history.clone();
         let excluded_clone = excluded.clone();
+        let estimator = user_config.forecast_estimator;
+        let charge_limit = user_config.charge_limit_percent;
 
         let handle = thread::spawn(move || {
-            Self::worker_loop(request_rx, response_tx, config, excluded_clone);
+            Self::worker_loop(
+                request_rx,
+                response_tx,
+                config,
+                excluded_clone,
+                estimator,
+                charge_limit,
+            );
         });
 
         Ok(Self {
@@ -93,9 +143,11 @@ impl RefreshWorker {
 
     fn worker_loop(
         request_rx: std_mpsc::Receiver<RefreshRequest>,
-        response_tx: std_mpsc::Sender<DataSnapshot>,
+        response_tx: std_mpsc::Sender<RefreshResult>,
         config: HistoryConfig,
         excluded: Vec<String>,
+        estimator: ForecastEstimator,
+        mut charge_limit: u8,
     ) {
         debug!("Worker thread starting initialization");
         let mut battery = match BatteryData::new() {
@@ -129,6 +181,9 @@ impl RefreshWorker {
             }
         };
         let mut forecast = ForecastData::new();
+        forecast.set_estimator(estimator, FORECAST_WINDOW_SECS);
+        let mut zone = config.timezone;
+        let mut profile_loaded_at: Option<Instant> = None;
 
         let mut recorder = match Recorder::new(config, excluded) {
             Ok(r) => Some(r),
@@ -144,9 +199,46 @@ impl RefreshWorker {
         debug!("Worker initialization complete, waiting for requests");
 
         while let Ok(mut request) = request_rx.recv() {
+            if let RefreshRequest::UpdateConfig {
+                history,
+                excluded,
+                estimator,
+                charge_limit: limit,
+            } = request
+            {
+                zone = history.timezone;
+                charge_limit = limit;
+                profile_loaded_at = None;
+                forecast.set_estimator(estimator, FORECAST_WINDOW_SECS);
+                Self::apply_config(&mut recorder, &mut processes, history, excluded);
+                continue;
+            }
+            if let RefreshRequest::SetThrottled(pids) = request {
+                processes.set_throttled(pids);
+                continue;
+            }
+
             // Drain any pending requests to avoid queue buildup in long-running daemon
             let mut drained_count = 0u64;
             while let Ok(pending) = request_rx.try_recv() {
+                if let RefreshRequest::UpdateConfig {
+                    history,
+                    excluded,
+                    estimator,
+                    charge_limit: limit,
+                } = pending
+                {
+                    zone = history.timezone;
+                    charge_limit = limit;
+                    profile_loaded_at = None;
+                    forecast.set_estimator(estimator, FORECAST_WINDOW_SECS);
+                    Self::apply_config(&mut recorder, &mut processes, history, excluded);
+                    continue;
+                }
+                if let RefreshRequest::SetThrottled(pids) = pending {
+                    processes.set_throttled(pids);
+                    continue;
+                }
                 drained_count += 1;
                 request = match (&request, &pending) {
                     // Shutdown always takes priority
@@ -168,10 +260,13 @@ impl RefreshWorker {
                 RefreshRequest::Shutdown => "shutdown",
                 RefreshRequest::Full => "full",
                 RefreshRequest::MetricsOnly => "metrics_only",
+                RefreshRequest::UpdateConfig { .. } => "update_config",
+                RefreshRequest::SetThrottled(_) => "set_throttled",
             };
 
             match request {
                 RefreshRequest::Shutdown => break,
+                RefreshRequest::UpdateConfig { .. } | RefreshRequest::SetThrottled(_) => continue,
                 RefreshRequest::Full => {
                     let _ = battery.refresh();
                     let _ = power.refresh();
@@ -220,11 +315,28 @@ impl RefreshWorker {
                 charging_state,
             });
 
-            let cutoff = now - FORECAST_WINDOW_SECS;
+            let cutoff = now - ESTIMATOR_HISTORY_SECS;
             while recent_samples.front().is_some_and(|s| s.timestamp < cutoff) {
                 recent_samples.pop_front();
             }
 
+            if profile_loaded_at.is_none_or(|at| at.elapsed() >= PROFILE_REFRESH_INTERVAL) {
+                if let Some(ref rec) = recorder {
+                    match UsageProfile::load(rec.store(), zone, now) {
+                        Ok(profile) => {
+                            debug!(hours = profile.hours(), "Usage profile loaded");
+                            forecast.set_profile(Some(profile));
+                        }
+                        Err(e) => warn!(error = %e, "Failed to load usage profile"),
+                    }
+                    match ChargeProfile::load(rec.store(), now) {
+                        Ok(profile) => forecast.set_charge_profile(Some(profile)),
+                        Err(e) => warn!(error = %e, "Failed to load charge profile"),
+                    }
+                }
+                profile_loaded_at = Some(Instant::now());
+            }
+
             let samples: Vec<_> = recent_samples.iter().cloned().collect();
             let success = forecast.calculate_from_daemon_samples(
                 &samples,
@@ -239,6 +351,16 @@ impl RefreshWorker {
                 forecast_duration_secs = ?forecast.duration_secs(),
                 "Forecast calculation"
             );
+            if battery.is_charging() {
+                forecast.calculate_charge(
+                    battery.charge_percent(),
+                    battery.charger_watts(),
+                    charge_limit as f32,
+                    battery.time_remaining(),
+                );
+            } else {
+                forecast.clear_charge();
+            }
 
             let forecast_snapshot: ForecastSnapshot = (&forecast).into();
             let system_stats_snapshot: SystemStatsSnapshot = (&system_stats).into();
@@ -260,12 +382,52 @@ impl RefreshWorker {
                 power_watts = snapshot.power.total_power_watts,
                 "Worker completed refresh"
             );
-            if response_tx.send(snapshot).is_err() {
+            let ended_sessions = recorder
+                .as_mut()
+                .map(Recorder::take_ended_sessions)
+                .unwrap_or_default();
+            let result = RefreshResult {
+                snapshot,
+                ended_sessions,
+            };
+            if response_tx.send(result).is_err() {
                 break;
             }
         }
     }
 
+    fn apply_config(
+        recorder: &mut Option<Recorder>,
+        processes: &mut ProcessData,
+        history: HistoryConfig,
+        excluded: Vec<String>,
+    ) {
+        processes.set_exclusions(excluded.clone());
+        if let Some(rec) = recorder {
+            rec.update_config(history, excluded);
+        }
+        debug!("Worker applied config update");
+    }
+
+    fn update_config(
+        &self,
+        history: HistoryConfig,
+        excluded: Vec<String>,
+        estimator: ForecastEstimator,
+        charge_limit: u8,
+    ) {
+        let _ = self.request_tx.send(RefreshRequest::UpdateConfig {
+            history,
+            excluded,
+            estimator,
+            charge_limit,
+        });
+    }
+
+    fn set_throttled(&self, pids: HashSet<u32>) {
+        let _ = self.request_tx.send(RefreshRequest::SetThrottled(pids));
+    }
+
     fn request_refresh(&self, full: bool) {
         let req = if full {
             RefreshRequest::Full
@@ -275,7 +437,7 @@ impl RefreshWorker {
         let _ = self.request_tx.send(req);
     }
 
-    fn try_recv_snapshot(&self) -> Option<DataSnapshot> {
+    fn try_recv_result(&self) -> Option<RefreshResult> {
         self.response_rx.try_recv().ok()
     }
 
@@ -362,6 +524,8 @@ fn create_snapshot(
         system: system.clone(),
         system_stats: system_stats.clone(),
         forecast: forecast.clone(),
+        battery_saver: BatterySaverSnapshot::default(),
+        throttles: Vec::new(),
     }
 }
 
@@ -394,6 +558,7 @@ fn process_to_snapshot(p: &crate::data::ProcessInfo) -> ProcessSnapshot {
         status,
         run_time_secs: p.run_time_secs,
         total_cpu_time_secs: p.total_cpu_time_secs,
+        throttled: p.throttled,
     }
 }
 
@@ -402,7 +567,17 @@ struct DaemonState {
     recorder: Recorder,
     start_time: Instant,
     config: HistoryConfig,
+    user_config: UserConfig,
     last_snapshot: Option<DataSnapshot>,
+    alerts: AlertEngine,
+    /// Fired alerts not yet pushed to subscribers
+    pending_alerts: Vec<Alert>,
+    notifier: Notifier,
+    watcher: EventWatcher,
+    saver: BatterySaver,
+    throttler: Throttler,
+    /// Throttled pids last sent to the worker
+    throttled_pids: HashSet<u32>,
 }
 
 impl DaemonState {
@@ -420,16 +595,72 @@ impl DaemonState {
             recorder: Recorder::new(user_config.history.clone(), excluded)?,
             start_time: Instant::now(),
             config: user_config.history.clone(),
+            user_config: user_config.clone(),
             last_snapshot: None,
+            alerts: AlertEngine::new(&user_config.alerts),
+            pending_alerts: Vec::new(),
+            notifier: Notifier::new(&user_config.notifications),
+            watcher: EventWatcher::new(),
+            saver: BatterySaver::new(&user_config.battery_saver),
+            throttler: Throttler::new(),
+            throttled_pids: HashSet::new(),
         })
     }
 
+    /// Applies history and exclusion settings from `user_config` without a restart.
+    ///
+    /// Other sections (metrics, MQTT, remote) are only read at startup.
+    fn apply_config(&mut self, user_config: UserConfig) {
+        let excluded: Vec<String> = user_config
+            .effective_excluded_processes()
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        self.worker.update_config(
+            user_config.history.clone(),
+            excluded.clone(),
+            user_config.forecast_estimator,
+            user_config.charge_limit_percent,
+        );
+        self.recorder
+            .update_config(user_config.history.clone(), excluded);
+        // Rebuilding resets hold timers and cooldowns, so only do it when
+        // the rules themselves changed
+        if user_config.alerts != self.user_config.alerts {
+            self.alerts = AlertEngine::new(&user_config.alerts);
+            info!(rules = self.alerts.rule_count(), "Alert rules updated");
+        }
+        self.notifier = Notifier::new(&user_config.notifications);
+        if let Some(message) = self.saver.apply_config(&user_config.battery_saver) {
+            self.record_battery_saver(message);
+        }
+        self.config = user_config.history.clone();
+        self.user_config = user_config;
+        info!(
+            sample_interval_secs = self.config.sample_interval_secs,
+            background_recording = self.config.background_recording,
+            "Config applied"
+        );
+    }
+
     fn request_refresh(&self, full: bool) {
         self.worker.request_refresh(full);
     }
 
     fn poll_snapshot(&mut self) -> Option<DataSnapshot> {
-        if let Some(snapshot) = self.worker.try_recv_snapshot() {
+        if let Some(RefreshResult {
+            mut snapshot,
+            ended_sessions,
+        }) = self.worker.try_recv_result()
+        {
+            if let Some(previous) = &self.last_snapshot {
+                self.record_transitions(previous, &snapshot);
+            }
+            self.check_alerts(&snapshot);
+            self.check_notifications(&snapshot, &ended_sessions);
+            self.check_battery_saver(&mut snapshot);
+            self.check_throttles(&mut snapshot);
             self.last_snapshot = Some(snapshot.clone());
             Some(snapshot)
         } else {
@@ -437,6 +668,188 @@ impl DaemonState {
         }
     }
 
+    /// Logs plug/unplug and power mode changes between two snapshots.
+    fn record_transitions(&self, previous: &DataSnapshot, current: &DataSnapshot) {
+        if previous.battery.external_connected != current.battery.external_connected {
+            let (kind, message) = if current.battery.external_connected {
+                (EventKind::PowerConnected, "Power adapter connected")
+            } else {
+                (EventKind::PowerDisconnected, "Power adapter disconnected")
+            };
+            self.record_event(
+                kind,
+                format!("{} at {:.0}%", message, current.battery.charge_percent),
+            );
+        }
+        if previous.power.power_mode != current.power.power_mode {
+            self.record_event(
+                EventKind::PowerModeChange,
+                format!("Power mode changed to {}", current.power.power_mode_label),
+            );
+        }
+    }
+
+    /// Evaluates alert rules, recording fired alerts and queueing them for
+    /// subscribers.
+    fn check_alerts(&mut self, snapshot: &DataSnapshot) {
+        for alert in self.alerts.evaluate(snapshot) {
+            info!(rule = %alert.rule, value = alert.value, "Alert fired");
+            self.record_event(EventKind::Alert, alert.message.clone());
+            self.notifier.notify(&Notification::alert(&alert, snapshot));
+            self.pending_alerts.push(alert);
+        }
+    }
+
+    /// Sends plug, session, low battery and charge limit notifications.
+    fn check_notifications(
+        &mut self,
+        snapshot: &DataSnapshot,
+        ended_sessions: &[crate::data::ChargeSession],
+    ) {
+        let notifications = self.watcher.check(
+            snapshot,
+            ended_sessions,
+            self.notifier.low_battery_percent(),
+            self.user_config.charge_limit_percent as f32,
+        );
+        for notification in notifications {
+            self.notifier.notify(&notification);
+        }
+    }
+
+    /// Applies or reverts the battery saver and attaches its state to the
+    /// snapshot.
+    fn check_battery_saver(&mut self, snapshot: &mut DataSnapshot) {
+        if let Some(message) = self.saver.update(&snapshot.battery, snapshot.timestamp) {
+            self.record_battery_saver(message);
+        }
+        snapshot.battery_saver = self.saver.snapshot();
+    }
+
+    fn undo_battery_saver(&mut self) -> DaemonResponse {
+        match self.saver.undo() {
+            Some(message) => {
+                self.record_battery_saver(message);
+                if let Some(snapshot) = &mut self.last_snapshot {
+                    snapshot.battery_saver = self.saver.snapshot();
+                }
+                DaemonResponse::Ok
+            }
+            None => DaemonResponse::Error("Battery saver is not active".to_string()),
+        }
+    }
+
+    fn stop_battery_saver(&mut self) {
+        if let Some(message) = self.saver.shutdown() {
+            self.record_battery_saver(message);
+        }
+    }
+
+    fn record_battery_saver(&self, message: String) {
+        info!(%message, "Battery saver");
+        self.record_event(EventKind::BatterySaver, message);
+    }
+
+    /// Follows the power source for battery-only CPU caps and attaches the
+    /// throttles to the snapshot.
+    fn check_throttles(&mut self, snapshot: &mut DataSnapshot) {
+        for message in self.throttler.update(snapshot) {
+            info!(%message, "CPU throttle");
+        }
+        snapshot.throttles = self.throttler.snapshots();
+        self.sync_throttled_pids();
+    }
+
+    /// Tells the worker which pids are throttled, so the next refresh marks
+    /// them and keeps them on the process list.
+    fn sync_throttled_pids(&mut self) {
+        let pids: HashSet<u32> = self
+            .throttler
+            .snapshots()
+            .iter()
+            .flat_map(|throttle| throttle.pids.iter().copied())
+            .collect();
+        if pids != self.throttled_pids {
+            self.worker.set_throttled(pids.clone());
+            self.throttled_pids = pids;
+        }
+    }
+
+    fn throttle(
+        &mut self,
+        target: ThrottleTarget,
+        cpu_percent: u32,
+        battery_only: bool,
+    ) -> DaemonResponse {
+        let (processes, timestamp) = match &self.last_snapshot {
+            Some(snapshot) => (snapshot.processes.as_slice(), snapshot.timestamp),
+            None => (&[][..], chrono::Utc::now().timestamp()),
+        };
+        match self
+            .throttler
+            .throttle(target, cpu_percent, battery_only, processes, timestamp)
+        {
+            Ok(throttle) => {
+                info!(
+                    target = %throttle.target,
+                    cpu_percent,
+                    battery_only,
+                    pids = ?throttle.pids,
+                    "CPU throttle applied"
+                );
+                self.refresh_throttles();
+                DaemonResponse::Throttle(throttle)
+            }
+            Err(e) => DaemonResponse::Error(e),
+        }
+    }
+
+    fn remove_throttle(&mut self, target: &ThrottleTarget) -> DaemonResponse {
+        match self.throttler.remove(target) {
+            Ok(()) => {
+                info!(%target, "CPU throttle removed");
+                self.refresh_throttles();
+                DaemonResponse::Ok
+            }
+            Err(e) => DaemonResponse::Error(e),
+        }
+    }
+
+    /// Updates the cached snapshot so the next reply shows the change.
+    fn refresh_throttles(&mut self) {
+        if let Some(snapshot) = &mut self.last_snapshot {
+            snapshot.throttles = self.throttler.snapshots();
+        }
+        self.sync_throttled_pids();
+    }
+
+    fn stop_throttles(&mut self) {
+        self.throttler.shutdown();
+    }
+
+    /// Broadcast interval to use when subscribers asked for `requested_ms`;
+    /// the battery saver can slow broadcasts down but never speeds them up.
+    fn broadcast_interval_ms(&self, requested_ms: u64) -> u64 {
+        self.saver
+            .broadcast_interval_ms()
+            .map_or(requested_ms, |saver_ms| saver_ms.max(requested_ms))
+    }
+
+    fn take_alerts(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.pending_alerts)
+    }
+
+    fn record_event(&self, kind: EventKind, message: String) {
+        debug!(?kind, %message, "Recording event");
+        if let Err(e) = self
+            .recorder
+            .store()
+            .insert_event(&Event::now(kind, message))
+        {
+            warn!(error = %e, "Failed to record event");
+        }
+    }
+
     fn current_snapshot(&self) -> Option<&DataSnapshot> {
         self.last_snapshot.as_ref()
     }
@@ -448,6 +861,20 @@ impl DaemonState {
     fn run_aggregation(&mut self) {
         let aggregator = Aggregator::new(self.recorder.store(), &self.config);
 
+        match aggregator.realign_daily_rows() {
+            Ok(Some(days)) => {
+                info!(
+                    days,
+                    timezone = %self.config.timezone,
+                    "Rebuilt daily stats for new day boundaries"
+                );
+            }
+            Err(e) => {
+                error!(error = %e, "Error rebuilding daily stats");
+            }
+            _ => {}
+        }
+
         match aggregator.aggregate_completed_hours() {
             Ok(count) if count > 0 => {
                 info!(count, "Aggregated hourly stats");
@@ -475,19 +902,34 @@ impl DaemonState {
         match aggregator.prune_old_data() {
             Ok(result) => {
                 let total = result.samples_deleted
+                    + result.process_samples_deleted
                     + result.hourly_deleted
+                    + result.hourly_processes_deleted
                     + result.daily_deleted
                     + result.processes_deleted;
                 if total > 0 {
                     info!(
                         total,
                         samples = result.samples_deleted,
+                        process_samples = result.process_samples_deleted,
                         hourly = result.hourly_deleted,
+                        hourly_processes = result.hourly_processes_deleted,
                         daily = result.daily_deleted,
                         processes = result.processes_deleted,
                         "Pruned old records"
                     );
                 }
+                if let Some(size) = result.size {
+                    info!(
+                        size_before_mb = size.size_before / (1024 * 1024),
+                        size_after_mb = size.size_after / (1024 * 1024),
+                        max_mb = self.config.max_database_mb,
+                        samples = size.samples_deleted,
+                        hourly = size.hourly_deleted,
+                        processes = size.processes_deleted,
+                        "Pruned database to size limit"
+                    );
+                }
             }
             Err(e) => {
                 error!(error = %e, "Error pruning data");
@@ -547,6 +989,14 @@ impl DaemonState {
                     Err(e) => DaemonResponse::Error(e.to_string()),
                 }
             }
+            DaemonRequest::GetProcessHistory { name, from, to } => {
+                match self.recorder.store().get_process_history(name, *from, *to) {
+                    Ok(stats) => {
+                        DaemonResponse::ProcessHistory(stats.iter().map(Into::into).collect())
+                    }
+                    Err(e) => DaemonResponse::Error(e.to_string()),
+                }
+            }
             DaemonRequest::GetRecentSamples { window_secs } => {
                 let now = chrono::Utc::now().timestamp();
                 let from = now - *window_secs as i64;
@@ -558,48 +1008,97 @@ impl DaemonState {
                     Err(e) => DaemonResponse::Error(e.to_string()),
                 }
             }
+            DaemonRequest::GetSamples {
+                from,
+                to,
+                max_points,
+                method,
+            } => match self.recorder.store().get_samples(*from, *to) {
+                Ok(samples) => {
+                    let reduced = downsample::downsample(samples, *max_points, *method);
+                    DaemonResponse::Samples(reduced.iter().map(Into::into).collect())
+                }
+                Err(e) => DaemonResponse::Error(e.to_string()),
+            },
             DaemonRequest::GetCurrentData => match self.current_snapshot() {
                 Some(snapshot) => DaemonResponse::CurrentData(snapshot.clone()),
                 None => DaemonResponse::Error("No data available yet".to_string()),
             },
-            DaemonRequest::KillProcess { pid, signal } => {
-                match std::process::Command::new("kill")
-                    .args([signal.as_arg(), &pid.to_string()])
-                    .output()
-                {
-                    Ok(output) => {
-                        let success = output.status.success();
-                        let error = if success {
-                            None
-                        } else {
-                            let mut msg = String::from("kill command failed");
-                            if let Some(code) = output.status.code() {
-                                msg = format!("{msg} with exit code {code}");
-                            }
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            let stderr = stderr.trim();
-                            if !stderr.is_empty() {
-                                msg = format!("{msg}: {stderr}");
-                            }
-                            Some(msg)
-                        };
-                        DaemonResponse::KillResult(KillProcessResult {
-                            pid: *pid,
-                            success,
-                            error,
-                        })
-                    }
-                    Err(e) => DaemonResponse::KillResult(KillProcessResult {
+            DaemonRequest::KillProcess { pid, signal, scope } => {
+                let mut targets = kill_targets(*pid, *scope);
+                if targets.is_empty() {
+                    // Let `kill` report the missing process
+                    targets.push(KillTarget {
                         pid: *pid,
-                        success: false,
-                        error: Some(e.to_string()),
-                    }),
+                        name: String::new(),
+                    });
+                }
+                let results: Vec<KillProcessResult> = targets
+                    .iter()
+                    .map(|target| kill_process(target.pid, *signal))
+                    .collect();
+                if *scope != KillScope::Process {
+                    let failed = results.iter().filter(|r| !r.success).count();
+                    info!(
+                        pid,
+                        scope = scope.as_str(),
+                        signalled = results.len() - failed,
+                        failed,
+                        "Killed processes"
+                    );
                 }
+                DaemonResponse::kill(*scope, results)
+            }
+            DaemonRequest::GetKillTargets { pid, scope } => {
+                DaemonResponse::KillTargets(kill_targets(*pid, *scope))
+            }
+            DaemonRequest::ProcessAction { pid, action } => {
+                let result = apply_action(*pid, *action);
+                match &result {
+                    Ok(()) => info!(pid, %action, "Process action applied"),
+                    Err(e) => warn!(pid, %action, error = %e, "Process action failed"),
+                }
+                DaemonResponse::KillResult(KillProcessResult {
+                    pid: *pid,
+                    success: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                })
             }
             DaemonRequest::Shutdown => DaemonResponse::Ok,
+            DaemonRequest::AddAnnotation { text, timestamp } => {
+                match Event::annotation(text, *timestamp) {
+                    Ok(event) => match self.recorder.store().insert_event(&event) {
+                        Ok(_) => {
+                            info!(timestamp = event.timestamp, "Annotation added");
+                            DaemonResponse::Ok
+                        }
+                        Err(e) => DaemonResponse::Error(e.to_string()),
+                    },
+                    Err(e) => DaemonResponse::Error(e),
+                }
+            }
+            DaemonRequest::GetEvents { from, to } => {
+                match self.recorder.store().get_events(*from, *to) {
+                    Ok(events) => DaemonResponse::Events(events.iter().map(Into::into).collect()),
+                    Err(e) => DaemonResponse::Error(e.to_string()),
+                }
+            }
+            DaemonRequest::GetConfig => DaemonResponse::Config(
+                self.user_config
+                    .entries()
+                    .into_iter()
+                    .map(|(key, value)| ConfigEntry { key, value })
+                    .collect(),
+            ),
             DaemonRequest::Subscribe
             | DaemonRequest::Unsubscribe
-            | DaemonRequest::SetBroadcastInterval { .. } => {
+            | DaemonRequest::SetBroadcastInterval { .. }
+            | DaemonRequest::Authenticate { .. }
+            | DaemonRequest::ReloadConfig
+            | DaemonRequest::SetConfig { .. }
+            | DaemonRequest::UndoBatterySaver
+            | DaemonRequest::Throttle { .. }
+            | DaemonRequest::RemoveThrottle { .. } => {
                 DaemonResponse::Error("Handled separately".to_string())
             }
             DaemonRequest::GetCycleSummary { days } => match self.compute_cycle_summary(*days) {
@@ -635,11 +1134,8 @@ impl DaemonState {
     {
         use crate::daemon::protocol::CycleSummary;
 
-        let now = chrono::Utc::now();
-        let from_date = (now - chrono::Duration::days(days as i64))
-            .format("%Y-%m-%d")
-            .to_string();
-        let to_date = now.format("%Y-%m-%d").to_string();
+        let from_date = self.config.timezone.days_ago(days);
+        let to_date = self.config.timezone.today();
 
         let daily_cycles = self
             .recorder
@@ -698,33 +1194,38 @@ impl DaemonState {
     }
 }
 
-async fn client_reader_task(
-    mut reader: BufReader<tokio::net::unix::OwnedReadHalf>,
+pub(super) async fn client_reader_task<R>(
+    mut reader: R,
     msg_tx: mpsc::Sender<(ClientId, ClientMessage)>,
     client_id: ClientId,
-) {
+) where
+    R: AsyncBufRead + Unpin,
+{
     let mut line = String::new();
     loop {
         line.clear();
-        match reader.read_line(&mut line).await {
+        match (&mut reader)
+            .take(MAX_REQUEST_LINE_BYTES)
+            .read_line(&mut line)
+            .await
+        {
             Ok(0) => {
                 let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
                 break;
             }
-            Ok(_) => match DaemonRequest::from_json(line.trim()) {
-                Ok(request) => {
-                    if msg_tx
-                        .send((client_id, ClientMessage::Request { request }))
-                        .await
-                        .is_err()
-                    {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    warn!(client_id, error = %e, "Invalid request from client");
+            Ok(n) if n as u64 == MAX_REQUEST_LINE_BYTES && !line.ends_with('\n') => {
+                warn!(
+                    client_id,
+                    "Request exceeds maximum size, closing connection"
+                );
+                let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
+                break;
+            }
+            Ok(_) => {
+                if !forward_request(&msg_tx, client_id, line.trim()).await {
+                    break;
                 }
-            },
+            }
             Err(e) => {
                 debug!(client_id, error = %e, "Client read error");
                 let _ = msg_tx.send((client_id, ClientMessage::Disconnect)).await;
@@ -734,10 +1235,32 @@ async fn client_reader_task(
     }
 }
 
-async fn client_writer_task(
-    mut writer: tokio::net::unix::OwnedWriteHalf,
+/// Parses a JSON request and forwards it to the main loop.
+///
+/// Returns false once the main loop has gone away.
+pub(super) async fn forward_request(
+    msg_tx: &mpsc::Sender<(ClientId, ClientMessage)>,
+    client_id: ClientId,
+    json: &str,
+) -> bool {
+    match DaemonRequest::from_json(json) {
+        Ok(request) => msg_tx
+            .send((client_id, ClientMessage::Request { request }))
+            .await
+            .is_ok(),
+        Err(e) => {
+            warn!(client_id, error = %e, "Invalid request from client");
+            true
+        }
+    }
+}
+
+pub(super) async fn client_writer_task<W>(
+    mut writer: W,
     mut response_rx: mpsc::Receiver<DaemonResponse>,
-) {
+) where
+    W: AsyncWrite + Unpin,
+{
     while let Some(response) = response_rx.recv().await {
         let is_data_update = matches!(response, DaemonResponse::DataUpdate(_));
         let json = match response.to_json() {
@@ -763,6 +1286,62 @@ async fn client_writer_task(
     debug!("Client writer task ending");
 }
 
+/// Sends `signal` to `pid` with `kill`, reporting its error output.
+fn kill_process(pid: u32, signal: KillSignal) -> KillProcessResult {
+    match std::process::Command::new("kill")
+        .args([signal.as_arg(), &pid.to_string()])
+        .output()
+    {
+        Ok(output) => {
+            let success = output.status.success();
+            let error = if success {
+                None
+            } else {
+                let mut msg = String::from("kill command failed");
+                if let Some(code) = output.status.code() {
+                    msg = format!("{msg} with exit code {code}");
+                }
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stderr = stderr.trim();
+                if !stderr.is_empty() {
+                    msg = format!("{msg}: {stderr}");
+                }
+                Some(msg)
+            };
+            KillProcessResult {
+                pid,
+                success,
+                error,
+            }
+        }
+        Err(e) => KillProcessResult {
+            pid,
+            success: false,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Applies a new config to the daemon, resetting the sample timer if the
+/// sample interval changed.
+fn apply_config(
+    state: &mut DaemonState,
+    user_config: UserConfig,
+    sample_tick: &mut tokio::time::Interval,
+) {
+    let old_interval = state.config.sample_interval_secs;
+    state.apply_config(user_config);
+    if state.config.sample_interval_secs != old_interval {
+        let sample_interval = Duration::from_secs(state.config.sample_interval_secs.max(1));
+        *sample_tick = tokio::time::interval(sample_interval);
+        sample_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        info!(
+            sample_interval_secs = state.config.sample_interval_secs,
+            "Sample interval updated"
+        );
+    }
+}
+
 pub fn run_daemon(
     foreground: bool,
     log_level: crate::config::LogLevel,
@@ -777,7 +1356,13 @@ pub fn run_daemon(
         fs::remove_file(&socket)?;
     }
 
-    fs::create_dir_all(runtime_dir())?;
+    // The socket's directory is its first line of defence, so refuse to start
+    // rather than listen in a directory other users can reach.
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(runtime_dir())?;
+    fs::set_permissions(runtime_dir(), fs::Permissions::from_mode(0o700))?;
 
     if !foreground {
         match daemonize::Daemonize::new()
@@ -805,14 +1390,47 @@ pub fn run_daemon(
     local.block_on(&runtime, run_daemon_async(socket))
 }
 
+/// Binds the daemon socket with mode `0600` from the start. `bind` creates
+/// the file according to the umask, so a plain chmod afterwards would leave a
+/// window where other users could connect.
+fn bind_socket(socket: &std::path::Path) -> std::io::Result<UnixListener> {
+    // SAFETY: `libc::umask` only swaps the process file mode mask and cannot
+    // fail.
+    let previous = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(socket);
+    // SAFETY: as above.
+    unsafe { libc::umask(previous) };
+    let listener = listener?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
 async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
     let user_config = UserConfig::load();
     let mut state = DaemonState::new(&user_config)?;
+    state.record_event(
+        EventKind::DaemonStart,
+        format!("Daemon started (v{})", env!("CARGO_PKG_VERSION")),
+    );
 
-    let listener = UnixListener::bind(&socket)?;
+    let listener = bind_socket(&socket)?;
     info!(socket = ?socket, "Listening for connections");
+    let daemon_uid = peer::daemon_uid();
+
+    let metrics_listener = metrics::bind(&user_config.metrics).await;
+    let metrics_top_processes = user_config.metrics.top_processes;
+
+    let remote_listener = remote::bind(&user_config.remote).await;
+    let remote_token = user_config.remote.token.clone();
+    let remote_allow_mutations = user_config.remote.allow_mutations;
 
-    let sample_interval = Duration::from_secs(state.config.sample_interval_secs);
+    let mqtt_publisher = mqtt::MqttPublisher::spawn(&user_config.mqtt, &SystemInfo::new().chip);
+    let mqtt_interval = mqtt_publisher
+        .as_ref()
+        .map(|m| m.interval())
+        .unwrap_or(Duration::from_secs(3600));
+
+    let sample_interval = Duration::from_secs(state.config.sample_interval_secs.max(1));
     let aggregation_interval = Duration::from_secs(3600);
     let prune_interval = Duration::from_secs(86400);
     let mut broadcast_interval_ms = user_config.refresh_ms;
@@ -826,28 +1444,56 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
     let mut sample_tick = tokio::time::interval(sample_interval);
     let mut aggregation_tick = tokio::time::interval(aggregation_interval);
     let mut prune_tick = tokio::time::interval(prune_interval);
+    let mut effective_broadcast_ms = broadcast_interval_ms;
     let mut broadcast_tick = tokio::time::interval(Duration::from_millis(broadcast_interval_ms));
     let mut poll_tick = tokio::time::interval(Duration::from_millis(50));
+    let mut mqtt_tick = tokio::time::interval(mqtt_interval);
 
     sample_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     aggregation_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     prune_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     broadcast_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     poll_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    mqtt_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    // Handled so service managers stopping the daemon still get stopped
+    // processes resumed by the battery saver
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
 
     let (msg_tx, mut msg_rx) = mpsc::channel::<(ClientId, ClientMessage)>(256);
     let mut clients: HashMap<ClientId, ClientHandle> = HashMap::new();
     let mut next_client_id: ClientId = 1;
     let mut shutdown_requested = false;
     let mut pending_broadcast = false;
+    let mut pending_mqtt = false;
 
     state.request_refresh(true);
 
     loop {
+        let target_broadcast_ms = state.broadcast_interval_ms(broadcast_interval_ms);
+        if target_broadcast_ms != effective_broadcast_ms {
+            effective_broadcast_ms = target_broadcast_ms;
+            broadcast_tick = tokio::time::interval(Duration::from_millis(effective_broadcast_ms));
+            broadcast_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            info!(
+                broadcast_interval_ms = effective_broadcast_ms,
+                "Broadcast interval updated"
+            );
+        }
+
         tokio::select! {
             _ = sample_tick.tick() => {
                 state.request_refresh(true);
             }
+            _ = sighup.recv() => {
+                info!("SIGHUP received, reloading config");
+                apply_config(&mut state, UserConfig::load(), &mut sample_tick);
+            }
+            _ = sigterm.recv() => {
+                info!("SIGTERM received, shutting down");
+                break;
+            }
             _ = aggregation_tick.tick() => {
                 state.run_aggregation();
             }
@@ -866,6 +1512,10 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                     pending_broadcast = true;
                 }
             }
+            _ = mqtt_tick.tick(), if mqtt_publisher.is_some() => {
+                state.request_refresh(false);
+                pending_mqtt = true;
+            }
             _ = poll_tick.tick() => {
                 if let Some(snapshot) = state.poll_snapshot() {
                     debug!(
@@ -877,6 +1527,12 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                         pending_broadcast,
                         "Daemon polled new snapshot"
                     );
+                    if pending_mqtt {
+                        pending_mqtt = false;
+                        if let Some(ref publisher) = mqtt_publisher {
+                            publisher.publish(&snapshot);
+                        }
+                    }
                     if pending_broadcast {
                         pending_broadcast = false;
                         let update = DaemonResponse::DataUpdate(snapshot);
@@ -900,6 +1556,12 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                             debug!(client_id = id, "Removed disconnected subscriber");
                         }
                     }
+                    for alert in state.take_alerts() {
+                        let alert = DaemonResponse::Alert(alert);
+                        for client in clients.values().filter(|c| c.is_subscriber) {
+                            let _ = client.response_tx.send(alert.clone()).await;
+                        }
+                    }
                 }
             }
             result = listener.accept() => {
@@ -907,7 +1569,14 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                     Ok((stream, _)) => {
                         let client_id = next_client_id;
                         next_client_id += 1;
-                        debug!(client_id, "Client connected");
+                        let peer_uid = match stream.peer_cred() {
+                            Ok(cred) => Some(cred.uid()),
+                            Err(e) => {
+                                warn!(client_id, error = %e, "Failed to read peer credentials");
+                                None
+                            }
+                        };
+                        debug!(client_id, ?peer_uid, "Client connected");
 
                         let (reader, writer) = stream.into_split();
                         let (response_tx, response_rx) = mpsc::channel::<DaemonResponse>(64);
@@ -915,6 +1584,9 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                         clients.insert(client_id, ClientHandle {
                             response_tx,
                             is_subscriber: false,
+                            is_remote: false,
+                            authenticated: true,
+                            peer_uid,
                         });
 
                         let msg_tx_clone = msg_tx.clone();
@@ -930,6 +1602,66 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                     }
                 }
             }
+            result = remote::accept(remote_listener.as_ref()) => {
+                match result {
+                    Ok((stream, addr)) => {
+                        let remote_count = clients.values().filter(|c| c.is_remote).count();
+                        if remote_count >= remote::MAX_CONNECTIONS {
+                            warn!(%addr, "Too many remote clients, closing connection");
+                            continue;
+                        }
+                        let client_id = next_client_id;
+                        next_client_id += 1;
+                        info!(client_id, %addr, "Remote client connected");
+
+                        let (response_tx, response_rx) = mpsc::channel::<DaemonResponse>(64);
+
+                        clients.insert(client_id, ClientHandle {
+                            response_tx,
+                            is_subscriber: false,
+                            is_remote: true,
+                            authenticated: false,
+                            peer_uid: None,
+                        });
+
+                        tokio::task::spawn_local(remote::serve_connection(
+                            stream,
+                            client_id,
+                            msg_tx.clone(),
+                            response_rx,
+                        ));
+                        let msg_tx_clone = msg_tx.clone();
+                        tokio::task::spawn_local(async move {
+                            tokio::time::sleep(remote::AUTH_TIMEOUT).await;
+                            let _ = msg_tx_clone.send((client_id, ClientMessage::AuthTimeout)).await;
+                        });
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Remote accept error");
+                    }
+                }
+            }
+            result = metrics::accept(metrics_listener.as_ref()) => {
+                match result {
+                    Ok((stream, addr)) => {
+                        trace!(%addr, "Metrics scrape");
+                        let subscriber_count = clients.values().filter(|c| c.is_subscriber).count();
+                        let daemon_metrics = metrics::DaemonMetrics {
+                            status: state.get_status(subscriber_count),
+                            client_count: clients.len(),
+                        };
+                        tokio::task::spawn_local(metrics::serve_connection(
+                            stream,
+                            state.current_snapshot().cloned(),
+                            daemon_metrics,
+                            metrics_top_processes,
+                        ));
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Metrics accept error");
+                    }
+                }
+            }
             Some((client_id, msg)) = msg_rx.recv() => {
                 match msg {
                     ClientMessage::Disconnect => {
@@ -937,10 +1669,69 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                             debug!(client_id, count = clients.len(), "Client disconnected");
                         }
                     }
+                    ClientMessage::AuthTimeout => {
+                        // Dropping the response channel closes the connection
+                        if clients.get(&client_id).is_some_and(|c| !c.authenticated) {
+                            clients.remove(&client_id);
+                            warn!(client_id, "Remote client did not authenticate in time");
+                        }
+                    }
                     ClientMessage::Request { request } => {
-                        debug!(client_id, request = ?request, "Handling request");
+                        let Some((is_remote, authenticated, peer_uid)) = clients
+                            .get(&client_id)
+                            .map(|c| (c.is_remote, c.authenticated, c.peer_uid))
+                        else {
+                            continue;
+                        };
+                        if !matches!(request, DaemonRequest::Authenticate { .. }) {
+                            debug!(client_id, request = ?request, "Handling request");
+                        }
+
+                        // Remote clients are gated by token and allow_mutations instead.
+                        let peer_denial = if is_remote {
+                            None
+                        } else {
+                            peer::authorize(&request, peer_uid, daemon_uid, peer::process_owner).err()
+                        };
 
+                        let mut drop_client = false;
                         let response = match &request {
+                            DaemonRequest::Authenticate { token } => {
+                                if !is_remote || remote::verify_token(&remote_token, token) {
+                                    if let Some(client) = clients.get_mut(&client_id) {
+                                        client.authenticated = true;
+                                    }
+                                    DaemonResponse::Ok
+                                } else {
+                                    warn!(client_id, "Remote client failed authentication");
+                                    drop_client = true;
+                                    DaemonResponse::Error("Invalid token".to_string())
+                                }
+                            }
+                            _ if is_remote && !authenticated => {
+                                warn!(client_id, "Rejecting request from unauthenticated remote client");
+                                drop_client = true;
+                                DaemonResponse::Error("Authentication required".to_string())
+                            }
+                            // Even with allow_mutations, a remote client must not be
+                            // able to rewrite the config it was authorized by.
+                            DaemonRequest::SetConfig { .. } | DaemonRequest::ReloadConfig if is_remote => {
+                                warn!(client_id, request = ?request, "Rejecting config change from remote client");
+                                DaemonResponse::Error(
+                                    "Config changes are not permitted for remote clients".to_string(),
+                                )
+                            }
+                            _ if is_remote && request.is_mutating() && !remote_allow_mutations => {
+                                warn!(client_id, request = ?request, "Rejecting mutating request from remote client");
+                                DaemonResponse::Error(
+                                    "Request not permitted for remote clients".to_string(),
+                                )
+                            }
+                            _ if peer_denial.is_some() => {
+                                let reason = peer_denial.unwrap_or_default();
+                                warn!(client_id, ?peer_uid, request = ?request, %reason, "Denied request from local client");
+                                DaemonResponse::Error(reason)
+                            }
                             DaemonRequest::Subscribe => {
                                 let subscriber_count = clients.values().filter(|c| c.is_subscriber).count();
                                 if subscriber_count >= MAX_SUBSCRIBERS {
@@ -970,16 +1761,41 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                                 shutdown_requested = true;
                                 DaemonResponse::Ok
                             }
-                            DaemonRequest::SetBroadcastInterval { interval_ms } => {
-                                let new_interval = (*interval_ms).max(100);
-                                if new_interval != broadcast_interval_ms {
-                                    broadcast_interval_ms = new_interval;
-                                    broadcast_tick = tokio::time::interval(Duration::from_millis(broadcast_interval_ms));
-                                    broadcast_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-                                    info!(broadcast_interval_ms, "Broadcast interval updated");
+                            DaemonRequest::ReloadConfig => {
+                                info!(client_id, "Config reload requested by client");
+                                apply_config(&mut state, UserConfig::load(), &mut sample_tick);
+                                DaemonResponse::Ok
+                            }
+                            DaemonRequest::SetConfig { key, value } => {
+                                // Start from the file on disk so settings saved by
+                                // other clients since startup are preserved.
+                                let mut updated = UserConfig::load();
+                                match updated.set_value(key, value) {
+                                    Ok(()) => match updated.save() {
+                                        Ok(()) => {
+                                            info!(client_id, key = %key, "Config value set by client");
+                                            apply_config(&mut state, updated, &mut sample_tick);
+                                            DaemonResponse::Ok
+                                        }
+                                        Err(e) => DaemonResponse::Error(format!("Failed to save config: {}", e)),
+                                    },
+                                    Err(e) => DaemonResponse::Error(e.to_string()),
                                 }
+                            }
+                            DaemonRequest::SetBroadcastInterval { interval_ms } => {
+                                // Applied at the top of the loop, together with
+                                // any slowdown from the battery saver
+                                broadcast_interval_ms = (*interval_ms).max(100);
                                 DaemonResponse::Ok
                             }
+                            DaemonRequest::UndoBatterySaver => {
+                                info!(client_id, "Battery saver undo requested by client");
+                                state.undo_battery_saver()
+                            }
+                            DaemonRequest::Throttle { target, cpu_percent, battery_only } => {
+                                state.throttle(target.clone(), *cpu_percent, *battery_only)
+                            }
+                            DaemonRequest::RemoveThrottle { target } => state.remove_throttle(target),
                             _ => {
                                 let subscriber_count = clients.values().filter(|c| c.is_subscriber).count();
                                 state.handle_request(&request, subscriber_count)
@@ -989,6 +1805,9 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
                         if let Some(client) = clients.get(&client_id) {
                             let _ = client.response_tx.send(response).await;
                         }
+                        if drop_client {
+                            clients.remove(&client_id);
+                        }
 
                         if shutdown_requested {
                             break;
@@ -1000,7 +1819,13 @@ async fn run_daemon_async(socket: std::path::PathBuf) -> Result<()> {
     }
 
     info!("Daemon shutting down");
+    state.stop_battery_saver();
+    state.stop_throttles();
+    state.record_event(EventKind::DaemonStop, "Daemon stopped".to_string());
     state.shutdown_worker();
+    if let Some(publisher) = mqtt_publisher {
+        publisher.shutdown().await;
+    }
     fs::remove_file(&socket).ok();
 
     Ok(())
@@ -0,0 +1,527 @@
+//! CPU throttling with cgroup v2.
+//!
+//! A throttle moves the processes it targets into a group in jolt's slice
+//! (see [`crate::data::cgroup`]) and caps the group's CPU time; processes
+//! they start inherit the group. A process in an application's own systemd
+//! scope brings the rest of the scope along, so helpers are capped too.
+//! Battery-only throttles lift the cap while on external power. Removing a
+//! throttle, or stopping the daemon, moves the processes back.
+
+use std::collections::HashSet;
+
+use tracing::debug;
+
+use crate::daemon::protocol::{
+    BatteryState, DataSnapshot, ProcessSnapshot, ThrottleSnapshot, ThrottleTarget,
+};
+use crate::data::cgroup::{cgroup_of, is_app_scope, Slice};
+use crate::data::processes::find_by_name;
+
+struct Throttle {
+    target: ThrottleTarget,
+    /// Name of the throttle's group in the slice
+    group: String,
+    cpu_percent: u32,
+    battery_only: bool,
+    active: bool,
+    since: i64,
+    pids: Vec<u32>,
+    /// Cgroup each moved process came from, to move it back on removal
+    origins: Vec<(u32, String)>,
+    energy_before: f32,
+    energy_sum: f32,
+    energy_samples: u32,
+}
+
+impl Throttle {
+    fn snapshot(&self) -> ThrottleSnapshot {
+        ThrottleSnapshot {
+            target: self.target.clone(),
+            pids: self.pids.clone(),
+            cpu_percent: self.cpu_percent,
+            battery_only: self.battery_only,
+            active: self.active,
+            since: self.since,
+            energy_before: self.energy_before,
+            energy_after: (self.energy_samples > 0)
+                .then(|| self.energy_sum / self.energy_samples as f32),
+        }
+    }
+
+    /// Applies or lifts the cap for the current power source.
+    fn apply(&mut self, slice: &Slice, on_battery: bool) -> Result<(), String> {
+        let active = !self.battery_only || on_battery;
+        slice
+            .set_limit(&self.group, active.then_some(self.cpu_percent))
+            .map_err(|e| e.to_string())?;
+        self.active = active;
+        Ok(())
+    }
+}
+
+pub struct Throttler {
+    slice: Option<Slice>,
+    throttles: Vec<Throttle>,
+    on_battery: bool,
+}
+
+impl Throttler {
+    /// Creates the throttler, lifting caps left behind by a previous daemon.
+    pub fn new() -> Self {
+        if let Ok(slice) = Slice::locate() {
+            clear_leftovers(&slice);
+        }
+        Self {
+            slice: None,
+            throttles: Vec::new(),
+            on_battery: false,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_slice(slice: Slice) -> Self {
+        Self {
+            slice: Some(slice),
+            throttles: Vec::new(),
+            on_battery: false,
+        }
+    }
+
+    /// The slice, created the first time a throttle needs it.
+    fn slice(&mut self) -> Result<Slice, String> {
+        if let Some(slice) = &self.slice {
+            return Ok(slice.clone());
+        }
+        let slice = Slice::locate().map_err(|e| e.to_string())?;
+        slice.ensure().map_err(|e| e.to_string())?;
+        self.slice = Some(slice.clone());
+        Ok(slice)
+    }
+
+    /// Caps `target` at `cpu_percent` of one CPU. Throttling a target again
+    /// changes its cap.
+    pub fn throttle(
+        &mut self,
+        target: ThrottleTarget,
+        cpu_percent: u32,
+        battery_only: bool,
+        processes: &[ProcessSnapshot],
+        timestamp: i64,
+    ) -> Result<ThrottleSnapshot, String> {
+        if cpu_percent == 0 {
+            return Err("CPU cap must be at least 1%".to_string());
+        }
+        let slice = self.slice()?;
+        let on_battery = self.on_battery;
+
+        if let Some(throttle) = self.throttles.iter_mut().find(|t| t.target == target) {
+            throttle.cpu_percent = cpu_percent;
+            throttle.battery_only = battery_only;
+            throttle.apply(&slice, on_battery)?;
+            return Ok(throttle.snapshot());
+        }
+
+        let members = members(&slice, &target)?;
+        let group = group_name(&target);
+        slice.create_group(&group).map_err(|e| e.to_string())?;
+        let mut origins = Vec::new();
+        let mut error = None;
+        for (pid, origin) in members {
+            match slice.move_into(&group, pid) {
+                Ok(()) => origins.push((pid, origin)),
+                Err(e) => error = Some(e.to_string()),
+            }
+        }
+        if origins.is_empty() {
+            let _ = slice.remove_group(&group);
+            return Err(error.unwrap_or_else(|| format!("No process matches {}", target)));
+        }
+
+        let pids: Vec<u32> = origins.iter().map(|(pid, _)| *pid).collect();
+        let mut throttle = Throttle {
+            target,
+            group,
+            cpu_percent,
+            battery_only,
+            active: false,
+            since: timestamp,
+            energy_before: energy_of(processes, &pids),
+            pids,
+            origins,
+            energy_sum: 0.0,
+            energy_samples: 0,
+        };
+        if let Err(e) = throttle.apply(&slice, on_battery) {
+            release(&slice, &throttle);
+            return Err(e);
+        }
+        let snapshot = throttle.snapshot();
+        self.throttles.push(throttle);
+        Ok(snapshot)
+    }
+
+    /// Lifts the cap on `target` and moves its processes back.
+    pub fn remove(&mut self, target: &ThrottleTarget) -> Result<(), String> {
+        let index = self
+            .throttles
+            .iter()
+            .position(|t| t.target == *target)
+            .ok_or_else(|| format!("{} is not throttled", target))?;
+        let throttle = self.throttles.remove(index);
+        if let Some(slice) = &self.slice {
+            release(slice, &throttle);
+        }
+        Ok(())
+    }
+
+    /// Follows the power source and the throttled processes. Returns a
+    /// message for each throttle whose processes have all exited.
+    pub fn update(&mut self, snapshot: &DataSnapshot) -> Vec<String> {
+        self.on_battery = snapshot.battery.state == BatteryState::Discharging;
+        let Some(slice) = &self.slice else {
+            return Vec::new();
+        };
+
+        let mut ended = Vec::new();
+        self.throttles.retain_mut(|throttle| {
+            throttle.pids = slice.pids(&throttle.group);
+            if throttle.pids.is_empty() {
+                let _ = slice.remove_group(&throttle.group);
+                ended.push(format!(
+                    "CPU cap on {} ended: its processes exited",
+                    throttle.target
+                ));
+                return false;
+            }
+            if let Err(e) = throttle.apply(slice, self.on_battery) {
+                debug!(target = %throttle.target, error = %e, "Failed to update CPU cap");
+            }
+            if throttle.active {
+                throttle.energy_sum += energy_of(&snapshot.processes, &throttle.pids);
+                throttle.energy_samples += 1;
+            }
+            true
+        });
+        ended
+    }
+
+    /// Removes every throttle before the daemon exits.
+    pub fn shutdown(&mut self) {
+        if let Some(slice) = &self.slice {
+            for throttle in self.throttles.drain(..) {
+                release(slice, &throttle);
+            }
+        }
+    }
+
+    pub fn snapshots(&self) -> Vec<ThrottleSnapshot> {
+        self.throttles.iter().map(Throttle::snapshot).collect()
+    }
+}
+
+/// Processes `target` covers, with the cgroup each is in now.
+fn members(slice: &Slice, target: &ThrottleTarget) -> Result<Vec<(u32, String)>, String> {
+    let pids = match target {
+        ThrottleTarget::Pid(pid) if *pid <= 1 || *pid == std::process::id() => {
+            return Err(format!("Process {} can't be throttled", pid));
+        }
+        ThrottleTarget::Pid(pid) => vec![*pid],
+        ThrottleTarget::Name(name) => find_by_name(std::slice::from_ref(name))
+            .into_iter()
+            .map(|(pid, _)| pid)
+            .collect(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut members = Vec::new();
+    for pid in pids {
+        let Some(cgroup) = cgroup_of(pid) else {
+            if matches!(target, ThrottleTarget::Pid(_)) {
+                return Err(format!("Process {} not found", pid));
+            }
+            continue;
+        };
+        let mut scope = if is_app_scope(&cgroup) {
+            slice.procs_in(&cgroup)
+        } else {
+            Vec::new()
+        };
+        scope.push(pid);
+        for member in scope {
+            if seen.insert(member) {
+                members.push((member, cgroup.clone()));
+            }
+        }
+    }
+    if members.is_empty() {
+        return Err(format!("No process matches {}", target));
+    }
+    Ok(members)
+}
+
+/// Lifts the cap and moves the processes back where they came from. Any
+/// that can't go back, such as ones whose systemd scope was removed once it
+/// emptied, stay in the group without a cap.
+fn release(slice: &Slice, throttle: &Throttle) {
+    let _ = slice.set_limit(&throttle.group, None);
+    for (pid, origin) in &throttle.origins {
+        let _ = slice.move_to(origin, *pid);
+    }
+    // Children started while throttled follow the first process back
+    if let Some((_, origin)) = throttle.origins.first() {
+        for pid in slice.pids(&throttle.group) {
+            let _ = slice.move_to(origin, pid);
+        }
+    }
+    if let Err(e) = slice.remove_group(&throttle.group) {
+        debug!(target = %throttle.target, error = %e, "CPU throttle group left in place");
+    }
+}
+
+/// Lifts the caps on groups a previous daemon left behind and moves their
+/// processes out, since a group can only be removed once it is empty. Where
+/// they came from is lost with that daemon, so they go to the slice's parent,
+/// or the root if that refuses them.
+fn clear_leftovers(slice: &Slice) {
+    for group in slice.groups() {
+        let _ = slice.set_limit(&group, None);
+        for pid in slice.pids(&group) {
+            if slice.move_to_parent(pid).is_err() {
+                let _ = slice.move_to("/", pid);
+            }
+        }
+        if let Err(e) = slice.remove_group(&group) {
+            debug!(%group, error = %e, "Leftover CPU throttle group left in place");
+        }
+    }
+}
+
+/// Group name for `target`, limited to characters safe in a cgroup name.
+fn group_name(target: &ThrottleTarget) -> String {
+    match target {
+        ThrottleTarget::Pid(pid) => format!("pid-{}", pid),
+        ThrottleTarget::Name(name) => {
+            let safe: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("name-{}", safe)
+        }
+    }
+}
+
+/// Combined energy impact of the listed processes that are in `processes`.
+fn energy_of(processes: &[ProcessSnapshot], pids: &[u32]) -> f32 {
+    processes
+        .iter()
+        .filter(|p| pids.contains(&p.pid))
+        .map(|p| p.energy_impact)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::{Child, Command};
+
+    use super::*;
+    use crate::daemon::protocol::{BatterySnapshot, ProcessState};
+    use crate::data::cgroup::FakeRoot;
+
+    /// The slice on `root`, ready for groups.
+    fn slice(root: &FakeRoot) -> Slice {
+        // The kernel fills this in for a real cgroup
+        fs::create_dir_all(root.0.join("jolt.slice")).unwrap();
+        fs::write(root.0.join("jolt.slice/cgroup.controllers"), "cpu").unwrap();
+        let slice = Slice::locate_in(&root.0, 0).unwrap();
+        slice.ensure().unwrap();
+        slice
+    }
+
+    fn throttler(root: &FakeRoot) -> Throttler {
+        Throttler::with_slice(slice(root))
+    }
+
+    struct Sleeper(Child);
+
+    impl Sleeper {
+        fn spawn() -> Self {
+            Self(Command::new("sleep").arg("30").spawn().unwrap())
+        }
+
+        fn pid(&self) -> u32 {
+            self.0.id()
+        }
+    }
+
+    impl Drop for Sleeper {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn process(pid: u32, energy_impact: f32) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            name: "sleep".to_string(),
+            command: "sleep".to_string(),
+            command_args: String::new(),
+            cpu_usage: 0.0,
+            memory_mb: 0.0,
+            energy_impact,
+            parent_pid: None,
+            children: None,
+            is_killable: true,
+            disk_read_bytes: 0,
+            disk_write_bytes: 0,
+            status: ProcessState::Sleeping,
+            run_time_secs: 0,
+            total_cpu_time_secs: 0,
+            throttled: false,
+        }
+    }
+
+    fn snapshot(state: BatteryState, processes: Vec<ProcessSnapshot>) -> DataSnapshot {
+        DataSnapshot {
+            battery: BatterySnapshot {
+                state,
+                ..Default::default()
+            },
+            processes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn group_names_are_safe() {
+        assert_eq!(group_name(&ThrottleTarget::Pid(42)), "pid-42");
+        assert_eq!(
+            group_name(&ThrottleTarget::Name("Web Content/1".to_string())),
+            "name-Web_Content_1"
+        );
+    }
+
+    #[test]
+    fn refuses_init_and_zero_cap() {
+        let root = FakeRoot::new("throttle-refuse");
+        let mut throttler = throttler(&root);
+        assert!(throttler
+            .throttle(ThrottleTarget::Pid(1), 25, false, &[], 0)
+            .is_err());
+        assert!(throttler
+            .throttle(ThrottleTarget::Pid(42), 0, false, &[], 0)
+            .is_err());
+        assert!(throttler.remove(&ThrottleTarget::Pid(42)).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn caps_compares_energy_and_removes() {
+        let root = FakeRoot::new("throttle-caps");
+        let sleeper = Sleeper::spawn();
+        let pid = sleeper.pid();
+        let target = ThrottleTarget::Pid(pid);
+        let group = format!("jolt.slice/pid-{}", pid);
+        let mut throttler = throttler(&root);
+
+        let throttle = throttler
+            .throttle(target.clone(), 25, false, &[process(pid, 8.0)], 100)
+            .unwrap();
+        assert!(throttle.active);
+        assert_eq!(throttle.pids, vec![pid]);
+        assert_eq!(throttle.energy_before, 8.0);
+        assert_eq!(root.read(&format!("{}/cpu.max", group)), "25000 100000");
+        assert_eq!(
+            root.read(&format!("{}/cgroup.procs", group)),
+            pid.to_string()
+        );
+
+        throttler.update(&snapshot(BatteryState::Charging, vec![process(pid, 2.0)]));
+        throttler.update(&snapshot(BatteryState::Charging, vec![process(pid, 3.0)]));
+        let snapshots = throttler.snapshots();
+        assert_eq!(snapshots[0].energy_after, Some(2.5));
+
+        // Throttling again changes the cap
+        throttler
+            .throttle(target.clone(), 50, false, &[], 200)
+            .unwrap();
+        assert_eq!(root.read(&format!("{}/cpu.max", group)), "50000 100000");
+        assert_eq!(throttler.snapshots()[0].since, 100);
+
+        throttler.remove(&target).unwrap();
+        assert!(throttler.snapshots().is_empty());
+        assert_eq!(root.read(&format!("{}/cpu.max", group)), "max 100000");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn battery_only_follows_power_source() {
+        let root = FakeRoot::new("throttle-battery");
+        let sleeper = Sleeper::spawn();
+        let pid = sleeper.pid();
+        let cpu_max = format!("jolt.slice/pid-{}/cpu.max", pid);
+        let mut throttler = throttler(&root);
+
+        let throttle = throttler
+            .throttle(ThrottleTarget::Pid(pid), 25, true, &[], 0)
+            .unwrap();
+        assert!(!throttle.active);
+        assert_eq!(root.read(&cpu_max), "max 100000");
+
+        throttler.update(&snapshot(
+            BatteryState::Discharging,
+            vec![process(pid, 1.0)],
+        ));
+        assert!(throttler.snapshots()[0].active);
+        assert_eq!(root.read(&cpu_max), "25000 100000");
+
+        // Energy is only averaged while capped
+        throttler.update(&snapshot(BatteryState::Charging, vec![process(pid, 9.0)]));
+        let snapshots = throttler.snapshots();
+        assert!(!snapshots[0].active);
+        assert_eq!(snapshots[0].energy_after, Some(1.0));
+        assert_eq!(root.read(&cpu_max), "max 100000");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn ends_when_processes_exit() {
+        let root = FakeRoot::new("throttle-exit");
+        let sleeper = Sleeper::spawn();
+        let pid = sleeper.pid();
+        let mut throttler = throttler(&root);
+        throttler
+            .throttle(ThrottleTarget::Pid(pid), 25, false, &[], 0)
+            .unwrap();
+
+        // The kernel empties cgroup.procs as the processes exit
+        fs::write(
+            root.0.join(format!("jolt.slice/pid-{}/cgroup.procs", pid)),
+            "",
+        )
+        .unwrap();
+        let ended = throttler.update(&snapshot(BatteryState::Discharging, Vec::new()));
+        assert_eq!(ended.len(), 1);
+        assert!(throttler.snapshots().is_empty());
+    }
+
+    #[test]
+    fn startup_moves_leftovers_out_and_lifts_caps() {
+        let root = FakeRoot::new("throttle-leftovers");
+        let slice = slice(&root);
+        slice.create_group("pid-42").unwrap();
+        slice.set_limit("pid-42", Some(25)).unwrap();
+        slice.move_into("pid-42", 42).unwrap();
+
+        clear_leftovers(&slice);
+        assert_eq!(root.read("jolt.slice/pid-42/cpu.max"), "max 100000");
+        assert_eq!(root.read("cgroup.procs"), "42");
+    }
+}
@@ -1,11 +1,22 @@
-use chrono::{DateTime, Duration, Timelike, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
 use tracing::debug;
 
 use crate::config::HistoryConfig;
+use crate::data::history_store::DAY_ZONE_KEY;
+use crate::data::timezone::format_date;
 use crate::data::{
-    DailyCycle, DailyStat, HistoryStore, HistoryStoreError, HourlyStat, SessionType,
+    DailyCycle, DailyStat, DailyTopProcess, DayZone, HistoryStore, HistoryStoreError,
+    HourlyProcessStat, HourlyStat, SessionType,
 };
 
+/// Size pruning stops at this share of the budget so the next few samples
+/// don't push the database straight back over it.
+const SIZE_PRUNE_TARGET_PERCENT: u64 = 90;
+/// Rows deleted per step while pruning for size
+const SIZE_PRUNE_BATCH: usize = 5000;
+
 pub struct Aggregator<'a> {
     store: &'a HistoryStore,
     config: &'a HistoryConfig,
@@ -55,9 +66,47 @@ impl<'a> Aggregator<'a> {
         }))
     }
 
+    /// Rolls one hour of stored process samples up into a row per process.
+    pub fn compute_hourly_processes(
+        &self,
+        hour_start: i64,
+    ) -> Result<Vec<HourlyProcessStat>, HistoryStoreError> {
+        let samples = self
+            .store
+            .get_process_samples(hour_start, hour_start + 3600 - 1)?;
+        let sample_hours = self.config.sample_interval_secs as f32 / 3600.0;
+
+        let mut totals: BTreeMap<&str, ProcessTotals> = BTreeMap::new();
+        for sample in &samples {
+            let total = totals.entry(&sample.process_name).or_default();
+            total.impact += sample.energy_impact;
+            total.cpu += sample.cpu_usage;
+            total.memory_mb += sample.memory_mb;
+            total.power += sample.power_watts;
+            total.count += 1;
+        }
+
+        Ok(totals
+            .into_iter()
+            .map(|(name, total)| {
+                let count = total.count as f32;
+                HourlyProcessStat {
+                    id: None,
+                    hour_start,
+                    process_name: name.to_string(),
+                    avg_impact: total.impact / count,
+                    avg_cpu: total.cpu / count,
+                    avg_memory_mb: total.memory_mb / count,
+                    avg_power: total.power / count,
+                    total_energy_wh: total.power * sample_hours,
+                    sample_count: total.count,
+                }
+            })
+            .collect())
+    }
+
     pub fn compute_daily_stats(&self, date: &str) -> Result<Option<DailyStat>, HistoryStoreError> {
-        let day_start = date_to_timestamp(date)?;
-        let day_end = day_start + 86400;
+        let (day_start, day_end) = self.day_bounds(date)?;
 
         let samples = self.store.get_samples(day_start, day_end - 1)?;
         if samples.is_empty() {
@@ -103,8 +152,7 @@ impl<'a> Aggregator<'a> {
         &self,
         date: &str,
     ) -> Result<Option<DailyCycle>, HistoryStoreError> {
-        let day_start = date_to_timestamp(date)?;
-        let day_end = day_start + 86400;
+        let (day_start, day_end) = self.day_bounds(date)?;
 
         let sessions = self
             .store
@@ -207,6 +255,8 @@ impl<'a> Aggregator<'a> {
         let current_hour = now.date_naive().and_hms_opt(now.hour(), 0, 0).unwrap();
         let current_hour_ts = current_hour.and_utc().timestamp();
 
+        self.aggregate_process_hours(current_hour_ts)?;
+
         let stats = self.store.get_stats()?;
         let oldest_sample = match stats.oldest_sample {
             Some(ts) => ts,
@@ -240,8 +290,37 @@ impl<'a> Aggregator<'a> {
         Ok(aggregated)
     }
 
+    /// Rolls up every hour before `until` that has process samples but no
+    /// per-process rows yet.
+    fn aggregate_process_hours(&self, until: i64) -> Result<(), HistoryStoreError> {
+        let Some(oldest) = self.store.oldest_process_sample()? else {
+            return Ok(());
+        };
+
+        let mut hour_ts = oldest - oldest.rem_euclid(3600);
+        while hour_ts < until {
+            if !self.store.has_hourly_processes(hour_ts)? {
+                self.replace_hourly_processes(hour_ts)?;
+            }
+            hour_ts += 3600;
+        }
+        Ok(())
+    }
+
+    /// Recomputes one hour's per-process rows, leaving the hour alone when
+    /// it has no process samples, e.g. because they were pruned.
+    fn replace_hourly_processes(&self, hour_start: i64) -> Result<(), HistoryStoreError> {
+        let processes = self.compute_hourly_processes(hour_start)?;
+        if !processes.is_empty() {
+            self.store
+                .replace_hourly_processes(hour_start, &processes)?;
+        }
+        Ok(())
+    }
+
     pub fn aggregate_completed_days(&self) -> Result<usize, HistoryStoreError> {
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let zone = self.config.timezone;
+        let today = zone.today();
 
         let stats = self.store.get_stats()?;
         let oldest_sample = match stats.oldest_sample {
@@ -249,9 +328,7 @@ impl<'a> Aggregator<'a> {
             None => return Ok(0),
         };
 
-        let oldest_date = DateTime::from_timestamp(oldest_sample, 0)
-            .map(|dt| dt.format("%Y-%m-%d").to_string())
-            .unwrap_or_else(|| today.clone());
+        let oldest_date = format_date(zone.date_of(oldest_sample));
 
         let mut aggregated = 0;
         let mut current_date = oldest_date;
@@ -278,6 +355,199 @@ impl<'a> Aggregator<'a> {
         Ok(aggregated)
     }
 
+    /// Recomputes completed hours and days overlapping `[from, to]`,
+    /// replacing any existing rows. Used after samples are backfilled.
+    /// Returns the number of hourly and daily rows written.
+    pub fn reaggregate_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<(usize, usize), HistoryStoreError> {
+        let now = Utc::now().timestamp();
+        let current_hour = now - now.rem_euclid(3600);
+
+        let mut hours = 0;
+        let mut hour_ts = from - from.rem_euclid(3600);
+        while hour_ts <= to && hour_ts < current_hour {
+            if let Some(stat) = self.compute_hourly_stats(hour_ts)? {
+                self.store.upsert_hourly_stat(&stat)?;
+                hours += 1;
+            }
+            self.replace_hourly_processes(hour_ts)?;
+            hour_ts += 3600;
+        }
+
+        let zone = self.config.timezone;
+        let today = zone.date_of(now);
+        let mut days = 0;
+        let mut date = zone.date_of(from);
+        while date <= zone.date_of(to) && date < today {
+            let day = format_date(date);
+            if let Some(stat) = self.compute_daily_stats(&day)? {
+                self.store.upsert_daily_stat(&stat)?;
+                days += 1;
+            }
+            if let Some(cycle) = self.compute_daily_cycles(&day)? {
+                self.store.upsert_daily_cycle(&cycle)?;
+            }
+            date += Duration::days(1);
+        }
+
+        Ok((hours, days))
+    }
+
+    /// Recomputes every hourly and daily row that raw samples fully cover,
+    /// dropping rows in that span that no longer have samples behind them.
+    /// Older rows, whose samples were pruned, are kept. Returns the number
+    /// of hourly and daily rows written.
+    pub fn rebuild_aggregates(&self) -> Result<(usize, usize), HistoryStoreError> {
+        let Some(oldest) = self.store.get_stats()?.oldest_sample else {
+            return Ok((0, 0));
+        };
+
+        let first_hour = oldest + (3600 - oldest.rem_euclid(3600)) % 3600;
+        let first_day = format_date(first_full_day(self.config.timezone, oldest));
+        self.store.delete_hourly_stats_since(first_hour)?;
+        self.store.delete_daily_aggregates_since(&first_day)?;
+        if let Some(oldest) = self.store.oldest_process_sample()? {
+            let first_process_hour = oldest + (3600 - oldest.rem_euclid(3600)) % 3600;
+            self.store
+                .delete_hourly_processes_since(first_process_hour)?;
+        }
+
+        Ok((
+            self.aggregate_completed_hours()?,
+            self.aggregate_completed_days()?,
+        ))
+    }
+
+    /// Rebuilds daily rows computed with different day boundaries than the
+    /// configured zone, e.g. rows from before local-time days were supported
+    /// or written before `history.timezone` changed. Only days fully covered
+    /// by raw samples or hourly process rows can be rebuilt; older rows are
+    /// left as they are. Returns the number of days rebuilt, or `None` if
+    /// the rows already match.
+    pub fn realign_daily_rows(&self) -> Result<Option<usize>, HistoryStoreError> {
+        let zone = self.config.timezone;
+        let zone_id = zone.to_string();
+
+        match self.store.get_metadata(DAY_ZONE_KEY)? {
+            Some(stored) if stored == zone_id => return Ok(None),
+            Some(stored) => {
+                debug!(from = %stored, to = %zone_id, "Day boundaries changed, rebuilding daily rows");
+            }
+            None => {
+                self.store.set_metadata(DAY_ZONE_KEY, &zone_id)?;
+                return Ok(None);
+            }
+        }
+
+        let mut rebuilt = 0;
+
+        if let Some(oldest) = self.store.get_stats()?.oldest_sample {
+            let first_day = format_date(first_full_day(zone, oldest));
+            self.store.delete_daily_aggregates_since(&first_day)?;
+            rebuilt = self.aggregate_completed_days()?;
+        }
+
+        if let Some(oldest) = self.store.oldest_hourly_process()? {
+            let first_day = first_full_day(zone, oldest);
+            self.store
+                .delete_daily_processes_since(&format_date(first_day))?;
+
+            // Completed hours come from their rolled-up rows, the current
+            // hour straight from its samples.
+            let now = Utc::now().timestamp();
+            let current_hour = now - now.rem_euclid(3600);
+            self.aggregate_process_hours(current_hour)?;
+
+            let hours = self
+                .store
+                .get_hourly_processes(zone.day_start(first_day), current_hour - 1)?;
+            let sample_hours = self.config.sample_interval_secs as f32 / 3600.0;
+            for sample in self.store.get_process_samples(current_hour, i64::MAX)? {
+                self.store.upsert_daily_process(&DailyTopProcess {
+                    id: None,
+                    date: format_date(zone.date_of(sample.timestamp)),
+                    process_name: sample.process_name,
+                    total_impact: sample.energy_impact,
+                    avg_cpu: sample.cpu_usage,
+                    avg_memory_mb: sample.memory_mb,
+                    sample_count: 1,
+                    avg_power: sample.power_watts,
+                    total_energy_wh: sample.power_watts * sample_hours,
+                })?;
+            }
+            for hour in hours {
+                self.store.upsert_daily_process(&DailyTopProcess {
+                    id: None,
+                    date: format_date(zone.date_of(hour.hour_start)),
+                    process_name: hour.process_name,
+                    total_impact: hour.avg_impact * hour.sample_count as f32,
+                    avg_cpu: hour.avg_cpu,
+                    avg_memory_mb: hour.avg_memory_mb,
+                    sample_count: hour.sample_count,
+                    avg_power: hour.avg_power,
+                    total_energy_wh: hour.total_energy_wh,
+                })?;
+            }
+        }
+
+        self.store.set_metadata(DAY_ZONE_KEY, &zone_id)?;
+        Ok(Some(rebuilt))
+    }
+
+    /// Delete the oldest raw samples, then hourly stats, then per-process
+    /// rows until the live data fits in `max_bytes`. Freed pages are not
+    /// returned to the filesystem; `size_after` is the size a vacuum would
+    /// leave.
+    pub fn prune_to_size(&self, max_bytes: u64) -> Result<SizePruneResult, HistoryStoreError> {
+        let target = max_bytes * SIZE_PRUNE_TARGET_PERCENT / 100;
+        let mut result = SizePruneResult {
+            size_before: self.store.size_bytes()?,
+            ..Default::default()
+        };
+
+        result.processes_deleted = self.prune_until(target, |store| {
+            store.delete_oldest_process_samples(SIZE_PRUNE_BATCH)
+        })?;
+        result.samples_deleted = self.prune_until(target, |store| {
+            store.delete_oldest_samples(SIZE_PRUNE_BATCH)
+        })?;
+        result.hourly_deleted = self.prune_until(target, |store| {
+            store.delete_oldest_hourly_stats(SIZE_PRUNE_BATCH)
+        })?;
+        result.processes_deleted += self.prune_until(target, |store| {
+            store.delete_oldest_process_rows(SIZE_PRUNE_BATCH)
+        })?;
+        result.size_after = self.store.used_bytes()?;
+
+        Ok(result)
+    }
+
+    fn day_bounds(&self, date: &str) -> Result<(i64, i64), HistoryStoreError> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+            HistoryStoreError::Database(rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+        })?;
+        Ok(self.config.timezone.day_bounds(date))
+    }
+
+    fn prune_until(
+        &self,
+        target: u64,
+        delete: impl Fn(&HistoryStore) -> Result<usize, HistoryStoreError>,
+    ) -> Result<usize, HistoryStoreError> {
+        let mut total = 0;
+        while self.store.used_bytes()? > target {
+            let deleted = delete(self.store)?;
+            if deleted == 0 {
+                break;
+            }
+            total += deleted;
+        }
+        Ok(total)
+    }
+
     pub fn prune_old_data(&self) -> Result<PruneResult, HistoryStoreError> {
         let now = Utc::now();
         let mut result = PruneResult::default();
@@ -286,17 +556,22 @@ impl<'a> Aggregator<'a> {
             let cutoff = now - Duration::days(self.config.retention_raw_days as i64);
             let cutoff_ts = cutoff.timestamp();
             result.samples_deleted = self.store.delete_samples_before(cutoff_ts)?;
+            result.process_samples_deleted = self.store.delete_process_samples_before(cutoff_ts)?;
         }
 
         if self.config.retention_hourly_days > 0 {
             let cutoff = now - Duration::days(self.config.retention_hourly_days as i64);
             let cutoff_ts = cutoff.timestamp();
             result.hourly_deleted = self.store.delete_hourly_stats_before(cutoff_ts)?;
+            result.hourly_processes_deleted =
+                self.store.delete_hourly_processes_before(cutoff_ts)?;
         }
 
         if self.config.retention_daily_days > 0 {
-            let cutoff = now - Duration::days(self.config.retention_daily_days as i64);
-            let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
+            let cutoff_date = self
+                .config
+                .timezone
+                .days_ago(self.config.retention_daily_days);
             result.daily_deleted = self.store.delete_daily_stats_before(&cutoff_date)?;
             result.processes_deleted = self.store.delete_daily_processes_before(&cutoff_date)?;
             result.daily_cycles_deleted = self.store.delete_daily_cycles_before(&cutoff_date)?;
@@ -307,6 +582,7 @@ impl<'a> Aggregator<'a> {
         result.sessions_deleted = self
             .store
             .delete_charge_sessions_before(session_cutoff_ts)?;
+        result.events_deleted = self.store.delete_events_before(session_cutoff_ts)?;
 
         if self.config.max_database_mb > 0 {
             let max_bytes = (self.config.max_database_mb as u64) * 1024 * 1024;
@@ -318,22 +594,10 @@ impl<'a> Aggregator<'a> {
                     max_mb = self.config.max_database_mb,
                     "Database size exceeded limit, pruning"
                 );
-                let stats = self.store.get_stats()?;
-                if stats.oldest_sample.is_some() {
-                    let target_size = max_bytes * 80 / 100;
-                    let ratio = target_size as f64 / current_size as f64;
-                    let samples_per_day =
-                        (86400_f64 / self.config.sample_interval_secs as f64).max(1.0);
-                    let estimated_days = (stats.sample_count as f64 * ratio) / samples_per_day;
-                    let min_retention_days: i64 = 7;
-                    let days_to_keep = (estimated_days.floor() as i64).max(min_retention_days);
-
-                    let cutoff = now - Duration::days(days_to_keep);
-                    let cutoff_ts = cutoff.timestamp();
-                    result.samples_deleted += self.store.delete_samples_before(cutoff_ts)?;
-                }
-
-                self.store.vacuum()?;
+                let mut size = self.prune_to_size(max_bytes)?;
+                self.store.incremental_vacuum()?;
+                size.size_after = self.store.size_bytes()?;
+                result.size = Some(size);
             }
         }
 
@@ -344,22 +608,36 @@ impl<'a> Aggregator<'a> {
 #[derive(Debug, Default)]
 pub struct PruneResult {
     pub samples_deleted: usize,
+    pub process_samples_deleted: usize,
     pub hourly_deleted: usize,
+    pub hourly_processes_deleted: usize,
     pub sessions_deleted: usize,
+    pub events_deleted: usize,
     pub daily_cycles_deleted: usize,
     pub daily_deleted: usize,
     pub processes_deleted: usize,
+    /// Set when the database was over `max_database_mb`
+    pub size: Option<SizePruneResult>,
 }
 
-fn date_to_timestamp(date: &str) -> Result<i64, HistoryStoreError> {
-    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .map(|d| {
-            let time = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
-            d.and_time(time).and_utc().timestamp()
-        })
-        .map_err(|e| {
-            HistoryStoreError::Database(rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
-        })
+/// Rows removed to bring the database back under its size budget
+#[derive(Debug, Default)]
+pub struct SizePruneResult {
+    pub size_before: u64,
+    pub size_after: u64,
+    pub samples_deleted: usize,
+    pub hourly_deleted: usize,
+    pub processes_deleted: usize,
+}
+
+/// First date whose whole span lies at or after `timestamp`.
+fn first_full_day(zone: DayZone, timestamp: i64) -> NaiveDate {
+    let date = zone.date_of(timestamp);
+    if zone.day_start(date) == timestamp {
+        date
+    } else {
+        date + Duration::days(1)
+    }
 }
 
 fn next_date(date: &str) -> String {
@@ -367,3 +645,90 @@ fn next_date(date: &str) -> String {
         .map(|d| (d + Duration::days(1)).format("%Y-%m-%d").to_string())
         .unwrap_or_else(|_| date.to_string())
 }
+
+/// Running sums for one process while rolling up an hour
+#[derive(Default)]
+struct ProcessTotals {
+    impact: f32,
+    cpu: f32,
+    memory_mb: f32,
+    power: f32,
+    count: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::data::{ChargingState, ProcessSample, Sample};
+
+    fn process_sample(timestamp: i64, name: &str, power_watts: f32) -> ProcessSample {
+        ProcessSample {
+            timestamp,
+            process_name: name.to_string(),
+            energy_impact: power_watts * 10.0,
+            cpu_usage: power_watts * 5.0,
+            memory_mb: 100.0,
+            power_watts,
+        }
+    }
+
+    #[test]
+    fn hourly_process_rows_are_rolled_up_from_samples() {
+        let store = HistoryStore::open_at(Path::new(":memory:")).unwrap();
+        let config = HistoryConfig {
+            sample_interval_secs: 60,
+            ..HistoryConfig::default()
+        };
+        let aggregator = Aggregator::new(&store, &config);
+
+        let now = Utc::now().timestamp();
+        let hour = now - now.rem_euclid(3600) - 2 * 3600;
+        store
+            .insert_process_samples(&[
+                process_sample(hour, "cargo", 2.0),
+                process_sample(hour + 70, "cargo", 4.0),
+                process_sample(hour + 70, "firefox", 1.0),
+                process_sample(now, "cargo", 9.0),
+            ])
+            .unwrap();
+
+        aggregator.aggregate_completed_hours().unwrap();
+        let rows = store.get_hourly_processes(0, i64::MAX).unwrap();
+        assert_eq!(rows.len(), 2, "the current hour is not rolled up yet");
+        let cargo = rows.iter().find(|r| r.process_name == "cargo").unwrap();
+        assert_eq!(cargo.hour_start, hour);
+        assert_eq!(cargo.sample_count, 2);
+        assert_eq!(cargo.avg_power, 3.0);
+        assert_eq!(cargo.avg_impact, 30.0);
+        assert!((cargo.total_energy_wh - 0.1).abs() < 1e-6);
+
+        // Late samples for a rolled-up hour are picked up by a reaggregation
+        store
+            .insert_process_samples(&[process_sample(hour + 130, "cargo", 6.0)])
+            .unwrap();
+        aggregator.reaggregate_range(hour, hour + 3599).unwrap();
+        let cargo = store.get_process_history("cargo", hour, hour).unwrap();
+        assert_eq!(cargo[0].sample_count, 3);
+        assert_eq!(cargo[0].avg_power, 4.0);
+
+        store
+            .insert_sample(&Sample {
+                id: None,
+                timestamp: hour,
+                battery_percent: 80.0,
+                power_watts: 10.0,
+                cpu_power: 5.0,
+                gpu_power: 1.0,
+                charging_state: ChargingState::Discharging,
+            })
+            .unwrap();
+        aggregator.rebuild_aggregates().unwrap();
+        let rows = store.get_hourly_processes(0, i64::MAX).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .any(|r| r.process_name == "cargo" && r.sample_count == 3));
+    }
+}
@@ -0,0 +1,198 @@
+//! Replays recorded samples to score each forecast estimator.
+//!
+//! Every `STEP_SECS` along each uninterrupted discharge, each estimator sees
+//! only the samples recorded up to that moment and predicts how far the
+//! battery will fall over the next `HORIZON_SECS`. The prediction is then
+//! compared with how far it actually fell.
+
+use crate::config::ForecastEstimator;
+use crate::data::estimator::{Reading, ESTIMATOR_HISTORY_SECS};
+use crate::data::{ChargingState, Sample};
+
+/// How far ahead each prediction looks
+pub const HORIZON_SECS: i64 = 3600;
+
+/// Time between predictions along a discharge
+const STEP_SECS: i64 = 300;
+
+/// A longer gap between samples ends a discharge run
+const MAX_GAP_SECS: i64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatorScore {
+    pub estimator: ForecastEstimator,
+    /// Predictions made
+    pub predictions: usize,
+    /// Points along the discharges where the estimator had no answer
+    pub skipped: usize,
+    /// Mean absolute error of the predicted drop, in percentage points
+    pub mean_abs_error: f32,
+    /// Mean signed error; positive means drain was overestimated
+    pub bias: f32,
+    /// Share of actual drops inside the predicted P10–P90 range
+    pub coverage: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Backtest {
+    /// Discharge runs long enough to score against
+    pub runs: usize,
+    pub scores: Vec<EstimatorScore>,
+}
+
+impl Backtest {
+    /// The estimator with the lowest error, if any made a prediction
+    pub fn best(&self) -> Option<&EstimatorScore> {
+        self.scores
+            .iter()
+            .filter(|s| s.predictions > 0)
+            .min_by(|a, b| a.mean_abs_error.total_cmp(&b.mean_abs_error))
+    }
+}
+
+/// Scores every estimator against `samples`, which may be in any order.
+pub fn run(samples: &[Sample], capacity_wh: f32, window_secs: i64) -> Backtest {
+    let mut sorted: Vec<&Sample> = samples.iter().collect();
+    sorted.sort_by_key(|s| s.timestamp);
+    let runs: Vec<Vec<Reading>> = discharge_runs(&sorted)
+        .into_iter()
+        .filter(|run| run.last().unwrap().timestamp - run[0].timestamp > HORIZON_SECS)
+        .collect();
+
+    let scores = ForecastEstimator::ALL
+        .iter()
+        .map(|&estimator| score(estimator, &runs, capacity_wh, window_secs))
+        .collect();
+    Backtest {
+        runs: runs.len(),
+        scores,
+    }
+}
+
+/// Splits samples into stretches of discharging with no long gaps
+fn discharge_runs(samples: &[&Sample]) -> Vec<Vec<Reading>> {
+    let mut runs = Vec::new();
+    let mut current: Vec<Reading> = Vec::new();
+    for sample in samples {
+        let continues = current
+            .last()
+            .is_some_and(|last| sample.timestamp - last.timestamp <= MAX_GAP_SECS);
+        if sample.charging_state != ChargingState::Discharging || !continues {
+            if current.len() > 1 {
+                runs.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        if sample.charging_state == ChargingState::Discharging {
+            current.push(Reading {
+                timestamp: sample.timestamp,
+                battery_percent: sample.battery_percent,
+                power_watts: sample.power_watts,
+            });
+        }
+    }
+    if current.len() > 1 {
+        runs.push(current);
+    }
+    runs
+}
+
+fn score(
+    estimator: ForecastEstimator,
+    runs: &[Vec<Reading>],
+    capacity_wh: f32,
+    window_secs: i64,
+) -> EstimatorScore {
+    let mut errors = Vec::new();
+    let mut covered = 0;
+    let mut skipped = 0;
+
+    for run in runs {
+        let end = run.last().unwrap().timestamp;
+        let mut at = run[0].timestamp + STEP_SECS;
+        while at + HORIZON_SECS <= end {
+            let now = run.partition_point(|r| r.timestamp <= at);
+            let history_start =
+                run[..now].partition_point(|r| r.timestamp < at - ESTIMATOR_HISTORY_SECS);
+            let later = run.partition_point(|r| r.timestamp <= at + HORIZON_SECS);
+            at += STEP_SECS;
+
+            let history = &run[history_start..now];
+            let Some(estimate) = estimator.estimate(history, capacity_wh, window_secs) else {
+                skipped += 1;
+                continue;
+            };
+            let actual = history.last().unwrap().battery_percent - run[later - 1].battery_percent;
+            let drop = |watts: f32| watts * HORIZON_SECS as f32 / 3600.0 / capacity_wh * 100.0;
+            let predicted = drop(estimate.watts);
+            errors.push(predicted - actual);
+            if (drop(estimate.low_watts)..=drop(estimate.high_watts)).contains(&actual) {
+                covered += 1;
+            }
+        }
+    }
+
+    let n = errors.len().max(1) as f32;
+    EstimatorScore {
+        estimator,
+        predictions: errors.len(),
+        skipped,
+        mean_abs_error: errors.iter().map(|e| e.abs()).sum::<f32>() / n,
+        bias: errors.iter().sum::<f32>() / n,
+        coverage: covered as f32 / n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, battery_percent: f32, power_watts: f32) -> Sample {
+        Sample {
+            id: None,
+            timestamp,
+            battery_percent,
+            power_watts,
+            cpu_power: 0.0,
+            gpu_power: 0.0,
+            charging_state: ChargingState::Discharging,
+        }
+    }
+
+    /// Two hours at 10 W on a 100 Wh battery, sampled every minute, then a
+    /// charge, a gap and a discharge too short to score
+    fn history() -> Vec<Sample> {
+        let mut samples: Vec<Sample> = (0..=120)
+            .map(|m| sample(m * 60, 90.0 - m as f32 / 6.0, 10.0))
+            .collect();
+        let mut charging = sample(121 * 60, 70.0, 0.0);
+        charging.charging_state = ChargingState::Charging;
+        samples.push(charging);
+        samples.extend((0..30).map(|m| sample(20_000 + m * 60, 80.0, 8.0)));
+        samples
+    }
+
+    #[test]
+    fn splits_runs_on_charging_and_gaps() {
+        let samples = history();
+        let sorted: Vec<&Sample> = samples.iter().collect();
+        let runs = discharge_runs(&sorted);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].len(), 121);
+        assert_eq!(runs[1].len(), 30);
+    }
+
+    #[test]
+    fn steady_drain_scores_well() {
+        let backtest = run(&history(), 100.0, 900);
+        assert_eq!(backtest.runs, 1);
+
+        let window = &backtest.scores[0];
+        assert_eq!(window.estimator, ForecastEstimator::Window);
+        // Every 5 minutes from 5 to 60 minutes into the run, but at
+        // 5 minutes there are too few samples
+        assert_eq!((window.predictions, window.skipped), (11, 1));
+        assert!(window.mean_abs_error < 0.1, "{window:?}");
+        assert!(backtest.best().is_some());
+    }
+}
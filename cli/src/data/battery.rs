@@ -1,7 +1,8 @@
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use color_eyre::eyre::Result;
-use jolt_platform::BatteryProvider;
+use jolt_platform::{BatteryInfo, BatteryProvider};
 
 use crate::daemon::{BatterySnapshot, BatteryState as ProtocolBatteryState};
 
@@ -17,7 +18,8 @@ type PlatformBattery = jolt_platform::linux::LinuxBattery;
 compile_error!("BatteryData (PlatformBattery) is only supported on macOS and Linux targets.");
 
 pub struct BatteryData {
-    provider: PlatformBattery,
+    /// `None` when every reading comes from a daemon snapshot
+    provider: Option<PlatformBattery>,
     time_to_full: Option<Duration>,
     time_to_empty: Option<Duration>,
     cached_snapshot: Option<BatterySnapshot>,
@@ -34,17 +36,39 @@ impl BatteryData {
         Ok(Self {
             time_to_full: info.time_to_full,
             time_to_empty: info.time_to_empty,
-            provider,
+            provider: Some(provider),
             cached_snapshot: None,
         })
     }
 
+    /// Battery data fed only by daemon snapshots, for watching a remote
+    /// machine without touching (or needing) a local battery.
+    pub fn from_daemon() -> Self {
+        Self {
+            provider: None,
+            time_to_full: None,
+            time_to_empty: None,
+            cached_snapshot: None,
+        }
+    }
+
+    fn info(&self) -> &BatteryInfo {
+        static EMPTY: OnceLock<BatteryInfo> = OnceLock::new();
+        match &self.provider {
+            Some(provider) => provider.info(),
+            None => EMPTY.get_or_init(BatteryInfo::default),
+        }
+    }
+
     pub fn refresh(&mut self) -> Result<()> {
         let prev_state = self.state();
         let prev_external = self.external_connected();
 
-        self.provider.refresh()?;
-        let info = self.provider.info();
+        let Some(provider) = self.provider.as_mut() else {
+            return Ok(());
+        };
+        provider.refresh()?;
+        let info = provider.info();
         self.time_to_full = info.time_to_full;
         self.time_to_empty = info.time_to_empty;
         self.cached_snapshot = None;
@@ -58,7 +82,7 @@ impl BatteryData {
                 new_state = ?new_state,
                 prev_external,
                 new_external,
-                charge_percent = info.charge_percent,
+                charge_percent = self.charge_percent(),
                 "Battery state changed during refresh"
             );
         }
@@ -70,21 +94,21 @@ impl BatteryData {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.charge_percent;
         }
-        self.provider.info().charge_percent
+        self.info().charge_percent
     }
 
     pub fn max_capacity_wh(&self) -> f32 {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.max_capacity_wh;
         }
-        self.provider.info().max_capacity_wh
+        self.info().max_capacity_wh
     }
 
     pub fn design_capacity_wh(&self) -> f32 {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.design_capacity_wh;
         }
-        self.provider.info().design_capacity_wh
+        self.info().design_capacity_wh
     }
 
     pub fn state(&self) -> ChargeState {
@@ -97,7 +121,7 @@ impl BatteryData {
                 ProtocolBatteryState::Unknown => ChargeState::Unknown,
             };
         }
-        self.provider.info().state
+        self.info().state
     }
 
     pub fn state_label(&self) -> &'static str {
@@ -105,8 +129,8 @@ impl BatteryData {
     }
 
     pub fn time_remaining(&self) -> Option<Duration> {
-        let info = self.provider.info();
-        let system_estimate = match info.state {
+        let state = self.state();
+        let system_estimate = match state {
             ChargeState::Charging => self.time_to_full,
             ChargeState::Discharging => self.time_to_empty,
             _ => None,
@@ -116,10 +140,10 @@ impl BatteryData {
             return system_estimate;
         }
 
-        if info.state == ChargeState::Discharging {
+        if state == ChargeState::Discharging {
             if let Some(watts) = self.discharge_watts() {
                 if watts > 0.1 {
-                    let current_wh = info.max_capacity_wh * (info.charge_percent / 100.0);
+                    let current_wh = self.max_capacity_wh() * (self.charge_percent() / 100.0);
                     let hours_remaining = current_wh / watts;
                     let secs = (hours_remaining * 3600.0) as u64;
                     if secs > 0 && secs < 86400 {
@@ -157,14 +181,14 @@ impl BatteryData {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.cycle_count;
         }
-        self.provider.info().cycle_count
+        self.info().cycle_count
     }
 
     pub fn health_percent(&self) -> f32 {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.health_percent;
         }
-        self.provider.info().health_percent
+        self.info().health_percent
     }
 
     pub fn is_charging(&self) -> bool {
@@ -175,87 +199,87 @@ impl BatteryData {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.charging_watts;
         }
-        self.provider.info().charging_watts()
+        self.info().charging_watts()
     }
 
     pub fn charger_watts(&self) -> Option<u32> {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.charger_watts;
         }
-        self.provider.info().charger_watts
+        self.info().charger_watts
     }
 
     pub fn voltage_mv(&self) -> u32 {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.voltage_mv;
         }
-        self.provider.info().voltage_mv
+        self.info().voltage_mv
     }
 
     pub fn amperage_ma(&self) -> i32 {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.amperage_ma;
         }
-        self.provider.info().amperage_ma
+        self.info().amperage_ma
     }
 
     pub fn external_connected(&self) -> bool {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.external_connected;
         }
-        self.provider.info().external_connected
+        self.info().external_connected
     }
 
     pub fn temperature_c(&self) -> Option<f32> {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.temperature_c;
         }
-        self.provider.info().temperature_c
+        self.info().temperature_c
     }
 
     pub fn daily_min_soc(&self) -> Option<f32> {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.daily_min_soc;
         }
-        self.provider.info().daily_min_soc
+        self.info().daily_min_soc
     }
 
     pub fn daily_max_soc(&self) -> Option<f32> {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.daily_max_soc;
         }
-        self.provider.info().daily_max_soc
+        self.info().daily_max_soc
     }
 
     pub fn discharge_watts(&self) -> Option<f32> {
         if let Some(ref snapshot) = self.cached_snapshot {
             return snapshot.discharge_watts;
         }
-        self.provider.info().discharge_watts()
+        self.info().discharge_watts()
     }
 
     pub fn vendor(&self) -> Option<&str> {
-        self.provider.info().vendor.as_deref()
+        self.info().vendor.as_deref()
     }
 
     pub fn model(&self) -> Option<&str> {
-        self.provider.info().model.as_deref()
+        self.info().model.as_deref()
     }
 
     pub fn serial_number(&self) -> Option<&str> {
-        self.provider.info().serial_number.as_deref()
+        self.info().serial_number.as_deref()
     }
 
     pub fn technology(&self) -> BatteryTechnology {
-        self.provider.info().technology
+        self.info().technology
     }
 
     pub fn energy_wh(&self) -> f32 {
-        self.provider.info().energy_wh
+        self.info().energy_wh
     }
 
     pub fn energy_rate_watts(&self) -> f32 {
-        self.provider.info().energy_rate_watts
+        self.info().energy_rate_watts
     }
 
     pub fn update_from_snapshot(&mut self, snapshot: &BatterySnapshot) {
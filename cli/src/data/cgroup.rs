@@ -0,0 +1,348 @@
+//! cgroup v2 CPU limits.
+//!
+//! Throttled processes are moved into a group of their own inside
+//! `jolt.slice`, and the group's `cpu.max` caps their combined CPU time. Run
+//! as root, the slice sits at the top of the hierarchy. Otherwise it goes in
+//! the user's systemd manager (`user@<uid>.service`), the subtree systemd
+//! delegates to the user, so only processes already under it can be moved.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the cgroup that holds jolt's throttle groups.
+pub const SLICE: &str = "jolt.slice";
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// `cpu.max` period in microseconds, the kernel default
+const PERIOD_USEC: u64 = 100_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CgroupError {
+    #[error("cgroup v2 is not mounted at {}", .0.display())]
+    NotMounted(PathBuf),
+
+    #[error(
+        "No delegated cgroup at {}; run the daemon as root or under systemd --user",
+        .0.display()
+    )]
+    NotDelegated(PathBuf),
+
+    #[error("The cpu controller is not available in {}", .0.display())]
+    NoCpuController(PathBuf),
+
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+type Result<T> = std::result::Result<T, CgroupError>;
+
+/// The cgroup throttled processes are moved under.
+#[derive(Debug, Clone)]
+pub struct Slice {
+    root: PathBuf,
+    dir: PathBuf,
+}
+
+impl Slice {
+    /// Finds where the slice goes for the user the daemon runs as.
+    pub fn locate() -> Result<Self> {
+        // SAFETY: `libc::geteuid` is a read-only syscall with no arguments and
+        // cannot fail.
+        let uid = unsafe { libc::geteuid() };
+        Self::locate_in(Path::new(CGROUP_ROOT), uid)
+    }
+
+    pub(crate) fn locate_in(root: &Path, uid: u32) -> Result<Self> {
+        if !root.join("cgroup.controllers").exists() {
+            return Err(CgroupError::NotMounted(root.to_path_buf()));
+        }
+        let parent = if uid == 0 {
+            root.to_path_buf()
+        } else {
+            let manager = root
+                .join("user.slice")
+                .join(format!("user-{}.slice", uid))
+                .join(format!("user@{}.service", uid));
+            if !manager.is_dir() {
+                return Err(CgroupError::NotDelegated(manager));
+            }
+            manager
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            dir: parent.join(SLICE),
+        })
+    }
+
+    /// Creates the slice if needed and enables the cpu controller for the
+    /// groups inside it.
+    pub fn ensure(&self) -> Result<()> {
+        if let Some(parent) = self.dir.parent() {
+            enable_cpu(parent)?;
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+        enable_cpu(&self.dir)
+    }
+
+    fn group_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Creates the group `name` if it doesn't exist.
+    pub fn create_group(&self, name: &str) -> Result<()> {
+        let dir = self.group_dir(name);
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))
+    }
+
+    /// Caps the group at `cpu_percent` of one CPU, or lifts the cap.
+    pub fn set_limit(&self, name: &str, cpu_percent: Option<u32>) -> Result<()> {
+        write(&self.group_dir(name).join("cpu.max"), &cpu_max(cpu_percent))
+    }
+
+    /// Moves `pid` into the group `name`.
+    pub fn move_into(&self, name: &str, pid: u32) -> Result<()> {
+        write(&self.group_dir(name).join("cgroup.procs"), &pid.to_string())
+    }
+
+    /// Moves `pid` to `cgroup`, a path as read by [`cgroup_of`].
+    pub fn move_to(&self, cgroup: &str, pid: u32) -> Result<()> {
+        let dir = self.root.join(cgroup.trim_start_matches('/'));
+        write(&dir.join("cgroup.procs"), &pid.to_string())
+    }
+
+    /// Moves `pid` to the cgroup the slice sits in.
+    pub fn move_to_parent(&self, pid: u32) -> Result<()> {
+        let parent = self.dir.parent().unwrap_or(&self.root);
+        write(&parent.join("cgroup.procs"), &pid.to_string())
+    }
+
+    /// Processes in the group `name`; empty once they have all exited.
+    pub fn pids(&self, name: &str) -> Vec<u32> {
+        read_procs(&self.group_dir(name))
+    }
+
+    /// Processes in `cgroup`, a path as read by [`cgroup_of`].
+    pub fn procs_in(&self, cgroup: &str) -> Vec<u32> {
+        read_procs(&self.root.join(cgroup.trim_start_matches('/')))
+    }
+
+    /// Removes the group `name`, which fails while it still has processes.
+    pub fn remove_group(&self, name: &str) -> Result<()> {
+        let dir = self.group_dir(name);
+        match fs::remove_dir(&dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(io_error(&dir, e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Names of the groups in the slice.
+    pub fn groups(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut groups: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        groups.sort();
+        groups
+    }
+}
+
+fn enable_cpu(dir: &Path) -> Result<()> {
+    let has_cpu = |file: &str| {
+        fs::read_to_string(dir.join(file))
+            .map(|list| list.split_whitespace().any(|c| c == "cpu"))
+            .unwrap_or(false)
+    };
+    if has_cpu("cgroup.subtree_control") {
+        return Ok(());
+    }
+    if !has_cpu("cgroup.controllers") {
+        return Err(CgroupError::NoCpuController(dir.to_path_buf()));
+    }
+    write(&dir.join("cgroup.subtree_control"), "+cpu")
+}
+
+fn read_procs(dir: &Path) -> Vec<u32> {
+    fs::read_to_string(dir.join("cgroup.procs"))
+        .map(|procs| {
+            procs
+                .lines()
+                .filter_map(|l| l.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, source: io::Error) -> CgroupError {
+    CgroupError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// The cgroup v2 path of `pid`, e.g. `/user.slice/user-1000.slice/...`.
+pub fn cgroup_of(pid: u32) -> Option<String> {
+    let contents = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    parse_cgroup(&contents)
+}
+
+fn parse_cgroup(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::to_string)
+}
+
+/// Whether `cgroup` is the systemd scope of a single application, such as
+/// `app-firefox-1234.scope`, whose processes are throttled together. Login
+/// session scopes hold everything started from a terminal, so they aren't.
+pub fn is_app_scope(cgroup: &str) -> bool {
+    let leaf = cgroup.rsplit('/').next().unwrap_or_default();
+    leaf.ends_with(".scope") && !leaf.starts_with("session-") && leaf != "init.scope"
+}
+
+/// `cpu.max` contents for a cap of `cpu_percent` of one CPU, or no cap.
+pub fn cpu_max(cpu_percent: Option<u32>) -> String {
+    match cpu_percent {
+        Some(percent) => format!(
+            "{} {}",
+            PERIOD_USEC * u64::from(percent.max(1)) / 100,
+            PERIOD_USEC
+        ),
+        None => format!("max {}", PERIOD_USEC),
+    }
+}
+
+/// A directory laid out like a cgroup v2 mount. `name` must be unique among
+/// tests running at the same time.
+#[cfg(test)]
+pub(crate) struct FakeRoot(pub(crate) PathBuf);
+
+#[cfg(test)]
+impl FakeRoot {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("jolt-cgroup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cgroup.controllers"), "cpuset cpu io memory pids").unwrap();
+        Self(dir)
+    }
+
+    /// Contents of `path`, relative to the root.
+    pub(crate) fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.0.join(path)).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Drop for FakeRoot {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unified_hierarchy_line() {
+        let contents = "1:name=systemd:/\n0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            parse_cgroup(contents).as_deref(),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+        assert_eq!(parse_cgroup("1:cpu:/\n"), None);
+    }
+
+    #[test]
+    fn app_scopes_move_together() {
+        let app = "/user.slice/user-1000.slice/user@1000.service/app.slice/app-code-42.scope";
+        assert!(is_app_scope(app));
+        assert!(!is_app_scope("/user.slice/user-1000.slice/session-2.scope"));
+        assert!(!is_app_scope(
+            "/user.slice/user-1000.slice/user@1000.service/init.scope"
+        ));
+        assert!(!is_app_scope("/system.slice/cron.service"));
+    }
+
+    #[test]
+    fn cpu_max_scales_to_period() {
+        assert_eq!(cpu_max(Some(25)), "25000 100000");
+        assert_eq!(cpu_max(Some(150)), "150000 100000");
+        assert_eq!(cpu_max(None), "max 100000");
+    }
+
+    #[test]
+    fn locates_slice_for_root_and_users() {
+        let root = FakeRoot::new("locate");
+        let slice = Slice::locate_in(&root.0, 0).unwrap();
+        assert_eq!(slice.dir, root.0.join(SLICE));
+
+        assert!(matches!(
+            Slice::locate_in(&root.0, 1000),
+            Err(CgroupError::NotDelegated(_))
+        ));
+        let manager = root.0.join("user.slice/user-1000.slice/user@1000.service");
+        fs::create_dir_all(&manager).unwrap();
+        let slice = Slice::locate_in(&root.0, 1000).unwrap();
+        assert_eq!(slice.dir, manager.join(SLICE));
+
+        let missing = root.0.join("missing");
+        assert!(matches!(
+            Slice::locate_in(&missing, 0),
+            Err(CgroupError::NotMounted(_))
+        ));
+    }
+
+    #[test]
+    fn creates_and_limits_groups() {
+        let root = FakeRoot::new("groups");
+        let slice = Slice::locate_in(&root.0, 0).unwrap();
+        // The kernel fills these in for a real cgroup
+        fs::create_dir_all(&slice.dir).unwrap();
+        fs::write(slice.dir.join("cgroup.controllers"), "cpu memory").unwrap();
+        slice.ensure().unwrap();
+        let control = |dir: &Path| fs::read_to_string(dir.join("cgroup.subtree_control")).unwrap();
+        assert_eq!(control(&root.0), "+cpu");
+        assert_eq!(control(&slice.dir), "+cpu");
+
+        slice.create_group("pid-42").unwrap();
+        slice.set_limit("pid-42", Some(25)).unwrap();
+        slice.move_into("pid-42", 42).unwrap();
+        let group = slice.dir.join("pid-42");
+        assert_eq!(
+            fs::read_to_string(group.join("cpu.max")).unwrap(),
+            "25000 100000"
+        );
+        assert_eq!(slice.pids("pid-42"), vec![42]);
+        assert_eq!(slice.groups(), vec!["pid-42".to_string()]);
+
+        slice.move_to("/", 42).unwrap();
+        assert_eq!(root.read("cgroup.procs"), "42");
+    }
+
+    #[test]
+    fn cpu_controller_must_be_available() {
+        let root = FakeRoot::new("nocpu");
+        fs::write(root.0.join("cgroup.controllers"), "memory pids").unwrap();
+        let slice = Slice::locate_in(&root.0, 0).unwrap();
+        assert!(matches!(
+            slice.ensure(),
+            Err(CgroupError::NoCpuController(_))
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+//! Time-to-full forecast learned from completed charge sessions.
+//!
+//! Lithium batteries charge at a roughly constant rate (CC) up to a knee,
+//! then hold the voltage (CV) while the current tapers off toward full. For
+//! each charger wattage, the charging samples of past sessions are fitted to
+//! that shape: a constant rate below the knee, falling in proportion to the
+//! capacity still missing above it.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::data::{ChargeSession, ChargingState, HistoryStore, HistoryStoreError, SessionType};
+
+/// How far back charge sessions are learned from
+const CHARGE_LOOKBACK_DAYS: i64 = 90;
+
+/// Sessions needed before a charger's curve is trusted
+const MIN_SESSIONS: usize = 2;
+
+/// Rate observations needed to fit a curve
+const MIN_OBSERVATIONS: usize = 8;
+
+/// An observation spans at least this many points, or `OBSERVATION_SECS`
+const OBSERVATION_PERCENT: f32 = 2.0;
+const OBSERVATION_SECS: i64 = 900;
+
+/// A longer gap between samples is not charging we saw
+const MAX_GAP_SECS: i64 = 600;
+
+/// Knee assumed when the sessions never got far enough to show one
+const DEFAULT_KNEE_PERCENT: f32 = 80.0;
+
+/// The OS shows 100% from here; the CV taper never quite reaches full
+const FULL_PERCENT: f32 = 99.5;
+
+const STEP_PERCENT: f32 = 0.5;
+
+/// `(percent, points per hour)`: the charge rate seen around a percentage
+type Observation = (f32, f32);
+
+/// Where the charge estimate came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeForecastSource {
+    /// Fitted from this machine's charge sessions
+    Learned,
+    /// The OS time-to-full, when nothing has been learned yet
+    Os,
+}
+
+impl ChargeForecastSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChargeForecastSource::Learned => "learned",
+            ChargeForecastSource::Os => "OS-reported",
+        }
+    }
+}
+
+/// Time to each charge milestone; `None` for milestones already passed or
+/// that the source cannot tell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChargeForecast {
+    pub to_80: Option<Duration>,
+    pub to_limit: Option<Duration>,
+    pub to_full: Option<Duration>,
+    pub limit_percent: f32,
+    pub source: ChargeForecastSource,
+}
+
+/// CC/CV charge rate for one charger, in percentage points per hour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChargeCurve {
+    pub charger_watts: Option<u32>,
+    /// Rate below the knee
+    pub cc_rate: f32,
+    /// Charge level where the taper begins
+    pub knee_percent: f32,
+    pub sessions: usize,
+}
+
+impl ChargeCurve {
+    pub fn rate_at(&self, percent: f32) -> f32 {
+        if percent < self.knee_percent {
+            self.cc_rate
+        } else {
+            self.cc_rate * (100.0 - percent) / (100.0 - self.knee_percent)
+        }
+    }
+
+    /// Time to charge from `from` to `to` percent, or `None` if `from`
+    /// is already there.
+    pub fn time_between(&self, from: f32, to: f32) -> Option<Duration> {
+        let to = to.min(FULL_PERCENT);
+        if from >= to {
+            return None;
+        }
+        let mut hours = 0.0;
+        let mut percent = from;
+        while percent < to {
+            let step = STEP_PERCENT.min(to - percent);
+            hours += step / self.rate_at(percent + step / 2.0);
+            percent += step;
+        }
+        Some(Duration::from_secs((hours * 3600.0) as u64))
+    }
+
+    /// Least-squares fit of `(percent, rate)` observations. Each candidate
+    /// knee takes the median rate below it as the CC rate.
+    fn fit(
+        charger_watts: Option<u32>,
+        observations: &[Observation],
+        sessions: usize,
+    ) -> Option<Self> {
+        if sessions < MIN_SESSIONS || observations.len() < MIN_OBSERVATIONS {
+            return None;
+        }
+
+        let mut best: Option<(f32, f32, Self)> = None;
+        for knee in 50..=98 {
+            let knee_percent = knee as f32;
+            let mut below: Vec<f32> = observations
+                .iter()
+                .filter(|(percent, _)| *percent < knee_percent)
+                .map(|(_, rate)| *rate)
+                .collect();
+            if below.is_empty() {
+                continue;
+            }
+            below.sort_by(f32::total_cmp);
+            let curve = Self {
+                charger_watts,
+                cc_rate: below[below.len() / 2],
+                knee_percent,
+                sessions,
+            };
+            if curve.cc_rate <= 0.0 {
+                continue;
+            }
+            let error: f32 = observations
+                .iter()
+                .map(|(percent, rate)| (curve.rate_at(*percent) - rate).powi(2))
+                .sum();
+            // Knees above every observation fit equally well; prefer the
+            // typical one
+            let distance = (knee_percent - DEFAULT_KNEE_PERCENT).abs();
+            let better = best.as_ref().is_none_or(|(best_error, best_distance, _)| {
+                error < *best_error - f32::EPSILON
+                    || (error <= *best_error + f32::EPSILON && distance < *best_distance)
+            });
+            if better {
+                best = Some((error, distance, curve));
+            }
+        }
+        best.map(|(_, _, curve)| curve)
+    }
+}
+
+/// Charge curves for every charger seen, plus one pooled across them all
+#[derive(Debug, Clone, Default)]
+pub struct ChargeProfile {
+    curves: Vec<ChargeCurve>,
+    pooled: Option<ChargeCurve>,
+}
+
+impl ChargeProfile {
+    /// Learns from completed charge sessions and the charging samples
+    /// recorded during each.
+    pub fn from_sessions(sessions: &[(ChargeSession, Vec<(i64, f32)>)]) -> Self {
+        let mut by_charger: BTreeMap<Option<u32>, (Vec<Observation>, usize)> = BTreeMap::new();
+        let mut all = Vec::new();
+        for (session, samples) in sessions {
+            let observations = observations(samples);
+            if observations.is_empty() {
+                continue;
+            }
+            let entry = by_charger.entry(session.charger_watts).or_default();
+            entry.0.extend_from_slice(&observations);
+            entry.1 += 1;
+            all.extend(observations);
+        }
+
+        let session_count = by_charger.values().map(|(_, n)| n).sum();
+        Self {
+            curves: by_charger
+                .into_iter()
+                .filter_map(|(watts, (obs, n))| ChargeCurve::fit(watts, &obs, n))
+                .collect(),
+            pooled: ChargeCurve::fit(None, &all, session_count),
+        }
+    }
+
+    /// Loads the last `CHARGE_LOOKBACK_DAYS` of charge sessions from `store`.
+    pub fn load(store: &HistoryStore, now: i64) -> Result<Self, HistoryStoreError> {
+        let from = now - CHARGE_LOOKBACK_DAYS * 86400;
+        let mut sessions = Vec::new();
+        for session in store.get_charge_sessions(from, now, Some(SessionType::Charge))? {
+            let Some(end) = session.end_time.filter(|_| session.is_complete) else {
+                continue;
+            };
+            let samples = store
+                .get_samples(session.start_time, end)?
+                .into_iter()
+                .filter(|s| s.charging_state == ChargingState::Charging)
+                .map(|s| (s.timestamp, s.battery_percent))
+                .collect();
+            sessions.push((session, samples));
+        }
+        Ok(Self::from_sessions(&sessions))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pooled.is_none() && self.curves.is_empty()
+    }
+
+    /// The curve for `charger_watts`: its own if learned, else the nearest
+    /// wattage, else the pooled one.
+    pub fn curve_for(&self, charger_watts: Option<u32>) -> Option<&ChargeCurve> {
+        if let Some(curve) = self
+            .curves
+            .iter()
+            .find(|c| c.charger_watts == charger_watts)
+        {
+            return Some(curve);
+        }
+        if let Some(watts) = charger_watts {
+            let nearest = self
+                .curves
+                .iter()
+                .filter_map(|c| c.charger_watts.map(|w| (w.abs_diff(watts), c)))
+                .min_by_key(|(distance, _)| *distance);
+            if let Some((_, curve)) = nearest {
+                return Some(curve);
+            }
+        }
+        self.pooled.as_ref()
+    }
+}
+
+/// Charge forecast from `percent`, learned if possible, else from the OS
+/// time-to-full.
+pub fn forecast(
+    profile: Option<&ChargeProfile>,
+    percent: f32,
+    charger_watts: Option<u32>,
+    limit_percent: f32,
+    os_time_to_full: Option<Duration>,
+) -> Option<ChargeForecast> {
+    let limit_percent = limit_percent.clamp(1.0, 100.0);
+    if let Some(curve) = profile.and_then(|p| p.curve_for(charger_watts)) {
+        return Some(ChargeForecast {
+            to_80: curve.time_between(percent, 80.0),
+            to_limit: curve.time_between(percent, limit_percent),
+            to_full: curve.time_between(percent, 100.0),
+            limit_percent,
+            source: ChargeForecastSource::Learned,
+        });
+    }
+    os_time_to_full.map(|to_full| ChargeForecast {
+        to_80: None,
+        to_limit: None,
+        to_full: Some(to_full),
+        limit_percent,
+        source: ChargeForecastSource::Os,
+    })
+}
+
+/// Observations from one session's charging samples, each spanning enough
+/// time or charge to rise above the 1% reporting steps.
+fn observations(samples: &[(i64, f32)]) -> Vec<Observation> {
+    let mut observations = Vec::new();
+    let Some(mut anchor) = samples.first().copied() else {
+        return observations;
+    };
+    let mut last = anchor;
+    for &(timestamp, percent) in &samples[1..] {
+        if timestamp - last.0 > MAX_GAP_SECS {
+            anchor = (timestamp, percent);
+        } else if percent - anchor.1 >= OBSERVATION_PERCENT
+            || timestamp - anchor.0 >= OBSERVATION_SECS
+        {
+            let hours = (timestamp - anchor.0) as f32 / 3600.0;
+            let rise = percent - anchor.1;
+            if hours > 0.0 && rise >= 0.0 {
+                observations.push(((anchor.1 + percent) / 2.0, rise / hours));
+            }
+            anchor = (timestamp, percent);
+        }
+        last = (timestamp, percent);
+    }
+    observations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(cc_rate: f32, knee_percent: f32) -> ChargeCurve {
+        ChargeCurve {
+            charger_watts: None,
+            cc_rate,
+            knee_percent,
+            sessions: 3,
+        }
+    }
+
+    /// Samples every minute charging along `curve` from `start` percent,
+    /// reported in whole percent
+    fn charge(curve: &ChargeCurve, start_time: i64, start: f32) -> Vec<(i64, f32)> {
+        let mut percent = start;
+        (0..240)
+            .map(|minute| {
+                let sample = (start_time + minute * 60, percent.floor());
+                percent = (percent + curve.rate_at(percent) / 60.0).min(100.0);
+                sample
+            })
+            .collect()
+    }
+
+    fn session(start_time: i64, charger_watts: Option<u32>) -> ChargeSession {
+        ChargeSession {
+            end_time: Some(start_time + 4 * 3600),
+            is_complete: true,
+            ..ChargeSession::new_charge(start_time, 20.0, charger_watts)
+        }
+    }
+
+    #[test]
+    fn taper_slows_the_last_stretch() {
+        let curve = curve(60.0, 80.0);
+        // 20% to 80% at 60 points an hour
+        let cc = curve.time_between(20.0, 80.0).unwrap().as_secs();
+        assert!((3599..=3600).contains(&cc), "{cc}");
+        let taper = curve.time_between(80.0, 100.0).unwrap();
+        assert!(taper > Duration::from_secs(3600), "{taper:?}");
+        assert_eq!(curve.time_between(90.0, 80.0), None);
+    }
+
+    #[test]
+    fn learns_each_charger_and_falls_back_to_nearest() {
+        let fast = curve(90.0, 75.0);
+        let slow = curve(30.0, 85.0);
+        let sessions = vec![
+            (session(0, Some(96)), charge(&fast, 0, 10.0)),
+            (session(86_400, Some(96)), charge(&fast, 86_400, 25.0)),
+            (session(172_800, Some(30)), charge(&slow, 172_800, 10.0)),
+            (session(259_200, Some(30)), charge(&slow, 259_200, 30.0)),
+        ];
+        let profile = ChargeProfile::from_sessions(&sessions);
+
+        let learned = profile.curve_for(Some(96)).unwrap();
+        assert!((learned.cc_rate - 90.0).abs() < 10.0, "{learned:?}");
+        assert!((learned.knee_percent - 75.0).abs() <= 5.0, "{learned:?}");
+
+        let learned = profile.curve_for(Some(30)).unwrap();
+        assert!((learned.cc_rate - 30.0).abs() < 5.0, "{learned:?}");
+
+        // An unseen 65 W charger borrows the 96 W curve
+        assert_eq!(profile.curve_for(Some(65)).unwrap().charger_watts, Some(96));
+        assert!(profile.curve_for(None).is_some());
+    }
+
+    #[test]
+    fn falls_back_to_the_os_estimate() {
+        let os = Some(Duration::from_secs(1800));
+        let forecast = forecast(None, 50.0, Some(65), 80.0, os).unwrap();
+        assert_eq!(forecast.source, ChargeForecastSource::Os);
+        assert_eq!((forecast.to_80, forecast.to_full), (None, os));
+
+        let profile = ChargeProfile::default();
+        assert!(super::forecast(Some(&profile), 50.0, None, 80.0, None).is_none());
+    }
+}
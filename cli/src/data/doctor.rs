@@ -0,0 +1,243 @@
+//! Consistency checks for the rows in the history database.
+//!
+//! File-level problems (corruption, schema drift) are found by
+//! `HistoryStore::check_file`. This module looks for rows that disagree with
+//! each other: sessions the daemon never closed, and completed hours or days
+//! whose raw samples were never aggregated.
+
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+
+use crate::config::HistoryConfig;
+use crate::data::aggregator::Aggregator;
+use crate::data::timezone::format_date;
+use crate::data::{ChargeSession, DayZone, HistoryStore, HistoryStoreError};
+
+/// An incomplete session that a later session has superseded, usually left
+/// behind when the daemon stopped mid-session
+#[derive(Debug, Clone)]
+pub struct OrphanedSession {
+    pub session: ChargeSession,
+    pub repair: SessionRepair,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionRepair {
+    /// A completed row for the same session exists
+    Delete,
+    /// Close the session where the next one begins
+    Close { end_time: i64, end_percent: f32 },
+}
+
+#[derive(Debug, Default)]
+pub struct Gaps {
+    /// Completed hours with raw samples but no hourly stats
+    pub hours: Vec<i64>,
+    /// Completed days with raw samples but no daily stats
+    pub days: Vec<String>,
+}
+
+impl Gaps {
+    pub fn is_empty(&self) -> bool {
+        self.hours.is_empty() && self.days.is_empty()
+    }
+}
+
+pub fn find_orphaned_sessions(
+    store: &HistoryStore,
+) -> Result<Vec<OrphanedSession>, HistoryStoreError> {
+    Ok(orphaned_sessions(store.get_charge_sessions(
+        0,
+        i64::MAX,
+        None,
+    )?))
+}
+
+/// Closes or deletes orphaned sessions, returning how many were repaired.
+pub fn repair_sessions(
+    store: &HistoryStore,
+    orphans: &[OrphanedSession],
+) -> Result<usize, HistoryStoreError> {
+    let mut repaired = 0;
+    for orphan in orphans {
+        let Some(id) = orphan.session.id else {
+            continue;
+        };
+        match orphan.repair {
+            SessionRepair::Delete => store.delete_charge_session(id)?,
+            SessionRepair::Close {
+                end_time,
+                end_percent,
+            } => store.update_charge_session(&ChargeSession {
+                end_time: Some(end_time),
+                end_percent: Some(end_percent),
+                is_complete: true,
+                ..orphan.session.clone()
+            })?,
+        }
+        repaired += 1;
+    }
+    Ok(repaired)
+}
+
+pub fn find_gaps(store: &HistoryStore, config: &HistoryConfig) -> Result<Gaps, HistoryStoreError> {
+    let hourly: BTreeSet<i64> = store
+        .get_hourly_stats(0, i64::MAX)?
+        .into_iter()
+        .map(|stat| stat.hour_start)
+        .collect();
+    let daily: BTreeSet<String> = store
+        .get_daily_stats_limited("", "9999-12-31", u32::MAX as usize)?
+        .into_iter()
+        .map(|stat| stat.date)
+        .collect();
+
+    Ok(gaps(
+        &store.get_sample_hours()?,
+        &hourly,
+        &daily,
+        config.timezone,
+        Utc::now().timestamp(),
+    ))
+}
+
+/// Aggregates the missing hours and days, returning the rows written.
+pub fn fill_gaps(
+    store: &HistoryStore,
+    config: &HistoryConfig,
+) -> Result<(usize, usize), HistoryStoreError> {
+    let aggregator = Aggregator::new(store, config);
+    Ok((
+        aggregator.aggregate_completed_hours()?,
+        aggregator.aggregate_completed_days()?,
+    ))
+}
+
+/// The newest session may legitimately be in progress; any incomplete
+/// session before it is an orphan.
+fn orphaned_sessions(mut sessions: Vec<ChargeSession>) -> Vec<OrphanedSession> {
+    sessions.sort_by_key(|s| (s.start_time, s.id));
+
+    let mut orphans = Vec::new();
+    for (i, session) in sessions.iter().enumerate() {
+        let Some(next) = sessions.get(i + 1) else {
+            break;
+        };
+        if session.is_complete {
+            continue;
+        }
+
+        let duplicate = sessions.iter().any(|other| {
+            other.is_complete
+                && other.start_time == session.start_time
+                && other.session_type == session.session_type
+        });
+        let repair = if duplicate {
+            SessionRepair::Delete
+        } else {
+            SessionRepair::Close {
+                end_time: next.start_time,
+                end_percent: next.start_percent,
+            }
+        };
+        orphans.push(OrphanedSession {
+            session: session.clone(),
+            repair,
+        });
+    }
+    orphans
+}
+
+fn gaps(
+    sample_hours: &[i64],
+    hourly: &BTreeSet<i64>,
+    daily: &BTreeSet<String>,
+    zone: DayZone,
+    now: i64,
+) -> Gaps {
+    let current_hour = now - now.rem_euclid(3600);
+    let today = zone.date_of(now);
+
+    let hours = sample_hours
+        .iter()
+        .copied()
+        .filter(|hour| *hour < current_hour && !hourly.contains(hour))
+        .collect();
+    let days: BTreeSet<String> = sample_hours
+        .iter()
+        .map(|hour| zone.date_of(*hour))
+        .filter(|date| *date < today)
+        .map(format_date)
+        .filter(|date| !daily.contains(date))
+        .collect();
+
+    Gaps {
+        hours,
+        days: days.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SessionType;
+
+    fn session(id: i64, start_time: i64, start_percent: f32, complete: bool) -> ChargeSession {
+        ChargeSession {
+            id: Some(id),
+            is_complete: complete,
+            end_time: complete.then_some(start_time + 600),
+            ..ChargeSession::new_discharge(start_time, start_percent)
+        }
+    }
+
+    #[test]
+    fn superseded_incomplete_sessions_are_orphans() {
+        let mut duplicate = session(4, 3_000, 60.0, true);
+        duplicate.session_type = SessionType::Charge;
+        let mut started = session(3, 3_000, 60.0, false);
+        started.session_type = SessionType::Charge;
+
+        let orphans = orphaned_sessions(vec![
+            session(1, 1_000, 90.0, false),
+            session(2, 2_000, 80.0, true),
+            started,
+            duplicate,
+            session(5, 4_000, 70.0, false),
+        ]);
+
+        let repairs: Vec<_> = orphans
+            .iter()
+            .map(|o| (o.session.id.unwrap(), o.repair))
+            .collect();
+        assert_eq!(
+            repairs,
+            vec![
+                (
+                    1,
+                    SessionRepair::Close {
+                        end_time: 2_000,
+                        end_percent: 80.0
+                    }
+                ),
+                (3, SessionRepair::Delete),
+            ],
+            "the newest session is still in progress"
+        );
+    }
+
+    #[test]
+    fn gaps_skip_current_hour_and_today() {
+        let zone = DayZone::parse("utc").unwrap();
+        let day = 1_709_251_200; // 2024-03-01 00:00 UTC
+        let now = day + 86400 + 2 * 3600 + 60;
+        let sample_hours = [day, day + 3600, day + 86400, now - 60];
+        let hourly = BTreeSet::from([day]);
+        let daily = BTreeSet::new();
+
+        let gaps = gaps(&sample_hours, &hourly, &daily, zone, now);
+        assert_eq!(gaps.hours, vec![day + 3600, day + 86400]);
+        assert_eq!(gaps.days, vec!["2024-03-01".to_string()]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Reduces raw sample ranges to a bounded number of points for charting.
+//!
+//! Both methods work on `power_watts`, the series the history chart draws,
+//! and always return samples that exist in the input so every point can be
+//! traced back to a stored row.
+
+use crate::daemon::DownsampleMethod;
+use crate::data::Sample;
+
+/// Downsamples `samples` (sorted by timestamp) to at most `max_points`.
+///
+/// A `max_points` of zero, or an input that already fits, is returned as is.
+pub fn downsample(
+    samples: Vec<Sample>,
+    max_points: usize,
+    method: DownsampleMethod,
+) -> Vec<Sample> {
+    if max_points == 0 || samples.len() <= max_points {
+        return samples;
+    }
+    match method {
+        DownsampleMethod::Lttb => lttb(&samples, max_points),
+        DownsampleMethod::MinMax => min_max(&samples, max_points),
+    }
+}
+
+/// Largest-Triangle-Three-Buckets: keeps the first and last samples and, from
+/// each bucket in between, the sample forming the largest triangle with the
+/// previously kept sample and the average of the next bucket.
+fn lttb(samples: &[Sample], max_points: usize) -> Vec<Sample> {
+    if max_points < 3 {
+        return endpoints(samples, max_points);
+    }
+
+    let len = samples.len();
+    let bucket_size = (len - 2) as f64 / (max_points - 2) as f64;
+    let mut out = Vec::with_capacity(max_points);
+    out.push(samples[0].clone());
+
+    let mut prev = 0;
+    for bucket in 0..max_points - 2 {
+        let start = (bucket as f64 * bucket_size) as usize + 1;
+        let end = (((bucket + 1) as f64 * bucket_size) as usize + 1).min(len - 1);
+
+        let next_start = end;
+        let next_end = (((bucket + 2) as f64 * bucket_size) as usize + 1).min(len);
+        let next = &samples[next_start..next_end.max(next_start + 1)];
+        let avg_x = next.iter().map(|s| s.timestamp as f64).sum::<f64>() / next.len() as f64;
+        let avg_y = next.iter().map(|s| s.power_watts as f64).sum::<f64>() / next.len() as f64;
+
+        let ax = samples[prev].timestamp as f64;
+        let ay = samples[prev].power_watts as f64;
+
+        let mut best = start;
+        let mut best_area = -1.0;
+        for (i, s) in samples.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (s.power_watts as f64 - ay)
+                - (ax - s.timestamp as f64) * (avg_y - ay))
+                .abs();
+            if area > best_area {
+                best_area = area;
+                best = i;
+            }
+        }
+
+        out.push(samples[best].clone());
+        prev = best;
+    }
+
+    out.push(samples[len - 1].clone());
+    out
+}
+
+/// Splits the range into `max_points / 2` buckets and keeps the lowest and
+/// highest power sample of each, in time order, so short spikes survive.
+fn min_max(samples: &[Sample], max_points: usize) -> Vec<Sample> {
+    if max_points < 2 {
+        return endpoints(samples, max_points);
+    }
+
+    let buckets = max_points / 2;
+    let bucket_size = samples.len().div_ceil(buckets);
+    let mut out = Vec::with_capacity(buckets * 2);
+
+    for chunk in samples.chunks(bucket_size) {
+        let (mut lo, mut hi) = (0, 0);
+        for (i, s) in chunk.iter().enumerate() {
+            if s.power_watts < chunk[lo].power_watts {
+                lo = i;
+            }
+            if s.power_watts > chunk[hi].power_watts {
+                hi = i;
+            }
+        }
+        let (first, second) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+        out.push(chunk[first].clone());
+        if second != first {
+            out.push(chunk[second].clone());
+        }
+    }
+
+    out
+}
+
+fn endpoints(samples: &[Sample], max_points: usize) -> Vec<Sample> {
+    match max_points {
+        0 => Vec::new(),
+        1 => vec![samples[samples.len() - 1].clone()],
+        _ => vec![samples[0].clone(), samples[samples.len() - 1].clone()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ChargingState;
+
+    fn series(powers: &[f32]) -> Vec<Sample> {
+        powers
+            .iter()
+            .enumerate()
+            .map(|(i, &power_watts)| Sample {
+                id: Some(i as i64),
+                timestamp: i as i64 * 60,
+                battery_percent: 50.0,
+                power_watts,
+                cpu_power: 0.0,
+                gpu_power: 0.0,
+                charging_state: ChargingState::Discharging,
+            })
+            .collect()
+    }
+
+    fn timestamps(samples: &[Sample]) -> Vec<i64> {
+        samples.iter().map(|s| s.timestamp).collect()
+    }
+
+    #[test]
+    fn small_input_is_returned_unchanged() {
+        let samples = series(&[1.0, 2.0, 3.0]);
+        for method in [DownsampleMethod::Lttb, DownsampleMethod::MinMax] {
+            assert_eq!(downsample(samples.clone(), 10, method).len(), 3);
+        }
+        assert_eq!(downsample(samples, 0, DownsampleMethod::Lttb).len(), 3);
+    }
+
+    #[test]
+    fn lttb_respects_limit_and_keeps_endpoints() {
+        let powers: Vec<f32> = (0..1000).map(|i| (i as f32 / 20.0).sin() * 10.0).collect();
+        let out = downsample(series(&powers), 50, DownsampleMethod::Lttb);
+        assert_eq!(out.len(), 50);
+        assert_eq!(out[0].timestamp, 0);
+        assert_eq!(out[49].timestamp, 999 * 60);
+        assert!(timestamps(&out).windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn lttb_keeps_isolated_spike() {
+        let mut powers = vec![5.0; 500];
+        powers[250] = 60.0;
+        let out = downsample(series(&powers), 20, DownsampleMethod::Lttb);
+        assert!(out.iter().any(|s| s.power_watts == 60.0));
+    }
+
+    #[test]
+    fn min_max_keeps_extremes_of_each_bucket() {
+        let mut powers = vec![5.0; 100];
+        powers[10] = 1.0;
+        powers[30] = 40.0;
+        let out = downsample(series(&powers), 10, DownsampleMethod::MinMax);
+        assert!(out.len() <= 10);
+        assert!(out.iter().any(|s| s.power_watts == 1.0));
+        assert!(out.iter().any(|s| s.power_watts == 40.0));
+        assert!(timestamps(&out).windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn tiny_limits_fall_back_to_endpoints() {
+        let samples = series(&[1.0, 2.0, 3.0, 4.0]);
+        let out = downsample(samples.clone(), 2, DownsampleMethod::Lttb);
+        assert_eq!(timestamps(&out), vec![0, 180]);
+        let out = downsample(samples, 1, DownsampleMethod::MinMax);
+        assert_eq!(timestamps(&out), vec![180]);
+    }
+}
@@ -0,0 +1,330 @@
+//! Discharge rate estimators for the battery forecast.
+//!
+//! Each estimator turns a run of discharging readings into a current power
+//! draw with a P10–P90 spread. The window average and EWMA only look at
+//! reported power; the Kalman filter also tracks remaining energy from the
+//! battery percentage, and the slope estimator uses the percentage alone,
+//! for machines whose battery never reports power.
+
+use crate::config::ForecastEstimator;
+use crate::data::usage_profile::percentile;
+
+/// Readings needed before any estimator reports
+pub const MIN_READINGS: usize = 10;
+
+/// History the EWMA, Kalman and slope estimators can make use of
+pub const ESTIMATOR_HISTORY_SECS: i64 = 1800;
+
+const MIN_POWER_WATTS: f32 = 0.1;
+
+/// z-score of the 90th percentile of a normal distribution
+const Z_90: f32 = 1.2816;
+
+/// Drift of the true power draw in the Kalman model, in W² per second
+const KALMAN_POWER_DRIFT: f32 = 0.5 / 60.0;
+
+/// Variance of a single power reading, in W²
+const KALMAN_POWER_NOISE: f32 = 1.0;
+
+/// A rise larger than this many points means the battery was charged
+const SLOPE_RESET_PERCENT: f32 = 1.0;
+
+/// A discharging reading, in ascending time order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub timestamp: i64,
+    pub battery_percent: f32,
+    pub power_watts: f32,
+}
+
+/// Estimated discharge rate, with the P10 and P90 of where it may really be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerEstimate {
+    pub watts: f32,
+    pub low_watts: f32,
+    pub high_watts: f32,
+    /// Readings the estimate was built from
+    pub samples: usize,
+}
+
+impl PowerEstimate {
+    /// Mean of `powers`, spread over their P10–P90.
+    pub fn from_powers(powers: &[f32]) -> Option<Self> {
+        if powers.is_empty() {
+            return None;
+        }
+        let mean = powers.iter().sum::<f32>() / powers.len() as f32;
+        let mut sorted = powers.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        Some(Self {
+            watts: mean,
+            low_watts: percentile(&sorted, 0.1).min(mean),
+            high_watts: percentile(&sorted, 0.9).max(mean),
+            samples: powers.len(),
+        })
+    }
+
+    fn normal(watts: f32, std_dev: f32, samples: usize) -> Self {
+        Self {
+            watts,
+            low_watts: watts - Z_90 * std_dev,
+            high_watts: watts + Z_90 * std_dev,
+            samples,
+        }
+    }
+}
+
+impl ForecastEstimator {
+    /// Whether the estimator needs power readings, rather than only the
+    /// battery percentage
+    pub fn uses_power(self) -> bool {
+        !matches!(self, ForecastEstimator::Slope)
+    }
+
+    /// Estimates the current draw from `readings`, which must be ascending.
+    /// `window_secs` is the averaging window, and the EWMA time constant.
+    /// `None` if there is not enough data or the battery is not draining.
+    pub fn estimate(
+        self,
+        readings: &[Reading],
+        capacity_wh: f32,
+        window_secs: i64,
+    ) -> Option<PowerEstimate> {
+        let estimate = match self {
+            ForecastEstimator::Window => window(readings, window_secs),
+            ForecastEstimator::Ewma => ewma(readings, window_secs),
+            ForecastEstimator::Kalman => kalman(readings, capacity_wh),
+            ForecastEstimator::Slope => slope(readings, capacity_wh),
+        }?;
+
+        if estimate.samples < MIN_READINGS || estimate.watts < MIN_POWER_WATTS {
+            return None;
+        }
+        Some(PowerEstimate {
+            low_watts: estimate.low_watts.clamp(MIN_POWER_WATTS, estimate.watts),
+            high_watts: estimate.high_watts.max(estimate.watts),
+            ..estimate
+        })
+    }
+}
+
+/// Readings no older than `secs` before the newest one
+fn recent(readings: &[Reading], secs: i64) -> &[Reading] {
+    let Some(last) = readings.last() else {
+        return readings;
+    };
+    let start = readings.partition_point(|r| r.timestamp < last.timestamp - secs);
+    &readings[start..]
+}
+
+fn window(readings: &[Reading], window_secs: i64) -> Option<PowerEstimate> {
+    let powers: Vec<f32> = recent(readings, window_secs)
+        .iter()
+        .map(|r| r.power_watts)
+        .collect();
+    PowerEstimate::from_powers(&powers)
+}
+
+/// Exponentially weighted mean and variance, weighting each reading by the
+/// time since the previous one so uneven sampling does not skew it.
+fn ewma(readings: &[Reading], window_secs: i64) -> Option<PowerEstimate> {
+    let tau = window_secs.max(1) as f32;
+    let mut iter = readings.iter();
+    let first = iter.next()?;
+    let (mut mean, mut var, mut last_ts) = (first.power_watts, 0.0_f32, first.timestamp);
+
+    for reading in iter {
+        let dt = (reading.timestamp - last_ts).max(1) as f32;
+        let alpha = 1.0 - (-dt / tau).exp();
+        let diff = reading.power_watts - mean;
+        let step = alpha * diff;
+        mean += step;
+        var = (1.0 - alpha) * (var + diff * step);
+        last_ts = reading.timestamp;
+    }
+    Some(PowerEstimate::normal(mean, var.sqrt(), readings.len()))
+}
+
+/// Two-state Kalman filter over remaining energy (Wh) and power (W). The
+/// percentage pins down the energy, and through its fall the power, even
+/// when power readings are noisy or missing.
+fn kalman(readings: &[Reading], capacity_wh: f32) -> Option<PowerEstimate> {
+    let first = readings.first()?;
+    // Quantization of a one-point percentage step
+    let energy_noise = (capacity_wh * 0.01).powi(2) / 12.0;
+
+    let mut energy = first.battery_percent * capacity_wh / 100.0;
+    let mut power = first.power_watts.max(0.0);
+    let power_var = if first.power_watts > MIN_POWER_WATTS {
+        KALMAN_POWER_NOISE
+    } else {
+        100.0
+    };
+    // Covariance [[ee, ep], [ep, pp]]
+    let (mut ee, mut ep, mut pp) = (energy_noise, 0.0_f32, power_var);
+    let mut last_ts = first.timestamp;
+
+    for reading in &readings[1..] {
+        let dt = (reading.timestamp - last_ts).max(0) as f32;
+        let h = dt / 3600.0;
+        last_ts = reading.timestamp;
+
+        // Predict: energy falls by power over dt, power drifts
+        energy -= power * h;
+        ee += -2.0 * h * ep + h * h * pp;
+        ep -= h * pp;
+        pp += KALMAN_POWER_DRIFT * dt;
+
+        // Update with the energy implied by the percentage
+        let measured = reading.battery_percent * capacity_wh / 100.0;
+        let s = ee + energy_noise;
+        let (ke, kp) = (ee / s, ep / s);
+        let innovation = measured - energy;
+        energy += ke * innovation;
+        power += kp * innovation;
+        (ee, ep, pp) = ((1.0 - ke) * ee, (1.0 - ke) * ep, pp - kp * ep);
+
+        // Update with the reported power, when there is one
+        if reading.power_watts > MIN_POWER_WATTS {
+            let s = pp + KALMAN_POWER_NOISE;
+            let (ke, kp) = (ep / s, pp / s);
+            let innovation = reading.power_watts - power;
+            energy += ke * innovation;
+            power += kp * innovation;
+            (ee, ep, pp) = (ee - ke * ep, (1.0 - kp) * ep, (1.0 - kp) * pp);
+        }
+    }
+
+    Some(PowerEstimate::normal(
+        power,
+        pp.max(0.0).sqrt(),
+        readings.len(),
+    ))
+}
+
+/// Least-squares fit of remaining energy against time since the battery
+/// last rose, so a brief charge does not flatten the slope.
+fn slope(readings: &[Reading], capacity_wh: f32) -> Option<PowerEstimate> {
+    let readings = recent(readings, ESTIMATOR_HISTORY_SECS);
+    let start = readings
+        .windows(2)
+        .rposition(|pair| pair[1].battery_percent - pair[0].battery_percent > SLOPE_RESET_PERCENT)
+        .map_or(0, |i| i + 1);
+    let readings = &readings[start..];
+    if readings.len() < 3 {
+        return None;
+    }
+
+    let t0 = readings[0].timestamp;
+    let points: Vec<(f64, f64)> = readings
+        .iter()
+        .map(|r| {
+            (
+                (r.timestamp - t0) as f64 / 3600.0,
+                (r.battery_percent * capacity_wh / 100.0) as f64,
+            )
+        })
+        .collect();
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_e = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_t).powi(2)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_t) * (p.1 - mean_e)).sum();
+    let slope = sxy / sxx;
+    let intercept = mean_e - slope * mean_t;
+    let residuals: f64 = points
+        .iter()
+        .map(|p| (p.1 - intercept - slope * p.0).powi(2))
+        .sum();
+    let std_err = (residuals / (n - 2.0) / sxx).sqrt();
+
+    Some(PowerEstimate::normal(
+        -slope as f32,
+        std_err as f32,
+        readings.len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A steady `watts` drain on a 100 Wh battery, read every `step` seconds
+    fn steady(watts: f32, count: usize, step: i64) -> Vec<Reading> {
+        (0..count)
+            .map(|i| {
+                let hours = (i as i64 * step) as f32 / 3600.0;
+                Reading {
+                    timestamp: 1_000_000 + i as i64 * step,
+                    battery_percent: 80.0 - watts * hours,
+                    power_watts: watts + if i % 2 == 0 { 1.0 } else { -1.0 },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimators_agree_on_a_steady_drain() {
+        let readings = steady(10.0, 120, 15);
+        for estimator in ForecastEstimator::ALL {
+            let estimate = estimator.estimate(&readings, 100.0, 300).unwrap();
+            assert!(
+                (estimate.watts - 10.0).abs() < 1.0,
+                "{estimator:?}: {estimate:?}"
+            );
+            assert!(estimate.low_watts <= estimate.watts && estimate.watts <= estimate.high_watts);
+        }
+    }
+
+    #[test]
+    fn slope_works_without_power_readings() {
+        let mut readings = steady(12.0, 120, 15);
+        for reading in &mut readings {
+            reading.power_watts = 0.0;
+            // Whole-percent reporting, as on many laptops
+            reading.battery_percent = reading.battery_percent.floor();
+        }
+        assert_eq!(
+            ForecastEstimator::Window.estimate(&readings, 100.0, 300),
+            None
+        );
+
+        let estimate = ForecastEstimator::Slope
+            .estimate(&readings, 100.0, 300)
+            .unwrap();
+        assert!((estimate.watts - 12.0).abs() < 1.0, "{estimate:?}");
+    }
+
+    #[test]
+    fn slope_ignores_readings_before_a_charge() {
+        let mut readings = steady(10.0, 60, 15);
+        // The battery was at 60% and draining fast until a charge at index 20
+        for reading in &mut readings[..20] {
+            reading.battery_percent = 60.0 - (reading.timestamp - 1_000_000) as f32 / 36.0;
+        }
+        let estimate = ForecastEstimator::Slope
+            .estimate(&readings, 100.0, 300)
+            .unwrap();
+        assert_eq!(estimate.samples, 40);
+        assert!((estimate.watts - 10.0).abs() < 0.5, "{estimate:?}");
+    }
+
+    #[test]
+    fn ewma_follows_a_change_faster_than_the_window() {
+        let mut readings = steady(10.0, 40, 15);
+        readings.extend(steady(20.0, 40, 15).into_iter().map(|r| Reading {
+            timestamp: r.timestamp + 600,
+            ..r
+        }));
+        let window = ForecastEstimator::Window
+            .estimate(&readings, 100.0, 900)
+            .unwrap();
+        let ewma = ForecastEstimator::Ewma
+            .estimate(&readings, 100.0, 300)
+            .unwrap();
+        assert!(ewma.watts > window.watts, "{ewma:?} vs {window:?}");
+    }
+}
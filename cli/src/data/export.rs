@@ -0,0 +1,561 @@
+//! Streaming, single-table exports of the history database.
+//!
+//! Rows are written as they're read from SQLite, so exporting months of
+//! samples doesn't hold them in memory. Each table can be written as NDJSON,
+//! CSV with a header row, or InfluxDB line protocol with nanosecond
+//! timestamps.
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::data::timezone::parse_date;
+use crate::data::{
+    ChargeSession, ChargingState, DailyCycle, DailyStat, DayZone, HistoryStore, HistoryStoreError,
+    HourlyProcessStat, HourlyStat, Sample, SessionType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTable {
+    Samples,
+    Hourly,
+    Daily,
+    Processes,
+    Sessions,
+    Cycles,
+}
+
+impl ExportTable {
+    pub const ALL: [ExportTable; 6] = [
+        Self::Samples,
+        Self::Hourly,
+        Self::Daily,
+        Self::Processes,
+        Self::Sessions,
+        Self::Cycles,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|table| table.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Samples => "samples",
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Processes => "processes",
+            Self::Sessions => "sessions",
+            Self::Cycles => "cycles",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Ndjson,
+    Csv,
+    Influx,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "csv" => Some(Self::Csv),
+            "influx" | "line" => Some(Self::Influx),
+            _ => None,
+        }
+    }
+}
+
+/// Range of rows to export. Timestamped tables use `from_ts..=to_ts`; daily
+/// tables use the dates.
+#[derive(Debug, Clone)]
+pub struct ExportRange {
+    pub from_date: String,
+    pub to_date: String,
+    pub from_ts: i64,
+    pub to_ts: i64,
+}
+
+impl ExportRange {
+    pub fn new(from_date: &str, to_date: &str, zone: DayZone) -> Self {
+        let (from_ts, to_ts) = zone.date_range(from_date, to_date);
+        Self {
+            from_date: from_date.to_string(),
+            to_date: to_date.to_string(),
+            from_ts,
+            to_ts,
+        }
+    }
+}
+
+/// Writes every row of `table` in `range` to `out`, returning the number of
+/// rows written.
+pub fn export_table(
+    store: &HistoryStore,
+    table: ExportTable,
+    format: ExportFormat,
+    range: &ExportRange,
+    zone: DayZone,
+    out: &mut impl Write,
+) -> Result<usize, HistoryStoreError> {
+    let mut writer = RowWriter { format, zone, out };
+    match table {
+        ExportTable::Samples => {
+            writer.header::<Sample>()?;
+            store.for_each_sample(range.from_ts, range.to_ts, |row| writer.row(&row))
+        }
+        ExportTable::Hourly => {
+            writer.header::<HourlyStat>()?;
+            store.for_each_hourly_stat(range.from_ts, range.to_ts, |row| writer.row(&row))
+        }
+        ExportTable::Daily => {
+            writer.header::<DailyStat>()?;
+            store.for_each_daily_stat(&range.from_date, &range.to_date, |row| writer.row(&row))
+        }
+        ExportTable::Processes => {
+            writer.header::<HourlyProcessStat>()?;
+            store.for_each_hourly_process(range.from_ts, range.to_ts, |row| writer.row(&row))
+        }
+        ExportTable::Sessions => {
+            writer.header::<ChargeSession>()?;
+            store.for_each_charge_session(range.from_ts, range.to_ts, |row| writer.row(&row))
+        }
+        ExportTable::Cycles => {
+            writer.header::<DailyCycle>()?;
+            store.for_each_daily_cycle(&range.from_date, &range.to_date, |row| writer.row(&row))
+        }
+    }
+}
+
+struct RowWriter<'a, W: Write> {
+    format: ExportFormat,
+    zone: DayZone,
+    out: &'a mut W,
+}
+
+impl<W: Write> RowWriter<'_, W> {
+    fn header<R: ExportRow>(&mut self) -> std::io::Result<()> {
+        if self.format == ExportFormat::Csv {
+            writeln!(self.out, "{}", R::CSV_HEADER)?;
+        }
+        Ok(())
+    }
+
+    fn row<R: ExportRow>(&mut self, row: &R) -> std::io::Result<()> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *self.out, row)?;
+                writeln!(self.out)
+            }
+            ExportFormat::Csv => writeln!(self.out, "{}", row.csv_row()),
+            ExportFormat::Influx => {
+                let line = row.influx_line(self.zone);
+                if line.fields.is_empty() {
+                    return Ok(());
+                }
+                writeln!(self.out, "{}", line.finish())
+            }
+        }
+    }
+}
+
+/// A row type that can be exported in every format
+trait ExportRow: Serialize {
+    const CSV_HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+
+    fn influx_line(&self, zone: DayZone) -> InfluxLine;
+}
+
+impl ExportRow for Sample {
+    const CSV_HEADER: &'static str =
+        "timestamp,battery_percent,power_watts,cpu_power,gpu_power,charging_state";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{:.1},{:.2},{:.2},{:.2},{}",
+            self.timestamp,
+            self.battery_percent,
+            self.power_watts,
+            self.cpu_power,
+            self.gpu_power,
+            charging_label(self.charging_state)
+        )
+    }
+
+    fn influx_line(&self, _zone: DayZone) -> InfluxLine {
+        InfluxLine::new("jolt_sample", self.timestamp)
+            .tag("state", charging_label(self.charging_state))
+            .float("battery_percent", self.battery_percent)
+            .float("power_watts", self.power_watts)
+            .float("cpu_power", self.cpu_power)
+            .float("gpu_power", self.gpu_power)
+    }
+}
+
+impl ExportRow for HourlyStat {
+    const CSV_HEADER: &'static str =
+        "hour_start,avg_power_w,max_power_w,min_power_w,avg_battery,battery_delta,total_samples";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{:.2},{:.2},{:.2},{:.1},{:.1},{}",
+            self.hour_start,
+            self.avg_power,
+            self.max_power,
+            self.min_power,
+            self.avg_battery,
+            self.battery_delta,
+            self.total_samples
+        )
+    }
+
+    fn influx_line(&self, _zone: DayZone) -> InfluxLine {
+        InfluxLine::new("jolt_hourly", self.hour_start)
+            .float("avg_power", self.avg_power)
+            .float("max_power", self.max_power)
+            .float("min_power", self.min_power)
+            .float("avg_battery", self.avg_battery)
+            .float("battery_delta", self.battery_delta)
+            .int("total_samples", self.total_samples as i64)
+    }
+}
+
+impl ExportRow for DailyStat {
+    const CSV_HEADER: &'static str =
+        "date,avg_power_w,max_power_w,total_energy_wh,screen_on_hours,charging_hours,battery_cycles";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.3}",
+            self.date,
+            self.avg_power,
+            self.max_power,
+            self.total_energy_wh,
+            self.screen_on_hours,
+            self.charging_hours,
+            self.battery_cycles
+        )
+    }
+
+    fn influx_line(&self, zone: DayZone) -> InfluxLine {
+        InfluxLine::new("jolt_daily", day_timestamp(&self.date, zone))
+            .float("avg_power", self.avg_power)
+            .float("max_power", self.max_power)
+            .float("total_energy_wh", self.total_energy_wh)
+            .float("screen_on_hours", self.screen_on_hours)
+            .float("charging_hours", self.charging_hours)
+            .float("battery_cycles", self.battery_cycles)
+    }
+}
+
+impl ExportRow for HourlyProcessStat {
+    const CSV_HEADER: &'static str = "hour_start,process_name,avg_impact,avg_cpu_percent,avg_memory_mb,avg_power_w,total_energy_wh,sample_count";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{:.2},{:.2},{:.1},{:.3},{:.4},{}",
+            self.hour_start,
+            escape_csv(&self.process_name),
+            self.avg_impact,
+            self.avg_cpu,
+            self.avg_memory_mb,
+            self.avg_power,
+            self.total_energy_wh,
+            self.sample_count
+        )
+    }
+
+    fn influx_line(&self, _zone: DayZone) -> InfluxLine {
+        InfluxLine::new("jolt_process", self.hour_start)
+            .tag("process", &self.process_name)
+            .float("avg_impact", self.avg_impact)
+            .float("avg_cpu", self.avg_cpu)
+            .float("avg_memory_mb", self.avg_memory_mb)
+            .float("avg_power", self.avg_power)
+            .float("total_energy_wh", self.total_energy_wh)
+            .int("sample_count", self.sample_count as i64)
+    }
+}
+
+impl ExportRow for ChargeSession {
+    const CSV_HEADER: &'static str = "start_time,end_time,session_type,start_percent,end_percent,energy_wh,charger_watts,avg_power_w,is_complete";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{:.1},{},{},{},{},{}",
+            self.start_time,
+            optional(self.end_time),
+            session_label(self.session_type),
+            self.start_percent,
+            optional(self.end_percent.map(|v| format!("{:.1}", v))),
+            optional(self.energy_wh.map(|v| format!("{:.2}", v))),
+            optional(self.charger_watts),
+            optional(self.avg_power_watts.map(|v| format!("{:.2}", v))),
+            self.is_complete
+        )
+    }
+
+    fn influx_line(&self, _zone: DayZone) -> InfluxLine {
+        let mut line = InfluxLine::new("jolt_session", self.start_time)
+            .tag("type", session_label(self.session_type))
+            .float("start_percent", self.start_percent)
+            .bool("is_complete", self.is_complete);
+        if let Some(end_time) = self.end_time {
+            line = line.int("duration_secs", end_time - self.start_time);
+        }
+        if let Some(end_percent) = self.end_percent {
+            line = line.float("end_percent", end_percent);
+        }
+        if let Some(energy_wh) = self.energy_wh {
+            line = line.float("energy_wh", energy_wh);
+        }
+        if let Some(charger_watts) = self.charger_watts {
+            line = line.int("charger_watts", charger_watts as i64);
+        }
+        if let Some(avg_power) = self.avg_power_watts {
+            line = line.float("avg_power", avg_power);
+        }
+        line
+    }
+}
+
+impl ExportRow for DailyCycle {
+    const CSV_HEADER: &'static str = "date,charge_sessions,discharge_sessions,total_charging_mins,total_discharge_mins,deepest_discharge_percent,energy_charged_wh,energy_discharged_wh,partial_cycles,cycle_count,avg_temperature_c,time_at_high_soc_mins";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{:.2},{:.2},{:.3},{},{},{}",
+            self.date,
+            self.charge_sessions,
+            self.discharge_sessions,
+            self.total_charging_mins,
+            self.total_discharge_mins,
+            optional(self.deepest_discharge_percent.map(|v| format!("{:.1}", v))),
+            self.energy_charged_wh,
+            self.energy_discharged_wh,
+            self.partial_cycles,
+            optional(self.macos_cycle_count),
+            optional(self.avg_temperature_c.map(|v| format!("{:.1}", v))),
+            self.time_at_high_soc_mins
+        )
+    }
+
+    fn influx_line(&self, zone: DayZone) -> InfluxLine {
+        let mut line = InfluxLine::new("jolt_cycles", day_timestamp(&self.date, zone))
+            .int("charge_sessions", self.charge_sessions as i64)
+            .int("discharge_sessions", self.discharge_sessions as i64)
+            .int("total_charging_mins", self.total_charging_mins as i64)
+            .int("total_discharge_mins", self.total_discharge_mins as i64)
+            .float("energy_charged_wh", self.energy_charged_wh)
+            .float("energy_discharged_wh", self.energy_discharged_wh)
+            .float("partial_cycles", self.partial_cycles)
+            .int("time_at_high_soc_mins", self.time_at_high_soc_mins as i64);
+        if let Some(deepest) = self.deepest_discharge_percent {
+            line = line.float("deepest_discharge_percent", deepest);
+        }
+        if let Some(count) = self.macos_cycle_count {
+            line = line.int("cycle_count", count as i64);
+        }
+        if let Some(temperature) = self.avg_temperature_c {
+            line = line.float("avg_temperature_c", temperature);
+        }
+        line
+    }
+}
+
+/// One line of InfluxDB line protocol:
+/// `measurement,tag=value field=value,... timestamp_ns`
+struct InfluxLine {
+    head: String,
+    fields: String,
+    timestamp: i64,
+}
+
+impl InfluxLine {
+    fn new(measurement: &str, timestamp: i64) -> Self {
+        Self {
+            head: measurement.to_string(),
+            fields: String::new(),
+            timestamp,
+        }
+    }
+
+    fn tag(mut self, key: &str, value: &str) -> Self {
+        if !value.is_empty() {
+            let _ = write!(self.head, ",{}={}", key, escape_influx(value));
+        }
+        self
+    }
+
+    fn float(self, key: &str, value: f32) -> Self {
+        if value.is_finite() {
+            self.field(key, format_args!("{}", value))
+        } else {
+            self
+        }
+    }
+
+    fn int(self, key: &str, value: i64) -> Self {
+        self.field(key, format_args!("{}i", value))
+    }
+
+    fn bool(self, key: &str, value: bool) -> Self {
+        self.field(key, format_args!("{}", value))
+    }
+
+    fn field(mut self, key: &str, value: std::fmt::Arguments) -> Self {
+        if !self.fields.is_empty() {
+            self.fields.push(',');
+        }
+        let _ = write!(self.fields, "{}={}", key, value);
+        self
+    }
+
+    fn finish(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.head,
+            self.fields,
+            self.timestamp.saturating_mul(1_000_000_000)
+        )
+    }
+}
+
+/// Escapes commas, spaces and equals signs in tag values
+fn escape_influx(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ',' | ' ' | '=' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn escape_csv(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        let escaped = s.replace('"', "\"\"").replace('\n', " ");
+        format!("\"{}\"", escaped)
+    } else {
+        s.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn day_timestamp(date: &str, zone: DayZone) -> i64 {
+    parse_date(date).map(|d| zone.day_start(d)).unwrap_or(0)
+}
+
+fn charging_label(state: ChargingState) -> &'static str {
+    match state {
+        ChargingState::Discharging => "discharging",
+        ChargingState::Charging => "charging",
+        ChargingState::Full => "full",
+        ChargingState::Unknown => "unknown",
+    }
+}
+
+fn session_label(session_type: SessionType) -> &'static str {
+    match session_type {
+        SessionType::Charge => "charge",
+        SessionType::Discharge => "discharge",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Sample {
+        Sample {
+            id: Some(1),
+            timestamp: 1_700_000_000,
+            battery_percent: 81.5,
+            power_watts: 12.25,
+            cpu_power: 4.0,
+            gpu_power: 1.5,
+            charging_state: ChargingState::Discharging,
+        }
+    }
+
+    #[test]
+    fn influx_line_has_tags_fields_and_ns_timestamp() {
+        let line = sample().influx_line(DayZone::Local).finish();
+        assert_eq!(
+            line,
+            "jolt_sample,state=discharging battery_percent=81.5,power_watts=12.25,cpu_power=4,gpu_power=1.5 1700000000000000000"
+        );
+    }
+
+    #[test]
+    fn influx_escapes_process_tags() {
+        let process = HourlyProcessStat {
+            id: None,
+            hour_start: 3600,
+            process_name: "Google Chrome Helper, GPU".to_string(),
+            avg_impact: 2.0,
+            avg_cpu: 10.0,
+            avg_memory_mb: 100.0,
+            avg_power: 0.5,
+            total_energy_wh: 0.5,
+            sample_count: 180,
+        };
+        let line = process.influx_line(DayZone::Local).finish();
+        assert!(line
+            .starts_with("jolt_process,process=Google\\ Chrome\\ Helper\\,\\ GPU avg_impact=2,"));
+        assert!(line.contains("sample_count=180i"));
+    }
+
+    #[test]
+    fn csv_rows_leave_missing_values_empty() {
+        let session = ChargeSession::new_charge(1_000, 20.0, None);
+        assert_eq!(session.csv_row(), "1000,,charge,20.0,,,,,false");
+        assert_eq!(
+            ChargeSession::CSV_HEADER.split(',').count(),
+            session.csv_row().split(',').count()
+        );
+    }
+
+    #[test]
+    fn ndjson_writes_one_object_per_line() {
+        let mut out = Vec::new();
+        let mut writer = RowWriter {
+            format: ExportFormat::Ndjson,
+            zone: DayZone::Local,
+            out: &mut out,
+        };
+        writer.row(&sample()).unwrap();
+        writer.row(&sample()).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["timestamp"], 1_700_000_000);
+    }
+
+    #[test]
+    fn parses_table_and_format_names() {
+        assert_eq!(ExportTable::parse("Samples"), Some(ExportTable::Samples));
+        assert_eq!(ExportTable::parse("bogus"), None);
+        assert_eq!(ExportFormat::parse("jsonl"), Some(ExportFormat::Ndjson));
+        assert_eq!(ExportFormat::parse("influx"), Some(ExportFormat::Influx));
+        assert_eq!(ExportFormat::parse("json"), None);
+    }
+}
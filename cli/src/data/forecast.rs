@@ -1,18 +1,35 @@
 //! Battery runtime forecast based on historical power consumption.
 //!
-//! Calculates a more accurate battery runtime estimate by averaging power draw
-//! over a configurable time window, rather than using just the instantaneous value.
+//! Calculates a more accurate battery runtime estimate from a smoothed power
+//! draw, rather than using just the instantaneous value. How the draw is
+//! smoothed is up to the configured `ForecastEstimator`.
+//! When a usage profile is available, the projection blends that average
+//! into the power usually drawn at each upcoming weekday and hour.
+//!
+//! Forecasts are reported as a P10/P50/P90 range. The low end assumes the
+//! heavy end of recent and typical usage, the high end the light end.
 
 use std::time::Duration;
 
+use crate::config::ForecastEstimator;
 use crate::daemon::ForecastSnapshot;
+use crate::data::charge_forecast::{self, ChargeForecast, ChargeProfile};
+use crate::data::estimator::{PowerEstimate, Reading, MIN_READINGS};
 use crate::data::history::DataPoint;
 use crate::data::history_store::{ChargingState, Sample};
+use crate::data::usage_profile::UsageProfile;
 
-const MIN_SAMPLES_FOR_FORECAST: usize = 10;
+const DEFAULT_WINDOW_SECS: i64 = 300;
 const MIN_POWER_THRESHOLD_WATTS: f32 = 0.1;
 const MAX_FORECAST_HOURS: f32 = 24.0;
 
+/// Projection step when following the usage profile
+const PROFILE_STEP_HOURS: f32 = 5.0 / 60.0;
+
+/// How quickly the projection hands over from the current window to the
+/// profile: the window's weight falls to 1/e after this many hours
+const WINDOW_WEIGHT_HOURS: f32 = 1.0;
+
 /// Source of data used for forecast calculation
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ForecastSource {
@@ -24,13 +41,40 @@ pub enum ForecastSource {
     None,
 }
 
+/// How the forecast range was projected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForecastModel {
+    /// Current estimate held constant
+    Window,
+    /// Current estimate blended into the weekday/hour usage profile
+    Profile,
+}
+
+impl ForecastModel {
+    pub fn label(self) -> &'static str {
+        match self {
+            ForecastModel::Window => "recent usage",
+            ForecastModel::Profile => "usage profile",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ForecastData {
+    /// Median (P50) runtime
     forecast_duration: Option<Duration>,
+    p10_duration: Option<Duration>,
+    p90_duration: Option<Duration>,
+    model: ForecastModel,
     avg_power_watts: Option<f32>,
     sample_count: usize,
     source: ForecastSource,
     last_sample_timestamp: Option<i64>,
+    profile: Option<UsageProfile>,
+    estimator: ForecastEstimator,
+    window_secs: i64,
+    charge_profile: Option<ChargeProfile>,
+    charge: Option<ChargeForecast>,
 }
 
 impl Default for ForecastData {
@@ -43,13 +87,69 @@ impl ForecastData {
     pub fn new() -> Self {
         Self {
             forecast_duration: None,
+            p10_duration: None,
+            p90_duration: None,
+            model: ForecastModel::Window,
             avg_power_watts: None,
             sample_count: 0,
             source: ForecastSource::None,
             last_sample_timestamp: None,
+            profile: None,
+            estimator: ForecastEstimator::default(),
+            window_secs: DEFAULT_WINDOW_SECS,
+            charge_profile: None,
+            charge: None,
         }
     }
 
+    /// Sets how the current draw is estimated. `window_secs` is the
+    /// averaging window, and the EWMA time constant.
+    pub fn set_estimator(&mut self, estimator: ForecastEstimator, window_secs: i64) {
+        self.estimator = estimator;
+        self.window_secs = window_secs.max(1);
+    }
+
+    /// Sets the usage profile to project with. Profiles with too little
+    /// data are ignored.
+    pub fn set_profile(&mut self, profile: Option<UsageProfile>) {
+        self.profile = profile.filter(UsageProfile::is_usable);
+    }
+
+    /// Sets the charge curves learned from past charge sessions
+    pub fn set_charge_profile(&mut self, profile: Option<ChargeProfile>) {
+        self.charge_profile = profile.filter(|p| !p.is_empty());
+    }
+
+    pub fn has_charge_profile(&self) -> bool {
+        self.charge_profile.is_some()
+    }
+
+    /// Forecasts time to 80%, `limit_percent` and full while charging,
+    /// from the learned curve if there is one, else the OS time to full.
+    pub fn calculate_charge(
+        &mut self,
+        percent: f32,
+        charger_watts: Option<u32>,
+        limit_percent: f32,
+        os_time_to_full: Option<Duration>,
+    ) {
+        self.charge = charge_forecast::forecast(
+            self.charge_profile.as_ref(),
+            percent,
+            charger_watts,
+            limit_percent,
+            os_time_to_full,
+        );
+    }
+
+    pub fn clear_charge(&mut self) {
+        self.charge = None;
+    }
+
+    pub fn charge(&self) -> Option<&ChargeForecast> {
+        self.charge.as_ref()
+    }
+
     /// Calculate forecast from daemon samples
     ///
     /// Returns true if forecast was successfully calculated.
@@ -63,7 +163,7 @@ impl ForecastData {
     ) -> bool {
         self.source = ForecastSource::Daemon;
 
-        if samples.len() < MIN_SAMPLES_FOR_FORECAST {
+        if samples.len() < MIN_READINGS {
             self.clear_forecast();
             return false;
         }
@@ -79,26 +179,18 @@ impl ForecastData {
         self.last_sample_timestamp = Some(most_recent);
 
         // Filter to only discharging samples
-        let discharging_samples: Vec<_> = samples
+        let mut readings: Vec<Reading> = samples
             .iter()
             .filter(|s| s.charging_state == ChargingState::Discharging)
+            .map(|s| Reading {
+                timestamp: s.timestamp,
+                battery_percent: s.battery_percent,
+                power_watts: s.power_watts,
+            })
             .collect();
+        readings.sort_by_key(|r| r.timestamp);
 
-        if discharging_samples.len() < MIN_SAMPLES_FOR_FORECAST {
-            self.clear_forecast();
-            return false;
-        }
-
-        // Calculate average power consumption
-        let total_power: f32 = discharging_samples.iter().map(|s| s.power_watts).sum();
-        let avg_power = total_power / discharging_samples.len() as f32;
-
-        self.calculate_forecast(
-            avg_power,
-            discharging_samples.len(),
-            current_battery_percent,
-            battery_capacity_wh,
-        )
+        self.calculate_from_readings(&readings, current_battery_percent, battery_capacity_wh, now)
     }
 
     /// Calculate forecast from in-memory session data points
@@ -113,44 +205,84 @@ impl ForecastData {
         self.source = ForecastSource::Session;
         self.last_sample_timestamp = None;
 
-        if points.len() < MIN_SAMPLES_FOR_FORECAST {
+        if points.len() < MIN_READINGS {
             self.clear_forecast();
             return false;
         }
 
-        // Use all available points (already filtered by time window in caller if needed)
-        // Filter to points with positive power draw (discharging)
-        let discharging_points: Vec<_> = points
+        // Points carry no charging state; those with positive power draw are
+        // discharging. The slope estimator only needs the percentage.
+        let uses_power = self.estimator.uses_power();
+        let readings: Vec<Reading> = points
             .iter()
-            .filter(|p| p.power_watts > MIN_POWER_THRESHOLD_WATTS)
+            .filter(|p| !uses_power || p.power_watts > MIN_POWER_THRESHOLD_WATTS)
+            .map(|p| Reading {
+                timestamp: p.timestamp,
+                battery_percent: p.battery_percent,
+                power_watts: p.power_watts,
+            })
             .collect();
 
-        if discharging_points.len() < MIN_SAMPLES_FOR_FORECAST {
-            self.clear_forecast();
-            return false;
-        }
-
-        // Calculate average power consumption
-        let total_power: f32 = discharging_points.iter().map(|p| p.power_watts).sum();
-        let avg_power = total_power / discharging_points.len() as f32;
-
-        self.calculate_forecast(
-            avg_power,
-            discharging_points.len(),
+        self.calculate_from_readings(
+            &readings,
             current_battery_percent,
             battery_capacity_wh,
+            chrono::Utc::now().timestamp(),
         )
     }
 
-    /// Internal forecast calculation
+    fn calculate_from_readings(
+        &mut self,
+        readings: &[Reading],
+        current_battery_percent: f32,
+        battery_capacity_wh: f32,
+        now: i64,
+    ) -> bool {
+        let estimate = self
+            .estimator
+            .estimate(readings, battery_capacity_wh, self.window_secs);
+        let Some(estimate) = estimate else {
+            self.clear_forecast();
+            return false;
+        };
+        self.calculate_forecast(estimate, current_battery_percent, battery_capacity_wh, now)
+    }
+
+    /// Forecast for unplugging now, from the usage profile alone. Used
+    /// while charging, when there is no recent discharge to measure.
+    ///
+    /// Returns true if forecast was successfully calculated
+    pub fn calculate_from_profile(
+        &mut self,
+        current_battery_percent: f32,
+        battery_capacity_wh: f32,
+        now: i64,
+    ) -> bool {
+        let typical = self.profile.as_ref().and_then(|profile| {
+            Some(PowerEstimate {
+                watts: profile.power_at(now, 0.5)?,
+                low_watts: profile.power_at(now, 0.1)?,
+                high_watts: profile.power_at(now, 0.9)?,
+                samples: 0,
+            })
+        });
+        let Some(estimate) = typical else {
+            self.clear_forecast();
+            return false;
+        };
+        self.calculate_forecast(estimate, current_battery_percent, battery_capacity_wh, now)
+    }
+
+    /// Projects the runtime from the estimated draw and its spread
     fn calculate_forecast(
         &mut self,
-        avg_power: f32,
-        sample_count: usize,
+        estimate: PowerEstimate,
         current_battery_percent: f32,
         battery_capacity_wh: f32,
+        now: i64,
     ) -> bool {
-        self.sample_count = sample_count;
+        let avg_power = estimate.watts;
+        self.sample_count = estimate.samples;
         self.avg_power_watts = Some(avg_power);
 
         if avg_power < MIN_POWER_THRESHOLD_WATTS {
@@ -162,21 +294,38 @@ impl ForecastData {
         // Calculate remaining energy
         let remaining_wh = battery_capacity_wh * (current_battery_percent / 100.0);
 
-        // Calculate time remaining: hours = Wh / W
-        let hours_remaining = remaining_wh / avg_power;
+        let heavy = estimate.high_watts.max(avg_power);
+        let light = estimate
+            .low_watts
+            .min(avg_power)
+            .max(MIN_POWER_THRESHOLD_WATTS);
 
-        if hours_remaining > 0.0 && hours_remaining < MAX_FORECAST_HOURS {
-            let secs = (hours_remaining * 3600.0) as u64;
-            self.forecast_duration = Some(Duration::from_secs(secs));
-            true
-        } else {
+        let profile = self.profile.as_ref();
+        let Some(p50) = project_hours(remaining_wh, avg_power, profile, now, 0.5) else {
             self.clear_forecast();
-            false
-        }
+            return false;
+        };
+        let p10 = project_hours(remaining_wh, heavy, profile, now, 0.9).unwrap_or(p50);
+        let p90 =
+            project_hours(remaining_wh, light, profile, now, 0.1).unwrap_or(MAX_FORECAST_HOURS);
+
+        let to_duration = |hours: f32| Duration::from_secs((hours * 3600.0) as u64);
+        self.forecast_duration = Some(to_duration(p50));
+        self.p10_duration = Some(to_duration(p10.min(p50)));
+        self.p90_duration = Some(to_duration(p90.max(p50)));
+        self.model = if profile.is_some() {
+            ForecastModel::Profile
+        } else {
+            ForecastModel::Window
+        };
+        true
     }
 
     fn clear_forecast(&mut self) {
         self.forecast_duration = None;
+        self.p10_duration = None;
+        self.p90_duration = None;
+        self.model = ForecastModel::Window;
         self.avg_power_watts = None;
         self.sample_count = 0;
         self.last_sample_timestamp = None;
@@ -184,20 +333,25 @@ impl ForecastData {
     }
 
     pub fn formatted(&self) -> Option<String> {
-        self.forecast_duration.map(|d| {
-            let total_mins = d.as_secs() / 60;
-            if total_mins == 0 {
-                return "< 1m".to_string();
-            }
-            let hours = total_mins / 60;
-            let mins = total_mins % 60;
+        self.forecast_duration.map(format_duration)
+    }
 
-            if hours > 0 {
-                format!("{}h {}m", hours, mins)
-            } else {
-                format!("{}m", mins)
-            }
-        })
+    /// P10 to P90 range, e.g. "1h 50m–3h 10m"
+    pub fn formatted_range(&self) -> Option<String> {
+        let (low, high) = (self.p10_duration?, self.p90_duration?);
+        Some(format!(
+            "{}–{}",
+            format_duration(low),
+            format_duration(high)
+        ))
+    }
+
+    pub fn model(&self) -> ForecastModel {
+        self.model
+    }
+
+    pub fn estimator(&self) -> ForecastEstimator {
+        self.estimator
     }
 
     pub fn source(&self) -> ForecastSource {
@@ -220,16 +374,82 @@ impl ForecastData {
         self.forecast_duration.map(|d| d.as_secs())
     }
 
+    pub fn p10_secs(&self) -> Option<u64> {
+        self.p10_duration.map(|d| d.as_secs())
+    }
+
+    pub fn p90_secs(&self) -> Option<u64> {
+        self.p90_duration.map(|d| d.as_secs())
+    }
+
     pub fn avg_power_watts(&self) -> Option<f32> {
         self.avg_power_watts
     }
 
     pub fn update_from_snapshot(&mut self, snapshot: &ForecastSnapshot) {
         self.forecast_duration = snapshot.duration_secs.map(Duration::from_secs);
+        self.p10_duration = snapshot.p10_secs.map(Duration::from_secs);
+        self.p90_duration = snapshot.p90_secs.map(Duration::from_secs);
+        self.model = snapshot.model.into();
+        self.estimator = snapshot.estimator.into();
         self.avg_power_watts = snapshot.avg_power_watts;
         self.sample_count = snapshot.sample_count;
         self.source = snapshot.source.into();
         self.last_sample_timestamp = None;
+        self.charge = snapshot.charge.as_ref().map(Into::into);
+    }
+}
+
+/// Hours until `remaining_wh` runs out, starting at `current_watts` and
+/// drifting toward the profile's `quantile` for each upcoming hour. `None`
+/// if the battery outlasts `MAX_FORECAST_HOURS`.
+fn project_hours(
+    remaining_wh: f32,
+    current_watts: f32,
+    profile: Option<&UsageProfile>,
+    now: i64,
+    quantile: f32,
+) -> Option<f32> {
+    let Some(profile) = profile else {
+        let hours = remaining_wh / current_watts;
+        return (hours > 0.0 && hours < MAX_FORECAST_HOURS).then_some(hours);
+    };
+
+    let mut remaining = remaining_wh;
+    let mut elapsed = 0.0;
+    while elapsed < MAX_FORECAST_HOURS {
+        let midpoint = elapsed + PROFILE_STEP_HOURS / 2.0;
+        let timestamp = now + (midpoint * 3600.0) as i64;
+        let power = match profile.power_at(timestamp, quantile) {
+            Some(typical) => {
+                let weight = (-midpoint / WINDOW_WEIGHT_HOURS).exp();
+                weight * current_watts + (1.0 - weight) * typical
+            }
+            None => current_watts,
+        };
+
+        let used = power * PROFILE_STEP_HOURS;
+        if used >= remaining {
+            return Some(elapsed + remaining / power);
+        }
+        remaining -= used;
+        elapsed += PROFILE_STEP_HOURS;
+    }
+    None
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let total_mins = duration.as_secs() / 60;
+    if total_mins == 0 {
+        return "< 1m".to_string();
+    }
+    let hours = total_mins / 60;
+    let mins = total_mins % 60;
+
+    if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else {
+        format!("{}m", mins)
     }
 }
 
@@ -361,6 +581,7 @@ mod tests {
 
         let points: Vec<_> = (0..10)
             .map(|i| DataPoint {
+                timestamp: i as i64,
                 battery_percent: 80.0 - i as f32,
                 power_watts: 10.0 + (i % 3) as f32,
                 temperature_c: None,
@@ -378,11 +599,80 @@ mod tests {
         assert!(hours > 4.0 && hours < 5.0);
     }
 
+    #[test]
+    fn test_window_range_brackets_median() {
+        let mut forecast = ForecastData::new();
+        let powers: Vec<f32> = (0..20).map(|i| 8.0 + (i % 5) as f32).collect();
+
+        let estimate = PowerEstimate::from_powers(&powers).unwrap();
+        assert!(forecast.calculate_forecast(estimate, 50.0, 100.0, 0));
+        assert_eq!(forecast.model(), ForecastModel::Window);
+        let (p10, p50, p90) = (
+            forecast.p10_secs().unwrap(),
+            forecast.duration_secs().unwrap(),
+            forecast.p90_secs().unwrap(),
+        );
+        assert!(p10 < p50 && p50 < p90, "{p10} < {p50} < {p90}");
+        // 50 Wh at the 10 W average
+        assert_eq!(p50, 5 * 3600);
+    }
+
+    #[test]
+    fn test_profile_anticipates_heavy_hours() {
+        use crate::data::{DayZone, HourlyStat};
+
+        // 2024-03-04 00:00 UTC, a Monday
+        let monday = 1_709_510_400;
+        let busy_mondays: Vec<HourlyStat> = (1..=4)
+            .flat_map(|week| (0..24).map(move |hour| monday - week * 7 * 86400 + hour * 3600))
+            .map(|hour_start| HourlyStat {
+                id: None,
+                hour_start,
+                avg_power: 25.0,
+                max_power: 30.0,
+                min_power: 20.0,
+                avg_battery: 50.0,
+                battery_delta: -20.0,
+                total_samples: 60,
+            })
+            .collect();
+
+        let mut forecast = ForecastData::new();
+        forecast.set_profile(Some(UsageProfile::from_hourly(
+            &busy_mondays,
+            DayZone::parse("utc").unwrap(),
+        )));
+
+        // An idle moment at 09:00 on Monday
+        let estimate = PowerEstimate::from_powers(&[5.0; 20]).unwrap();
+        assert!(forecast.calculate_forecast(estimate, 50.0, 100.0, monday + 9 * 3600));
+        assert_eq!(forecast.model(), ForecastModel::Profile);
+
+        let hours = forecast.duration_secs().unwrap() as f32 / 3600.0;
+        assert!(
+            hours > 2.0 && hours < 4.0,
+            "idle window alone says 10h, got {hours}"
+        );
+    }
+
+    #[test]
+    fn test_sparse_profile_is_ignored() {
+        let mut forecast = ForecastData::new();
+        forecast.set_profile(Some(UsageProfile::from_hourly(
+            &[],
+            crate::data::DayZone::Local,
+        )));
+        let estimate = PowerEstimate::from_powers(&[10.0; 20]).unwrap();
+        assert!(forecast.calculate_forecast(estimate, 50.0, 100.0, 0));
+        assert_eq!(forecast.model(), ForecastModel::Window);
+    }
+
     #[test]
     fn test_insufficient_samples() {
         let mut forecast = ForecastData::new();
 
         let points = vec![DataPoint {
+            timestamp: 0,
             battery_percent: 80.0,
             power_watts: 10.0,
             temperature_c: None,
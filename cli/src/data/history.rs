@@ -12,6 +12,7 @@ pub enum HistoryMetric {
 
 #[derive(Debug, Clone, Copy)]
 pub struct DataPoint {
+    pub timestamp: i64,
     pub battery_percent: f32,
     pub power_watts: f32,
     pub temperature_c: Option<f32>,
@@ -42,6 +43,7 @@ impl HistoryData {
 
     pub fn record(&mut self, battery_percent: f32, power_watts: f32, temperature_c: Option<f32>) {
         let point = DataPoint {
+            timestamp: chrono::Utc::now().timestamp(),
             battery_percent,
             power_watts,
             temperature_c,
@@ -167,6 +169,17 @@ impl HistoryData {
         ((min - padding).max(0.0) as f64, (max + padding) as f64)
     }
 
+    /// Maps a wall-clock time onto a point index, if it falls within the
+    /// recorded window.
+    pub fn index_at(&self, timestamp: i64) -> Option<usize> {
+        let first = self.points.front()?.timestamp;
+        if timestamp < first {
+            return None;
+        }
+        let index = self.points.partition_point(|p| p.timestamp < timestamp);
+        Some(index.min(self.points.len() - 1))
+    }
+
     pub fn has_temperature_data(&self) -> bool {
         self.points.iter().any(|p| p.temperature_c.is_some())
     }
@@ -3,16 +3,122 @@
 //! Uses SQLite with WAL mode for efficient concurrent access between
 //! the daemon (writer) and TUI (reader).
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use chrono::Utc;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::backup::Backup;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
 use crate::config::data_dir;
 
-const CURRENT_SCHEMA_VERSION: i32 = 3;
+pub const CURRENT_SCHEMA_VERSION: i32 = 10;
 const DATABASE_NAME: &str = "history.db";
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Pages copied per online-backup step, and the pause between steps that
+/// lets the daemon's writes through
+const BACKUP_PAGES_PER_STEP: i32 = 100;
+const BACKUP_PAUSE: Duration = Duration::from_millis(10);
+
+/// Source tag for rows recorded on this machine; merged databases use
+/// their own tag
+pub const LOCAL_SOURCE: &str = "local";
+
+/// Metadata key holding the zone daily rows were computed in
+pub const DAY_ZONE_KEY: &str = "day_zone";
+
+/// Longest annotation text accepted, in characters
+pub const MAX_ANNOTATION_LEN: usize = 500;
+
+/// Views for ad-hoc queries that spell out the integer enum columns and
+/// format timestamps in local time. Documented in historical-data.md.
+const QUERY_VIEWS: &str = r#"
+    CREATE VIEW v_samples AS
+    SELECT id,
+           timestamp,
+           datetime(timestamp, 'unixepoch', 'localtime') AS time,
+           battery_percent,
+           power_watts,
+           cpu_power,
+           gpu_power,
+           CASE charging_state
+               WHEN 0 THEN 'discharging'
+               WHEN 1 THEN 'charging'
+               WHEN 2 THEN 'full'
+               ELSE 'unknown'
+           END AS charging_state,
+           source
+    FROM samples;
+
+    CREATE VIEW v_hourly AS
+    SELECT hour_start,
+           datetime(hour_start, 'unixepoch', 'localtime') AS hour,
+           avg_power,
+           max_power,
+           min_power,
+           avg_battery,
+           battery_delta,
+           total_samples,
+           source
+    FROM hourly_stats;
+
+    CREATE VIEW v_daily_energy AS
+    SELECT d.date,
+           d.total_energy_wh,
+           d.avg_power,
+           d.max_power,
+           d.screen_on_hours,
+           d.charging_hours,
+           d.battery_cycles,
+           c.energy_charged_wh,
+           c.energy_discharged_wh,
+           d.source
+    FROM daily_stats d
+    LEFT JOIN daily_cycles c ON c.source = d.source AND c.date = d.date;
+
+    CREATE VIEW v_sessions AS
+    SELECT id,
+           CASE session_type WHEN 0 THEN 'charge' ELSE 'discharge' END AS session_type,
+           start_time,
+           end_time,
+           datetime(start_time, 'unixepoch', 'localtime') AS started,
+           datetime(end_time, 'unixepoch', 'localtime') AS ended,
+           end_time - start_time AS duration_secs,
+           start_percent,
+           end_percent,
+           energy_wh,
+           charger_watts,
+           avg_power_watts,
+           is_complete,
+           source
+    FROM charge_sessions;
+"#;
+
+/// Query view over the event log. Kept apart from `QUERY_VIEWS` so it can be
+/// recreated when new event kinds are added.
+const EVENTS_VIEW: &str = r#"
+    CREATE VIEW v_events AS
+    SELECT id,
+           timestamp,
+           datetime(timestamp, 'unixepoch', 'localtime') AS time,
+           CASE kind
+               WHEN 0 THEN 'daemon start'
+               WHEN 1 THEN 'daemon stop'
+               WHEN 2 THEN 'plugged in'
+               WHEN 3 THEN 'unplugged'
+               WHEN 4 THEN 'session start'
+               WHEN 5 THEN 'session end'
+               WHEN 6 THEN 'power mode'
+               WHEN 8 THEN 'alert'
+               WHEN 9 THEN 'battery saver'
+               ELSE 'note'
+           END AS kind,
+           message
+    FROM events;
+"#;
 
 /// Charging state for a sample
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -87,6 +193,31 @@ pub struct DailyTopProcess {
     pub total_energy_wh: f32, // Total energy consumed in Wh
 }
 
+/// Per-process usage within a single hour
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyProcessStat {
+    pub id: Option<i64>,
+    pub hour_start: i64,
+    pub process_name: String,
+    pub avg_impact: f32,
+    pub avg_cpu: f32,
+    pub avg_memory_mb: f32,
+    pub avg_power: f32,
+    pub total_energy_wh: f32,
+    pub sample_count: i32,
+}
+
+/// One process's usage at a single recorded sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSample {
+    pub timestamp: i64,
+    pub process_name: String,
+    pub energy_impact: f32,
+    pub cpu_usage: f32,
+    pub memory_mb: f32,
+    pub power_watts: f32,
+}
+
 /// Battery health snapshot (stored daily)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatteryHealthSnapshot {
@@ -115,6 +246,96 @@ impl From<i32> for SessionType {
     }
 }
 
+/// Kind of entry in the event log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(i32)]
+pub enum EventKind {
+    DaemonStart = 0,
+    DaemonStop = 1,
+    PowerConnected = 2,
+    PowerDisconnected = 3,
+    SessionStart = 4,
+    SessionEnd = 5,
+    PowerModeChange = 6,
+    Annotation = 7,
+    Alert = 8,
+    BatterySaver = 9,
+}
+
+impl From<i32> for EventKind {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => EventKind::DaemonStart,
+            1 => EventKind::DaemonStop,
+            2 => EventKind::PowerConnected,
+            3 => EventKind::PowerDisconnected,
+            4 => EventKind::SessionStart,
+            5 => EventKind::SessionEnd,
+            6 => EventKind::PowerModeChange,
+            8 => EventKind::Alert,
+            9 => EventKind::BatterySaver,
+            _ => EventKind::Annotation,
+        }
+    }
+}
+
+impl EventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::DaemonStart => "daemon start",
+            EventKind::DaemonStop => "daemon stop",
+            EventKind::PowerConnected => "plugged in",
+            EventKind::PowerDisconnected => "unplugged",
+            EventKind::SessionStart => "session start",
+            EventKind::SessionEnd => "session end",
+            EventKind::PowerModeChange => "power mode",
+            EventKind::Annotation => "note",
+            EventKind::Alert => "alert",
+            EventKind::BatterySaver => "battery saver",
+        }
+    }
+}
+
+/// Something that happened at a point in time (plug, session, annotation...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: Option<i64>,
+    pub timestamp: i64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+impl Event {
+    pub fn now(kind: EventKind, message: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            timestamp: Utc::now().timestamp(),
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Builds a user annotation, rejecting empty or overly long text.
+    pub fn annotation(text: &str, timestamp: Option<i64>) -> std::result::Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Annotation text is empty".to_string());
+        }
+        if text.chars().count() > MAX_ANNOTATION_LEN {
+            return Err(format!(
+                "Annotation text is longer than {} characters",
+                MAX_ANNOTATION_LEN
+            ));
+        }
+        Ok(Self {
+            id: None,
+            timestamp: timestamp.unwrap_or_else(|| Utc::now().timestamp()),
+            kind: EventKind::Annotation,
+            message: text.to_string(),
+        })
+    }
+}
+
 /// Individual charge or discharge session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChargeSession {
@@ -230,6 +451,21 @@ pub enum HistoryStoreError {
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("{0} is not a jolt history database")]
+    NotHistory(PathBuf),
+
+    #[error("{0} uses schema v{1}, which is newer than this version of jolt supports")]
+    NewerSchema(PathBuf, i32),
+
+    #[error("Invalid source name '{0}': use a non-empty name other than \"local\"")]
+    InvalidSource(String),
+
+    #[error("{0} uses schema v{1}; run `jolt history doctor` to upgrade it")]
+    OlderSchema(PathBuf, i32),
+
+    #[error("Only read-only statements can be run against the history database")]
+    NotReadOnly,
 }
 
 pub type Result<T> = std::result::Result<T, HistoryStoreError>;
@@ -243,14 +479,44 @@ pub struct HistoryStore {
 impl HistoryStore {
     /// Open or create the history database
     pub fn open() -> Result<Self> {
-        let dir = data_dir();
-        std::fs::create_dir_all(&dir)?;
+        std::fs::create_dir_all(data_dir())?;
+        Self::open_at(&Self::default_path())
+    }
+
+    /// Location of the history database
+    pub fn default_path() -> PathBuf {
+        data_dir().join(DATABASE_NAME)
+    }
+
+    /// Open the history database without write access, for running
+    /// user-supplied queries alongside the daemon. The schema isn't
+    /// migrated, so an outdated database is rejected.
+    pub fn open_read_only() -> Result<Self> {
+        let path = Self::default_path();
+        let conn = open_history_read_only(&path)?;
+
+        let version = schema_version(&conn)?;
+        if version < CURRENT_SCHEMA_VERSION {
+            return Err(HistoryStoreError::OlderSchema(path, version));
+        }
+
+        conn.execute_batch(
+            "PRAGMA query_only=ON;
+             PRAGMA busy_timeout=5000;",
+        )?;
+
+        Ok(Self { conn, path })
+    }
 
-        let path = dir.join(DATABASE_NAME);
+    /// Open or create a history database at `path`, migrating it to the
+    /// current schema
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
         let conn = Connection::open(&path)?;
 
         conn.execute_batch(
-            "PRAGMA journal_mode=WAL;
+            "PRAGMA auto_vacuum=INCREMENTAL;
+             PRAGMA journal_mode=WAL;
              PRAGMA synchronous=NORMAL;
              PRAGMA foreign_keys=ON;
              PRAGMA busy_timeout=5000;",
@@ -262,6 +528,10 @@ impl HistoryStore {
         Ok(store)
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Get the database file size in bytes
     pub fn size_bytes(&self) -> Result<u64> {
         if self.path.to_string_lossy() == ":memory:" {
@@ -285,25 +555,7 @@ impl HistoryStore {
 
     /// Get the current schema version (0 if not initialized)
     fn get_schema_version(&self) -> Result<i32> {
-        let exists: bool = self.conn.query_row(
-            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='schema_version'",
-            [],
-            |row| row.get(0),
-        )?;
-
-        if !exists {
-            return Ok(0);
-        }
-
-        let version: i32 = self
-            .conn
-            .query_row("SELECT version FROM schema_version LIMIT 1", [], |row| {
-                row.get(0)
-            })
-            .optional()?
-            .unwrap_or(0);
-
-        Ok(version)
+        schema_version(&self.conn)
     }
 
     /// Create the initial database schema
@@ -325,31 +577,36 @@ impl HistoryStore {
                 power_watts REAL NOT NULL,
                 cpu_power REAL NOT NULL,
                 gpu_power REAL NOT NULL,
-                charging_state INTEGER NOT NULL DEFAULT 0
+                charging_state INTEGER NOT NULL DEFAULT 0,
+                source TEXT NOT NULL DEFAULT 'local'
             );
 
             -- Hourly aggregates for efficient long-term queries
             CREATE TABLE hourly_stats (
                 id INTEGER PRIMARY KEY,
-                hour_start INTEGER NOT NULL UNIQUE,
+                hour_start INTEGER NOT NULL,
                 avg_power REAL NOT NULL,
                 max_power REAL NOT NULL,
                 min_power REAL NOT NULL,
                 avg_battery REAL NOT NULL,
                 battery_delta REAL NOT NULL,
-                total_samples INTEGER NOT NULL
+                total_samples INTEGER NOT NULL,
+                source TEXT NOT NULL DEFAULT 'local',
+                UNIQUE(source, hour_start)
             );
 
             -- Daily summaries
             CREATE TABLE daily_stats (
                 id INTEGER PRIMARY KEY,
-                date TEXT NOT NULL UNIQUE,
+                date TEXT NOT NULL,
                 avg_power REAL NOT NULL,
                 max_power REAL NOT NULL,
                 total_energy_wh REAL NOT NULL,
                 screen_on_hours REAL NOT NULL DEFAULT 0,
                 charging_hours REAL NOT NULL DEFAULT 0,
-                battery_cycles REAL NOT NULL DEFAULT 0
+                battery_cycles REAL NOT NULL DEFAULT 0,
+                source TEXT NOT NULL DEFAULT 'local',
+                UNIQUE(source, date)
             );
 
             -- Top power consumers per day
@@ -366,14 +623,41 @@ impl HistoryStore {
                 UNIQUE(date, process_name)
             );
 
+            -- Per-process usage per hour
+            CREATE TABLE hourly_processes (
+                id INTEGER PRIMARY KEY,
+                hour_start INTEGER NOT NULL,
+                process_name TEXT NOT NULL,
+                avg_impact REAL NOT NULL,
+                avg_cpu REAL NOT NULL,
+                avg_memory_mb REAL NOT NULL,
+                avg_power REAL NOT NULL,
+                total_energy_wh REAL NOT NULL,
+                sample_count INTEGER NOT NULL,
+                UNIQUE(hour_start, process_name)
+            );
+
+            -- Raw per-process usage, rolled up into hourly_processes
+            CREATE TABLE process_samples (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                process_name TEXT NOT NULL,
+                energy_impact REAL NOT NULL,
+                cpu_usage REAL NOT NULL,
+                memory_mb REAL NOT NULL,
+                power_watts REAL NOT NULL
+            );
+
             -- Battery health snapshots (daily)
             CREATE TABLE battery_health (
                 id INTEGER PRIMARY KEY,
-                date TEXT NOT NULL UNIQUE,
+                date TEXT NOT NULL,
                 health_percent REAL NOT NULL,
                 cycle_count INTEGER,
                 max_capacity_wh REAL NOT NULL,
-                design_capacity_wh REAL NOT NULL
+                design_capacity_wh REAL NOT NULL,
+                source TEXT NOT NULL DEFAULT 'local',
+                UNIQUE(source, date)
             );
 
             -- Charge/discharge session tracking
@@ -387,13 +671,14 @@ impl HistoryStore {
                 charger_watts INTEGER,
                 avg_power_watts REAL,
                 session_type INTEGER NOT NULL,
-                is_complete INTEGER NOT NULL DEFAULT 0
+                is_complete INTEGER NOT NULL DEFAULT 0,
+                source TEXT NOT NULL DEFAULT 'local'
             );
 
             -- Daily cycle summary (aggregated from sessions)
             CREATE TABLE daily_cycles (
                 id INTEGER PRIMARY KEY,
-                date TEXT NOT NULL UNIQUE,
+                date TEXT NOT NULL,
                 charge_sessions INTEGER NOT NULL DEFAULT 0,
                 discharge_sessions INTEGER NOT NULL DEFAULT 0,
                 total_charging_mins INTEGER NOT NULL DEFAULT 0,
@@ -404,7 +689,9 @@ impl HistoryStore {
                 partial_cycles REAL NOT NULL DEFAULT 0,
                 macos_cycle_count INTEGER,
                 avg_temperature_c REAL,
-                time_at_high_soc_mins INTEGER NOT NULL DEFAULT 0
+                time_at_high_soc_mins INTEGER NOT NULL DEFAULT 0,
+                source TEXT NOT NULL DEFAULT 'local',
+                UNIQUE(source, date)
             );
 
             -- Long-term cycle tracking snapshots
@@ -416,6 +703,20 @@ impl HistoryStore {
                 battery_health_percent REAL NOT NULL
             );
 
+            -- Event log (plug/unplug, sessions, daemon lifecycle, annotations)
+            CREATE TABLE events (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                kind INTEGER NOT NULL,
+                message TEXT NOT NULL DEFAULT ''
+            );
+
+            -- Store-wide settings that affect how rows were computed
+            CREATE TABLE metadata (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
             -- Indexes for efficient queries
             CREATE INDEX idx_samples_timestamp ON samples(timestamp);
             CREATE INDEX idx_hourly_hour ON hourly_stats(hour_start);
@@ -426,8 +727,15 @@ impl HistoryStore {
             CREATE INDEX idx_charge_sessions_type ON charge_sessions(session_type);
             CREATE INDEX idx_daily_cycles_date ON daily_cycles(date);
             CREATE INDEX idx_cycle_snapshots_date ON cycle_snapshots(date);
+            CREATE INDEX idx_hourly_processes_name ON hourly_processes(process_name, hour_start);
+            CREATE INDEX idx_process_samples_timestamp ON process_samples(timestamp);
+            CREATE INDEX idx_events_timestamp ON events(timestamp);
+            CREATE INDEX idx_samples_source ON samples(source, timestamp);
+            CREATE INDEX idx_charge_sessions_source ON charge_sessions(source, start_time);
             "#,
         )?;
+        tx.execute_batch(QUERY_VIEWS)?;
+        tx.execute_batch(EVENTS_VIEW)?;
 
         tx.execute(
             "INSERT INTO schema_version (version) VALUES (?)",
@@ -502,6 +810,182 @@ impl HistoryStore {
             )?;
         }
 
+        if from_version < 4 {
+            tx.execute_batch(
+                r#"
+                -- Per-process usage per hour
+                CREATE TABLE hourly_processes (
+                    id INTEGER PRIMARY KEY,
+                    hour_start INTEGER NOT NULL,
+                    process_name TEXT NOT NULL,
+                    avg_impact REAL NOT NULL,
+                    avg_cpu REAL NOT NULL,
+                    avg_memory_mb REAL NOT NULL,
+                    avg_power REAL NOT NULL,
+                    total_energy_wh REAL NOT NULL,
+                    sample_count INTEGER NOT NULL,
+                    UNIQUE(hour_start, process_name)
+                );
+
+                CREATE INDEX idx_hourly_processes_name ON hourly_processes(process_name, hour_start);
+                "#,
+            )?;
+        }
+
+        if from_version < 5 {
+            tx.execute_batch(
+                r#"
+                -- Event log (plug/unplug, sessions, daemon lifecycle, annotations)
+                CREATE TABLE events (
+                    id INTEGER PRIMARY KEY,
+                    timestamp INTEGER NOT NULL,
+                    kind INTEGER NOT NULL,
+                    message TEXT NOT NULL DEFAULT ''
+                );
+
+                CREATE INDEX idx_events_timestamp ON events(timestamp);
+                "#,
+            )?;
+        }
+
+        if from_version < 6 {
+            // Daily rows written so far used UTC day boundaries; recording
+            // that lets the aggregator rebuild them in the configured zone.
+            tx.execute_batch(
+                r#"
+                CREATE TABLE metadata (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );
+                "#,
+            )?;
+            tx.execute(
+                "INSERT INTO metadata (key, value) VALUES (?, '+00:00')",
+                [DAY_ZONE_KEY],
+            )?;
+        }
+
+        if from_version < 7 {
+            // Tag every row with the machine it came from so other databases
+            // can be merged in. SQLite can't alter a UNIQUE constraint, so the
+            // per-period tables are rebuilt with one that includes the source.
+            tx.execute_batch(
+                r#"
+                ALTER TABLE samples ADD COLUMN source TEXT NOT NULL DEFAULT 'local';
+                ALTER TABLE charge_sessions ADD COLUMN source TEXT NOT NULL DEFAULT 'local';
+
+                CREATE TABLE hourly_stats_v7 (
+                    id INTEGER PRIMARY KEY,
+                    hour_start INTEGER NOT NULL,
+                    avg_power REAL NOT NULL,
+                    max_power REAL NOT NULL,
+                    min_power REAL NOT NULL,
+                    avg_battery REAL NOT NULL,
+                    battery_delta REAL NOT NULL,
+                    total_samples INTEGER NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'local',
+                    UNIQUE(source, hour_start)
+                );
+                INSERT INTO hourly_stats_v7 (id, hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples)
+                    SELECT id, hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples FROM hourly_stats;
+                DROP TABLE hourly_stats;
+                ALTER TABLE hourly_stats_v7 RENAME TO hourly_stats;
+
+                CREATE TABLE daily_stats_v7 (
+                    id INTEGER PRIMARY KEY,
+                    date TEXT NOT NULL,
+                    avg_power REAL NOT NULL,
+                    max_power REAL NOT NULL,
+                    total_energy_wh REAL NOT NULL,
+                    screen_on_hours REAL NOT NULL DEFAULT 0,
+                    charging_hours REAL NOT NULL DEFAULT 0,
+                    battery_cycles REAL NOT NULL DEFAULT 0,
+                    source TEXT NOT NULL DEFAULT 'local',
+                    UNIQUE(source, date)
+                );
+                INSERT INTO daily_stats_v7 (id, date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles)
+                    SELECT id, date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles FROM daily_stats;
+                DROP TABLE daily_stats;
+                ALTER TABLE daily_stats_v7 RENAME TO daily_stats;
+
+                CREATE TABLE battery_health_v7 (
+                    id INTEGER PRIMARY KEY,
+                    date TEXT NOT NULL,
+                    health_percent REAL NOT NULL,
+                    cycle_count INTEGER,
+                    max_capacity_wh REAL NOT NULL,
+                    design_capacity_wh REAL NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'local',
+                    UNIQUE(source, date)
+                );
+                INSERT INTO battery_health_v7 (id, date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh)
+                    SELECT id, date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh FROM battery_health;
+                DROP TABLE battery_health;
+                ALTER TABLE battery_health_v7 RENAME TO battery_health;
+
+                CREATE TABLE daily_cycles_v7 (
+                    id INTEGER PRIMARY KEY,
+                    date TEXT NOT NULL,
+                    charge_sessions INTEGER NOT NULL DEFAULT 0,
+                    discharge_sessions INTEGER NOT NULL DEFAULT 0,
+                    total_charging_mins INTEGER NOT NULL DEFAULT 0,
+                    total_discharge_mins INTEGER NOT NULL DEFAULT 0,
+                    deepest_discharge_percent REAL,
+                    energy_charged_wh REAL NOT NULL DEFAULT 0,
+                    energy_discharged_wh REAL NOT NULL DEFAULT 0,
+                    partial_cycles REAL NOT NULL DEFAULT 0,
+                    macos_cycle_count INTEGER,
+                    avg_temperature_c REAL,
+                    time_at_high_soc_mins INTEGER NOT NULL DEFAULT 0,
+                    source TEXT NOT NULL DEFAULT 'local',
+                    UNIQUE(source, date)
+                );
+                INSERT INTO daily_cycles_v7 (id, date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins)
+                    SELECT id, date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins FROM daily_cycles;
+                DROP TABLE daily_cycles;
+                ALTER TABLE daily_cycles_v7 RENAME TO daily_cycles;
+
+                CREATE INDEX idx_hourly_hour ON hourly_stats(hour_start);
+                CREATE INDEX idx_daily_date ON daily_stats(date);
+                CREATE INDEX idx_battery_health_date ON battery_health(date);
+                CREATE INDEX idx_daily_cycles_date ON daily_cycles(date);
+                CREATE INDEX idx_samples_source ON samples(source, timestamp);
+                CREATE INDEX idx_charge_sessions_source ON charge_sessions(source, start_time);
+                "#,
+            )?;
+        }
+
+        if from_version < 8 {
+            tx.execute_batch(QUERY_VIEWS)?;
+        }
+
+        if from_version < 9 {
+            // Hourly process rows used to be upserted live; they are now
+            // rolled up from raw samples so they can be rebuilt.
+            tx.execute_batch(
+                r#"
+                CREATE TABLE process_samples (
+                    id INTEGER PRIMARY KEY,
+                    timestamp INTEGER NOT NULL,
+                    process_name TEXT NOT NULL,
+                    energy_impact REAL NOT NULL,
+                    cpu_usage REAL NOT NULL,
+                    memory_mb REAL NOT NULL,
+                    power_watts REAL NOT NULL
+                );
+
+                CREATE INDEX idx_process_samples_timestamp ON process_samples(timestamp);
+                "#,
+            )?;
+        }
+
+        if from_version < 10 {
+            // Views are stored in the database, so newer event kinds only
+            // get their labels by recreating the view.
+            tx.execute_batch("DROP VIEW IF EXISTS v_events;")?;
+            tx.execute_batch(EVENTS_VIEW)?;
+        }
+
         tx.execute(
             "UPDATE schema_version SET version = ?",
             [CURRENT_SCHEMA_VERSION],
@@ -527,32 +1011,97 @@ impl HistoryStore {
         Ok(self.conn.last_insert_rowid())
     }
 
+    /// Insert samples whose timestamp isn't already stored, returning how
+    /// many were added
+    pub fn insert_samples_if_new(&self, samples: &[Sample]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO samples (timestamp, battery_percent, power_watts, cpu_power, gpu_power, charging_state)
+                 SELECT ?1, ?2, ?3, ?4, ?5, ?6
+                 WHERE NOT EXISTS (SELECT 1 FROM samples WHERE source = 'local' AND timestamp = ?1)",
+            )?;
+            for sample in samples {
+                inserted += stmt.execute(params![
+                    sample.timestamp,
+                    sample.battery_percent,
+                    sample.power_watts,
+                    sample.cpu_power,
+                    sample.gpu_power,
+                    sample.charging_state as i32,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
     /// Get samples in a time range
     pub fn get_samples(&self, from: i64, to: i64) -> Result<Vec<Sample>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, timestamp, battery_percent, power_watts, cpu_power, gpu_power, charging_state
              FROM samples
-             WHERE timestamp >= ? AND timestamp <= ?
+             WHERE source = 'local' AND timestamp >= ? AND timestamp <= ?
              ORDER BY timestamp ASC",
         )?;
 
         let samples = stmt
-            .query_map(params![from, to], |row| {
-                Ok(Sample {
-                    id: Some(row.get(0)?),
-                    timestamp: row.get(1)?,
-                    battery_percent: row.get(2)?,
-                    power_watts: row.get(3)?,
-                    cpu_power: row.get(4)?,
-                    gpu_power: row.get(5)?,
-                    charging_state: ChargingState::from(row.get::<_, i32>(6)?),
-                })
-            })?
+            .query_map(params![from, to], Self::map_sample)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(samples)
     }
 
+    /// Stream local samples in a time range to `f` without collecting them
+    pub fn for_each_sample(
+        &self,
+        from: i64,
+        to: i64,
+        f: impl FnMut(Sample) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, timestamp, battery_percent, power_watts, cpu_power, gpu_power, charging_state
+             FROM samples
+             WHERE source = 'local' AND timestamp >= ? AND timestamp <= ?
+             ORDER BY timestamp ASC",
+            params![from, to],
+            Self::map_sample,
+            f,
+        )
+    }
+
+    fn map_sample(row: &rusqlite::Row) -> rusqlite::Result<Sample> {
+        Ok(Sample {
+            id: Some(row.get(0)?),
+            timestamp: row.get(1)?,
+            battery_percent: row.get(2)?,
+            power_watts: row.get(3)?,
+            cpu_power: row.get(4)?,
+            gpu_power: row.get(5)?,
+            charging_state: ChargingState::from(row.get::<_, i32>(6)?),
+        })
+    }
+
+    /// Run `sql` and hand each mapped row to `f`, stopping at the first
+    /// error. Returns the number of rows passed to `f`.
+    fn for_each_row<T>(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+        map: fn(&rusqlite::Row) -> rusqlite::Result<T>,
+        mut f: impl FnMut(T) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let mut rows = stmt.query(params)?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            f(map(row)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     /// Delete samples older than the given timestamp
     pub fn delete_samples_before(&self, before: i64) -> Result<usize> {
         let deleted = self
@@ -561,11 +1110,16 @@ impl HistoryStore {
         Ok(deleted)
     }
 
+    /// Delete the `limit` oldest samples
+    pub fn delete_oldest_samples(&self, limit: usize) -> Result<usize> {
+        self.delete_oldest("samples", "timestamp", limit)
+    }
+
     pub fn upsert_hourly_stat(&self, stat: &HourlyStat) -> Result<()> {
         self.conn.execute(
             "INSERT INTO hourly_stats (hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples)
              VALUES (?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(hour_start) DO UPDATE SET
+             ON CONFLICT(source, hour_start) DO UPDATE SET
                 avg_power = excluded.avg_power,
                 max_power = excluded.max_power,
                 min_power = excluded.min_power,
@@ -590,28 +1144,48 @@ impl HistoryStore {
         let mut stmt = self.conn.prepare(
             "SELECT id, hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples
              FROM hourly_stats
-             WHERE hour_start >= ? AND hour_start <= ?
+             WHERE source = 'local' AND hour_start >= ? AND hour_start <= ?
              ORDER BY hour_start ASC",
         )?;
 
         let stats = stmt
-            .query_map(params![from, to], |row| {
-                Ok(HourlyStat {
-                    id: Some(row.get(0)?),
-                    hour_start: row.get(1)?,
-                    avg_power: row.get(2)?,
-                    max_power: row.get(3)?,
-                    min_power: row.get(4)?,
-                    avg_battery: row.get(5)?,
-                    battery_delta: row.get(6)?,
-                    total_samples: row.get(7)?,
-                })
-            })?
+            .query_map(params![from, to], Self::map_hourly_stat)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(stats)
     }
 
+    /// Stream local hourly stats in a time range to `f`
+    pub fn for_each_hourly_stat(
+        &self,
+        from: i64,
+        to: i64,
+        f: impl FnMut(HourlyStat) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples
+             FROM hourly_stats
+             WHERE source = 'local' AND hour_start >= ? AND hour_start <= ?
+             ORDER BY hour_start ASC",
+            params![from, to],
+            Self::map_hourly_stat,
+            f,
+        )
+    }
+
+    fn map_hourly_stat(row: &rusqlite::Row) -> rusqlite::Result<HourlyStat> {
+        Ok(HourlyStat {
+            id: Some(row.get(0)?),
+            hour_start: row.get(1)?,
+            avg_power: row.get(2)?,
+            max_power: row.get(3)?,
+            min_power: row.get(4)?,
+            avg_battery: row.get(5)?,
+            battery_delta: row.get(6)?,
+            total_samples: row.get(7)?,
+        })
+    }
+
     /// Delete hourly stats older than the given timestamp
     pub fn delete_hourly_stats_before(&self, before: i64) -> Result<usize> {
         let deleted = self
@@ -620,11 +1194,25 @@ impl HistoryStore {
         Ok(deleted)
     }
 
+    /// Delete local hourly stats starting at or after the given timestamp
+    pub fn delete_hourly_stats_since(&self, since: i64) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM hourly_stats WHERE source = 'local' AND hour_start >= ?",
+            [since],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Delete the `limit` oldest hourly stats
+    pub fn delete_oldest_hourly_stats(&self, limit: usize) -> Result<usize> {
+        self.delete_oldest("hourly_stats", "hour_start", limit)
+    }
+
     pub fn upsert_daily_stat(&self, stat: &DailyStat) -> Result<()> {
         self.conn.execute(
             "INSERT INTO daily_stats (date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles)
              VALUES (?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(date) DO UPDATE SET
+             ON CONFLICT(source, date) DO UPDATE SET
                 avg_power = excluded.avg_power,
                 max_power = excluded.max_power,
                 total_energy_wh = excluded.total_energy_wh,
@@ -655,28 +1243,31 @@ impl HistoryStore {
         from: &str,
         to: &str,
         limit: usize,
+    ) -> Result<Vec<DailyStat>> {
+        self.get_source_daily_stats(LOCAL_SOURCE, from, to, limit)
+    }
+
+    /// Get daily stats recorded by `source` (a merged machine or `local`)
+    pub fn get_source_daily_stats(
+        &self,
+        source: &str,
+        from: &str,
+        to: &str,
+        limit: usize,
     ) -> Result<Vec<DailyStat>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles
              FROM daily_stats
-             WHERE date >= ? AND date <= ?
+             WHERE source = ? AND date >= ? AND date <= ?
              ORDER BY date DESC
              LIMIT ?",
         )?;
 
         let mut stats: Vec<DailyStat> = stmt
-            .query_map(params![from, to, limit as i64], |row| {
-                Ok(DailyStat {
-                    id: Some(row.get(0)?),
-                    date: row.get(1)?,
-                    avg_power: row.get(2)?,
-                    max_power: row.get(3)?,
-                    total_energy_wh: row.get(4)?,
-                    screen_on_hours: row.get(5)?,
-                    charging_hours: row.get(6)?,
-                    battery_cycles: row.get(7)?,
-                })
-            })?
+            .query_map(
+                params![source, from, to, limit as i64],
+                Self::map_daily_stat,
+            )?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         stats.reverse();
@@ -689,26 +1280,46 @@ impl HistoryStore {
             .conn
             .query_row(
                 "SELECT id, date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles
-                 FROM daily_stats WHERE date = ?",
+                 FROM daily_stats WHERE source = 'local' AND date = ?",
                 [date],
-                |row| {
-                    Ok(DailyStat {
-                        id: Some(row.get(0)?),
-                        date: row.get(1)?,
-                        avg_power: row.get(2)?,
-                        max_power: row.get(3)?,
-                        total_energy_wh: row.get(4)?,
-                        screen_on_hours: row.get(5)?,
-                        charging_hours: row.get(6)?,
-                        battery_cycles: row.get(7)?,
-                    })
-                },
+                Self::map_daily_stat,
             )
             .optional()?;
 
         Ok(stat)
     }
 
+    /// Stream local daily stats in a date range to `f`, oldest first
+    pub fn for_each_daily_stat(
+        &self,
+        from: &str,
+        to: &str,
+        f: impl FnMut(DailyStat) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles
+             FROM daily_stats
+             WHERE source = 'local' AND date >= ? AND date <= ?
+             ORDER BY date ASC",
+            params![from, to],
+            Self::map_daily_stat,
+            f,
+        )
+    }
+
+    fn map_daily_stat(row: &rusqlite::Row) -> rusqlite::Result<DailyStat> {
+        Ok(DailyStat {
+            id: Some(row.get(0)?),
+            date: row.get(1)?,
+            avg_power: row.get(2)?,
+            max_power: row.get(3)?,
+            total_energy_wh: row.get(4)?,
+            screen_on_hours: row.get(5)?,
+            charging_hours: row.get(6)?,
+            battery_cycles: row.get(7)?,
+        })
+    }
+
     /// Delete daily stats older than the given date
     pub fn delete_daily_stats_before(&self, before: &str) -> Result<usize> {
         let deleted = self
@@ -717,8 +1328,21 @@ impl HistoryStore {
         Ok(deleted)
     }
 
-    pub fn upsert_daily_process(&self, process: &DailyTopProcess) -> Result<()> {
-        self.conn.execute(
+    /// Delete daily stats and cycle rows on or after the given date
+    pub fn delete_daily_aggregates_since(&self, date: &str) -> Result<usize> {
+        let stats = self.conn.execute(
+            "DELETE FROM daily_stats WHERE source = 'local' AND date >= ?",
+            [date],
+        )?;
+        let cycles = self.conn.execute(
+            "DELETE FROM daily_cycles WHERE source = 'local' AND date >= ?",
+            [date],
+        )?;
+        Ok(stats + cycles)
+    }
+
+    pub fn upsert_daily_process(&self, process: &DailyTopProcess) -> Result<()> {
+        self.conn.execute(
             "INSERT INTO daily_top_processes (date, process_name, total_impact, avg_cpu, avg_memory_mb, sample_count, avg_power, total_energy_wh)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(date, process_name) DO UPDATE SET
@@ -785,6 +1409,14 @@ impl HistoryStore {
         Ok(processes)
     }
 
+    /// Delete process entries on or after the given date
+    pub fn delete_daily_processes_since(&self, date: &str) -> Result<usize> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM daily_top_processes WHERE date >= ?", [date])?;
+        Ok(deleted)
+    }
+
     /// Delete process entries older than the given date
     pub fn delete_daily_processes_before(&self, before: &str) -> Result<usize> {
         let deleted = self
@@ -793,11 +1425,273 @@ impl HistoryStore {
         Ok(deleted)
     }
 
+    /// Replace one hour's per-process rows
+    pub fn replace_hourly_processes(
+        &self,
+        hour_start: i64,
+        processes: &[HourlyProcessStat],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM hourly_processes WHERE hour_start = ?",
+            [hour_start],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO hourly_processes (hour_start, process_name, avg_impact, avg_cpu, avg_memory_mb, avg_power, total_energy_wh, sample_count)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            for process in processes {
+                stmt.execute(params![
+                    hour_start,
+                    process.process_name,
+                    process.avg_impact,
+                    process.avg_cpu,
+                    process.avg_memory_mb,
+                    process.avg_power,
+                    process.total_energy_wh,
+                    process.sample_count,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn has_hourly_processes(&self, hour_start: i64) -> Result<bool> {
+        let exists = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM hourly_processes WHERE hour_start = ?)",
+            [hour_start],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    pub fn insert_process_samples(&self, samples: &[ProcessSample]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO process_samples (timestamp, process_name, energy_impact, cpu_usage, memory_mb, power_watts)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )?;
+            for sample in samples {
+                stmt.execute(params![
+                    sample.timestamp,
+                    sample.process_name,
+                    sample.energy_impact,
+                    sample.cpu_usage,
+                    sample.memory_mb,
+                    sample.power_watts,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get per-process samples in a time range
+    pub fn get_process_samples(&self, from: i64, to: i64) -> Result<Vec<ProcessSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, process_name, energy_impact, cpu_usage, memory_mb, power_watts
+             FROM process_samples
+             WHERE timestamp >= ? AND timestamp <= ?
+             ORDER BY timestamp ASC",
+        )?;
+
+        let samples = stmt
+            .query_map([from, to], |row| {
+                Ok(ProcessSample {
+                    timestamp: row.get(0)?,
+                    process_name: row.get(1)?,
+                    energy_impact: row.get(2)?,
+                    cpu_usage: row.get(3)?,
+                    memory_mb: row.get(4)?,
+                    power_watts: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(samples)
+    }
+
+    pub fn oldest_process_sample(&self) -> Result<Option<i64>> {
+        let oldest =
+            self.conn
+                .query_row("SELECT MIN(timestamp) FROM process_samples", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(oldest)
+    }
+
+    /// Delete per-process samples older than the given timestamp
+    pub fn delete_process_samples_before(&self, before: i64) -> Result<usize> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM process_samples WHERE timestamp < ?", [before])?;
+        Ok(deleted)
+    }
+
+    /// Delete the `limit` oldest per-process samples
+    pub fn delete_oldest_process_samples(&self, limit: usize) -> Result<usize> {
+        self.delete_oldest("process_samples", "timestamp", limit)
+    }
+
+    /// Get one process's hourly usage in a time range
+    pub fn get_process_history(
+        &self,
+        name: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<HourlyProcessStat>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, hour_start, process_name, avg_impact, avg_cpu, avg_memory_mb, avg_power, total_energy_wh, sample_count
+             FROM hourly_processes
+             WHERE process_name = ? AND hour_start >= ? AND hour_start <= ?
+             ORDER BY hour_start ASC",
+        )?;
+
+        let stats = stmt
+            .query_map(params![name, from, to], Self::map_hourly_process)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    fn map_hourly_process(row: &rusqlite::Row) -> rusqlite::Result<HourlyProcessStat> {
+        Ok(HourlyProcessStat {
+            id: Some(row.get(0)?),
+            hour_start: row.get(1)?,
+            process_name: row.get(2)?,
+            avg_impact: row.get(3)?,
+            avg_cpu: row.get(4)?,
+            avg_memory_mb: row.get(5)?,
+            avg_power: row.get(6)?,
+            total_energy_wh: row.get(7)?,
+            sample_count: row.get(8)?,
+        })
+    }
+
+    /// Get hourly rows for every process in a time range
+    pub fn get_hourly_processes(&self, from: i64, to: i64) -> Result<Vec<HourlyProcessStat>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, hour_start, process_name, avg_impact, avg_cpu, avg_memory_mb, avg_power, total_energy_wh, sample_count
+             FROM hourly_processes
+             WHERE hour_start >= ? AND hour_start <= ?
+             ORDER BY hour_start ASC",
+        )?;
+
+        let stats = stmt
+            .query_map([from, to], Self::map_hourly_process)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    /// Stream hourly rows for every process in a time range to `f`
+    pub fn for_each_hourly_process(
+        &self,
+        from: i64,
+        to: i64,
+        f: impl FnMut(HourlyProcessStat) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, hour_start, process_name, avg_impact, avg_cpu, avg_memory_mb, avg_power, total_energy_wh, sample_count
+             FROM hourly_processes
+             WHERE hour_start >= ? AND hour_start <= ?
+             ORDER BY hour_start ASC, process_name ASC",
+            params![from, to],
+            Self::map_hourly_process,
+            f,
+        )
+    }
+
+    pub fn oldest_hourly_process(&self) -> Result<Option<i64>> {
+        let oldest =
+            self.conn
+                .query_row("SELECT MIN(hour_start) FROM hourly_processes", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(oldest)
+    }
+
+    pub fn delete_hourly_processes_since(&self, since: i64) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM hourly_processes WHERE hour_start >= ?",
+            [since],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Delete hourly process rows older than the given timestamp
+    pub fn delete_hourly_processes_before(&self, before: i64) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM hourly_processes WHERE hour_start < ?",
+            [before],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Delete the `limit` oldest per-process rows, hourly rows before daily ones
+    pub fn delete_oldest_process_rows(&self, limit: usize) -> Result<usize> {
+        let deleted = self.delete_oldest("hourly_processes", "hour_start", limit)?;
+        if deleted > 0 {
+            return Ok(deleted);
+        }
+        self.delete_oldest("daily_top_processes", "date", limit)
+    }
+
+    fn delete_oldest(&self, table: &str, order_by: &str, limit: usize) -> Result<usize> {
+        let sql = format!(
+            "DELETE FROM {table} WHERE id IN (SELECT id FROM {table} ORDER BY {order_by} LIMIT ?)"
+        );
+        let deleted = self.conn.execute(&sql, [limit as i64])?;
+        Ok(deleted)
+    }
+
+    pub fn insert_event(&self, event: &Event) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO events (timestamp, kind, message) VALUES (?, ?, ?)",
+            params![event.timestamp, event.kind as i32, event.message],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Get events in a time range
+    pub fn get_events(&self, from: i64, to: i64) -> Result<Vec<Event>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, timestamp, kind, message
+             FROM events
+             WHERE timestamp >= ? AND timestamp <= ?
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let events = stmt
+            .query_map(params![from, to], |row| {
+                Ok(Event {
+                    id: Some(row.get(0)?),
+                    timestamp: row.get(1)?,
+                    kind: EventKind::from(row.get::<_, i32>(2)?),
+                    message: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// Delete events older than the given timestamp
+    pub fn delete_events_before(&self, before: i64) -> Result<usize> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM events WHERE timestamp < ?", [before])?;
+        Ok(deleted)
+    }
+
     pub fn upsert_battery_health(&self, snapshot: &BatteryHealthSnapshot) -> Result<()> {
         self.conn.execute(
             "INSERT INTO battery_health (date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh)
              VALUES (?, ?, ?, ?, ?)
-             ON CONFLICT(date) DO UPDATE SET
+             ON CONFLICT(source, date) DO UPDATE SET
                 health_percent = excluded.health_percent,
                 cycle_count = excluded.cycle_count,
                 max_capacity_wh = excluded.max_capacity_wh,
@@ -813,6 +1707,30 @@ impl HistoryStore {
         Ok(())
     }
 
+    /// Most recent local battery health snapshot
+    pub fn get_latest_battery_health(&self) -> Result<Option<BatteryHealthSnapshot>> {
+        let snapshot = self
+            .conn
+            .query_row(
+                "SELECT id, date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh
+                 FROM battery_health WHERE source = 'local' ORDER BY date DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(BatteryHealthSnapshot {
+                        id: row.get(0)?,
+                        date: row.get(1)?,
+                        health_percent: row.get(2)?,
+                        cycle_count: row.get(3)?,
+                        max_capacity_wh: row.get(4)?,
+                        design_capacity_wh: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(snapshot)
+    }
+
     pub fn insert_charge_session(&self, session: &ChargeSession) -> Result<i64> {
         self.conn.execute(
             "INSERT INTO charge_sessions (start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete)
@@ -832,6 +1750,21 @@ impl HistoryStore {
         Ok(self.conn.last_insert_rowid())
     }
 
+    pub fn delete_charge_session(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM charge_sessions WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    pub fn charge_session_exists(&self, start_time: i64) -> Result<bool> {
+        let exists = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM charge_sessions WHERE source = 'local' AND start_time = ?",
+            [start_time],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
     pub fn update_charge_session(&self, session: &ChargeSession) -> Result<()> {
         let id = session.id.ok_or_else(|| {
             HistoryStoreError::Database(rusqlite::Error::InvalidParameterName(
@@ -869,13 +1802,13 @@ impl HistoryStore {
             Some(_) => {
                 "SELECT id, start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete
                  FROM charge_sessions
-                 WHERE start_time >= ? AND start_time <= ? AND session_type = ?
+                 WHERE source = 'local' AND start_time >= ? AND start_time <= ? AND session_type = ?
                  ORDER BY start_time DESC"
             }
             None => {
                 "SELECT id, start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete
                  FROM charge_sessions
-                 WHERE start_time >= ? AND start_time <= ?
+                 WHERE source = 'local' AND start_time >= ? AND start_time <= ?
                  ORDER BY start_time DESC"
             }
         };
@@ -900,7 +1833,7 @@ impl HistoryStore {
             .query_row(
                 "SELECT id, start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete
                  FROM charge_sessions
-                 WHERE is_complete = 0
+                 WHERE source = 'local' AND is_complete = 0
                  ORDER BY start_time DESC
                  LIMIT 1",
                 [],
@@ -911,6 +1844,24 @@ impl HistoryStore {
         Ok(session)
     }
 
+    /// Stream local sessions starting in a time range to `f`, oldest first
+    pub fn for_each_charge_session(
+        &self,
+        from: i64,
+        to: i64,
+        f: impl FnMut(ChargeSession) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete
+             FROM charge_sessions
+             WHERE source = 'local' AND start_time >= ? AND start_time <= ?
+             ORDER BY start_time ASC",
+            params![from, to],
+            Self::map_charge_session,
+            f,
+        )
+    }
+
     fn map_charge_session(row: &rusqlite::Row) -> rusqlite::Result<ChargeSession> {
         Ok(ChargeSession {
             id: Some(row.get(0)?),
@@ -937,7 +1888,7 @@ impl HistoryStore {
         self.conn.execute(
             "INSERT INTO daily_cycles (date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(date) DO UPDATE SET
+             ON CONFLICT(source, date) DO UPDATE SET
                 charge_sessions = excluded.charge_sessions,
                 discharge_sessions = excluded.discharge_sessions,
                 total_charging_mins = excluded.total_charging_mins,
@@ -971,57 +1922,61 @@ impl HistoryStore {
         let mut stmt = self.conn.prepare(
             "SELECT id, date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins
              FROM daily_cycles
-             WHERE date >= ? AND date <= ?
+             WHERE source = 'local' AND date >= ? AND date <= ?
              ORDER BY date DESC",
         )?;
 
         let cycles = stmt
-            .query_map(params![from, to], |row| {
-                Ok(DailyCycle {
-                    id: Some(row.get(0)?),
-                    date: row.get(1)?,
-                    charge_sessions: row.get(2)?,
-                    discharge_sessions: row.get(3)?,
-                    total_charging_mins: row.get(4)?,
-                    total_discharge_mins: row.get(5)?,
-                    deepest_discharge_percent: row.get(6)?,
-                    energy_charged_wh: row.get(7)?,
-                    energy_discharged_wh: row.get(8)?,
-                    partial_cycles: row.get(9)?,
-                    macos_cycle_count: row.get(10)?,
-                    avg_temperature_c: row.get(11)?,
-                    time_at_high_soc_mins: row.get(12)?,
-                })
-            })?
+            .query_map(params![from, to], Self::map_daily_cycle)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(cycles)
     }
 
+    /// Stream local daily cycle rows in a date range to `f`, oldest first
+    pub fn for_each_daily_cycle(
+        &self,
+        from: &str,
+        to: &str,
+        f: impl FnMut(DailyCycle) -> std::io::Result<()>,
+    ) -> Result<usize> {
+        self.for_each_row(
+            "SELECT id, date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins
+             FROM daily_cycles
+             WHERE source = 'local' AND date >= ? AND date <= ?
+             ORDER BY date ASC",
+            params![from, to],
+            Self::map_daily_cycle,
+            f,
+        )
+    }
+
+    fn map_daily_cycle(row: &rusqlite::Row) -> rusqlite::Result<DailyCycle> {
+        Ok(DailyCycle {
+            id: Some(row.get(0)?),
+            date: row.get(1)?,
+            charge_sessions: row.get(2)?,
+            discharge_sessions: row.get(3)?,
+            total_charging_mins: row.get(4)?,
+            total_discharge_mins: row.get(5)?,
+            deepest_discharge_percent: row.get(6)?,
+            energy_charged_wh: row.get(7)?,
+            energy_discharged_wh: row.get(8)?,
+            partial_cycles: row.get(9)?,
+            macos_cycle_count: row.get(10)?,
+            avg_temperature_c: row.get(11)?,
+            time_at_high_soc_mins: row.get(12)?,
+        })
+    }
+
     pub fn get_daily_cycle(&self, date: &str) -> Result<Option<DailyCycle>> {
         let cycle = self
             .conn
             .query_row(
                 "SELECT id, date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins
-                 FROM daily_cycles WHERE date = ?",
+                 FROM daily_cycles WHERE source = 'local' AND date = ?",
                 [date],
-                |row| {
-                    Ok(DailyCycle {
-                        id: Some(row.get(0)?),
-                        date: row.get(1)?,
-                        charge_sessions: row.get(2)?,
-                        discharge_sessions: row.get(3)?,
-                        total_charging_mins: row.get(4)?,
-                        total_discharge_mins: row.get(5)?,
-                        deepest_discharge_percent: row.get(6)?,
-                        energy_charged_wh: row.get(7)?,
-                        energy_discharged_wh: row.get(8)?,
-                        partial_cycles: row.get(9)?,
-                        macos_cycle_count: row.get(10)?,
-                        avg_temperature_c: row.get(11)?,
-                        time_at_high_soc_mins: row.get(12)?,
-                    })
-                },
+                Self::map_daily_cycle,
             )
             .optional()?;
 
@@ -1053,11 +2008,328 @@ impl HistoryStore {
         Ok(())
     }
 
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row("SELECT value FROM metadata WHERE key = ?", [key], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO metadata (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Check the database file at `path` without opening it for writing or
+    /// running migrations, so a file that fails to open can still be
+    /// diagnosed
+    pub fn check_file(path: &Path) -> Result<FileCheck> {
+        ensure_file(path)?;
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+        let mut check = FileCheck::default();
+        let integrity = conn
+            .prepare("PRAGMA integrity_check(100)")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        match integrity {
+            Ok(messages) if messages.len() == 1 && messages[0] == "ok" => {}
+            Ok(messages) => check.integrity_errors = messages,
+            Err(e) => {
+                check.integrity_errors.push(e.to_string());
+                return Ok(check);
+            }
+        }
+
+        check.schema_version = schema_version(&conn)?;
+        if check.schema_version == CURRENT_SCHEMA_VERSION {
+            let reference = Self::reference_schema()?;
+            for table in table_names(&reference.conn)? {
+                let columns = column_names(&conn, &table)?;
+                if columns.is_empty() {
+                    check.missing_tables.push(table);
+                    continue;
+                }
+                for column in column_names(&reference.conn, &table)? {
+                    if !columns.contains(&column) {
+                        check.missing_columns.push(format!("{}.{}", table, column));
+                    }
+                }
+            }
+        }
+
+        Ok(check)
+    }
+
+    /// An empty in-memory database with the current schema
+    fn reference_schema() -> Result<Self> {
+        let mut store = Self {
+            conn: Connection::open_in_memory()?,
+            path: PathBuf::from(":memory:"),
+        };
+        store.create_initial_schema()?;
+        Ok(store)
+    }
+
+    /// Create tables (and their indexes) that the current schema expects
+    /// but are missing, returning their names
+    pub fn create_missing_tables(&self) -> Result<Vec<String>> {
+        let reference = Self::reference_schema()?;
+        let existing = table_names(&self.conn)?;
+        let missing: Vec<String> = table_names(&reference.conn)?
+            .into_iter()
+            .filter(|table| !existing.contains(table))
+            .collect();
+
+        let tx = self.conn.unchecked_transaction()?;
+        for table in &missing {
+            let mut stmt = reference.conn.prepare(
+                "SELECT sql FROM sqlite_master
+                 WHERE tbl_name = ? AND sql IS NOT NULL
+                 ORDER BY type = 'index'",
+            )?;
+            let statements = stmt
+                .query_map([table], |row| row.get::<_, String>(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for sql in statements {
+                tx.execute_batch(&sql)?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(missing)
+    }
+
+    /// Rebuild every index of the database at `path`, which repairs the
+    /// most common kind of corruption reported by `integrity_check`
+    pub fn reindex_file(path: &Path) -> Result<()> {
+        ensure_file(path)?;
+        Connection::open(path)?.execute_batch("PRAGMA busy_timeout=5000; REINDEX;")?;
+        Ok(())
+    }
+
+    /// Start of each hour that has local raw samples
+    pub fn get_sample_hours(&self) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT timestamp - timestamp % 3600 AS hour
+             FROM samples
+             WHERE source = 'local'
+             ORDER BY hour",
+        )?;
+        let hours = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(hours)
+    }
+
+    /// Run a single read-only statement and collect its rows
+    pub fn query(&self, sql: &str) -> Result<QueryResult> {
+        let mut stmt = self.conn.prepare(sql)?;
+        if !stmt.readonly() {
+            return Err(HistoryStoreError::NotReadOnly);
+        }
+
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let width = columns.len();
+        let rows = stmt
+            .query_map([], |row| {
+                (0..width)
+                    .map(|i| row.get::<_, Value>(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(QueryResult { columns, rows })
+    }
+
+    /// Copy the database to `dest` with SQLite's online backup, which works
+    /// in small steps so a running daemon can keep writing
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        let mut dst = Connection::open(dest)?;
+        copy_database(&self.conn, &mut dst)
+    }
+
+    /// Replace the database contents with the backup at `src`, migrating it
+    /// if it was made by an older version
+    pub fn restore_from(&mut self, src: &Path) -> Result<()> {
+        let source = open_history_read_only(src)?;
+        copy_database(&source, &mut self.conn)?;
+        self.initialize_schema()
+    }
+
+    /// Import the locally recorded rows of another machine's database,
+    /// tagging them with `source`.
+    ///
+    /// Samples and sessions already present for that source are skipped and
+    /// aggregates are replaced, so merging a newer copy of the same database
+    /// only adds what's new. Process and event history stay per-machine.
+    pub fn merge_from(&self, other: &Path, source: &str) -> Result<MergeResult> {
+        let source = source.trim();
+        if source.is_empty() || source == LOCAL_SOURCE {
+            return Err(HistoryStoreError::InvalidSource(source.to_string()));
+        }
+
+        // Migrate a copy rather than the user's file so both sides share a
+        // schema before rows are copied across. The copy sits next to the
+        // database, where other users can't plant or read it.
+        let staging = self
+            .path
+            .with_file_name(format!("merge-{}.db", std::process::id()));
+        remove_database_files(&staging);
+        let result = (|| {
+            {
+                let mut dst = Connection::open(&staging)?;
+                copy_database(&open_history_read_only(other)?, &mut dst)?;
+            }
+            drop(Self::open_at(&staging)?);
+            self.merge_attached(&staging, source)
+        })();
+
+        remove_database_files(&staging);
+        result
+    }
+
+    fn merge_attached(&self, staging: &Path, source: &str) -> Result<MergeResult> {
+        self.conn.execute(
+            "ATTACH DATABASE ? AS merge_src",
+            [staging.to_string_lossy()],
+        )?;
+
+        let merged = (|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let result = MergeResult {
+                samples: tx.execute(
+                    "INSERT INTO main.samples (timestamp, battery_percent, power_watts, cpu_power, gpu_power, charging_state, source)
+                     SELECT timestamp, battery_percent, power_watts, cpu_power, gpu_power, charging_state, ?1
+                     FROM merge_src.samples s
+                     WHERE s.source = 'local'
+                       AND NOT EXISTS (SELECT 1 FROM main.samples m WHERE m.source = ?1 AND m.timestamp = s.timestamp)",
+                    [source],
+                )?,
+                sessions: tx.execute(
+                    "INSERT INTO main.charge_sessions (start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete, source)
+                     SELECT start_time, end_time, start_percent, end_percent, energy_wh, charger_watts, avg_power_watts, session_type, is_complete, ?1
+                     FROM merge_src.charge_sessions s
+                     WHERE s.source = 'local'
+                       AND NOT EXISTS (SELECT 1 FROM main.charge_sessions m WHERE m.source = ?1 AND m.start_time = s.start_time)",
+                    [source],
+                )?,
+                hourly: tx.execute(
+                    "INSERT OR REPLACE INTO main.hourly_stats (hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples, source)
+                     SELECT hour_start, avg_power, max_power, min_power, avg_battery, battery_delta, total_samples, ?1
+                     FROM merge_src.hourly_stats WHERE source = 'local'",
+                    [source],
+                )?,
+                daily: tx.execute(
+                    "INSERT OR REPLACE INTO main.daily_stats (date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles, source)
+                     SELECT date, avg_power, max_power, total_energy_wh, screen_on_hours, charging_hours, battery_cycles, ?1
+                     FROM merge_src.daily_stats WHERE source = 'local'",
+                    [source],
+                )? + tx.execute(
+                    "INSERT OR REPLACE INTO main.daily_cycles (date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins, source)
+                     SELECT date, charge_sessions, discharge_sessions, total_charging_mins, total_discharge_mins, deepest_discharge_percent, energy_charged_wh, energy_discharged_wh, partial_cycles, macos_cycle_count, avg_temperature_c, time_at_high_soc_mins, ?1
+                     FROM merge_src.daily_cycles WHERE source = 'local'",
+                    [source],
+                )? + tx.execute(
+                    "INSERT OR REPLACE INTO main.battery_health (date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh, source)
+                     SELECT date, health_percent, cycle_count, max_capacity_wh, design_capacity_wh, ?1
+                     FROM merge_src.battery_health WHERE source = 'local'",
+                    [source],
+                )?,
+            };
+            tx.commit()?;
+            Ok(result)
+        })();
+
+        self.conn.execute("DETACH DATABASE merge_src", [])?;
+        merged
+    }
+
+    /// List the machines with data in this database, local first
+    pub fn get_sources(&self) -> Result<Vec<SourceSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.source,
+                (SELECT COUNT(*) FROM samples WHERE source = s.source),
+                (SELECT COUNT(*) FROM daily_stats WHERE source = s.source),
+                (SELECT MIN(date) FROM daily_stats WHERE source = s.source),
+                (SELECT MAX(date) FROM daily_stats WHERE source = s.source)
+             FROM (SELECT source FROM samples UNION SELECT source FROM daily_stats) s
+             ORDER BY s.source != 'local', s.source",
+        )?;
+
+        let sources = stmt
+            .query_map([], |row| {
+                Ok(SourceSummary {
+                    source: row.get(0)?,
+                    sample_count: row.get(1)?,
+                    day_count: row.get(2)?,
+                    first_date: row.get(3)?,
+                    last_date: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(sources)
+    }
+
     pub fn vacuum(&self) -> Result<()> {
         self.conn.execute("VACUUM", [])?;
         Ok(())
     }
 
+    /// Return free pages to the filesystem without rewriting the database.
+    ///
+    /// Databases created before incremental auto-vacuum was enabled are
+    /// converted first, which takes one full VACUUM.
+    pub fn incremental_vacuum(&self) -> Result<()> {
+        let mode: i64 = self
+            .conn
+            .query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+
+        if mode == AUTO_VACUUM_INCREMENTAL {
+            let mut stmt = self.conn.prepare("PRAGMA incremental_vacuum")?;
+            let mut rows = stmt.query([])?;
+            while rows.next()?.is_some() {}
+        } else {
+            self.conn
+                .execute_batch("PRAGMA auto_vacuum=INCREMENTAL; VACUUM;")?;
+        }
+
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Bytes held by live pages, i.e. the file size after a vacuum
+    pub fn used_bytes(&self) -> Result<u64> {
+        let pragma = |name: &str| -> Result<u64> {
+            let value: i64 = self
+                .conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))?;
+            Ok(value.max(0) as u64)
+        };
+        let live_pages = pragma("page_count")?.saturating_sub(pragma("freelist_count")?);
+        Ok(live_pages * pragma("page_size")?)
+    }
+
+    /// Run `f` against the store and roll back everything it changed
+    pub fn dry_run<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT dry_run")?;
+        let result = f(self);
+        self.conn
+            .execute_batch("ROLLBACK TO dry_run; RELEASE dry_run")?;
+        result
+    }
+
     /// Get database statistics
     pub fn get_stats(&self) -> Result<DatabaseStats> {
         let sample_count: i64 = self
@@ -1074,13 +2346,21 @@ impl HistoryStore {
 
         let oldest_sample: Option<i64> = self
             .conn
-            .query_row("SELECT MIN(timestamp) FROM samples", [], |row| row.get(0))
+            .query_row(
+                "SELECT MIN(timestamp) FROM samples WHERE source = 'local'",
+                [],
+                |row| row.get(0),
+            )
             .optional()?
             .flatten();
 
         let newest_sample: Option<i64> = self
             .conn
-            .query_row("SELECT MAX(timestamp) FROM samples", [], |row| row.get(0))
+            .query_row(
+                "SELECT MAX(timestamp) FROM samples WHERE source = 'local'",
+                [],
+                |row| row.get(0),
+            )
             .optional()?
             .flatten();
 
@@ -1097,6 +2377,55 @@ impl HistoryStore {
     }
 }
 
+/// Result of `HistoryStore::check_file`
+#[derive(Debug, Clone, Default)]
+pub struct FileCheck {
+    /// Problems reported by `PRAGMA integrity_check`; empty when healthy
+    pub integrity_errors: Vec<String>,
+    pub schema_version: i32,
+    /// Tables of the current schema missing from the file. Only checked
+    /// when the file claims the current version.
+    pub missing_tables: Vec<String>,
+    /// Columns missing from existing tables, as `table.column`
+    pub missing_columns: Vec<String>,
+}
+
+/// Columns and rows returned by `HistoryStore::query`
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Removes a SQLite database along with its WAL and shared-memory files.
+fn remove_database_files(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// Rows added by `HistoryStore::merge_from`
+#[derive(Debug, Clone, Default)]
+pub struct MergeResult {
+    pub samples: usize,
+    pub sessions: usize,
+    pub hourly: usize,
+    /// Daily stats, cycle and battery health rows
+    pub daily: usize,
+}
+
+/// Data held for one source machine
+#[derive(Debug, Clone)]
+pub struct SourceSummary {
+    pub source: String,
+    pub sample_count: i64,
+    pub day_count: i64,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
 /// Database statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseStats {
@@ -1111,21 +2440,127 @@ pub struct DatabaseStats {
 impl DatabaseStats {
     /// Format size as human-readable string
     pub fn size_formatted(&self) -> String {
-        let bytes = self.size_bytes as f64;
-        if bytes < 1024.0 {
-            format!("{} B", self.size_bytes)
-        } else if bytes < 1024.0 * 1024.0 {
-            format!("{:.1} KB", bytes / 1024.0)
-        } else if bytes < 1024.0 * 1024.0 * 1024.0 {
-            format!("{:.1} MB", bytes / (1024.0 * 1024.0))
-        } else {
-            format!("{:.2} GB", bytes / (1024.0 * 1024.0 * 1024.0))
+        format_bytes(self.size_bytes)
+    }
+}
+
+/// Format a byte count as a human-readable string
+pub fn format_bytes(size: u64) -> String {
+    let bytes = size as f64;
+    if bytes < 1024.0 {
+        format!("{} B", size)
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1} KB", bytes / 1024.0)
+    } else if bytes < 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1} MB", bytes / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} GB", bytes / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Get the schema version of `conn` (0 if not initialized)
+fn schema_version(conn: &Connection) -> Result<i32> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        return Ok(0);
+    }
+
+    let version: i32 = conn
+        .query_row("SELECT version FROM schema_version LIMIT 1", [], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .unwrap_or(0);
+
+    Ok(version)
+}
+
+/// Open another history database read-only, checking it's one this
+/// version can migrate
+fn open_history_read_only(path: &Path) -> Result<Connection> {
+    ensure_file(path)?;
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    match schema_version(&conn) {
+        Ok(version) if version > CURRENT_SCHEMA_VERSION => {
+            Err(HistoryStoreError::NewerSchema(path.to_path_buf(), version))
         }
+        Ok(version) if version > 0 => Ok(conn),
+        _ => Err(HistoryStoreError::NotHistory(path.to_path_buf())),
+    }
+}
+
+fn ensure_file(path: &Path) -> Result<()> {
+    if path.is_file() {
+        return Ok(());
     }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+    .into())
 }
 
-/// Helper to get a date string for N days ago
-pub fn days_ago_date_string(days: u32) -> String {
-    let date = Utc::now() - chrono::Duration::days(days as i64);
-    date.format("%Y-%m-%d").to_string()
+fn table_names(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+         ORDER BY name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+/// Column names of `table`, empty if it doesn't exist
+fn column_names(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?)")?;
+    let names = stmt
+        .query_map([table], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+fn copy_database(src: &Connection, dst: &mut Connection) -> Result<()> {
+    Backup::new(src, dst)?.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_PAUSE, None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_view_labels_every_event_kind() {
+        let store = HistoryStore::open_at(Path::new(":memory:")).unwrap();
+        let kinds: Vec<EventKind> = (0..=9).map(EventKind::from).collect();
+        for (i, kind) in kinds.iter().enumerate() {
+            assert_eq!(*kind as i32, i as i32, "event kinds are numbered 0..=9");
+            store
+                .insert_event(&Event {
+                    id: None,
+                    timestamp: i as i64,
+                    kind: *kind,
+                    message: String::new(),
+                })
+                .unwrap();
+        }
+
+        let mut stmt = store
+            .conn
+            .prepare("SELECT kind FROM v_events ORDER BY timestamp")
+            .unwrap();
+        let labels: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        let expected: Vec<&str> = kinds.iter().map(|kind| kind.label()).collect();
+        assert_eq!(labels, expected);
+    }
 }
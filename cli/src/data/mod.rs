@@ -1,22 +1,34 @@
 pub mod aggregator;
+pub mod backtest;
 pub mod battery;
+pub mod cgroup;
+pub mod charge_forecast;
+pub mod doctor;
+pub mod downsample;
+pub mod estimator;
+pub mod export;
 pub mod forecast;
 pub mod history;
 pub mod history_store;
+pub mod plan;
 pub mod power;
 pub mod processes;
+pub mod query;
 pub mod recorder;
 pub mod session_tracker;
 pub mod system;
 pub mod system_stats;
+pub mod timezone;
+pub mod upower;
+pub mod usage_profile;
 
 pub use battery::BatteryData;
 pub use forecast::{ForecastData, ForecastSource};
 pub use history::{HistoryData, HistoryMetric};
 pub use history_store::{
     BatteryHealthSnapshot, ChargeSession, ChargingState, CycleSnapshot, DailyCycle, DailyStat,
-    DailyTopProcess, DatabaseStats, HistoryStore, HistoryStoreError, HourlyStat, Sample,
-    SessionType,
+    DailyTopProcess, DatabaseStats, Event, EventKind, HistoryStore, HistoryStoreError,
+    HourlyProcessStat, HourlyStat, ProcessSample, Sample, SessionType,
 };
 pub use power::PowerData;
 pub use processes::{ProcessData, ProcessInfo, ProcessState};
@@ -24,3 +36,4 @@ pub use recorder::Recorder;
 pub use session_tracker::{SessionEvent, SessionTracker};
 pub use system::SystemInfo;
 pub use system_stats::SystemStatsData;
+pub use timezone::DayZone;
@@ -0,0 +1,334 @@
+//! "Will I make it until HH:MM?" runway planner.
+//!
+//! Compares the forecast runtime with the time left until a target, then
+//! estimates how much longer the battery would last with some load cut:
+//! quitting the heaviest processes, or switching to low power mode. A cut
+//! stretches the forecast runtime by how much it lowers the current draw.
+
+use std::time::Duration;
+
+use chrono::NaiveTime;
+
+use crate::data::forecast::{format_duration, ForecastData};
+use crate::data::history_store::DailyTopProcess;
+use crate::data::power::PowerMode;
+use crate::data::processes::{cpu_power_shares, ProcessInfo};
+
+/// Processes suggested for quitting
+pub const TOP_PROCESS_COUNT: usize = 3;
+
+/// Days of recorded per-process power averaged into the live estimate
+pub const PROCESS_HISTORY_DAYS: u32 = 7;
+
+/// Processes the CPU power is split across, as the recorder does
+const POWER_SHARE_PROCESSES: usize = 10;
+
+/// Rough share of CPU and GPU power that low power mode saves
+const LOW_POWER_SAVING: f32 = 0.3;
+
+/// No cut brings the draw below this share of the current draw
+const MIN_POWER_SHARE: f32 = 0.2;
+
+/// Cuts saving less than this are not worth suggesting
+const MIN_CUT_WATTS: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Even the pessimistic (P10) runtime lasts
+    Yes,
+    /// The median runtime lasts, the pessimistic one does not
+    Likely,
+    No,
+}
+
+impl Verdict {
+    fn new(needed: Duration, p10: Duration, p50: Duration) -> Self {
+        if p10 >= needed {
+            Verdict::Yes
+        } else if p50 >= needed {
+            Verdict::Likely
+        } else {
+            Verdict::No
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Yes => "Yes",
+            Verdict::Likely => "Probably",
+            Verdict::No => "No",
+        }
+    }
+}
+
+/// What is drawing power now, and what each process drew recently
+pub struct Load<'a> {
+    /// Processes sorted by energy impact, as `ProcessData` keeps them
+    pub processes: &'a [ProcessInfo],
+    /// Recorded per-process power, summed over `PROCESS_HISTORY_DAYS`
+    pub history: &'a [DailyTopProcess],
+    pub cpu_watts: f32,
+    pub gpu_watts: f32,
+    pub power_mode: PowerMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutKind {
+    Quit,
+    LowPower,
+    QuitAndLowPower,
+}
+
+/// Load that could be cut, and how much runtime it would add
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cut {
+    pub kind: CutKind,
+    pub label: String,
+    pub watts: f32,
+    pub added: Duration,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    /// Time from now until the target
+    pub needed: Duration,
+    /// Median (P50) runtime
+    pub runtime: Duration,
+    /// Pessimistic (P10) runtime
+    pub pessimistic: Duration,
+    pub verdict: Verdict,
+    /// Median runtime left at the target; negative when it falls short
+    pub margin_secs: i64,
+    pub cuts: Vec<Cut>,
+}
+
+impl Plan {
+    /// Plans against `forecast`, which must already be calculated. `None`
+    /// if it has no runtime.
+    pub fn new(forecast: &ForecastData, needed: Duration, load: &Load) -> Option<Self> {
+        let runtime = forecast.duration()?;
+        let pessimistic = forecast
+            .p10_secs()
+            .map_or(runtime, Duration::from_secs)
+            .min(runtime);
+        let watts = forecast.avg_power_watts()?;
+
+        let stretch = |saved: f32| {
+            let saved = saved.min(watts * (1.0 - MIN_POWER_SHARE));
+            let factor = watts / (watts - saved);
+            (
+                runtime.mul_f32(factor),
+                pessimistic.mul_f32(factor).min(runtime.mul_f32(factor)),
+            )
+        };
+        let cut = |kind: CutKind, label: String, saved: f32| {
+            let (p50, p10) = stretch(saved);
+            Cut {
+                kind,
+                label,
+                watts: saved,
+                added: p50.saturating_sub(runtime),
+                verdict: Verdict::new(needed, p10, p50),
+            }
+        };
+
+        let mut cuts = Vec::new();
+        let heaviest = heaviest_processes(load);
+        for (name, saved) in &heaviest {
+            cuts.push(cut(CutKind::Quit, format!("Quit {}", name), *saved));
+        }
+        let processes_saved: f32 = heaviest.iter().map(|(_, w)| w).sum();
+        let quit_all = match heaviest.as_slice() {
+            [] => None,
+            [(name, _)] => Some(format!("Quit {}", name)),
+            _ => {
+                let label = format!("Quit all {}", heaviest.len());
+                cuts.push(cut(CutKind::Quit, label.clone(), processes_saved));
+                Some(label)
+            }
+        };
+
+        let chip_watts = load.cpu_watts + load.gpu_watts;
+        if load.power_mode != PowerMode::LowPower && chip_watts > 0.0 {
+            cuts.push(cut(
+                CutKind::LowPower,
+                "Low power mode".to_string(),
+                chip_watts * LOW_POWER_SAVING,
+            ));
+            if let Some(quit_all) = quit_all {
+                let rest = (chip_watts - processes_saved).max(0.0);
+                cuts.push(cut(
+                    CutKind::QuitAndLowPower,
+                    format!("{} + low power mode", quit_all),
+                    processes_saved + rest * LOW_POWER_SAVING,
+                ));
+            }
+        }
+        cuts.retain(|c| c.watts >= MIN_CUT_WATTS);
+
+        Some(Self {
+            needed,
+            runtime,
+            pessimistic,
+            verdict: Verdict::new(needed, pessimistic, runtime),
+            margin_secs: runtime.as_secs() as i64 - needed.as_secs() as i64,
+            cuts,
+        })
+    }
+
+    /// One-line answer, e.g. "Yes, with 53m to spare"
+    pub fn summary(&self) -> String {
+        let margin = format_duration(Duration::from_secs(self.margin_secs.unsigned_abs()));
+        match self.verdict {
+            Verdict::Yes => format!("Yes, with {} to spare", margin),
+            Verdict::Likely => format!(
+                "Probably, with {} to spare, but heavy use could run out {} before then",
+                margin,
+                format_duration(self.needed.saturating_sub(self.pessimistic))
+            ),
+            Verdict::No => format!("No, about {} short", margin),
+        }
+    }
+}
+
+/// Parses a wall-clock target such as `17:30`
+pub fn parse_until(spec: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(spec.trim(), "%H:%M").ok()
+}
+
+/// The `TOP_PROCESS_COUNT` heaviest processes that can be quit and draw
+/// any power: the live CPU share, averaged with the recorded power if any.
+fn heaviest_processes(load: &Load) -> Vec<(String, f32)> {
+    let sharing: Vec<&ProcessInfo> = load.processes.iter().take(POWER_SHARE_PROCESSES).collect();
+    let live = cpu_power_shares(&sharing, load.cpu_watts);
+
+    sharing
+        .iter()
+        .zip(live)
+        .filter(|(p, _)| p.is_killable)
+        .map(|(process, live)| {
+            let recorded = load
+                .history
+                .iter()
+                .find(|h| h.process_name == process.name)
+                .map(|h| h.avg_power)
+                .filter(|w| *w > 0.0);
+            let watts = match recorded {
+                Some(recorded) if live > 0.0 => (live + recorded) / 2.0,
+                Some(recorded) => recorded,
+                None => live,
+            };
+            (process.name.clone(), watts)
+        })
+        .filter(|(_, watts)| *watts >= MIN_CUT_WATTS)
+        .take(TOP_PROCESS_COUNT)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::processes::ProcessState;
+
+    fn process(name: &str, cpu_usage: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid: 1000,
+            name: name.to_string(),
+            command: name.to_string(),
+            command_args: String::new(),
+            cpu_usage,
+            memory_mb: 100.0,
+            energy_impact: cpu_usage,
+            parent_pid: None,
+            children: None,
+            is_killable: true,
+            disk_read_bytes: 0,
+            disk_write_bytes: 0,
+            status: ProcessState::Running,
+            run_time_secs: 0,
+            total_cpu_time_secs: 0,
+            throttled: false,
+        }
+    }
+
+    fn recorded(name: &str, avg_power: f32) -> DailyTopProcess {
+        DailyTopProcess {
+            id: None,
+            date: "2024-03-01".to_string(),
+            process_name: name.to_string(),
+            total_impact: 0.0,
+            avg_cpu: 0.0,
+            avg_memory_mb: 0.0,
+            sample_count: 1,
+            avg_power,
+            total_energy_wh: 0.0,
+        }
+    }
+
+    /// 10 W on a 50 Wh battery at 80%: a 4 hour runtime
+    fn forecast() -> ForecastData {
+        let samples: Vec<_> = (0..20)
+            .map(|i| crate::data::Sample {
+                id: None,
+                timestamp: chrono::Utc::now().timestamp() - 190 + i * 10,
+                battery_percent: 80.0,
+                power_watts: 10.0,
+                cpu_power: 0.0,
+                gpu_power: 0.0,
+                charging_state: crate::data::ChargingState::Discharging,
+            })
+            .collect();
+        let mut forecast = ForecastData::new();
+        assert!(forecast.calculate_from_daemon_samples(&samples, 80.0, 50.0, 300));
+        forecast
+    }
+
+    #[test]
+    fn verdict_follows_margin() {
+        let forecast = forecast();
+        let processes = [process("browser", 30.0), process("editor", 10.0)];
+        let load = Load {
+            processes: &processes,
+            history: &[],
+            cpu_watts: 4.0,
+            gpu_watts: 0.0,
+            power_mode: PowerMode::Automatic,
+        };
+
+        let plan = Plan::new(&forecast, Duration::from_secs(3 * 3600), &load).unwrap();
+        assert_eq!(plan.verdict, Verdict::Yes);
+        assert_eq!(plan.margin_secs, 3600);
+
+        let plan = Plan::new(&forecast, Duration::from_secs(5 * 3600), &load).unwrap();
+        assert_eq!(plan.verdict, Verdict::No);
+        assert_eq!(plan.margin_secs, -3600);
+    }
+
+    #[test]
+    fn cuts_stretch_runtime_by_power_saved() {
+        let forecast = forecast();
+        let processes = [process("browser", 30.0), process("editor", 10.0)];
+        let history = [recorded("editor", 3.0)];
+        let load = Load {
+            processes: &processes,
+            history: &history,
+            cpu_watts: 4.0,
+            gpu_watts: 0.0,
+            power_mode: PowerMode::Automatic,
+        };
+        let plan = Plan::new(&forecast, Duration::from_secs(5 * 3600), &load).unwrap();
+        let cut = |label: &str| plan.cuts.iter().find(|c| c.label == label).unwrap();
+
+        // 3 of the 4 CPU watts; 10 W -> 7 W stretches 4h to about 5h 43m
+        assert_eq!(cut("Quit browser").watts, 3.0);
+        assert_eq!(cut("Quit browser").added.as_secs() / 60, 102);
+        assert_eq!(cut("Quit browser").verdict, Verdict::Yes);
+        // Live 1 W averaged with the recorded 3 W
+        assert_eq!(cut("Quit editor").watts, 2.0);
+        assert_eq!(cut("Quit all 2").watts, 5.0);
+        assert!((cut("Low power mode").watts - 1.2).abs() < 1e-6);
+        // Nothing left to save on the chip once both are quit
+        assert_eq!(cut("Quit all 2 + low power mode").watts, 5.0);
+    }
+}
@@ -1,11 +1,14 @@
-use color_eyre::eyre::Result;
-use std::collections::HashMap;
+use color_eyre::eyre::{eyre, Result};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::Path;
-use sysinfo::{ProcessStatus, ProcessesToUpdate, System};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind};
 
 use crate::config::cache_dir;
-use crate::daemon::{KillSignal, ProcessSnapshot, ProcessState as ProtocolProcessState};
+use crate::daemon::{
+    IoClass, KillScope, KillSignal, KillTarget, ProcessAction, ProcessSnapshot,
+    ProcessState as ProtocolProcessState,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ProcessState {
@@ -64,6 +67,7 @@ impl From<ProcessSnapshot> for ProcessInfo {
             status: snapshot.status.into(),
             run_time_secs: snapshot.run_time_secs,
             total_cpu_time_secs: snapshot.total_cpu_time_secs,
+            throttled: snapshot.throttled,
         }
     }
 }
@@ -98,6 +102,8 @@ pub struct ProcessInfo {
     pub status: ProcessState,
     pub run_time_secs: u64,
     pub total_cpu_time_secs: u64,
+    /// In one of the daemon's CPU throttle groups
+    pub throttled: bool,
 }
 
 pub struct ProcessData {
@@ -105,6 +111,8 @@ pub struct ProcessData {
     pub processes: Vec<ProcessInfo>,
     display_name_cache: HashMap<String, String>,
     excluded_processes: Vec<String>,
+    /// Pids in the daemon's CPU throttle groups
+    throttled_pids: HashSet<u32>,
 }
 
 impl ProcessData {
@@ -123,12 +131,23 @@ impl ProcessData {
             processes: Vec::new(),
             display_name_cache,
             excluded_processes: excluded,
+            throttled_pids: HashSet::new(),
         };
 
         data.refresh()?;
         Ok(data)
     }
 
+    /// Replaces the exclusion list; takes effect on the next refresh.
+    pub fn set_exclusions(&mut self, excluded: Vec<String>) {
+        self.excluded_processes = excluded;
+    }
+
+    /// Replaces the set of throttled pids; takes effect on the next refresh.
+    pub fn set_throttled(&mut self, pids: HashSet<u32>) {
+        self.throttled_pids = pids;
+    }
+
     fn is_excluded(&self, name: &str, pid: u32) -> bool {
         if pid == 1 {
             return true;
@@ -217,6 +236,7 @@ impl ProcessData {
                 status,
                 run_time_secs,
                 total_cpu_time_secs,
+                throttled: self.throttled_pids.contains(&pid_u32),
             };
 
             process_map.insert(pid_u32, info.clone());
@@ -245,7 +265,7 @@ impl ProcessData {
                 }
             }
 
-            if process.energy_impact >= 0.5 {
+            if process.energy_impact >= 0.5 || is_pinned(&process) {
                 top_processes.push(process);
             }
         }
@@ -256,19 +276,17 @@ impl ProcessData {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        self.processes = top_processes.into_iter().take(50).collect();
+        let (pinned, mut processes): (Vec<_>, Vec<_>) =
+            top_processes.into_iter().partition(is_pinned);
+        processes.truncate(50);
+        processes.extend(pinned);
+        self.processes = processes;
 
         Ok(())
     }
 
     pub fn kill_process(&self, pid: u32, signal: KillSignal) -> Result<()> {
-        use std::process::Command;
-
-        Command::new("kill")
-            .args([signal.as_arg(), &pid.to_string()])
-            .output()?;
-
-        Ok(())
+        send_signal(pid, signal)
     }
 
     pub fn update_from_snapshots(&mut self, snapshots: Vec<ProcessSnapshot>) {
@@ -276,6 +294,205 @@ impl ProcessData {
     }
 }
 
+/// Stopped and throttled processes use little CPU, so they would otherwise
+/// drop off the list and be easy to forget about.
+fn is_pinned(process: &ProcessInfo) -> bool {
+    process.status == ProcessState::Stopped || process.throttled
+}
+
+/// Sends `signal` to `pid` with `kill`, failing if `kill` does.
+pub fn send_signal(pid: u32, signal: KillSignal) -> Result<()> {
+    run_checked("kill", &[signal.as_arg(), &pid.to_string()])
+}
+
+/// Suspends, resumes or reprioritizes `pid`.
+pub fn apply_action(pid: u32, action: ProcessAction) -> Result<()> {
+    match action {
+        ProcessAction::Stop => send_signal(pid, KillSignal::Stop),
+        ProcessAction::Continue => send_signal(pid, KillSignal::Continue),
+        ProcessAction::Renice { nice } => {
+            if !(-20..=19).contains(&nice) {
+                return Err(eyre!("nice value {} is outside -20..=19", nice));
+            }
+            // The bare priority is absolute on both util-linux and BSD renice,
+            // where `-n` means an increment
+            run_checked("renice", &[&nice.to_string(), "-p", &pid.to_string()])
+        }
+        ProcessAction::Ionice { class } => set_io_class(pid, class),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_class(pid: u32, class: IoClass) -> Result<()> {
+    run_checked("ionice", &["-c", class.as_arg(), "-p", &pid.to_string()])
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_class(_pid: u32, _class: IoClass) -> Result<()> {
+    Err(eyre!("IO priority can only be changed on Linux"))
+}
+
+fn run_checked(program: &str, args: &[&str]) -> Result<()> {
+    use std::process::Command;
+
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Running processes whose binary name is one of `names`, leaving out
+/// system processes and jolt itself.
+pub fn find_by_name(names: &[String]) -> Vec<(u32, String)> {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let own_pid = std::process::id();
+
+    let mut found: Vec<(u32, String)> = system
+        .processes()
+        .iter()
+        .filter(|(_, process)| process.thread_kind().is_none())
+        .filter_map(|(pid, process)| {
+            let pid = pid.as_u32();
+            let name = process.name().to_string_lossy();
+            let wanted = pid != own_pid
+                && names.iter().any(|n| *n == name)
+                && is_process_killable(pid, &name);
+            wanted.then(|| (pid, name.into_owned()))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// When `pid` started, in seconds since the epoch, or `None` if no such
+/// process is running.
+pub fn start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).map(|process| process.start_time())
+}
+
+/// A running process as seen when working out what a kill reaches.
+struct Candidate {
+    pid: u32,
+    parent_pid: Option<u32>,
+    /// Process group, only looked up for group kills
+    pgid: Option<u32>,
+    uid: Option<u32>,
+    name: String,
+}
+
+/// Processes a kill of `pid` with `scope` signals, `pid` first. Trees follow
+/// `parent_pid` down from `pid`; groups are the processes sharing its process
+/// group. Neither reaches system processes, jolt itself, or processes owned
+/// by a different user than `pid`.
+pub fn kill_targets(pid: u32, scope: KillScope) -> Vec<KillTarget> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_user(UpdateKind::Always),
+    );
+    let candidates: Vec<Candidate> = system
+        .processes()
+        .iter()
+        .filter(|(_, process)| process.thread_kind().is_none())
+        .map(|(pid, process)| Candidate {
+            pid: pid.as_u32(),
+            parent_pid: process.parent().map(|p| p.as_u32()),
+            pgid: (scope == KillScope::Group)
+                .then(|| process_group(pid.as_u32()))
+                .flatten(),
+            uid: process.user_id().map(|uid| **uid),
+            name: process.name().to_string_lossy().into_owned(),
+        })
+        .collect();
+    select_targets(pid, scope, &candidates, std::process::id())
+}
+
+fn select_targets(
+    pid: u32,
+    scope: KillScope,
+    candidates: &[Candidate],
+    own_pid: u32,
+) -> Vec<KillTarget> {
+    let Some(root) = candidates.iter().find(|c| c.pid == pid) else {
+        return Vec::new();
+    };
+    let mut members: Vec<&Candidate> = match scope {
+        KillScope::Process => Vec::new(),
+        KillScope::Tree => {
+            let mut children: HashMap<u32, Vec<&Candidate>> = HashMap::new();
+            for candidate in candidates {
+                if let Some(parent) = candidate.parent_pid {
+                    children.entry(parent).or_default().push(candidate);
+                }
+            }
+            let mut members = Vec::new();
+            // A pid can't be its own ancestor, but parents read at slightly
+            // different times could still form a loop
+            let mut seen = HashSet::from([root.pid]);
+            let mut queue = VecDeque::from([root.pid]);
+            while let Some(parent) = queue.pop_front() {
+                for child in children.get(&parent).into_iter().flatten() {
+                    if seen.insert(child.pid) {
+                        members.push(*child);
+                        queue.push_back(child.pid);
+                    }
+                }
+            }
+            members
+        }
+        KillScope::Group => candidates
+            .iter()
+            .filter(|c| c.pid != root.pid && root.pgid.is_some() && c.pgid == root.pgid)
+            .collect(),
+    };
+    members
+        .retain(|c| c.pid != own_pid && c.uid == root.uid && is_process_killable(c.pid, &c.name));
+    members.sort_by_key(|c| c.pid);
+
+    std::iter::once(root)
+        .chain(members)
+        .map(|c| KillTarget {
+            pid: c.pid,
+            name: c.name.clone(),
+        })
+        .collect()
+}
+
+fn process_group(pid: u32) -> Option<u32> {
+    // SAFETY: `libc::getpgid` only reads the process table; it fails with
+    // -1 for a pid that doesn't exist.
+    let pgid = unsafe { libc::getpgid(pid as libc::pid_t) };
+    (pgid > 0).then_some(pgid as u32)
+}
+
+/// Splits `cpu_power_watts` across `processes` by their share of the CPU
+/// usage among them. This is the per-process power the recorder stores.
+pub fn cpu_power_shares(processes: &[&ProcessInfo], cpu_power_watts: f32) -> Vec<f32> {
+    let total_cpu: f32 = processes.iter().map(|p| p.cpu_usage).sum();
+    processes
+        .iter()
+        .map(|p| {
+            if total_cpu > 0.0 {
+                (p.cpu_usage / total_cpu) * cpu_power_watts
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
 fn calculate_energy_impact(cpu_usage: f32, memory_mb: f32) -> f32 {
     let cpu_factor = cpu_usage * 0.8;
     let memory_factor = (memory_mb / 100.0).min(20.0) * 0.2;
@@ -439,3 +656,57 @@ fn save_display_name_cache(cache: &HashMap<String, String>) {
         let _ = fs::write(path, content);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(pid: u32, parent_pid: u32, pgid: u32, uid: u32, name: &str) -> Candidate {
+        Candidate {
+            pid,
+            parent_pid: Some(parent_pid),
+            pgid: Some(pgid),
+            uid: Some(uid),
+            name: name.to_string(),
+        }
+    }
+
+    fn pids(targets: &[KillTarget]) -> Vec<u32> {
+        targets.iter().map(|t| t.pid).collect()
+    }
+
+    fn candidates() -> Vec<Candidate> {
+        vec![
+            candidate(1, 0, 1, 0, "init"),
+            candidate(100, 1, 100, 1000, "code"),
+            candidate(110, 100, 100, 1000, "code helper"),
+            candidate(111, 110, 111, 1000, "rust-analyzer"),
+            candidate(120, 100, 100, 0, "sudo"),
+            candidate(130, 100, 100, 1000, "jolt"),
+            candidate(200, 1, 100, 1000, "detached"),
+            candidate(300, 1, 300, 1000, "other"),
+        ]
+    }
+
+    #[test]
+    fn tree_walks_descendants_of_the_same_user() {
+        let targets = select_targets(100, KillScope::Tree, &candidates(), 130);
+        // Root first, then descendants, skipping root-owned sudo and jolt
+        assert_eq!(pids(&targets), vec![100, 110, 111]);
+        assert_eq!(targets[2].name, "rust-analyzer");
+    }
+
+    #[test]
+    fn group_matches_process_group() {
+        let targets = select_targets(110, KillScope::Group, &candidates(), 130);
+        // 111 started its own group; 200 shares the group without being a child
+        assert_eq!(pids(&targets), vec![110, 100, 200]);
+    }
+
+    #[test]
+    fn process_scope_and_missing_pid() {
+        let targets = select_targets(100, KillScope::Process, &candidates(), 130);
+        assert_eq!(pids(&targets), vec![100]);
+        assert!(select_targets(999, KillScope::Tree, &candidates(), 130).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Rendering for `jolt history query` results.
+//!
+//! Stored metrics are `f32`s widened to SQLite's `REAL`, so reals that
+//! round-trip through `f32` are printed at that precision; values computed
+//! in the query (sums, averages) keep full `f64` precision.
+
+use std::io::{self, Write};
+
+use rusqlite::types::Value;
+
+use crate::data::export::escape_csv;
+use crate::data::history_store::QueryResult;
+
+/// Widest a table column is allowed to grow, in characters
+const MAX_COLUMN_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl QueryFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "table" => Some(Self::Table),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn render(result: &QueryResult, format: QueryFormat, out: &mut impl Write) -> io::Result<()> {
+    match format {
+        QueryFormat::Table => render_table(result, out),
+        QueryFormat::Csv => render_csv(result, out),
+        QueryFormat::Json => render_json(result, out),
+    }
+}
+
+fn render_table(result: &QueryResult, out: &mut impl Write) -> io::Result<()> {
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|v| truncate(&text(v, "NULL"))).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = result
+        .columns
+        .iter()
+        .map(|c| truncate(c).chars().count())
+        .collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header: Vec<String> = result
+        .columns
+        .iter()
+        .zip(&widths)
+        .map(|(name, width)| format!("{:<width$}", truncate(name), width = width))
+        .collect();
+    writeln!(out, "{}", header.join("  ").trim_end())?;
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(out, "{}", rule.join("  "))?;
+
+    for (row, values) in cells.iter().zip(&result.rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(values)
+            .zip(&widths)
+            .map(|((cell, value), width)| match value {
+                Value::Integer(_) | Value::Real(_) => format!("{:>width$}", cell, width = width),
+                _ => format!("{:<width$}", cell, width = width),
+            })
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+
+    let plural = if result.rows.len() == 1 { "" } else { "s" };
+    writeln!(out, "({} row{})", result.rows.len(), plural)
+}
+
+fn render_csv(result: &QueryResult, out: &mut impl Write) -> io::Result<()> {
+    let header: Vec<String> = result.columns.iter().map(|c| escape_csv(c)).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for row in &result.rows {
+        let line: Vec<String> = row.iter().map(|v| escape_csv(&text(v, ""))).collect();
+        writeln!(out, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+/// An array of objects, one row per line, with keys in column order
+fn render_json(result: &QueryResult, out: &mut impl Write) -> io::Result<()> {
+    let keys: Vec<String> = result
+        .columns
+        .iter()
+        .map(|c| serde_json::Value::from(c.as_str()).to_string())
+        .collect();
+
+    write!(out, "[")?;
+    for (i, row) in result.rows.iter().enumerate() {
+        let fields: Vec<String> = keys
+            .iter()
+            .zip(row)
+            .map(|(key, value)| format!("{}:{}", key, json(value)))
+            .collect();
+        let sep = if i == 0 { "" } else { "," };
+        write!(out, "{}\n  {{{}}}", sep, fields.join(","))?;
+    }
+    writeln!(out, "{}]", if result.rows.is_empty() { "" } else { "\n" })
+}
+
+fn text(value: &Value, null: &str) -> String {
+    match value {
+        Value::Null => null.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(r) => real(*r),
+        Value::Text(t) => t.clone(),
+        Value::Blob(b) => hex(b),
+    }
+}
+
+fn json(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(r) if r.is_finite() => real(*r),
+        Value::Real(_) => "null".to_string(),
+        Value::Text(t) => serde_json::Value::from(t.as_str()).to_string(),
+        Value::Blob(b) => serde_json::Value::from(hex(b)).to_string(),
+    }
+}
+
+fn real(value: f64) -> String {
+    let narrow = value as f32;
+    if narrow as f64 == value {
+        format!("{:?}", narrow)
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn truncate(cell: &str) -> String {
+    if cell.chars().count() <= MAX_COLUMN_WIDTH {
+        return cell.to_string();
+    }
+    let mut short: String = cell.chars().take(MAX_COLUMN_WIDTH - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> QueryResult {
+        QueryResult {
+            columns: vec!["date".to_string(), "energy".to_string(), "note".to_string()],
+            rows: vec![
+                vec![
+                    Value::Text("2024-03-01".to_string()),
+                    Value::Real(12.3_f32 as f64),
+                    Value::Null,
+                ],
+                vec![
+                    Value::Text("2024-03-02".to_string()),
+                    Value::Real(0.1 + 0.2),
+                    Value::Text("say \"hi\", ok".to_string()),
+                ],
+            ],
+        }
+    }
+
+    fn rendered(format: QueryFormat) -> String {
+        let mut out = Vec::new();
+        render(&result(), format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn table_aligns_columns_and_counts_rows() {
+        assert_eq!(
+            rendered(QueryFormat::Table),
+            "date        energy               note\n\
+             ----------  -------------------  ------------\n\
+             2024-03-01                 12.3  NULL\n\
+             2024-03-02  0.30000000000000004  say \"hi\", ok\n\
+             (2 rows)\n"
+        );
+    }
+
+    #[test]
+    fn csv_and_json_keep_column_order() {
+        assert_eq!(
+            rendered(QueryFormat::Csv),
+            "date,energy,note\n\
+             2024-03-01,12.3,\n\
+             2024-03-02,0.30000000000000004,\"say \"\"hi\"\", ok\"\n"
+        );
+        assert_eq!(
+            rendered(QueryFormat::Json),
+            "[\n  {\"date\":\"2024-03-01\",\"energy\":12.3,\"note\":null},\n  \
+             {\"date\":\"2024-03-02\",\"energy\":0.30000000000000004,\"note\":\"say \\\"hi\\\", ok\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn empty_json_result_is_empty_array() {
+        let mut out = Vec::new();
+        render_json(&QueryResult::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[]\n");
+    }
+}
@@ -4,9 +4,11 @@ use chrono::Utc;
 use tracing::{debug, trace};
 
 use crate::config::HistoryConfig;
+use crate::data::processes::cpu_power_shares;
 use crate::data::{
-    BatteryData, BatteryHealthSnapshot, ChargingState, CycleSnapshot, DailyTopProcess,
-    HistoryStore, HistoryStoreError, PowerData, ProcessData, Sample, SessionEvent, SessionTracker,
+    BatteryData, BatteryHealthSnapshot, ChargeSession, ChargingState, CycleSnapshot,
+    DailyTopProcess, Event, EventKind, HistoryStore, HistoryStoreError, PowerData, ProcessData,
+    ProcessSample, Sample, SessionEvent, SessionTracker, SessionType,
 };
 
 pub struct Recorder {
@@ -17,6 +19,8 @@ pub struct Recorder {
     last_cycle_snapshot_date: Option<String>,
     excluded_processes: Vec<String>,
     session_tracker: SessionTracker,
+    /// Sessions closed since the last `take_ended_sessions`
+    ended_sessions: Vec<ChargeSession>,
 }
 
 impl Recorder {
@@ -47,13 +51,30 @@ impl Recorder {
             last_cycle_snapshot_date: None,
             excluded_processes,
             session_tracker,
+            ended_sessions: Vec::new(),
         })
     }
 
+    /// Applies new history settings and exclusions to a running recorder.
+    pub fn update_config(&mut self, config: HistoryConfig, excluded_processes: Vec<String>) {
+        debug!(
+            sample_interval_secs = config.sample_interval_secs,
+            background_recording = config.background_recording,
+            "Recorder config updated"
+        );
+        self.config = config;
+        self.excluded_processes = excluded_processes;
+    }
+
     pub fn store(&self) -> &HistoryStore {
         &self.store
     }
 
+    /// Returns the sessions that ended since the last call.
+    pub fn take_ended_sessions(&mut self) -> Vec<ChargeSession> {
+        std::mem::take(&mut self.ended_sessions)
+    }
+
     pub fn should_record(&self) -> bool {
         if !self.config.background_recording {
             return false;
@@ -106,10 +127,30 @@ impl Recorder {
     fn handle_session_event(&mut self, event: SessionEvent) -> Result<(), HistoryStoreError> {
         match event {
             SessionEvent::Started(session) => {
+                self.store.insert_event(&Event {
+                    id: None,
+                    timestamp: session.start_time,
+                    kind: EventKind::SessionStart,
+                    message: format!(
+                        "{} session started at {:.0}%",
+                        session_label(session.session_type),
+                        session.start_percent
+                    ),
+                })?;
                 let id = self.store.insert_charge_session(&session)?;
                 trace!(session_id = id, session_type = ?session.session_type, "Session started");
             }
             SessionEvent::Ended(session) => {
+                self.store.insert_event(&Event {
+                    id: None,
+                    timestamp: session.end_time.unwrap_or_else(|| Utc::now().timestamp()),
+                    kind: EventKind::SessionEnd,
+                    message: format!(
+                        "{} session ended at {:.0}%",
+                        session_label(session.session_type),
+                        session.end_percent.unwrap_or(session.start_percent)
+                    ),
+                })?;
                 if session.id.is_some() {
                     self.store.update_charge_session(&session)?;
                     trace!(session_id = session.id, "Session ended and updated");
@@ -117,6 +158,7 @@ impl Recorder {
                     let id = self.store.insert_charge_session(&session)?;
                     trace!(session_id = id, "Session ended and inserted");
                 }
+                self.ended_sessions.push(session);
             }
         }
         Ok(())
@@ -127,7 +169,8 @@ impl Recorder {
         processes: &ProcessData,
         system_cpu_power: f32,
     ) -> Result<(), HistoryStoreError> {
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let timestamp = Utc::now().timestamp();
+        let today = self.config.timezone.today();
 
         let top_processes: Vec<_> = processes
             .processes
@@ -136,17 +179,12 @@ impl Recorder {
             .take(10)
             .collect();
 
-        let total_cpu: f32 = top_processes.iter().map(|p| p.cpu_usage).sum();
+        let powers = cpu_power_shares(&top_processes, system_cpu_power);
         let sample_hours = self.config.sample_interval_secs as f32 / 3600.0;
 
-        for process in top_processes {
-            let process_power = if total_cpu > 0.0 {
-                (process.cpu_usage / total_cpu) * system_cpu_power
-            } else {
-                0.0
-            };
-            let sample_energy_wh = process_power * sample_hours;
-
+        // Hourly rows are rolled up from these by the aggregator
+        let mut samples = Vec::with_capacity(top_processes.len());
+        for (process, process_power) in top_processes.into_iter().zip(powers) {
             let entry = DailyTopProcess {
                 id: None,
                 date: today.clone(),
@@ -156,10 +194,20 @@ impl Recorder {
                 avg_memory_mb: process.memory_mb as f32,
                 sample_count: 1,
                 avg_power: process_power,
-                total_energy_wh: sample_energy_wh,
+                total_energy_wh: process_power * sample_hours,
             };
             self.store.upsert_daily_process(&entry)?;
+
+            samples.push(ProcessSample {
+                timestamp,
+                process_name: process.name.clone(),
+                energy_impact: process.energy_impact,
+                cpu_usage: process.cpu_usage,
+                memory_mb: process.memory_mb as f32,
+                power_watts: process_power,
+            });
         }
+        self.store.insert_process_samples(&samples)?;
 
         Ok(())
     }
@@ -168,7 +216,7 @@ impl Recorder {
         &mut self,
         battery: &BatteryData,
     ) -> Result<(), HistoryStoreError> {
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let today = self.config.timezone.today();
 
         if self.last_health_date.as_ref() == Some(&today) {
             return Ok(());
@@ -193,7 +241,7 @@ impl Recorder {
         &mut self,
         battery: &BatteryData,
     ) -> Result<(), HistoryStoreError> {
-        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let today = self.config.timezone.today();
 
         if self.last_cycle_snapshot_date.as_ref() == Some(&today) {
             return Ok(());
@@ -238,3 +286,10 @@ impl Recorder {
         Ok(())
     }
 }
+
+fn session_label(session_type: SessionType) -> &'static str {
+    match session_type {
+        SessionType::Charge => "Charge",
+        SessionType::Discharge => "Discharge",
+    }
+}
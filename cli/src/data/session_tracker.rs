@@ -13,6 +13,31 @@ pub enum SessionEvent {
     Ended(ChargeSession),
 }
 
+/// One battery observation, either live or replayed from imported history.
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryReading {
+    pub timestamp: i64,
+    pub is_charging: bool,
+    pub external_connected: bool,
+    pub battery_percent: f32,
+    /// Full-charge capacity, or 0 if unknown
+    pub capacity_wh: f32,
+    pub charger_watts: Option<u32>,
+}
+
+impl BatteryReading {
+    pub fn from_battery(battery: &BatteryData) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp(),
+            is_charging: battery.is_charging(),
+            external_connected: battery.external_connected(),
+            battery_percent: battery.charge_percent(),
+            capacity_wh: battery.max_capacity_wh(),
+            charger_watts: battery.charger_watts(),
+        }
+    }
+}
+
 /// Tracks battery charge/discharge sessions by detecting state transitions.
 ///
 /// Monitors charging state changes and emits `SessionEvent`s when sessions
@@ -55,11 +80,17 @@ impl SessionTracker {
 
     /// Processes a battery sample and returns an event if a session state change occurred.
     pub fn process_sample(&mut self, battery: &BatteryData) -> Option<SessionEvent> {
-        let now = Utc::now().timestamp();
-        let is_charging = battery.is_charging();
-        let external_connected = battery.external_connected();
-        let battery_percent = battery.charge_percent();
-        let capacity_wh = battery.max_capacity_wh();
+        self.process_reading(BatteryReading::from_battery(battery))
+    }
+
+    /// Processes a reading taken at `reading.timestamp`. Readings must be fed
+    /// in time order.
+    pub fn process_reading(&mut self, reading: BatteryReading) -> Option<SessionEvent> {
+        let now = reading.timestamp;
+        let is_charging = reading.is_charging;
+        let external_connected = reading.external_connected;
+        let battery_percent = reading.battery_percent;
+        let capacity_wh = reading.capacity_wh;
 
         if let Some(last_time) = self.last_sample_time {
             let elapsed = now - last_time;
@@ -75,7 +106,7 @@ impl SessionTracker {
             external_connected,
             battery_percent,
             capacity_wh,
-            battery.charger_watts(),
+            reading.charger_watts,
         );
 
         if !is_charging {
@@ -210,7 +241,7 @@ impl SessionTracker {
             SessionType::Discharge => start_percent - end_percent,
         };
 
-        if delta <= 0.0 {
+        if delta <= 0.0 || capacity_wh <= 0.0 {
             return None;
         }
 
@@ -280,6 +311,35 @@ mod tests {
         assert!((tracker.get_partial_cycles()).abs() < 0.01);
     }
 
+    #[test]
+    fn test_replayed_readings_end_session() {
+        let reading = |timestamp, is_charging, battery_percent| BatteryReading {
+            timestamp,
+            is_charging,
+            external_connected: is_charging,
+            battery_percent,
+            capacity_wh: 50.0,
+            charger_watts: None,
+        };
+        let mut tracker = SessionTracker::new();
+
+        assert!(matches!(
+            tracker.process_reading(reading(1_000, true, 20.0)),
+            Some(SessionEvent::Started(_))
+        ));
+        assert!(tracker
+            .process_reading(reading(2_000, true, 60.0))
+            .is_none());
+        match tracker.process_reading(reading(4_600, false, 80.0)) {
+            Some(SessionEvent::Ended(session)) => {
+                assert_eq!(session.start_time, 1_000);
+                assert_eq!(session.end_time, Some(4_600));
+                assert!((session.energy_wh.unwrap() - 30.0).abs() < 0.1);
+            }
+            other => panic!("Expected session end, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_energy_calculation() {
         let tracker = SessionTracker::new();
@@ -292,5 +352,8 @@ mod tests {
 
         let energy = tracker.calculate_energy_wh(80.0, 20.0, 50.0, SessionType::Charge);
         assert!(energy.is_none());
+
+        let energy = tracker.calculate_energy_wh(20.0, 80.0, 0.0, SessionType::Charge);
+        assert!(energy.is_none());
     }
 }
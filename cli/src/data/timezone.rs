@@ -0,0 +1,263 @@
+//! Calendar-day boundaries for daily aggregates and period filters.
+//!
+//! Daily rows are keyed by `YYYY-MM-DD`. Which instants belong to a date
+//! depends on the zone: the system's local time by default, or a fixed UTC
+//! offset set with `history.timezone`.
+
+use std::fmt;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Timelike, Utc,
+};
+use serde::{Deserialize, Serialize};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DayZone {
+    /// The system timezone, including daylight saving changes
+    #[default]
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl DayZone {
+    /// Parses `local`, `utc` or an offset such as `-08:00` / `+0530`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if spec.eq_ignore_ascii_case("utc") || spec.eq_ignore_ascii_case("z") {
+            return Ok(Self::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+
+        let (sign, rest) = match spec.as_bytes().first() {
+            Some(b'+') => (1, &spec[1..]),
+            Some(b'-') => (-1, &spec[1..]),
+            _ => return Err(invalid(spec)),
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() == 4 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        let hours: i32 = hours.parse().map_err(|_| invalid(spec))?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid(spec))?;
+        if !(0..60).contains(&minutes) {
+            return Err(invalid(spec));
+        }
+
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Self::Fixed)
+            .ok_or_else(|| invalid(spec))
+    }
+
+    /// Calendar date containing `timestamp`.
+    pub fn date_of(&self, timestamp: i64) -> NaiveDate {
+        let utc = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+        match self {
+            Self::Local => utc.with_timezone(&Local).date_naive(),
+            Self::Fixed(offset) => utc.with_timezone(offset).date_naive(),
+        }
+    }
+
+    /// Day of the week (0 = Monday) and hour of the day at `timestamp`.
+    pub fn weekday_hour(&self, timestamp: i64) -> (u32, u32) {
+        let utc = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+        let local = match self {
+            Self::Local => utc.with_timezone(&Local).naive_local(),
+            Self::Fixed(offset) => utc.with_timezone(offset).naive_local(),
+        };
+        (local.weekday().num_days_from_monday(), local.hour())
+    }
+
+    pub fn today(&self) -> String {
+        self.date_of(Utc::now().timestamp())
+            .format(DATE_FORMAT)
+            .to_string()
+    }
+
+    /// Date `days` calendar days before today.
+    pub fn days_ago(&self, days: u32) -> String {
+        (self.date_of(Utc::now().timestamp()) - Duration::days(days as i64))
+            .format(DATE_FORMAT)
+            .to_string()
+    }
+
+    /// Timestamp of the first second of `date`.
+    pub fn day_start(&self, date: NaiveDate) -> i64 {
+        self.timestamp_of(date.and_time(NaiveTime::MIN))
+    }
+
+    /// Next timestamp after `after` when the wall clock shows `time`.
+    pub fn next_at(&self, after: i64, time: NaiveTime) -> i64 {
+        let today = self.date_of(after);
+        let at = self.timestamp_of(today.and_time(time));
+        if at > after {
+            at
+        } else {
+            self.timestamp_of((today + Duration::days(1)).and_time(time))
+        }
+    }
+
+    fn timestamp_of(&self, datetime: NaiveDateTime) -> i64 {
+        match self {
+            // A DST jump skips an hour of wall-clock time; a time inside the
+            // gap maps to the first instant after it.
+            Self::Local => Local
+                .from_local_datetime(&datetime)
+                .earliest()
+                .or_else(|| {
+                    Local
+                        .from_local_datetime(&(datetime + Duration::hours(1)))
+                        .earliest()
+                })
+                .map(|dt| dt.timestamp())
+                .unwrap_or_else(|| datetime.and_utc().timestamp()),
+            Self::Fixed(offset) => (datetime - *offset).and_utc().timestamp(),
+        }
+    }
+
+    /// Timestamp range `[start, end)` covered by `date`.
+    pub fn day_bounds(&self, date: NaiveDate) -> (i64, i64) {
+        (
+            self.day_start(date),
+            self.day_start(date + Duration::days(1)),
+        )
+    }
+
+    /// Inclusive timestamp range from the start of `from` to the last second
+    /// of `to`. Unparseable dates leave that end open.
+    pub fn date_range(&self, from: &str, to: &str) -> (i64, i64) {
+        let from_ts = parse_date(from).map(|d| self.day_start(d)).unwrap_or(0);
+        let to_ts = parse_date(to)
+            .map(|d| self.day_bounds(d).1 - 1)
+            .unwrap_or(i64::MAX);
+        (from_ts, to_ts)
+    }
+
+    /// Formats `timestamp` as wall-clock time in this zone.
+    pub fn format(&self, timestamp: i64, format: &str) -> String {
+        let Some(utc) = DateTime::from_timestamp(timestamp, 0) else {
+            return String::new();
+        };
+        match self {
+            Self::Local => utc.with_timezone(&Local).format(format).to_string(),
+            Self::Fixed(offset) => utc.with_timezone(offset).format(format).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DayZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl TryFrom<String> for DayZone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<DayZone> for String {
+    fn from(zone: DayZone) -> Self {
+        zone.to_string()
+    }
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).ok()
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+fn invalid(spec: &str) -> String {
+    format!(
+        "invalid timezone '{}': use \"local\", \"utc\" or an offset like \"-08:00\"",
+        spec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(spec: &str) -> DayZone {
+        DayZone::parse(spec).unwrap()
+    }
+
+    #[test]
+    fn parses_local_utc_and_offsets() {
+        assert_eq!(offset("local"), DayZone::Local);
+        assert_eq!(offset("UTC").to_string(), "+00:00");
+        assert_eq!(offset("-08:00").to_string(), "-08:00");
+        assert_eq!(offset("+0530").to_string(), "+05:30");
+        assert_eq!(offset("+9").to_string(), "+09:00");
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        for spec in ["", "America/Los_Angeles", "+25:00", "-08:75", "8"] {
+            assert!(DayZone::parse(spec).is_err(), "{spec} should be rejected");
+        }
+    }
+
+    #[test]
+    fn day_boundaries_follow_offset() {
+        let zone = offset("-08:00");
+        let date = parse_date("2024-03-01").unwrap();
+        // 2024-03-01 00:00 at UTC-8 is 08:00 UTC.
+        assert_eq!(zone.day_start(date), 1_709_280_000);
+        assert_eq!(zone.day_bounds(date).1 - zone.day_start(date), 86400);
+
+        // 16:30 UTC on the 2nd is still the morning of the 2nd at UTC-8,
+        // but 03:00 UTC on the 2nd is the evening of the 1st.
+        assert_eq!(format_date(zone.date_of(1_709_397_000)), "2024-03-02");
+        assert_eq!(format_date(zone.date_of(1_709_348_400)), "2024-03-01");
+    }
+
+    #[test]
+    fn weekday_hour_follows_offset() {
+        // 2024-03-01 03:00 UTC is a Friday, but still Thursday 19:00 at UTC-8.
+        assert_eq!(offset("utc").weekday_hour(1_709_262_000), (4, 3));
+        assert_eq!(offset("-08:00").weekday_hour(1_709_262_000), (3, 19));
+    }
+
+    #[test]
+    fn next_at_rolls_over_to_tomorrow() {
+        let zone = offset("-08:00");
+        // 2024-03-01 03:00 UTC is 19:00 on the 29th at UTC-8.
+        let now = 1_709_262_000;
+        let evening = NaiveTime::from_hms_opt(21, 30, 0).unwrap();
+        let morning = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        assert_eq!(zone.next_at(now, evening), now + 9000);
+        assert_eq!(zone.next_at(now, morning), now + 12 * 3600);
+    }
+
+    #[test]
+    fn date_range_is_inclusive_of_last_day() {
+        let zone = offset("utc");
+        let (from, to) = zone.date_range("2024-03-01", "2024-03-02");
+        assert_eq!(from, 1_709_251_200);
+        assert_eq!(to, 1_709_251_200 + 2 * 86400 - 1);
+        assert_eq!(zone.date_range("bogus", "bogus"), (0, i64::MAX));
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let zone: DayZone = serde_json::from_str("\"-08:00\"").unwrap();
+        assert_eq!(serde_json::to_string(&zone).unwrap(), "\"-08:00\"");
+        assert!(serde_json::from_str::<DayZone>("\"nowhere\"").is_err());
+    }
+}
@@ -0,0 +1,511 @@
+//! Backfills history from UPower's battery history files.
+//!
+//! UPower appends a line to `history-charge-<device>.dat` (percent) and
+//! `history-rate-<device>.dat` (watts) whenever a value changes, each as
+//! `<unix time>\t<value>\t<state>`. Those readings are resampled onto the
+//! configured sample interval so imported days aggregate exactly like
+//! recorded ones, and re-importing the same files lands on the same
+//! timestamps.
+//!
+//! UPower also keeps files for mice, keyboards and headsets, so only the
+//! system battery's files are imported.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::HistoryConfig;
+use crate::data::aggregator::Aggregator;
+use crate::data::session_tracker::BatteryReading;
+use crate::data::{
+    ChargingState, HistoryStore, HistoryStoreError, Sample, SessionEvent, SessionTracker,
+};
+
+pub const DEFAULT_UPOWER_DIR: &str = "/var/lib/upower";
+
+/// UPower only logs changes, so a reading is carried forward until the next
+/// one, but no longer than this; longer silences are usually suspend.
+const MAX_HOLD_SECS: i64 = 600;
+
+const CHARGE_PREFIX: &str = "history-charge-";
+const RATE_PREFIX: &str = "history-rate-";
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpowerError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("No UPower history files found in {0}")]
+    NoHistory(PathBuf),
+
+    #[error("No UPower history for device {device}; found {}", .available.join(", "))]
+    UnknownDevice {
+        device: String,
+        available: Vec<String>,
+    },
+
+    #[error(
+        "UPower has history for several devices: {}. Choose the battery with --device",
+        .0.join(", ")
+    )]
+    AmbiguousDevice(Vec<String>),
+
+    #[error(transparent)]
+    Store(#[from] HistoryStoreError),
+}
+
+/// What is known about the system battery, used to find its UPower device
+#[derive(Debug, Default)]
+pub struct BatteryIdentity {
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportResult {
+    /// UPower device id the history was read from
+    pub device: String,
+    pub files: usize,
+    pub skipped_lines: usize,
+    pub samples_imported: usize,
+    pub duplicate_samples: usize,
+    pub sessions_imported: usize,
+    pub hours_aggregated: usize,
+    pub days_aggregated: usize,
+    /// Timestamps of the first and last imported sample
+    pub range: Option<(i64, i64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    timestamp: i64,
+    value: f32,
+    state: ChargingState,
+}
+
+#[derive(Debug, Default)]
+struct DeviceHistory {
+    charge: Vec<Point>,
+    rate: Vec<Point>,
+    files: usize,
+    skipped_lines: usize,
+}
+
+/// Imports the system battery's history in `dir` into `store`.
+///
+/// The device is `device` when given, otherwise the only device in `dir` or
+/// the one whose id matches `battery`. `capacity_wh` is used to estimate
+/// session energy and may be 0 if unknown.
+pub fn import_dir(
+    store: &HistoryStore,
+    config: &HistoryConfig,
+    dir: &Path,
+    device: Option<&str>,
+    battery: &BatteryIdentity,
+    capacity_wh: f32,
+) -> Result<ImportResult, UpowerError> {
+    let mut devices = read_dir(dir)?;
+    if devices.is_empty() {
+        return Err(UpowerError::NoHistory(dir.to_path_buf()));
+    }
+    let id = select_device(&devices, device, battery)?;
+    let history = devices.remove(&id).unwrap_or_default();
+    let mut result = ImportResult {
+        device: id,
+        files: history.files,
+        skipped_lines: history.skipped_lines,
+        ..ImportResult::default()
+    };
+
+    let interval = config.sample_interval_secs.max(1) as i64;
+    let samples = resample(&history, interval);
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return Ok(result);
+    };
+    result.range = Some((first.timestamp, last.timestamp));
+
+    result.samples_imported = store.insert_samples_if_new(&samples)?;
+    result.duplicate_samples = samples.len() - result.samples_imported;
+    result.sessions_imported = import_sessions(store, &samples, capacity_wh)?;
+
+    let aggregator = Aggregator::new(store, config);
+    let (hours, days) = aggregator.reaggregate_range(first.timestamp, last.timestamp)?;
+    result.hours_aggregated = hours;
+    result.days_aggregated = days;
+
+    Ok(result)
+}
+
+/// Replays samples through a `SessionTracker` and stores the completed
+/// sessions that aren't already present.
+fn import_sessions(
+    store: &HistoryStore,
+    samples: &[Sample],
+    capacity_wh: f32,
+) -> Result<usize, HistoryStoreError> {
+    let mut tracker = SessionTracker::new();
+    let mut imported = 0;
+
+    for sample in samples {
+        tracker.record_power_sample(sample.power_watts);
+        let reading = BatteryReading {
+            timestamp: sample.timestamp,
+            is_charging: sample.charging_state == ChargingState::Charging,
+            external_connected: matches!(
+                sample.charging_state,
+                ChargingState::Charging | ChargingState::Full
+            ),
+            battery_percent: sample.battery_percent,
+            capacity_wh,
+            charger_watts: None,
+        };
+        if let Some(SessionEvent::Ended(session)) = tracker.process_reading(reading) {
+            if !store.charge_session_exists(session.start_time)? {
+                store.insert_charge_session(&session)?;
+                imported += 1;
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Picks the device to import: the requested one, the only one, or the one
+/// whose id carries the battery's model and serial.
+fn select_device(
+    devices: &BTreeMap<String, DeviceHistory>,
+    requested: Option<&str>,
+    battery: &BatteryIdentity,
+) -> Result<String, UpowerError> {
+    let available = || devices.keys().cloned().collect::<Vec<_>>();
+    if let Some(device) = requested {
+        return if devices.contains_key(device) {
+            Ok(device.to_string())
+        } else {
+            Err(UpowerError::UnknownDevice {
+                device: device.to_string(),
+                available: available(),
+            })
+        };
+    }
+    if devices.len() == 1 {
+        return Ok(available().remove(0));
+    }
+
+    // UPower builds battery ids from the model, design energy and serial,
+    // with punctuation and spaces replaced by underscores
+    let parts: Vec<String> = [&battery.model, &battery.serial]
+        .into_iter()
+        .flatten()
+        .map(|part| upower_id_part(part))
+        .filter(|part| !part.is_empty())
+        .collect();
+    let matches: Vec<&String> = devices
+        .keys()
+        .filter(|id| !parts.is_empty() && parts.iter().all(|part| id.contains(part.as_str())))
+        .collect();
+    match matches.as_slice() {
+        [id] => Ok((*id).clone()),
+        _ => Err(UpowerError::AmbiguousDevice(available())),
+    }
+}
+
+fn upower_id_part(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn read_dir(dir: &Path) -> Result<BTreeMap<String, DeviceHistory>, UpowerError> {
+    let mut devices: BTreeMap<String, DeviceHistory> = BTreeMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(stem) = name.strip_suffix(".dat") else {
+            continue;
+        };
+        let (device, is_charge) = if let Some(id) = stem.strip_prefix(CHARGE_PREFIX) {
+            (id, true)
+        } else if let Some(id) = stem.strip_prefix(RATE_PREFIX) {
+            (id, false)
+        } else {
+            continue;
+        };
+
+        let content = fs::read_to_string(&path)?;
+        let (points, skipped) = parse_history(&content);
+
+        let history = devices.entry(device.to_string()).or_default();
+        history.files += 1;
+        history.skipped_lines += skipped;
+        let target = if is_charge {
+            &mut history.charge
+        } else {
+            &mut history.rate
+        };
+        target.extend(
+            points
+                .into_iter()
+                .filter(|p| !is_charge || (0.0..=100.0).contains(&p.value)),
+        );
+    }
+
+    for history in devices.values_mut() {
+        history.charge.sort_by_key(|p| p.timestamp);
+        history.rate.sort_by_key(|p| p.timestamp);
+    }
+    Ok(devices)
+}
+
+/// Parses a history file, returning the readings and the number of lines
+/// that couldn't be parsed.
+fn parse_history(content: &str) -> (Vec<Point>, usize) {
+    let mut points = Vec::new();
+    let mut skipped = 0;
+
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match parse_line(line) {
+            Some(point) => points.push(point),
+            None => skipped += 1,
+        }
+    }
+
+    (points, skipped)
+}
+
+fn parse_line(line: &str) -> Option<Point> {
+    let mut fields = line.split('\t');
+    let timestamp = fields.next()?.trim().parse::<i64>().ok()?;
+    let value = fields.next()?.trim().parse::<f32>().ok()?;
+    let state = parse_state(fields.next().unwrap_or("unknown").trim());
+    value.is_finite().then_some(Point {
+        timestamp,
+        value,
+        state,
+    })
+}
+
+fn parse_state(state: &str) -> ChargingState {
+    match state {
+        "charging" => ChargingState::Charging,
+        "fully-charged" => ChargingState::Full,
+        "discharging" | "empty" | "pending-charge" | "pending-discharge" => {
+            ChargingState::Discharging
+        }
+        _ => ChargingState::Unknown,
+    }
+}
+
+/// Expands change-only readings into samples on the `interval` grid.
+fn resample(device: &DeviceHistory, interval: i64) -> Vec<Sample> {
+    let mut samples = Vec::new();
+
+    for (i, point) in device.charge.iter().enumerate() {
+        let next = device
+            .charge
+            .get(i + 1)
+            .map(|p| p.timestamp)
+            .unwrap_or(point.timestamp + interval);
+        let hold_until = next.min(point.timestamp + MAX_HOLD_SECS);
+
+        let mut timestamp =
+            point.timestamp + (interval - point.timestamp.rem_euclid(interval)) % interval;
+        while timestamp < hold_until {
+            samples.push(Sample {
+                id: None,
+                timestamp,
+                battery_percent: point.value,
+                power_watts: rate_at(&device.rate, timestamp),
+                cpu_power: 0.0,
+                gpu_power: 0.0,
+                charging_state: point.state,
+            });
+            timestamp += interval;
+        }
+    }
+
+    samples
+}
+
+fn rate_at(rate: &[Point], timestamp: i64) -> f32 {
+    let idx = rate.partition_point(|p| p.timestamp <= timestamp);
+    match idx.checked_sub(1).map(|i| rate[i]) {
+        Some(p) if timestamp - p.timestamp <= MAX_HOLD_SECS => p.value.abs(),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: i64, value: f32, state: ChargingState) -> Point {
+        Point {
+            timestamp,
+            value,
+            state,
+        }
+    }
+
+    #[test]
+    fn parses_lines_and_counts_garbage() {
+        let content = "1700000000\t85.000\tdischarging\n\
+                       1700000090\t84.000\tcharging\n\
+                       not a line\n\
+                       1700000200\tNaN\tcharging\n\
+                       \n\
+                       1700000300\t100.000\tfully-charged\n";
+        let (points, skipped) = parse_history(content);
+        assert_eq!(skipped, 2);
+        assert_eq!(
+            points,
+            vec![
+                point(1_700_000_000, 85.0, ChargingState::Discharging),
+                point(1_700_000_090, 84.0, ChargingState::Charging),
+                point(1_700_000_300, 100.0, ChargingState::Full),
+            ]
+        );
+    }
+
+    #[test]
+    fn resample_holds_values_on_interval_grid() {
+        let device = DeviceHistory {
+            charge: vec![
+                point(1_000, 80.0, ChargingState::Discharging),
+                point(1_130, 79.0, ChargingState::Discharging),
+            ],
+            rate: vec![point(990, 12.5, ChargingState::Discharging)],
+            ..DeviceHistory::default()
+        };
+        let samples = resample(&device, 60);
+        let timestamps: Vec<i64> = samples.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, vec![1_020, 1_080, 1_140]);
+        assert_eq!(samples[1].battery_percent, 80.0);
+        assert_eq!(samples[2].battery_percent, 79.0);
+        assert!(samples.iter().all(|s| s.power_watts == 12.5));
+    }
+
+    #[test]
+    fn resample_leaves_gaps_after_long_silence() {
+        let device = DeviceHistory {
+            charge: vec![
+                point(0, 50.0, ChargingState::Discharging),
+                point(7_200, 40.0, ChargingState::Discharging),
+            ],
+            rate: vec![point(0, 8.0, ChargingState::Discharging)],
+            ..DeviceHistory::default()
+        };
+        let samples = resample(&device, 60);
+        assert_eq!(samples.len(), (MAX_HOLD_SECS / 60) as usize + 1);
+        let last = samples.last().unwrap();
+        assert_eq!(last.timestamp, 7_200);
+        assert_eq!(last.power_watts, 0.0, "stale rate should not be reused");
+    }
+
+    /// A laptop battery and a mouse logging at the same times
+    fn write_two_devices(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jolt-upower-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let laptop = "5B10W13930-51-4716";
+        let mouse = "hidpp_battery_0";
+        for (device, percent) in [(laptop, 80.0), (mouse, 30.0)] {
+            let charge: String = (0..5)
+                .map(|i| {
+                    format!(
+                        "{}\t{:.3}\tdischarging\n",
+                        1_000 + i * 60,
+                        percent - i as f32
+                    )
+                })
+                .collect();
+            fs::write(dir.join(format!("history-charge-{}.dat", device)), charge).unwrap();
+        }
+        fs::write(
+            dir.join(format!("history-rate-{}.dat", laptop)),
+            "1000\t9.500\tdischarging\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn laptop_battery() -> BatteryIdentity {
+        BatteryIdentity {
+            model: Some("5B10W13930".to_string()),
+            serial: Some("4716".to_string()),
+        }
+    }
+
+    #[test]
+    fn imports_only_the_system_battery() {
+        let dir = write_two_devices("battery");
+        let store = HistoryStore::open_at(Path::new(":memory:")).unwrap();
+        let config = HistoryConfig {
+            sample_interval_secs: 60,
+            ..HistoryConfig::default()
+        };
+
+        let result = import_dir(&store, &config, &dir, None, &laptop_battery(), 50.0).unwrap();
+        assert_eq!(result.device, "5B10W13930-51-4716");
+        assert_eq!(result.files, 2);
+        let samples = store.get_samples(0, i64::MAX).unwrap();
+        assert_eq!(samples.len(), 5);
+        assert!(samples.iter().all(|s| s.battery_percent > 75.0));
+        assert!(samples.iter().all(|s| s.power_watts == 9.5));
+
+        // Naming the device directly reaches the peripheral too
+        let store = HistoryStore::open_at(Path::new(":memory:")).unwrap();
+        let result = import_dir(
+            &store,
+            &config,
+            &dir,
+            Some("hidpp_battery_0"),
+            &BatteryIdentity::default(),
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(result.device, "hidpp_battery_0");
+        let samples = store.get_samples(0, i64::MAX).unwrap();
+        assert!(samples.iter().all(|s| s.battery_percent <= 30.0));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn several_devices_without_a_match_are_listed() {
+        let dir = write_two_devices("ambiguous");
+        let store = HistoryStore::open_at(Path::new(":memory:")).unwrap();
+        let config = HistoryConfig::default();
+
+        let err = import_dir(
+            &store,
+            &config,
+            &dir,
+            None,
+            &BatteryIdentity::default(),
+            0.0,
+        )
+        .unwrap_err();
+        match err {
+            UpowerError::AmbiguousDevice(devices) => {
+                assert_eq!(devices, vec!["5B10W13930-51-4716", "hidpp_battery_0"]);
+            }
+            other => panic!("expected AmbiguousDevice, got {other}"),
+        }
+
+        let err =
+            import_dir(&store, &config, &dir, Some("BAT0"), &laptop_battery(), 0.0).unwrap_err();
+        assert!(matches!(err, UpowerError::UnknownDevice { .. }));
+        assert!(store.get_samples(0, i64::MAX).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+//! Typical power draw by weekday and hour, learned from `hourly_stats`.
+//!
+//! The battery forecast uses this to look ahead: an idle moment at 9am
+//! says little about the meetings that usually follow. Each of the 168
+//! weekday/hour slots keeps the average discharge power of every matching
+//! hour in the lookback window, so the forecast can read off a spread
+//! rather than a single mean.
+
+use crate::data::{DayZone, HistoryStore, HistoryStoreError, HourlyStat};
+
+/// How far back the profile looks
+pub const PROFILE_WEEKS: i64 = 8;
+
+/// Discharging hours needed before the profile is trusted at all
+pub const MIN_PROFILE_HOURS: usize = 24;
+
+/// Observations needed before a weekday/hour slot is used on its own;
+/// sparser slots borrow the same hour from every weekday
+const MIN_SLOT_HOURS: usize = 2;
+
+const MIN_POWER_WATTS: f32 = 0.1;
+const SLOTS: usize = 7 * 24;
+
+#[derive(Debug, Clone)]
+pub struct UsageProfile {
+    zone: DayZone,
+    /// Sorted power readings, indexed by `weekday * 24 + hour`
+    slots: Vec<Vec<f32>>,
+}
+
+impl UsageProfile {
+    /// Builds a profile from the discharging hours in `stats`.
+    pub fn from_hourly(stats: &[HourlyStat], zone: DayZone) -> Self {
+        let mut slots = vec![Vec::new(); SLOTS];
+        for stat in stats {
+            if stat.battery_delta >= 0.0 || stat.avg_power < MIN_POWER_WATTS {
+                continue;
+            }
+            let (weekday, hour) = zone.weekday_hour(stat.hour_start);
+            slots[(weekday * 24 + hour) as usize].push(stat.avg_power);
+        }
+        for slot in &mut slots {
+            slot.sort_by(f32::total_cmp);
+        }
+        Self { zone, slots }
+    }
+
+    /// Loads the last `PROFILE_WEEKS` of hourly stats from `store`.
+    pub fn load(store: &HistoryStore, zone: DayZone, now: i64) -> Result<Self, HistoryStoreError> {
+        let from = now - PROFILE_WEEKS * 7 * 86400;
+        Ok(Self::from_hourly(&store.get_hourly_stats(from, now)?, zone))
+    }
+
+    /// Number of discharging hours the profile was built from
+    pub fn hours(&self) -> usize {
+        self.slots.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_usable(&self) -> bool {
+        self.hours() >= MIN_PROFILE_HOURS
+    }
+
+    /// The `quantile` (0-1) of power drawn in the hour containing
+    /// `timestamp`, or `None` if that hour has never been seen.
+    pub fn power_at(&self, timestamp: i64, quantile: f32) -> Option<f32> {
+        let (weekday, hour) = self.zone.weekday_hour(timestamp);
+        let slot = &self.slots[(weekday * 24 + hour) as usize];
+        if slot.len() >= MIN_SLOT_HOURS {
+            return Some(percentile(slot, quantile));
+        }
+
+        let mut same_hour: Vec<f32> = (0..7)
+            .flat_map(|day| &self.slots[(day * 24 + hour) as usize])
+            .copied()
+            .collect();
+        if same_hour.is_empty() {
+            return None;
+        }
+        same_hour.sort_by(f32::total_cmp);
+        Some(percentile(&same_hour, quantile))
+    }
+}
+
+/// Linearly interpolated `quantile` (0-1) of `sorted`, which must be
+/// non-empty and ascending.
+pub fn percentile(sorted: &[f32], quantile: f32) -> f32 {
+    let rank = quantile.clamp(0.0, 1.0) * (sorted.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-03-04 00:00 UTC, a Monday
+    const MONDAY: i64 = 1_709_510_400;
+
+    fn hour(hour_start: i64, avg_power: f32, battery_delta: f32) -> HourlyStat {
+        HourlyStat {
+            id: None,
+            hour_start,
+            avg_power,
+            max_power: avg_power,
+            min_power: avg_power,
+            avg_battery: 50.0,
+            battery_delta,
+            total_samples: 60,
+        }
+    }
+
+    #[test]
+    fn percentile_interpolates() {
+        let sorted = [10.0, 20.0, 30.0, 40.0, 50.0];
+        assert_eq!(percentile(&sorted, 0.5), 30.0);
+        assert_eq!(percentile(&sorted, 0.1), 14.0);
+        assert_eq!(percentile(&sorted, 1.0), 50.0);
+        assert_eq!(percentile(&[7.0], 0.9), 7.0);
+    }
+
+    #[test]
+    fn slots_fall_back_to_same_hour_on_other_days() {
+        let week = 7 * 86400;
+        let stats = vec![
+            // Mondays at 09:00, over three weeks
+            hour(MONDAY + 9 * 3600, 20.0, -10.0),
+            hour(MONDAY + week + 9 * 3600, 24.0, -12.0),
+            hour(MONDAY + 2 * week + 9 * 3600, 22.0, -11.0),
+            // A single Tuesday at 09:00
+            hour(MONDAY + 86400 + 9 * 3600, 8.0, -4.0),
+            // Charging hours are ignored
+            hour(MONDAY + 10 * 3600, 15.0, 20.0),
+        ];
+        let profile = UsageProfile::from_hourly(&stats, DayZone::parse("utc").unwrap());
+        assert_eq!(profile.hours(), 4);
+
+        let next_monday = MONDAY + 3 * week + 9 * 3600 + 1800;
+        assert_eq!(profile.power_at(next_monday, 0.5), Some(22.0));
+
+        // Wednesday 09:00 has no data of its own, so every 09:00 is used
+        let wednesday = MONDAY + 2 * 86400 + 9 * 3600;
+        assert_eq!(profile.power_at(wednesday, 0.0), Some(8.0));
+        assert_eq!(profile.power_at(wednesday, 1.0), Some(24.0));
+
+        assert_eq!(profile.power_at(MONDAY + 10 * 3600, 0.5), None);
+    }
+}
@@ -19,6 +19,9 @@ pub mod keys {
     pub const ESC: &str = "Esc";
     pub const SETTINGS: &str = "s";
     pub const BATTERY_DETAILS: &str = "b";
+    pub const PLAN: &str = "p";
+    pub const UNDO_SAVER: &str = "u";
+    pub const PROCESS_ACTIONS: &str = "x";
 }
 
 pub fn handle_key(app: &App, key: KeyEvent) -> Action {
@@ -38,6 +41,8 @@ pub fn handle_key(app: &App, key: KeyEvent) -> Action {
         AppView::History => handle_history_keys(key),
         AppView::Settings => handle_settings_keys(key),
         AppView::BatteryDetails => handle_battery_details_keys(key),
+        AppView::Plan => handle_plan_keys(key),
+        AppView::ProcessActions => handle_process_actions_keys(key),
     }
 }
 
@@ -58,6 +63,7 @@ fn handle_main_keys(key: KeyEvent, selection_mode: bool) -> Action {
         KeyCode::Down | KeyCode::Char('j') => Action::SelectNext,
         KeyCode::Enter | KeyCode::Char(' ') => Action::ToggleExpand,
         KeyCode::Char('K') => Action::KillProcess,
+        KeyCode::Char('x') => Action::OpenProcessActions,
         KeyCode::Char('t') => Action::OpenThemePicker,
         KeyCode::Char('g') => Action::ToggleGraphView,
         KeyCode::Char('m') => Action::ToggleMerge,
@@ -73,6 +79,8 @@ fn handle_main_keys(key: KeyEvent, selection_mode: bool) -> Action {
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
         KeyCode::Char('h') => Action::ToggleHistory,
         KeyCode::Char('b') => Action::ToggleBatteryDetails,
+        KeyCode::Char('p') => Action::TogglePlan,
+        KeyCode::Char('u') => Action::UndoBatterySaver,
         _ => Action::None,
     }
 }
@@ -139,6 +147,17 @@ fn handle_kill_confirm_keys(key: KeyEvent) -> Action {
         KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Action::ConfirmKill,
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Action::CancelKill,
         KeyCode::Tab | KeyCode::Left | KeyCode::Right => Action::ToggleKillSignal,
+        KeyCode::Char('s') | KeyCode::Char('S') => Action::CycleKillScope,
+        _ => Action::None,
+    }
+}
+
+fn handle_process_actions_keys(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('q') => Action::CloseProcessActions,
+        KeyCode::Up | KeyCode::Char('k') => Action::SelectPrevious,
+        KeyCode::Down | KeyCode::Char('j') => Action::SelectNext,
+        KeyCode::Enter | KeyCode::Char(' ') => Action::ConfirmProcessActions,
         _ => Action::None,
     }
 }
@@ -173,6 +192,16 @@ fn handle_battery_details_keys(key: KeyEvent) -> Action {
     }
 }
 
+fn handle_plan_keys(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('p') | KeyCode::Char('q') => Action::TogglePlan,
+        KeyCode::Enter => Action::PlanSubmit,
+        KeyCode::Backspace => Action::PlanBackspace,
+        KeyCode::Char(c) => Action::PlanChar(c),
+        _ => Action::None,
+    }
+}
+
 pub struct KeyBinding {
     pub key: &'static str,
     pub description: &'static str,
@@ -199,6 +228,14 @@ pub const KEY_BINDINGS: &[KeyBinding] = &[
         key: keys::KILL,
         description: "Kill selected process",
     },
+    KeyBinding {
+        key: keys::PROCESS_ACTIONS,
+        description: "Suspend, resume or reprioritize selected process",
+    },
+    KeyBinding {
+        key: keys::UNDO_SAVER,
+        description: "Undo battery saver (resume stopped processes)",
+    },
     KeyBinding {
         key: keys::GRAPH,
         description: "Toggle graph metric",
@@ -255,6 +292,10 @@ pub const KEY_BINDINGS: &[KeyBinding] = &[
         key: keys::BATTERY_DETAILS,
         description: "Battery details",
     },
+    KeyBinding {
+        key: keys::PLAN,
+        description: "Plan: will the battery last until a given time?",
+    },
     KeyBinding {
         key: keys::QUIT,
         description: "Quit",
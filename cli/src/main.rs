@@ -12,10 +12,11 @@ mod ui;
 
 use app::run_tui;
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 
 use cli::{Cli, Commands};
 use config::{ensure_dirs, LogLevel, UserConfig};
+use daemon::{DaemonClient, DaemonTarget};
 use data::BatteryData;
 use logging::LogMode;
 
@@ -26,6 +27,18 @@ fn require_battery() {
     }
 }
 
+/// Builds a remote target and checks it is reachable before the TUI takes
+/// over the terminal, so connection and auth errors are printed plainly.
+fn remote_target(addr: String, token: Option<String>) -> Result<DaemonTarget> {
+    let token = token
+        .or_else(|| std::env::var("JOLT_TOKEN").ok())
+        .ok_or_else(|| eyre!("--connect requires --token or JOLT_TOKEN"))?;
+    let target = DaemonTarget::Remote { addr, token };
+    DaemonClient::connect_with_version_check(&target)
+        .map_err(|e| eyre!("Failed to connect to remote daemon: {}", e))?;
+    Ok(target)
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     let _ = ensure_dirs();
@@ -64,11 +77,48 @@ fn main() -> Result<()> {
             let _guard = logging::init(config.log_level, LogMode::Stderr, log_level_override);
             commands::history::run(command)
         }
+        Some(Commands::Annotate { text, at }) => {
+            let _guard = logging::init(config.log_level, LogMode::Stderr, log_level_override);
+            commands::annotate::run(text, at)
+        }
+        Some(Commands::Forecast {
+            backtest,
+            days,
+            capacity,
+            window,
+        }) => {
+            let _guard = logging::init(config.log_level, LogMode::Stderr, log_level_override);
+            commands::forecast::run(backtest, days, capacity, window)
+        }
+        Some(Commands::Plan { until, capacity }) => {
+            let _guard = logging::init(config.log_level, LogMode::Stderr, log_level_override);
+            commands::plan::run(&until, capacity)
+        }
+        Some(Commands::Throttle {
+            target,
+            cpu,
+            battery_only,
+            remove,
+        }) => {
+            let _guard = logging::init(config.log_level, LogMode::Stderr, log_level_override);
+            commands::throttle::run(target, cpu, battery_only, remove)
+        }
         Some(Commands::Logs { lines, follow }) => commands::logs::run(lines, follow),
-        Some(Commands::Ui) | None => {
+        Some(Commands::Ui { connect, token }) => {
+            let target = match connect {
+                Some(addr) => remote_target(addr, token)?,
+                None => {
+                    require_battery();
+                    DaemonTarget::Local
+                }
+            };
+            let _guard = logging::init(config.log_level, LogMode::File, log_level_override);
+            run_tui(config, target)
+        }
+        None => {
             require_battery();
             let _guard = logging::init(config.log_level, LogMode::File, log_level_override);
-            run_tui(config)
+            run_tui(config, DaemonTarget::Local)
         }
     }
 }
@@ -79,7 +129,11 @@ mod tests {
     use clap::CommandFactory;
 
     use crate::cli::{DaemonCommands, HistoryCommands, ThemeCommands};
-    use crate::commands::history::{escape_csv, get_date_range};
+    use crate::commands::annotate::parse_time;
+    use crate::commands::history::{get_date_range, parse_downsample};
+    use crate::daemon::DownsampleMethod;
+    use crate::data::export::escape_csv;
+    use crate::data::DayZone;
     use crate::ui::utils::truncate_str;
 
     #[test]
@@ -97,7 +151,38 @@ mod tests {
     #[test]
     fn cli_parse_ui_command() {
         let cli = Cli::try_parse_from(["jolt", "ui"]).unwrap();
-        assert!(matches!(cli.command, Some(Commands::Ui)));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Ui {
+                connect: None,
+                token: None
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parse_ui_connect_with_token() {
+        let cli = Cli::try_parse_from([
+            "jolt",
+            "ui",
+            "--connect",
+            "buildbox:8617",
+            "--token",
+            "s3cret",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Ui { connect, token }) => {
+                assert_eq!(connect.as_deref(), Some("buildbox:8617"));
+                assert_eq!(token.as_deref(), Some("s3cret"));
+            }
+            _ => panic!("Expected Ui command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_ui_token_requires_connect() {
+        assert!(Cli::try_parse_from(["jolt", "ui", "--token", "s3cret"]).is_err());
     }
 
     #[test]
@@ -167,6 +252,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parse_daemon_config_set() {
+        let cli = Cli::try_parse_from([
+            "jolt",
+            "daemon",
+            "config",
+            "history.sample_interval_secs",
+            "30",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Daemon {
+                command: DaemonCommands::Config { key, value },
+            }) => {
+                assert_eq!(key.as_deref(), Some("history.sample_interval_secs"));
+                assert_eq!(value.as_deref(), Some("30"));
+            }
+            _ => panic!("Expected Daemon config command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_annotate_with_time() {
+        let cli = Cli::try_parse_from([
+            "jolt",
+            "annotate",
+            "started gradle build",
+            "--at",
+            "2024-01-01T09:30:00Z",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Annotate { text, at }) => {
+                assert_eq!(text, "started gradle build");
+                assert_eq!(parse_time(at.as_deref().unwrap()).unwrap(), 1704101400);
+            }
+            _ => panic!("Expected Annotate command"),
+        }
+    }
+
+    #[test]
+    fn parse_time_accepts_unix_seconds_and_rejects_garbage() {
+        assert_eq!(parse_time("1704067200").unwrap(), 1704067200);
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn cli_parse_throttle() {
+        let cli = Cli::try_parse_from([
+            "jolt",
+            "throttle",
+            "firefox",
+            "--cpu",
+            "25%",
+            "--battery-only",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Throttle {
+                target,
+                cpu,
+                battery_only,
+                remove,
+            }) => {
+                assert_eq!(target.as_deref(), Some("firefox"));
+                assert_eq!(cpu, Some(25));
+                assert!(battery_only);
+                assert!(!remove);
+            }
+            _ => panic!("Expected Throttle command"),
+        }
+
+        let cli = Cli::try_parse_from(["jolt", "throttle", "4242", "--remove"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Throttle {
+                remove: true,
+                cpu: None,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["jolt", "throttle", "4242", "--cpu", "0"]).is_err());
+        assert!(Cli::try_parse_from(["jolt", "throttle", "4242", "--cpu", "lots"]).is_err());
+        assert!(Cli::try_parse_from(["jolt", "throttle", "1", "--remove", "--cpu", "5"]).is_err());
+    }
+
     #[test]
     fn cli_parse_theme_check_all() {
         let cli = Cli::try_parse_from(["jolt", "theme", "check", "--all"]).unwrap();
@@ -214,6 +385,252 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parse_history_process_defaults_to_one_day() {
+        let cli = Cli::try_parse_from(["jolt", "history", "process", "Google Chrome"]).unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command: Some(HistoryCommands::Process { name, hours }),
+            }) => {
+                assert_eq!(name, "Google Chrome");
+                assert_eq!(hours, 24);
+            }
+            _ => panic!("Expected History process command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_history_import_upower_default_dir() {
+        let cli = Cli::try_parse_from(["jolt", "history", "import", "--upower"]).unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command:
+                    Some(HistoryCommands::Import {
+                        upower,
+                        device: None,
+                    }),
+            }) => {
+                assert_eq!(upower, Some(std::path::PathBuf::from("/var/lib/upower")));
+            }
+            _ => panic!("Expected History import command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["jolt", "history", "import", "--upower", "/tmp/up"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::History {
+                command: Some(HistoryCommands::Import { upower: Some(dir), .. }),
+            }) if dir == std::path::Path::new("/tmp/up")
+        ));
+
+        assert!(Cli::try_parse_from(["jolt", "history", "import"]).is_err());
+    }
+
+    #[test]
+    fn cli_parse_history_merge_requires_source() {
+        let cli = Cli::try_parse_from([
+            "jolt", "history", "merge", "other.db", "--source", "laptop2",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command: Some(HistoryCommands::Merge { file, source }),
+            }) => {
+                assert_eq!(file, std::path::PathBuf::from("other.db"));
+                assert_eq!(source, "laptop2");
+            }
+            _ => panic!("Expected History merge command"),
+        }
+
+        assert!(Cli::try_parse_from(["jolt", "history", "merge", "other.db"]).is_err());
+    }
+
+    #[test]
+    fn cli_parse_history_backup_restore_and_summary_source() {
+        let cli = Cli::try_parse_from(["jolt", "history", "restore", "b.db", "-y"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::History {
+                command: Some(HistoryCommands::Restore { file, yes: true }),
+            }) if file == std::path::Path::new("b.db")
+        ));
+
+        assert!(Cli::try_parse_from(["jolt", "history", "backup"]).is_err());
+
+        let cli =
+            Cli::try_parse_from(["jolt", "history", "summary", "--source", "laptop2"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::History {
+                command: Some(HistoryCommands::Summary { source: Some(s), .. }),
+            }) if s == "laptop2"
+        ));
+    }
+
+    #[test]
+    fn cli_parse_history_doctor_rebuild() {
+        let cli =
+            Cli::try_parse_from(["jolt", "history", "doctor", "--rebuild-aggregates"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::History {
+                command: Some(HistoryCommands::Doctor {
+                    rebuild_aggregates: true,
+                    yes: false,
+                }),
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parse_history_query_defaults_to_table() {
+        let cli = Cli::try_parse_from(["jolt", "history", "query", "SELECT 1"]).unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command: Some(HistoryCommands::Query { sql, format }),
+            }) => {
+                assert_eq!(sql, "SELECT 1");
+                assert_eq!(format, "table");
+            }
+            _ => panic!("Expected History query command"),
+        }
+
+        assert!(Cli::try_parse_from(["jolt", "history", "query"]).is_err());
+    }
+
+    #[test]
+    fn cli_parse_forecast_backtest() {
+        let cli =
+            Cli::try_parse_from(["jolt", "forecast", "--backtest", "--capacity", "52.6"]).unwrap();
+        match cli.command {
+            Some(Commands::Forecast {
+                backtest,
+                days,
+                capacity,
+                window,
+            }) => {
+                assert!(backtest);
+                assert_eq!(days, 14);
+                assert_eq!(capacity, Some(52.6));
+                assert_eq!(window, None);
+            }
+            _ => panic!("Expected Forecast command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_plan_until() {
+        let cli = Cli::try_parse_from(["jolt", "plan", "--until", "17:30"]).unwrap();
+        match cli.command {
+            Some(Commands::Plan { until, capacity }) => {
+                assert_eq!(until, "17:30");
+                assert_eq!(capacity, None);
+            }
+            _ => panic!("Expected Plan command"),
+        }
+        assert!(Cli::try_parse_from(["jolt", "plan"]).is_err());
+    }
+
+    #[test]
+    fn cli_parse_history_prune_max_size_dry_run() {
+        let cli =
+            Cli::try_parse_from(["jolt", "history", "prune", "--max-size", "200", "--dry-run"])
+                .unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command:
+                    Some(HistoryCommands::Prune {
+                        older_than,
+                        max_size,
+                        dry_run,
+                        yes,
+                    }),
+            }) => {
+                assert_eq!(older_than, None);
+                assert_eq!(max_size, Some(200));
+                assert!(dry_run);
+                assert!(!yes);
+            }
+            _ => panic!("Expected History prune command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_history_export_downsample() {
+        let cli = Cli::try_parse_from([
+            "jolt",
+            "history",
+            "export",
+            "--include-samples",
+            "--max-points",
+            "500",
+            "--downsample",
+            "min_max",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command:
+                    Some(HistoryCommands::Export {
+                        max_points,
+                        downsample,
+                        ..
+                    }),
+            }) => {
+                assert_eq!(max_points, 500);
+                assert_eq!(parse_downsample(&downsample), DownsampleMethod::MinMax);
+            }
+            _ => panic!("Expected History export command"),
+        }
+    }
+
+    #[test]
+    fn cli_parse_history_export_max_points_requires_samples() {
+        assert!(Cli::try_parse_from(["jolt", "history", "export", "--max-points", "500"]).is_err());
+    }
+
+    #[test]
+    fn cli_parse_history_export_table_format() {
+        let cli = Cli::try_parse_from([
+            "jolt", "history", "export", "-t", "samples", "-f", "influx", "-o", "-",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::History {
+                command:
+                    Some(HistoryCommands::Export {
+                        table,
+                        format,
+                        output,
+                        ..
+                    }),
+            }) => {
+                assert_eq!(table.as_deref(), Some("samples"));
+                assert_eq!(format.as_deref(), Some("influx"));
+                assert_eq!(output.as_deref(), Some("-"));
+            }
+            _ => panic!("Expected History export command"),
+        }
+
+        assert!(Cli::try_parse_from([
+            "jolt",
+            "history",
+            "export",
+            "--table",
+            "samples",
+            "--include-samples"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn parse_downsample_defaults_to_lttb() {
+        assert_eq!(parse_downsample("lttb"), DownsampleMethod::Lttb);
+        assert_eq!(parse_downsample("MinMax"), DownsampleMethod::MinMax);
+        assert_eq!(parse_downsample("bogus"), DownsampleMethod::Lttb);
+    }
+
     #[test]
     fn cli_parse_global_log_level_before_subcommand() {
         let cli = Cli::try_parse_from(["jolt", "--log-level", "debug", "ui"]).unwrap();
@@ -222,13 +639,13 @@ mod tests {
 
     #[test]
     fn get_date_range_today_returns_same_from_and_to() {
-        let (from, to) = get_date_range("today");
+        let (from, to) = get_date_range("today", DayZone::Local);
         assert_eq!(from, to);
     }
 
     #[test]
     fn get_date_range_returns_yyyy_mm_dd_format() {
-        let (from, _) = get_date_range("today");
+        let (from, _) = get_date_range("today", DayZone::Local);
         assert_eq!(from.len(), 10, "date should be 10 chars: YYYY-MM-DD");
         assert_eq!(from.chars().nth(4), Some('-'));
         assert_eq!(from.chars().nth(7), Some('-'));
@@ -236,45 +653,57 @@ mod tests {
 
     #[test]
     fn get_date_range_week_to_equals_today() {
-        let (from, to) = get_date_range("week");
-        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let (from, to) = get_date_range("week", DayZone::Local);
+        let today = DayZone::Local.today();
         assert_ne!(from, to);
         assert_eq!(to, today);
     }
 
     #[test]
     fn get_date_range_month_to_equals_today() {
-        let (from, to) = get_date_range("month");
-        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let (from, to) = get_date_range("month", DayZone::Local);
+        let today = DayZone::Local.today();
         assert_ne!(from, to);
         assert_eq!(to, today);
     }
 
     #[test]
     fn get_date_range_all_starts_from_2000() {
-        let (from, to) = get_date_range("all");
-        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let (from, to) = get_date_range("all", DayZone::Local);
+        let today = DayZone::Local.today();
         assert_eq!(from, "2000-01-01");
         assert_eq!(to, today);
     }
 
     #[test]
     fn get_date_range_is_case_insensitive() {
-        let (from1, _) = get_date_range("TODAY");
-        let (from2, _) = get_date_range("today");
-        let (from3, _) = get_date_range("Today");
+        let (from1, _) = get_date_range("TODAY", DayZone::Local);
+        let (from2, _) = get_date_range("today", DayZone::Local);
+        let (from3, _) = get_date_range("Today", DayZone::Local);
         assert_eq!(from1, from2);
         assert_eq!(from2, from3);
     }
 
     #[test]
     fn get_date_range_unknown_period_defaults_to_week() {
-        let (from_unknown, to_unknown) = get_date_range("unknown");
-        let (from_week, to_week) = get_date_range("week");
+        let (from_unknown, to_unknown) = get_date_range("unknown", DayZone::Local);
+        let (from_week, to_week) = get_date_range("week", DayZone::Local);
         assert_eq!(from_unknown, from_week);
         assert_eq!(to_unknown, to_week);
     }
 
+    #[test]
+    fn get_date_range_uses_configured_zone() {
+        // 26 hours apart, so the two zones never share a calendar date.
+        let east = DayZone::parse("+14:00").unwrap();
+        let west = DayZone::parse("-12:00").unwrap();
+        let (east_from, east_to) = get_date_range("today", east);
+        let (west_from, west_to) = get_date_range("today", west);
+        assert_eq!(east_from, east_to);
+        assert_eq!(west_from, west_to);
+        assert!(east_from > west_from);
+    }
+
     #[test]
     fn truncate_str_returns_unchanged_when_shorter_than_max() {
         assert_eq!(truncate_str("hello", 10), "hello");
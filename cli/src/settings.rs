@@ -21,6 +21,9 @@ pub enum SettingId {
     EnergyUnit,
     TemperatureUnit,
     DataSizeUnit,
+    // Forecast
+    ForecastEstimator,
+    ChargeLimit,
     // Recording
     BackgroundRecording,
     SampleInterval,
@@ -124,6 +127,15 @@ pub const SETTINGS_LAYOUT: &[SettingsRow] = &[
         id: SettingId::DataSizeUnit,
         label: "Data Size",
     },
+    SettingsRow::Section("Forecast"),
+    SettingsRow::Item {
+        id: SettingId::ForecastEstimator,
+        label: "Estimator",
+    },
+    SettingsRow::Item {
+        id: SettingId::ChargeLimit,
+        label: "Charge Limit (%)",
+    },
     SettingsRow::Section("Recording"),
     SettingsRow::Item {
         id: SettingId::BackgroundRecording,
@@ -171,6 +183,13 @@ pub fn setting_value(app: &App, id: SettingId) -> String {
         SettingId::EnergyUnit => app.config.user_config.units.energy.label().to_string(),
         SettingId::TemperatureUnit => app.config.user_config.units.temperature.label().to_string(),
         SettingId::DataSizeUnit => app.config.user_config.units.data_size.label().to_string(),
+        SettingId::ForecastEstimator => app
+            .config
+            .user_config
+            .forecast_estimator
+            .label()
+            .to_string(),
+        SettingId::ChargeLimit => app.config.user_config.charge_limit_percent.to_string(),
         SettingId::BackgroundRecording => {
             bool_label(app.config.user_config.history.background_recording)
         }
@@ -210,8 +229,40 @@ pub fn setting_value(app: &App, id: SettingId) -> String {
     }
 }
 
+impl SettingId {
+    /// Config key the daemon needs to know about, for settings it applies live.
+    pub const fn daemon_key(&self) -> Option<&'static str> {
+        match self {
+            SettingId::ForecastEstimator => Some("forecast_estimator"),
+            SettingId::ChargeLimit => Some("charge_limit_percent"),
+            SettingId::BackgroundRecording => Some("history.background_recording"),
+            SettingId::SampleInterval => Some("history.sample_interval_secs"),
+            SettingId::RawRetention => Some("history.retention_raw_days"),
+            SettingId::HourlyRetention => Some("history.retention_hourly_days"),
+            SettingId::DailyRetention => Some("history.retention_daily_days"),
+            SettingId::SessionRetention => Some("history.retention_sessions_days"),
+            SettingId::MaxDatabase => Some("history.max_database_mb"),
+            _ => None,
+        }
+    }
+}
+
 /// Apply an input action to a setting. Returns the outcome.
+///
+/// Changes to settings the daemon uses are pushed to it as well.
 pub fn setting_apply(app: &mut App, id: SettingId, input: SettingInput) -> SettingOutcome {
+    let Some(key) = id.daemon_key() else {
+        return apply_setting(app, id, input);
+    };
+    let before = app.config.user_config.get_value(key);
+    let outcome = apply_setting(app, id, input);
+    if app.config.user_config.get_value(key) != before {
+        app.push_config_to_daemon(key);
+    }
+    outcome
+}
+
+fn apply_setting(app: &mut App, id: SettingId, input: SettingInput) -> SettingOutcome {
     match id {
         SettingId::Theme => SettingOutcome { open_modal: true },
         SettingId::Appearance => {
@@ -292,6 +343,21 @@ pub fn setting_apply(app: &mut App, id: SettingId, input: SettingInput) -> Setti
             |a| a.config.user_config.units.data_size,
             |a, v| a.config.user_config.units.data_size = v,
         ),
+        SettingId::ForecastEstimator => apply_cycle(
+            app,
+            input,
+            |a| a.config.user_config.forecast_estimator,
+            |a, v| a.config.user_config.forecast_estimator = v,
+        ),
+        SettingId::ChargeLimit => apply_int(
+            app,
+            input,
+            |a| a.config.user_config.charge_limit_percent as i64,
+            |a, v| a.config.user_config.charge_limit_percent = v as u8,
+            50,
+            100,
+            5,
+        ),
         SettingId::BackgroundRecording => apply_bool(
             app,
             input,
@@ -490,6 +556,15 @@ impl Cyclable for crate::config::DataSizeUnit {
     }
 }
 
+impl Cyclable for crate::config::ForecastEstimator {
+    fn next(&self) -> Self {
+        crate::config::ForecastEstimator::next(self)
+    }
+    fn prev(&self) -> Self {
+        crate::config::ForecastEstimator::prev(self)
+    }
+}
+
 fn apply_cycle<T, G, S>(app: &mut App, input: SettingInput, get: G, set: S) -> SettingOutcome
 where
     T: Cyclable,
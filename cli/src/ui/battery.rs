@@ -8,6 +8,7 @@ use ratatui::{
 
 use crate::app::App;
 use crate::data::battery::ChargeState;
+use crate::data::forecast::{format_duration, ForecastModel};
 use crate::data::power::PowerMode;
 use crate::theme::ThemeColors;
 
@@ -160,8 +161,11 @@ fn render_battery_info_card(
     let (time_label, time_value) = match app.battery.state() {
         ChargeState::Charging => (
             "Full in",
-            app.battery
-                .time_remaining_formatted()
+            app.forecast
+                .charge()
+                .and_then(|c| c.to_full)
+                .map(format_duration)
+                .or_else(|| app.battery.time_remaining_formatted())
                 .unwrap_or_else(|| "—".to_string()),
         ),
         ChargeState::Discharging => (
@@ -192,11 +196,45 @@ fn render_battery_info_card(
         left_spans.push(Span::styled(&time_value, theme.fg_style()));
     }
 
+    if app.battery.state() == ChargeState::Charging {
+        if let Some(charge) = app.forecast.charge() {
+            let mut parts = Vec::new();
+            if let Some(to_80) = charge.to_80 {
+                parts.push(format!("80% in {}", format_duration(to_80)));
+            }
+            if charge.limit_percent < 100.0 {
+                if let Some(to_limit) = charge.to_limit {
+                    parts.push(format!(
+                        "{:.0}% limit in {}",
+                        charge.limit_percent,
+                        format_duration(to_limit)
+                    ));
+                }
+            }
+            parts.push(charge.source.label().to_string());
+            left_spans.push(Span::styled(
+                format!(" ({})", parts.join(", ")),
+                theme.muted_style(),
+            ));
+        }
+    }
+
     if app.battery.state() == ChargeState::Discharging {
         if let Some(forecast) = app.forecast.formatted() {
             left_spans.push(Span::styled("  ", Style::default()));
             left_spans.push(Span::styled("Forecast: ", theme.muted_style()));
             left_spans.push(Span::styled(forecast, theme.success_style()));
+            if let Some(range) = app.forecast.formatted_range() {
+                let estimator = app.forecast.estimator().label();
+                let basis = match app.forecast.model() {
+                    ForecastModel::Window => estimator.to_string(),
+                    model => format!("{}, {}", estimator, model.label()),
+                };
+                left_spans.push(Span::styled(
+                    format!(" ({}, {})", range, basis),
+                    theme.muted_style(),
+                ));
+            }
         }
     }
 
@@ -8,7 +8,7 @@ use ratatui::{
 
 use crate::app::App;
 use crate::config::EnergyUnit;
-use crate::data::{ChargeSession, SessionType};
+use crate::data::{ChargeSession, DayZone, SessionType};
 use crate::theme::ThemeColors;
 
 use super::utils::format_energy_compact;
@@ -129,7 +129,7 @@ pub fn render_recent_sessions(frame: &mut Frame, area: Rect, app: &App, theme: &
         .iter()
         .rev()
         .take(5)
-        .map(|s| session_to_row(s, theme, energy_unit))
+        .map(|s| session_to_row(s, theme, energy_unit, app.day_zone()))
         .collect();
 
     let table = Table::new(
@@ -152,6 +152,7 @@ fn session_to_row(
     session: &ChargeSession,
     theme: &ThemeColors,
     energy_unit: EnergyUnit,
+    zone: DayZone,
 ) -> Row<'static> {
     let type_label = match session.session_type {
         SessionType::Charge => "Charge",
@@ -163,9 +164,7 @@ fn session_to_row(
         SessionType::Discharge => theme.warning,
     };
 
-    let start_time = chrono::DateTime::from_timestamp(session.start_time, 0)
-        .map(|dt| dt.format("%m/%d %H:%M").to_string())
-        .unwrap_or_else(|| "-".to_string());
+    let start_time = zone.format(session.start_time, "%m/%d %H:%M");
 
     let duration = session
         .duration_secs()
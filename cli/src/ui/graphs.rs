@@ -9,6 +9,7 @@ use ratatui::{
 
 use crate::app::App;
 use crate::data::history::HistoryMetric;
+use crate::data::EventKind;
 use crate::theme::ThemeColors;
 
 use super::utils::{
@@ -359,6 +360,7 @@ fn render_single(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors)
     if is_battery && !app.history.battery_changes.is_empty() {
         render_battery_markers(frame, area, app, theme, max_x, min_y, max_y);
     }
+    render_event_markers(frame, area, app, theme, max_x);
 }
 
 fn render_battery_markers(
@@ -393,6 +395,48 @@ fn render_battery_markers(
     }
 }
 
+/// Glyph and style used to mark an event on a time axis.
+pub(super) fn event_marker(kind: EventKind, theme: &ThemeColors) -> (&'static str, Style) {
+    match kind {
+        EventKind::PowerConnected => ("+", theme.success_style()),
+        EventKind::PowerDisconnected => ("-", theme.warning_style()),
+        EventKind::SessionStart | EventKind::SessionEnd => ("S", theme.muted_style()),
+        EventKind::PowerModeChange => ("M", theme.accent_style()),
+        EventKind::DaemonStart | EventKind::DaemonStop => ("D", theme.muted_style()),
+        EventKind::Annotation => ("*", theme.accent_secondary_style()),
+        EventKind::Alert => ("!", theme.danger_style()),
+        EventKind::BatterySaver => ("B", theme.warning_style()),
+    }
+}
+
+/// Draws event glyphs along the top edge of the plot, aligned with the
+/// sample recorded closest to when each event happened.
+fn render_event_markers(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors, max_x: f64) {
+    let inner = Rect::new(
+        area.x + 8,
+        area.y + 1,
+        area.width.saturating_sub(10),
+        area.height.saturating_sub(3),
+    );
+    if inner.height == 0 {
+        return;
+    }
+
+    for event in &app.recent_events {
+        let Some(index) = app.history.index_at(event.timestamp) else {
+            continue;
+        };
+        let x = inner.x + (index as f64 / max_x * inner.width as f64) as u16;
+        if x < inner.x + inner.width {
+            let (glyph, style) = event_marker(event.kind, theme);
+            frame.render_widget(
+                Paragraph::new(glyph).style(style),
+                Rect::new(x, inner.y, 1, 1),
+            );
+        }
+    }
+}
+
 fn render_merged(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors) {
     let bg = theme.bg_color(app.config.user_config.transparent_background);
     let power_val = app
@@ -516,6 +560,7 @@ fn render_merged(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors)
         .style(Style::default().bg(bg));
 
     frame.render_widget(chart, area);
+    render_event_markers(frame, area, app, theme, max_x);
 }
 
 fn render_split(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors) {
@@ -6,13 +6,14 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::App;
-use crate::daemon::KillSignal;
+use crate::app::{App, ProcessMenuItem, PROCESS_ACTIONS};
+use crate::daemon::{KillScope, KillSignal};
+use crate::data::ProcessState;
 use crate::input::KEY_BINDINGS;
 use crate::theme::ThemeColors;
 use crate::ui::VERSION;
 
-use super::utils::centered_rect;
+use super::utils::{centered_rect, truncate_str};
 
 pub fn render_help(frame: &mut Frame, app: &App, theme: &ThemeColors) {
     let legend_lines = 6;
@@ -124,13 +125,28 @@ pub fn render_help(frame: &mut Frame, app: &App, theme: &ThemeColors) {
     frame.render_widget(legend_text, chunks[2]);
 }
 
+/// Most processes the kill dialog lists before summarizing the rest
+const MAX_LISTED_TARGETS: usize = 8;
+
 pub fn render_kill_confirm(frame: &mut Frame, app: &App, theme: &ThemeColors) {
-    let area = centered_rect(frame.area(), 54, 16);
+    let targets = app.kill_targets();
+    let listed = if app.kill_scope == KillScope::Process {
+        0
+    } else {
+        // The header, the listed processes and, past the limit, a summary
+        targets.len().min(MAX_LISTED_TARGETS + 1) + 2
+    };
+    let area = centered_rect(frame.area(), 54, 17 + listed as u16);
 
     frame.render_widget(Clear, area);
 
+    let title = match app.kill_scope {
+        KillScope::Process => " Kill Process? ",
+        KillScope::Tree => " Kill Process Tree? ",
+        KillScope::Group => " Kill Process Group? ",
+    };
     let block = Block::default()
-        .title(" Kill Process? ")
+        .title(title)
         .borders(Borders::ALL)
         .border_style(theme.danger_style())
         .style(Style::default().bg(theme.dialog_bg));
@@ -145,24 +161,35 @@ pub fn render_kill_confirm(frame: &mut Frame, app: &App, theme: &ThemeColors) {
         .split(inner)[0];
 
     let content = if let Some(process) = app.process_to_kill() {
+        let subject = match targets.len() {
+            0 | 1 => "Process".to_string(),
+            n => format!("{} processes", n),
+        };
         let (graceful_style, force_style, warning_text) = match app.kill_signal {
             KillSignal::Graceful => (
                 Style::default()
                     .fg(theme.success)
                     .add_modifier(Modifier::BOLD),
                 theme.muted_style(),
-                "Process will be asked to terminate gracefully.",
+                format!("{} will be asked to terminate gracefully.", subject),
             ),
-            KillSignal::Force => (
+            _ => (
                 theme.muted_style(),
                 Style::default()
                     .fg(theme.danger)
                     .add_modifier(Modifier::BOLD),
-                "Process will be forcefully terminated immediately.",
+                format!("{} will be forcefully terminated immediately.", subject),
             ),
         };
+        let scope_style = |scope: KillScope| {
+            if app.kill_scope == scope {
+                theme.accent_style().add_modifier(Modifier::BOLD)
+            } else {
+                theme.muted_style()
+            }
+        };
 
-        vec![
+        let mut content = vec![
             Line::from(""),
             Line::from(vec![
                 Span::styled("Process: ", theme.muted_style()),
@@ -181,6 +208,15 @@ pub fn render_kill_confirm(frame: &mut Frame, app: &App, theme: &ThemeColors) {
                 Span::styled(format!("{:.1}MB", process.memory_mb), theme.fg_style()),
             ]),
             Line::from(""),
+            Line::from(vec![
+                Span::styled("Scope: ", theme.muted_style()),
+                Span::styled(" Process ", scope_style(KillScope::Process)),
+                Span::styled(" | ", theme.muted_style()),
+                Span::styled(" Tree ", scope_style(KillScope::Tree)),
+                Span::styled(" | ", theme.muted_style()),
+                Span::styled(" Group ", scope_style(KillScope::Group)),
+                Span::styled("  [S]", theme.muted_style()),
+            ]),
             Line::from(vec![
                 Span::styled("Signal: ", theme.muted_style()),
                 Span::styled(" Graceful ", graceful_style),
@@ -207,7 +243,31 @@ pub fn render_kill_confirm(frame: &mut Frame, app: &App, theme: &ThemeColors) {
                 ),
                 Span::styled(" Cancel", theme.fg_style()),
             ]),
-        ]
+        ];
+        if listed > 0 {
+            let mut lines = vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    format!("Affects {}:", subject.to_lowercase()),
+                    theme.muted_style(),
+                )),
+            ];
+            for target in targets.iter().take(MAX_LISTED_TARGETS) {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:>7} ", target.pid), theme.muted_style()),
+                    Span::styled(truncate_str(&target.name, 36), theme.fg_style()),
+                ]));
+            }
+            if targets.len() > MAX_LISTED_TARGETS {
+                lines.push(Line::from(Span::styled(
+                    format!("… and {} more", targets.len() - MAX_LISTED_TARGETS),
+                    theme.muted_style(),
+                )));
+            }
+            // Listed after the scope and signal, before the warning
+            content.splice(8..8, lines);
+        }
+        content
     } else {
         vec![Line::from("No process selected")]
     };
@@ -217,6 +277,95 @@ pub fn render_kill_confirm(frame: &mut Frame, app: &App, theme: &ThemeColors) {
     frame.render_widget(paragraph, padded);
 }
 
+pub fn render_process_actions(frame: &mut Frame, app: &App, theme: &ThemeColors) {
+    let area = centered_rect(frame.area(), 44, PROCESS_ACTIONS.len() as u16 + 9);
+
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Process Actions ")
+        .borders(Borders::ALL)
+        .border_style(theme.accent_style())
+        .style(Style::default().bg(theme.dialog_bg));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let padded = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0)])
+        .margin(1)
+        .split(inner)[0];
+
+    let Some(process) = app.process_for_action() else {
+        frame.render_widget(Paragraph::new("No process selected").centered(), padded);
+        return;
+    };
+
+    let state_style = if process.status == ProcessState::Stopped {
+        theme.warning_style()
+    } else {
+        theme.fg_style()
+    };
+    let mut content = vec![
+        Line::from(vec![
+            Span::styled(&process.name, theme.fg_style().add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" ({})", process.pid), theme.muted_style()),
+        ]),
+        Line::from(vec![
+            Span::styled("State: ", theme.muted_style()),
+            Span::styled(state_label(process.status), state_style),
+            Span::styled(
+                if process.throttled {
+                    ", CPU capped"
+                } else {
+                    ""
+                },
+                theme.warning_style(),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    let cpu_percent = app.config.user_config.throttle.cpu_percent;
+    for (i, (item, label)) in PROCESS_ACTIONS.iter().enumerate() {
+        let label = match item {
+            ProcessMenuItem::Throttle => format!("{} at {}%", label, cpu_percent),
+            _ => label.to_string(),
+        };
+        let style = if i == app.process_action_index {
+            Style::default()
+                .fg(theme.selection_fg)
+                .bg(theme.selection_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            theme.fg_style()
+        };
+        content.push(Line::from(Span::styled(format!(" {:<32} ", label), style)));
+    }
+
+    content.push(Line::from(""));
+    content.push(Line::from(vec![
+        Span::styled("[Enter]", theme.accent_style()),
+        Span::styled(" Apply  ", theme.fg_style()),
+        Span::styled("[Esc]", theme.accent_style()),
+        Span::styled(" Cancel", theme.fg_style()),
+    ]));
+
+    frame.render_widget(Paragraph::new(content).centered(), padded);
+}
+
+fn state_label(state: ProcessState) -> &'static str {
+    match state {
+        ProcessState::Running => "running",
+        ProcessState::Sleeping => "sleeping",
+        ProcessState::Idle => "idle",
+        ProcessState::Stopped => "stopped",
+        ProcessState::Zombie => "zombie",
+        ProcessState::Unknown => "unknown",
+    }
+}
+
 pub fn render_about(frame: &mut Frame, _app: &App, theme: &ThemeColors) {
     let area = centered_rect(frame.area(), 60, 16);
 
@@ -9,7 +9,7 @@ use ratatui::{
     Frame,
 };
 
-use super::cycles;
+use super::{cycles, graphs};
 use crate::app::{App, HistoryPeriod};
 use crate::input::keys;
 use crate::theme::ThemeColors;
@@ -147,6 +147,11 @@ fn render_power_chart(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeCol
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    if !app.history_samples.is_empty() {
+        render_sample_chart(frame, inner, app, theme);
+        return;
+    }
+
     if app.history_daily_stats.is_empty() && app.history_hourly_stats.is_empty() {
         let no_data = Paragraph::new(vec![Line::from(vec![Span::styled(
             "No data for this period",
@@ -221,6 +226,114 @@ fn render_power_chart(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeCol
     frame.render_widget(chart, inner);
 }
 
+/// Plots downsampled raw samples against wall-clock time, with a vertical
+/// line at each recorded event and a legend of the most recent ones.
+fn render_sample_chart(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors) {
+    let (area, legend_area) = if app.history_events.is_empty() || area.height < 6 {
+        (area, None)
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area);
+        (chunks[0], Some(chunks[1]))
+    };
+
+    let samples = &app.history_samples;
+    let start = samples.first().map(|s| s.timestamp).unwrap_or(0);
+    let end = samples.last().map(|s| s.timestamp).unwrap_or(start);
+
+    let data_points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|s| (s.timestamp as f64, s.power_watts as f64))
+        .collect();
+
+    let max_power = data_points
+        .iter()
+        .map(|(_, p)| *p)
+        .fold(0.0_f64, f64::max)
+        .max(1.0);
+
+    let event_lines: Vec<(Vec<(f64, f64)>, Style)> = app
+        .history_events
+        .iter()
+        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+        .map(|e| {
+            let x = e.timestamp as f64;
+            let (_, style) = graphs::event_marker(e.kind, theme);
+            (vec![(x, 0.0), (x, max_power * 1.1)], style)
+        })
+        .collect();
+
+    let mut datasets: Vec<Dataset> = event_lines
+        .iter()
+        .map(|(points, style)| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(*style)
+                .data(points)
+        })
+        .collect();
+
+    datasets.push(
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(theme.accent_style())
+            .data(&data_points),
+    );
+
+    let time_format = if app.history_period == HistoryPeriod::Today {
+        "%H:%M"
+    } else {
+        "%m-%d %H:%M"
+    };
+    let zone = app.day_zone();
+    let time_label = |ts: i64| zone.format(ts, time_format);
+
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(theme.border_style())
+                .bounds([start as f64, end.max(start + 1) as f64])
+                .labels(vec![
+                    Span::styled(time_label(start), theme.muted_style()),
+                    Span::styled(time_label(end), theme.muted_style()),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(Span::styled("Watts", theme.muted_style()))
+                .style(theme.border_style())
+                .bounds([0.0, max_power * 1.1])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", max_power / 2.0)),
+                    Span::raw(format!("{:.0}", max_power)),
+                ]),
+        );
+
+    frame.render_widget(chart, area);
+
+    if let Some(legend_area) = legend_area {
+        let mut spans = Vec::new();
+        for event in app.history_events.iter().rev().take(4) {
+            let (glyph, style) = graphs::event_marker(event.kind, theme);
+            spans.push(Span::styled(format!("{} ", glyph), style));
+            spans.push(Span::styled(
+                format!(
+                    "{} {}  ",
+                    time_label(event.timestamp),
+                    truncate_str(&event.message, 32)
+                ),
+                theme.muted_style(),
+            ));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), legend_area);
+    }
+}
+
 fn render_cycle_row(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
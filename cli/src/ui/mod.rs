@@ -4,6 +4,7 @@ mod cycles;
 mod graphs;
 mod help;
 mod history;
+mod plan;
 mod power;
 mod processes;
 mod settings;
@@ -145,11 +146,13 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         AppView::Help => help::render_help(frame, app, &theme),
         AppView::About => help::render_about(frame, app, &theme),
         AppView::KillConfirm => help::render_kill_confirm(frame, app, &theme),
+        AppView::ProcessActions => help::render_process_actions(frame, app, &theme),
         AppView::ThemePicker => theme_picker::render(frame, app, &theme),
         AppView::ThemeImporter => theme_importer::render(frame, app, &theme),
         AppView::History => history::render(frame, app, &theme),
         AppView::Settings => settings::render(frame, app, &theme),
         AppView::BatteryDetails => battery_details::render(frame, app, &theme),
+        AppView::Plan => plan::render(frame, app, &theme),
         AppView::Main => {}
     }
 }
@@ -0,0 +1,137 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::App;
+use crate::data::forecast::format_duration;
+use crate::data::plan::Verdict;
+use crate::theme::ThemeColors;
+
+use super::utils::{centered_rect, truncate_str};
+
+const CUT_LABEL_WIDTH: usize = 30;
+
+pub fn render(frame: &mut Frame, app: &App, theme: &ThemeColors) {
+    let area = centered_rect(frame.area(), 64, 20);
+
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Will I Make It? ")
+        .borders(Borders::ALL)
+        .border_style(theme.accent_style())
+        .style(Style::default().bg(theme.dialog_bg));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let input = if app.plan_input.is_empty() {
+        Span::styled("HH:MM", theme.muted_style())
+    } else {
+        Span::styled(
+            app.plan_input.as_str(),
+            theme.fg_style().add_modifier(Modifier::BOLD),
+        )
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Last until: ", theme.muted_style()),
+            input,
+            Span::styled("▏", theme.accent_style()),
+        ]),
+        Line::from(""),
+    ];
+
+    if let (Some(plan), Some(target)) = (&app.plan, app.plan_target) {
+        let verdict_style = match plan.verdict {
+            Verdict::Yes => theme.success_style(),
+            Verdict::Likely => theme.warning_style(),
+            Verdict::No => theme.danger_style(),
+        };
+        lines.push(Line::from(Span::styled(
+            plan.summary(),
+            verdict_style.add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(vec![
+            Span::styled("Needed: ", theme.muted_style()),
+            Span::styled(
+                format!(
+                    "{} (until {})",
+                    format_duration(plan.needed),
+                    app.day_zone().format(target, "%a %H:%M")
+                ),
+                theme.fg_style(),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("Runtime: ", theme.muted_style()),
+            Span::styled(format_duration(plan.runtime), theme.fg_style()),
+            Span::styled(
+                format!(" (pessimistic {})", format_duration(plan.pessimistic)),
+                theme.muted_style(),
+            ),
+        ]));
+
+        if !plan.cuts.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{:<width$} {:>6} {:>8}  {}",
+                    "What would help",
+                    "Saves",
+                    "Adds",
+                    "Makes it",
+                    width = CUT_LABEL_WIDTH
+                ),
+                theme.muted_style(),
+            )));
+            for cut in &plan.cuts {
+                let style = match cut.verdict {
+                    Verdict::Yes => theme.success_style(),
+                    Verdict::Likely => theme.warning_style(),
+                    Verdict::No => theme.fg_style(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:<width$} {:>5.1}W {:>8}  ",
+                            truncate_str(&cut.label, CUT_LABEL_WIDTH),
+                            cut.watts,
+                            format!("+{}", format_duration(cut.added)),
+                            width = CUT_LABEL_WIDTH
+                        ),
+                        theme.fg_style(),
+                    ),
+                    Span::styled(cut.verdict.label(), style),
+                ]));
+            }
+        }
+    }
+
+    if let Some(message) = &app.plan_message {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            message.as_str(),
+            theme.muted_style(),
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        "Enter to check, Esc to close",
+        theme.muted_style(),
+    )))
+    .centered();
+    frame.render_widget(footer, chunks[1]);
+}
@@ -1,12 +1,13 @@
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::Span,
-    widgets::{Block, Borders, Row, Table},
+    widgets::{Block, Borders, Row, Sparkline, Table},
     Frame,
 };
 
 use crate::app::{App, SortColumn};
+use crate::daemon::HourlyProcessStat;
 use crate::data::ProcessState;
 use crate::theme::ThemeColors;
 
@@ -25,6 +26,8 @@ const COL_KILL: u16 = 4;
 const COL_SPACING: u16 = 12;
 const COL_NAME_MIN: u16 = 12;
 const COL_COMMAND_MIN: u16 = 15;
+const HISTORY_HEIGHT: u16 = 4;
+const HISTORY_HOURS: i64 = 24;
 
 fn energy_gradient_color(energy: f32, theme: &ThemeColors) -> Color {
     let (low_r, low_g, low_b) = extract_rgb(theme.success);
@@ -65,13 +68,30 @@ fn lerp(a: u8, b: u8, t: f32) -> u8 {
 
 pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors) {
     let bg = theme.bg_color(app.config.user_config.transparent_background);
-    let title = if app.selection_mode {
-        " Processes [SELECTION MODE - Esc to exit] "
+    let mode = if app.selection_mode {
+        " [SELECTION MODE - Esc to exit]"
     } else if app.merge_mode {
-        " Processes [MERGED] "
+        " [MERGED]"
     } else {
-        " Processes "
+        ""
     };
+    let all_processes = app.get_visible_processes();
+    let stopped = all_processes
+        .iter()
+        .filter(|(p, depth)| *depth == 0 && p.status == ProcessState::Stopped)
+        .count();
+    let throttled = all_processes
+        .iter()
+        .filter(|(p, depth)| *depth == 0 && p.throttled)
+        .count();
+    let mut title = format!(" Processes{}", mode);
+    if stopped > 0 {
+        title.push_str(&format!(" · {} stopped", stopped));
+    }
+    if throttled > 0 {
+        title.push_str(&format!(" · {} throttled", throttled));
+    }
+    title.push(' ');
 
     let border_color = if app.selection_mode {
         theme.accent
@@ -85,9 +105,20 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors)
         .border_style(Style::default().fg(border_color))
         .style(Style::default().bg(bg));
 
-    let inner = block.inner(area);
+    let mut inner = block.inner(area);
     frame.render_widget(block, area);
 
+    if let Some((name, stats)) = selected_history(app) {
+        if inner.height > HISTORY_HEIGHT * 3 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(HISTORY_HEIGHT)])
+                .split(inner);
+            inner = chunks[0];
+            render_process_history(frame, chunks[1], name, stats, theme);
+        }
+    }
+
     let fixed_width = COL_EXPAND
         + COL_PID
         + COL_STATUS
@@ -160,7 +191,6 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors)
     }))
     .height(1);
 
-    let all_processes = app.get_visible_processes();
     let max_visible = (inner.height.saturating_sub(1)) as usize;
     let visible_processes: Vec<_> = all_processes
         .iter()
@@ -230,6 +260,12 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors)
             let disk_io = format_disk_io(process.disk_read_bytes, process.disk_write_bytes);
             let runtime = format_duration(process.run_time_secs);
             let cpu_time = format_duration(process.total_cpu_time_secs);
+            // Marks processes under a CPU cap
+            let cpu_usage = if process.throttled {
+                format!("{:.1}↓", process.cpu_usage)
+            } else {
+                format!("{:.1}", process.cpu_usage)
+            };
 
             let cells = vec![
                 Span::styled(format!("{}{}", indent, expand_icon), style),
@@ -238,7 +274,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors)
                 Span::styled(format!("{:.1}", process.energy_impact), style),
                 Span::styled(truncate_str(display_name, name_width), style),
                 Span::styled(truncate_str(&process.command_args, command_width), style),
-                Span::styled(format!("{:.1}", process.cpu_usage), style),
+                Span::styled(cpu_usage, style),
                 Span::styled(format_memory(process.memory_mb), style),
                 Span::styled(disk_io, style),
                 Span::styled(runtime, style),
@@ -270,6 +306,60 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App, theme: &ThemeColors)
     frame.render_widget(table, inner);
 }
 
+/// Returns the cached history for the selected process, if it has any rows.
+fn selected_history(app: &App) -> Option<(&str, &[HourlyProcessStat])> {
+    if !app.selection_mode {
+        return None;
+    }
+    let (name, stats) = app.selected_process_history.as_ref()?;
+    let selected = app.get_selected_process()?;
+    (selected.name == *name && !stats.is_empty()).then_some((name.as_str(), stats.as_slice()))
+}
+
+fn render_process_history(
+    frame: &mut Frame,
+    area: Rect,
+    name: &str,
+    stats: &[HourlyProcessStat],
+    theme: &ThemeColors,
+) {
+    let now = chrono::Utc::now().timestamp();
+    let current_hour = now - now.rem_euclid(3600);
+    let first_hour = current_hour - (HISTORY_HOURS - 1) * 3600;
+
+    let mut impact = vec![0u64; HISTORY_HOURS as usize];
+    for stat in stats {
+        if stat.hour_start >= first_hour {
+            let slot = ((stat.hour_start - first_hour) / 3600) as usize;
+            if let Some(value) = impact.get_mut(slot) {
+                *value = (stat.avg_impact * 10.0) as u64;
+            }
+        }
+    }
+
+    let peak_watts = stats.iter().map(|s| s.avg_power).fold(0.0_f32, f32::max);
+    let energy: f32 = stats.iter().map(|s| s.total_energy_wh).sum();
+    let title = format!(
+        " {} · impact last 24h · peak {:.1} W · {:.1} Wh ",
+        truncate_str(name, 24),
+        peak_watts,
+        energy
+    );
+
+    let block = Block::default()
+        .title(Span::styled(title, theme.muted_style()))
+        .borders(Borders::TOP)
+        .border_style(theme.border_style());
+
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(&impact)
+        .max(impact.iter().copied().max().unwrap_or(1).max(1))
+        .style(theme.accent_style());
+
+    frame.render_widget(sparkline, area);
+}
+
 fn format_header(name: &str, col: SortColumn, current: SortColumn, indicator: &str) -> String {
     if col == current {
         format!("{} {}", name, indicator)
@@ -12,6 +12,11 @@ use crate::theme::ThemeColors;
 
 use super::utils::truncate_str;
 
+/// Longest alert message shown in the status bar
+const ALERT_WIDTH: usize = 48;
+/// Longest list of paused processes shown in the status bar
+const SAVER_WIDTH: usize = 32;
+
 pub fn render_title_bar(frame: &mut Frame, area: Rect, app: &App, theme: &ThemeColors) {
     let bg = theme.bg_color(app.config.user_config.transparent_background);
     let version = super::VERSION;
@@ -72,6 +77,30 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App, theme: &Theme
 
     let mut right_spans: Vec<Span> = Vec::new();
 
+    if let Some(alert) = app.active_alert() {
+        right_spans.push(Span::styled(
+            format!("⚠ {} ", truncate_str(&alert.message, ALERT_WIDTH)),
+            theme.danger_style(),
+        ));
+        right_spans.push(Span::styled("│ ", theme.border_style()));
+    }
+
+    if let Some(saver) = app.battery_saver() {
+        let detail = if saver.stopped.is_empty() {
+            "on".to_string()
+        } else {
+            let names: Vec<&str> = saver.stopped.iter().map(|p| p.name.as_str()).collect();
+            format!("paused {}", names.join(", "))
+        };
+        right_spans.push(Span::styled(
+            format!("saver {} ", truncate_str(&detail, SAVER_WIDTH)),
+            theme.warning_style(),
+        ));
+        right_spans.push(Span::styled(keys::UNDO_SAVER, theme.accent_style()));
+        right_spans.push(Span::styled(" undo ", theme.muted_style()));
+        right_spans.push(Span::styled("│ ", theme.border_style()));
+    }
+
     if app.is_reconnecting() {
         right_spans.push(Span::styled("⟳ reconnecting ", theme.warning_style()));
         right_spans.push(Span::styled("│ ", theme.border_style()));
@@ -66,9 +66,7 @@ impl LinuxBattery {
         self.info.voltage_mv = battery.voltage().get::<millivolt>() as u32;
         self.info.health_percent = battery.state_of_health().get::<percent>();
         self.info.cycle_count = battery.cycle_count();
-        self.info.temperature_c = battery
-            .temperature()
-            .map(|t| t.get::<degree_celsius>() as f32);
+        self.info.temperature_c = battery.temperature().map(|t| t.get::<degree_celsius>());
         self.info.time_to_full = battery
             .time_to_full()
             .map(|t| Duration::from_secs(t.get::<second>() as u64));
@@ -112,9 +110,7 @@ impl LinuxBattery {
         let status_path = battery_path.join("status");
         if let Ok(status) = fs::read_to_string(status_path) {
             let status = status.trim();
-            if status.eq_ignore_ascii_case("Not charging") {
-                self.info.state = ChargeState::NotCharging;
-            } else if self.info.external_connected {
+            if status.eq_ignore_ascii_case("Not charging") || self.info.external_connected {
                 self.info.state = ChargeState::NotCharging;
             }
         }
@@ -6,9 +6,13 @@ mod version;
 pub use request::DaemonRequest;
 pub use response::DaemonResponse;
 pub use types::{
-    BatterySnapshot, BatteryState, ChargeSession, ChargingState, CycleSummary, DaemonStatus,
-    DailyCycle, DailyStat, DailyTopProcess, DataSnapshot, ForecastSnapshot, ForecastSource,
-    HourlyStat, KillProcessResult, KillSignal, PowerMode, PowerSnapshot, ProcessSnapshot,
-    ProcessState, Sample, SessionType, SystemSnapshot, SystemStatsSnapshot, MAX_SUBSCRIBERS,
+    Alert, BatterySaverSnapshot, BatterySnapshot, BatteryState, ChargeForecastSnapshot,
+    ChargeForecastSource, ChargeSession, ChargingState, ConfigEntry, CycleSummary, DaemonStatus,
+    DailyCycle, DailyStat, DailyTopProcess, DataSnapshot, DownsampleMethod, Event, EventKind,
+    ForecastEstimator, ForecastModel, ForecastSnapshot, ForecastSource, HourlyProcessStat,
+    HourlyStat, IoClass, KillProcessResult, KillScope, KillSignal, KillTarget, PowerMode,
+    PowerSnapshot, ProcessAction, ProcessSnapshot, ProcessState, Sample, SessionType,
+    StoppedProcess, SystemSnapshot, SystemStatsSnapshot, ThrottleSnapshot, ThrottleTarget,
+    MAX_SUBSCRIBERS,
 };
 pub use version::{MIN_SUPPORTED_VERSION, PROTOCOL_VERSION};
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::KillSignal;
+use crate::types::{DownsampleMethod, KillScope, KillSignal, ProcessAction, ThrottleTarget};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DaemonRequest {
@@ -18,16 +18,43 @@ pub enum DaemonRequest {
         to: String,
         limit: usize,
     },
+    GetProcessHistory {
+        name: String,
+        from: i64,
+        to: i64,
+    },
     GetRecentSamples {
         window_secs: u64,
     },
+    GetSamples {
+        from: i64,
+        to: i64,
+        max_points: usize,
+        #[serde(default)]
+        method: DownsampleMethod,
+    },
     Shutdown,
     Subscribe,
     Unsubscribe,
     GetCurrentData,
+    /// Signals the process, or every process in `scope`; answered with
+    /// `KillResult` for a single process and `KillResults` otherwise
     KillProcess {
         pid: u32,
         signal: KillSignal,
+        #[serde(default)]
+        scope: KillScope,
+    },
+    /// Lists the processes a `KillProcess` with `scope` would signal
+    GetKillTargets {
+        pid: u32,
+        #[serde(default)]
+        scope: KillScope,
+    },
+    /// Suspends, resumes or reprioritizes a process; answered with `KillResult`
+    ProcessAction {
+        pid: u32,
+        action: ProcessAction,
     },
     SetBroadcastInterval {
         interval_ms: u64,
@@ -43,6 +70,37 @@ pub enum DaemonRequest {
         from: String,
         to: String,
     },
+    Authenticate {
+        token: String,
+    },
+    AddAnnotation {
+        text: String,
+        #[serde(default)]
+        timestamp: Option<i64>,
+    },
+    GetEvents {
+        from: i64,
+        to: i64,
+    },
+    ReloadConfig,
+    GetConfig,
+    SetConfig {
+        key: String,
+        value: String,
+    },
+    /// Reverts the battery saver's actions until the next plug-in
+    UndoBatterySaver,
+    /// Caps the CPU time of `target` with a cgroup v2 limit
+    Throttle {
+        target: ThrottleTarget,
+        cpu_percent: u32,
+        /// Only apply the cap on battery power
+        #[serde(default)]
+        battery_only: bool,
+    },
+    RemoveThrottle {
+        target: ThrottleTarget,
+    },
 }
 
 impl DaemonRequest {
@@ -53,4 +111,23 @@ impl DaemonRequest {
     pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(s)
     }
+
+    /// Returns true for requests that change system or daemon state.
+    ///
+    /// Remote listeners reject these unless mutations are explicitly allowed.
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            DaemonRequest::KillProcess { .. }
+                | DaemonRequest::ProcessAction { .. }
+                | DaemonRequest::Shutdown
+                | DaemonRequest::SetBroadcastInterval { .. }
+                | DaemonRequest::AddAnnotation { .. }
+                | DaemonRequest::ReloadConfig
+                | DaemonRequest::SetConfig { .. }
+                | DaemonRequest::UndoBatterySaver
+                | DaemonRequest::Throttle { .. }
+                | DaemonRequest::RemoveThrottle { .. }
+        )
+    }
 }
@@ -1,17 +1,20 @@
 use serde::{Deserialize, Serialize};
 
 use crate::types::{
-    ChargeSession, CycleSummary, DaemonStatus, DailyCycle, DailyStat, DailyTopProcess,
-    DataSnapshot, HourlyStat, KillProcessResult, Sample,
+    Alert, ChargeSession, ConfigEntry, CycleSummary, DaemonStatus, DailyCycle, DailyStat,
+    DailyTopProcess, DataSnapshot, Event, HourlyProcessStat, HourlyStat, KillProcessResult,
+    KillScope, KillTarget, Sample, ThrottleSnapshot,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DaemonResponse {
     Status(DaemonStatus),
     HourlyStats(Vec<HourlyStat>),
+    ProcessHistory(Vec<HourlyProcessStat>),
     DailyStats(Vec<DailyStat>),
     TopProcesses(Vec<DailyTopProcess>),
     RecentSamples(Vec<Sample>),
+    Samples(Vec<Sample>),
     Ok,
     Error(String),
     Subscribed,
@@ -19,10 +22,19 @@ pub enum DaemonResponse {
     DataUpdate(DataSnapshot),
     CurrentData(DataSnapshot),
     KillResult(KillProcessResult),
-    SubscriptionRejected { reason: String },
+    /// One result per process a kill signalled
+    KillResults(Vec<KillProcessResult>),
+    KillTargets(Vec<KillTarget>),
+    SubscriptionRejected {
+        reason: String,
+    },
     CycleSummary(CycleSummary),
     ChargeSessions(Vec<ChargeSession>),
     DailyCycles(Vec<DailyCycle>),
+    Config(Vec<ConfigEntry>),
+    Events(Vec<Event>),
+    Alert(Alert),
+    Throttle(ThrottleSnapshot),
 }
 
 impl DaemonResponse {
@@ -33,4 +45,14 @@ impl DaemonResponse {
     pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(s)
     }
+
+    /// Answers a `KillProcess`. A single-process kill is answered with
+    /// `KillResult`, which is all v2 clients understand.
+    pub fn kill(scope: KillScope, mut results: Vec<KillProcessResult>) -> Self {
+        if scope == KillScope::Process && results.len() == 1 {
+            DaemonResponse::KillResult(results.remove(0))
+        } else {
+            DaemonResponse::KillResults(results)
+        }
+    }
 }
@@ -41,6 +41,9 @@ pub enum KillSignal {
     Graceful,
     #[default]
     Force,
+    /// Pauses the process until it is sent `Continue`
+    Stop,
+    Continue,
 }
 
 impl KillSignal {
@@ -48,10 +51,97 @@ impl KillSignal {
         match self {
             KillSignal::Graceful => "-15",
             KillSignal::Force => "-9",
+            KillSignal::Stop => "-STOP",
+            KillSignal::Continue => "-CONT",
         }
     }
 }
 
+/// Which processes a kill reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KillScope {
+    /// Only the named process
+    #[default]
+    Process,
+    /// The process and all of its descendants
+    Tree,
+    /// Every process in the process's process group
+    Group,
+}
+
+impl KillScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KillScope::Process => "process",
+            KillScope::Tree => "tree",
+            KillScope::Group => "group",
+        }
+    }
+}
+
+/// A change to a running process short of ending it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessAction {
+    /// Suspends the process with SIGSTOP
+    Stop,
+    /// Resumes a stopped process with SIGCONT
+    Continue,
+    /// Sets the CPU scheduling priority, from -20 (highest) to 19 (lowest)
+    Renice { nice: i32 },
+    /// Sets the IO scheduling class (Linux only)
+    Ionice { class: IoClass },
+}
+
+impl std::fmt::Display for ProcessAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessAction::Stop => write!(f, "stop"),
+            ProcessAction::Continue => write!(f, "continue"),
+            ProcessAction::Renice { nice } => write!(f, "renice to {}", nice),
+            ProcessAction::Ionice { class } => write!(f, "ionice to {}", class.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoClass {
+    /// Only gets disk time when no other process wants it
+    Idle,
+    /// The default class
+    BestEffort,
+}
+
+impl IoClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IoClass::Idle => "idle",
+            IoClass::BestEffort => "best-effort",
+        }
+    }
+
+    /// Class number for `ionice -c`.
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            IoClass::Idle => "3",
+            IoClass::BestEffort => "2",
+        }
+    }
+}
+
+/// How the daemon reduces a sample range to at most `max_points` points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DownsampleMethod {
+    /// Largest-Triangle-Three-Buckets on power, preserving visual shape.
+    #[default]
+    Lttb,
+    /// Minimum and maximum power sample from each bucket, preserving peaks.
+    MinMax,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[repr(i32)]
 pub enum ChargingState {
@@ -62,6 +152,29 @@ pub enum ChargingState {
     Unknown = 3,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    DaemonStart,
+    DaemonStop,
+    PowerConnected,
+    PowerDisconnected,
+    SessionStart,
+    SessionEnd,
+    PowerModeChange,
+    #[default]
+    Annotation,
+    Alert,
+    BatterySaver,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: i64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[repr(i32)]
 pub enum SessionType {
@@ -147,30 +260,83 @@ pub enum ForecastSource {
     None,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ForecastModel {
+    /// Recent power average held constant
+    #[default]
+    Window,
+    /// Recent average blended into the weekday/hour usage profile
+    Profile,
+}
+
+/// How the current discharge rate was estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ForecastEstimator {
+    #[default]
+    Window,
+    Ewma,
+    Kalman,
+    Slope,
+}
+
+/// Where a charge forecast came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargeForecastSource {
+    /// Fitted from the machine's past charge sessions
+    #[default]
+    Learned,
+    /// The OS time-to-full
+    Os,
+}
+
+/// Time to each charge milestone while charging
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChargeForecastSnapshot {
+    pub to_80_secs: Option<u64>,
+    pub to_limit_secs: Option<u64>,
+    pub to_full_secs: Option<u64>,
+    pub limit_percent: f32,
+    pub source: ChargeForecastSource,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ForecastSnapshot {
+    /// Median (P50) runtime
     pub duration_secs: Option<u64>,
     pub avg_power_watts: Option<f32>,
     pub sample_count: usize,
     pub source: ForecastSource,
+    /// Pessimistic (P10) runtime
+    #[serde(default)]
+    pub p10_secs: Option<u64>,
+    /// Optimistic (P90) runtime
+    #[serde(default)]
+    pub p90_secs: Option<u64>,
+    #[serde(default)]
+    pub model: ForecastModel,
+    #[serde(default)]
+    pub estimator: ForecastEstimator,
+    /// Only set while charging
+    #[serde(default)]
+    pub charge: Option<ChargeForecastSnapshot>,
 }
 
 impl ForecastSnapshot {
     pub fn formatted(&self) -> Option<String> {
-        self.duration_secs.map(|secs| {
-            let total_mins = secs / 60;
-            if total_mins == 0 {
-                return "< 1m".to_string();
-            }
-            let hours = total_mins / 60;
-            let mins = total_mins % 60;
-
-            if hours > 0 {
-                format!("{}h {}m", hours, mins)
-            } else {
-                format!("{}m", mins)
-            }
-        })
+        self.duration_secs.map(format_duration)
+    }
+
+    /// P10 to P90 range, e.g. "1h 50m–3h 10m"
+    pub fn formatted_range(&self) -> Option<String> {
+        let (low, high) = (self.p10_secs?, self.p90_secs?);
+        Some(format!(
+            "{}–{}",
+            format_duration(low),
+            format_duration(high)
+        ))
     }
 
     pub fn has_forecast(&self) -> bool {
@@ -178,6 +344,21 @@ impl ForecastSnapshot {
     }
 }
 
+fn format_duration(secs: u64) -> String {
+    let total_mins = secs / 60;
+    if total_mins == 0 {
+        return "< 1m".to_string();
+    }
+    let hours = total_mins / 60;
+    let mins = total_mins % 60;
+
+    if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else {
+        format!("{}m", mins)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessSnapshot {
     pub pid: u32,
@@ -196,6 +377,9 @@ pub struct ProcessSnapshot {
     pub status: ProcessState,
     pub run_time_secs: u64,
     pub total_cpu_time_secs: u64,
+    /// In one of the daemon's CPU throttle groups
+    #[serde(default)]
+    pub throttled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -208,6 +392,8 @@ pub struct DataSnapshot {
     pub system: SystemSnapshot,
     pub system_stats: SystemStatsSnapshot,
     pub forecast: ForecastSnapshot,
+    pub battery_saver: BatterySaverSnapshot,
+    pub throttles: Vec<ThrottleSnapshot>,
 }
 
 impl Default for DataSnapshot {
@@ -220,10 +406,33 @@ impl Default for DataSnapshot {
             system: SystemSnapshot::default(),
             system_stats: SystemStatsSnapshot::default(),
             forecast: ForecastSnapshot::default(),
+            battery_saver: BatterySaverSnapshot::default(),
+            throttles: Vec::new(),
         }
     }
 }
 
+/// A process the battery saver paused with SIGSTOP
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoppedProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// What the daemon's battery saver currently has applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BatterySaverSnapshot {
+    pub active: bool,
+    /// When the saver kicked in
+    pub since: Option<i64>,
+    /// The power profile was switched to low power
+    pub low_power: bool,
+    pub stopped: Vec<StoppedProcess>,
+    /// Broadcast interval the saver slowed updates to
+    pub broadcast_interval_ms: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonStatus {
     pub running: bool,
@@ -255,6 +464,72 @@ impl Default for DaemonStatus {
     }
 }
 
+/// A threshold alert rule that fired
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub timestamp: i64,
+    /// Rule name, or the condition if the rule has none
+    pub name: String,
+    /// Condition as written in the config, e.g. `health < 80`
+    pub rule: String,
+    pub message: String,
+    pub value: f32,
+    pub threshold: f32,
+}
+
+/// What a throttle caps: one process, or every process with a binary name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleTarget {
+    Pid(u32),
+    Name(String),
+}
+
+impl ThrottleTarget {
+    /// Reads a pid if `value` is numeric and a process name otherwise.
+    pub fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(pid) => ThrottleTarget::Pid(pid),
+            Err(_) => ThrottleTarget::Name(value.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ThrottleTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThrottleTarget::Pid(pid) => write!(f, "{}", pid),
+            ThrottleTarget::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// A CPU cap the daemon keeps on a process or group of processes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleSnapshot {
+    pub target: ThrottleTarget,
+    /// Processes in the throttle's cgroup
+    pub pids: Vec<u32>,
+    /// Cap as a percentage of one CPU
+    pub cpu_percent: u32,
+    /// The cap only applies on battery power
+    pub battery_only: bool,
+    /// The cap is in force right now
+    pub active: bool,
+    pub since: i64,
+    /// Combined energy impact of the processes when they were throttled
+    pub energy_before: f32,
+    /// Average combined energy impact while capped
+    pub energy_after: Option<f32>,
+}
+
+/// A process a kill would reach.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KillTarget {
+    pub pid: u32,
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KillProcessResult {
     pub pid: u32,
@@ -262,6 +537,14 @@ pub struct KillProcessResult {
     pub error: Option<String>,
 }
 
+/// A single config setting as a dotted key and a TOML-encoded value,
+/// e.g. `history.sample_interval_secs` = `60`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CycleSummary {
     pub total_cycles_macos: u32,
@@ -284,6 +567,18 @@ pub struct Sample {
     pub charging_state: ChargingState,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyProcessStat {
+    pub hour_start: i64,
+    pub process_name: String,
+    pub avg_impact: f32,
+    pub avg_cpu: f32,
+    pub avg_memory_mb: f32,
+    pub avg_power: f32,
+    pub total_energy_wh: f32,
+    pub sample_count: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HourlyStat {
     pub hour_start: i64,
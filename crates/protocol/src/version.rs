@@ -6,6 +6,7 @@
 //! |---------|---------|
 //! | 1 | Initial protocol version |
 //! | 2 | Added `os_name` to SystemSnapshot, forecast fields |
+//! | 3 | `KillProcess` takes a `scope`; tree and group kills are answered with `KillResults` |
 //!
 //! # Breaking Changes (require PROTOCOL_VERSION bump)
 //!
@@ -30,8 +31,8 @@
 //! 3. Only bump `MIN_SUPPORTED_VERSION` when dropping support for old versions
 
 /// Current protocol version. Bump when making breaking changes.
-pub const PROTOCOL_VERSION: u32 = 2;
+pub const PROTOCOL_VERSION: u32 = 3;
 
 /// Minimum protocol version this build can communicate with.
 /// Kept at N-1 to allow one version of backwards compatibility.
-pub const MIN_SUPPORTED_VERSION: u32 = 1;
+pub const MIN_SUPPORTED_VERSION: u32 = 2;
@@ -101,6 +101,7 @@ fn sample_process_snapshot() -> ProcessSnapshot {
             status: ProcessState::Running,
             run_time_secs: 3600,
             total_cpu_time_secs: 120,
+            throttled: false,
         }]),
         is_killable: true,
         disk_read_bytes: 10000,
@@ -108,6 +109,7 @@ fn sample_process_snapshot() -> ProcessSnapshot {
         status: ProcessState::Running,
         run_time_secs: 7200,
         total_cpu_time_secs: 600,
+        throttled: true,
     }
 }
 
@@ -140,6 +142,11 @@ fn sample_forecast_snapshot() -> ForecastSnapshot {
         avg_power_watts: Some(12.5),
         sample_count: 30,
         source: ForecastSource::Daemon,
+        p10_secs: Some(14400),
+        p90_secs: Some(21600),
+        model: ForecastModel::Profile,
+        estimator: ForecastEstimator::Kalman,
+        charge: None,
     }
 }
 
@@ -152,6 +159,34 @@ fn sample_data_snapshot() -> DataSnapshot {
         system: sample_system_snapshot(),
         system_stats: sample_system_stats_snapshot(),
         forecast: sample_forecast_snapshot(),
+        battery_saver: sample_battery_saver_snapshot(),
+        throttles: vec![sample_throttle_snapshot()],
+    }
+}
+
+fn sample_battery_saver_snapshot() -> BatterySaverSnapshot {
+    BatterySaverSnapshot {
+        active: true,
+        since: Some(1704066000),
+        low_power: true,
+        stopped: vec![StoppedProcess {
+            pid: 4321,
+            name: "mds_stores".to_string(),
+        }],
+        broadcast_interval_ms: Some(5000),
+    }
+}
+
+fn sample_throttle_snapshot() -> ThrottleSnapshot {
+    ThrottleSnapshot {
+        target: ThrottleTarget::Name("Safari".to_string()),
+        pids: vec![1234, 1235],
+        cpu_percent: 25,
+        battery_only: true,
+        active: true,
+        since: 1704066000,
+        energy_before: 35.0,
+        energy_after: Some(12.5),
     }
 }
 
@@ -182,6 +217,19 @@ fn sample_hourly_stat() -> HourlyStat {
     }
 }
 
+fn sample_hourly_process_stat() -> HourlyProcessStat {
+    HourlyProcessStat {
+        hour_start: 1704067200,
+        process_name: "Google Chrome".to_string(),
+        avg_impact: 42.0,
+        avg_cpu: 35.5,
+        avg_memory_mb: 1024.0,
+        avg_power: 15.2,
+        total_energy_wh: 15.2,
+        sample_count: 60,
+    }
+}
+
 fn sample_daily_stat() -> DailyStat {
     DailyStat {
         date: "2024-01-01".to_string(),
@@ -270,12 +318,9 @@ fn sample_kill_result() -> KillProcessResult {
     }
 }
 
-#[test]
-fn generate_request_fixtures() {
-    let dir = requests_dir();
-    fs::create_dir_all(&dir).unwrap();
-
-    let requests: Vec<(&str, DaemonRequest)> = vec![
+/// One or more samples of every request variant.
+fn sample_requests() -> Vec<(&'static str, DaemonRequest)> {
+    vec![
         ("get_status", DaemonRequest::GetStatus),
         (
             "get_hourly_stats",
@@ -299,10 +344,36 @@ fn generate_request_fixtures() {
                 limit: 10,
             },
         ),
+        (
+            "get_process_history",
+            DaemonRequest::GetProcessHistory {
+                name: "Google Chrome".to_string(),
+                from: 1704067200,
+                to: 1704153600,
+            },
+        ),
         (
             "get_recent_samples",
             DaemonRequest::GetRecentSamples { window_secs: 300 },
         ),
+        (
+            "get_samples_lttb",
+            DaemonRequest::GetSamples {
+                from: 1704067200,
+                to: 1704672000,
+                max_points: 400,
+                method: DownsampleMethod::Lttb,
+            },
+        ),
+        (
+            "get_samples_min_max",
+            DaemonRequest::GetSamples {
+                from: 1704067200,
+                to: 1704672000,
+                max_points: 400,
+                method: DownsampleMethod::MinMax,
+            },
+        ),
         ("shutdown", DaemonRequest::Shutdown),
         ("subscribe", DaemonRequest::Subscribe),
         ("unsubscribe", DaemonRequest::Unsubscribe),
@@ -312,6 +383,7 @@ fn generate_request_fixtures() {
             DaemonRequest::KillProcess {
                 pid: 1234,
                 signal: KillSignal::Force,
+                scope: KillScope::Process,
             },
         ),
         (
@@ -319,6 +391,75 @@ fn generate_request_fixtures() {
             DaemonRequest::KillProcess {
                 pid: 1234,
                 signal: KillSignal::Graceful,
+                scope: KillScope::Process,
+            },
+        ),
+        (
+            "kill_process_stop",
+            DaemonRequest::KillProcess {
+                pid: 1234,
+                signal: KillSignal::Stop,
+                scope: KillScope::Process,
+            },
+        ),
+        (
+            "kill_process_tree",
+            DaemonRequest::KillProcess {
+                pid: 1234,
+                signal: KillSignal::Graceful,
+                scope: KillScope::Tree,
+            },
+        ),
+        (
+            "get_kill_targets_group",
+            DaemonRequest::GetKillTargets {
+                pid: 1234,
+                scope: KillScope::Group,
+            },
+        ),
+        (
+            "process_action_stop",
+            DaemonRequest::ProcessAction {
+                pid: 1234,
+                action: ProcessAction::Stop,
+            },
+        ),
+        (
+            "process_action_renice",
+            DaemonRequest::ProcessAction {
+                pid: 1234,
+                action: ProcessAction::Renice { nice: 10 },
+            },
+        ),
+        (
+            "process_action_ionice",
+            DaemonRequest::ProcessAction {
+                pid: 1234,
+                action: ProcessAction::Ionice {
+                    class: IoClass::Idle,
+                },
+            },
+        ),
+        (
+            "throttle_pid",
+            DaemonRequest::Throttle {
+                target: ThrottleTarget::Pid(1234),
+                cpu_percent: 25,
+                battery_only: false,
+            },
+        ),
+        (
+            "throttle_name_battery_only",
+            DaemonRequest::Throttle {
+                target: ThrottleTarget::Name("node".to_string()),
+                cpu_percent: 50,
+                battery_only: true,
+            },
+        ),
+        (
+            "remove_throttle",
+            DaemonRequest::RemoveThrottle {
+                target: ThrottleTarget::Pid(1234),
             },
         ),
         (
@@ -343,9 +484,45 @@ fn generate_request_fixtures() {
                 to: "2024-01-07".to_string(),
             },
         ),
-    ];
+        (
+            "authenticate",
+            DaemonRequest::Authenticate {
+                token: "s3cret".to_string(),
+            },
+        ),
+        (
+            "add_annotation",
+            DaemonRequest::AddAnnotation {
+                text: "started gradle build".to_string(),
+                timestamp: Some(1704067200),
+            },
+        ),
+        (
+            "get_events",
+            DaemonRequest::GetEvents {
+                from: 1704067200,
+                to: 1704153600,
+            },
+        ),
+        ("reload_config", DaemonRequest::ReloadConfig),
+        ("get_config", DaemonRequest::GetConfig),
+        (
+            "set_config",
+            DaemonRequest::SetConfig {
+                key: "history.sample_interval_secs".to_string(),
+                value: "120".to_string(),
+            },
+        ),
+        ("undo_battery_saver", DaemonRequest::UndoBatterySaver),
+    ]
+}
+
+#[test]
+fn generate_request_fixtures() {
+    let dir = requests_dir();
+    fs::create_dir_all(&dir).unwrap();
 
-    for (name, request) in requests {
+    for (name, request) in sample_requests() {
         let json = serde_json::to_string_pretty(&request).unwrap();
         write_fixture(&dir, name, &json);
     }
@@ -362,6 +539,10 @@ fn generate_response_fixtures() {
             "hourly_stats",
             DaemonResponse::HourlyStats(vec![sample_hourly_stat()]),
         ),
+        (
+            "process_history",
+            DaemonResponse::ProcessHistory(vec![sample_hourly_process_stat()]),
+        ),
         (
             "daily_stats",
             DaemonResponse::DailyStats(vec![sample_daily_stat()]),
@@ -374,6 +555,7 @@ fn generate_response_fixtures() {
             "recent_samples",
             DaemonResponse::RecentSamples(vec![sample_sample()]),
         ),
+        ("samples", DaemonResponse::Samples(vec![sample_sample()])),
         ("ok", DaemonResponse::Ok),
         (
             "error",
@@ -393,6 +575,30 @@ fn generate_response_fixtures() {
             "kill_result",
             DaemonResponse::KillResult(sample_kill_result()),
         ),
+        (
+            "kill_results",
+            DaemonResponse::KillResults(vec![
+                sample_kill_result(),
+                KillProcessResult {
+                    pid: 1240,
+                    success: false,
+                    error: Some("kill command failed with exit code 1".to_string()),
+                },
+            ]),
+        ),
+        (
+            "kill_targets",
+            DaemonResponse::KillTargets(vec![
+                KillTarget {
+                    pid: 1234,
+                    name: "Google Chrome".to_string(),
+                },
+                KillTarget {
+                    pid: 1240,
+                    name: "Google Chrome Helper (Renderer)".to_string(),
+                },
+            ]),
+        ),
         (
             "subscription_rejected",
             DaemonResponse::SubscriptionRejected {
@@ -411,6 +617,36 @@ fn generate_response_fixtures() {
             "daily_cycles",
             DaemonResponse::DailyCycles(vec![sample_daily_cycle()]),
         ),
+        (
+            "config",
+            DaemonResponse::Config(vec![ConfigEntry {
+                key: "history.sample_interval_secs".to_string(),
+                value: "60".to_string(),
+            }]),
+        ),
+        (
+            "events",
+            DaemonResponse::Events(vec![Event {
+                timestamp: 1704067200,
+                kind: EventKind::Annotation,
+                message: "started gradle build".to_string(),
+            }]),
+        ),
+        (
+            "alert",
+            DaemonResponse::Alert(Alert {
+                timestamp: 1704067200,
+                name: "Low battery".to_string(),
+                rule: "battery.charge_percent < 15 while discharging".to_string(),
+                message: "Low battery: battery.charge_percent is 14.0 (< 15)".to_string(),
+                value: 14.0,
+                threshold: 15.0,
+            }),
+        ),
+        (
+            "throttle",
+            DaemonResponse::Throttle(sample_throttle_snapshot()),
+        ),
     ];
 
     for (name, response) in responses {
@@ -478,3 +714,99 @@ fn test_system_snapshot_backward_compatibility() {
     assert_eq!(snapshot.p_cores, 8);
     assert_eq!(snapshot.e_cores, 2);
 }
+
+/// Whether each request variant changes daemon or system state. There is no
+/// wildcard arm, so a new variant has to be classified here before this
+/// compiles.
+fn expected_mutating(request: &DaemonRequest) -> bool {
+    match request {
+        DaemonRequest::GetStatus
+        | DaemonRequest::GetHourlyStats { .. }
+        | DaemonRequest::GetDailyStats { .. }
+        | DaemonRequest::GetTopProcessesRange { .. }
+        | DaemonRequest::GetProcessHistory { .. }
+        | DaemonRequest::GetRecentSamples { .. }
+        | DaemonRequest::GetSamples { .. }
+        | DaemonRequest::Subscribe
+        | DaemonRequest::Unsubscribe
+        | DaemonRequest::GetCurrentData
+        | DaemonRequest::GetKillTargets { .. }
+        | DaemonRequest::GetCycleSummary { .. }
+        | DaemonRequest::GetChargeSessions { .. }
+        | DaemonRequest::GetDailyCycles { .. }
+        | DaemonRequest::Authenticate { .. }
+        | DaemonRequest::GetEvents { .. }
+        | DaemonRequest::GetConfig => false,
+        DaemonRequest::Shutdown
+        | DaemonRequest::KillProcess { .. }
+        | DaemonRequest::ProcessAction { .. }
+        | DaemonRequest::SetBroadcastInterval { .. }
+        | DaemonRequest::AddAnnotation { .. }
+        | DaemonRequest::ReloadConfig
+        | DaemonRequest::SetConfig { .. }
+        | DaemonRequest::UndoBatterySaver
+        | DaemonRequest::Throttle { .. }
+        | DaemonRequest::RemoveThrottle { .. } => true,
+    }
+}
+
+#[test]
+fn test_mutating_requests() {
+    for (name, request) in sample_requests() {
+        assert_eq!(
+            request.is_mutating(),
+            expected_mutating(&request),
+            "is_mutating is wrong for {}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_get_samples_method_defaults_to_lttb() {
+    let json = r#"{"GetSamples":{"from":0,"to":60,"max_points":10}}"#;
+    match DaemonRequest::from_json(json).expect("Failed to deserialize GetSamples") {
+        DaemonRequest::GetSamples { method, .. } => assert_eq!(method, DownsampleMethod::Lttb),
+        other => panic!("Expected GetSamples, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_add_annotation_timestamp_is_optional() {
+    let json = r#"{"AddAnnotation":{"text":"lunch"}}"#;
+    match DaemonRequest::from_json(json).expect("Failed to deserialize AddAnnotation") {
+        DaemonRequest::AddAnnotation { text, timestamp } => {
+            assert_eq!(text, "lunch");
+            assert_eq!(timestamp, None);
+        }
+        other => panic!("Expected AddAnnotation, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_kill_process_scope_defaults_to_process() {
+    let json = r#"{"KillProcess":{"pid":1234,"signal":"graceful"}}"#;
+    match DaemonRequest::from_json(json).expect("Failed to deserialize KillProcess") {
+        DaemonRequest::KillProcess { scope, .. } => assert_eq!(scope, KillScope::Process),
+        other => panic!("Expected KillProcess, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_v2_kill_request_is_answered_with_kill_result() {
+    // v2 clients send no scope and only understand a single KillResult
+    let json = r#"{"KillProcess":{"pid":1234,"signal":"graceful"}}"#;
+    let scope = match DaemonRequest::from_json(json).expect("Failed to deserialize KillProcess") {
+        DaemonRequest::KillProcess { scope, .. } => scope,
+        other => panic!("Expected KillProcess, got {:?}", other),
+    };
+    match DaemonResponse::kill(scope, vec![sample_kill_result()]) {
+        DaemonResponse::KillResult(result) => assert_eq!(result.pid, 1234),
+        other => panic!("Expected KillResult, got {:?}", other),
+    }
+
+    match DaemonResponse::kill(KillScope::Tree, vec![sample_kill_result()]) {
+        DaemonResponse::KillResults(results) => assert_eq!(results.len(), 1),
+        other => panic!("Expected KillResults, got {:?}", other),
+    }
+}